@@ -52,6 +52,11 @@ fn hash32(domain: &[u8], value: &[u8]) -> [u8; 32] {
     sha256(&material)
 }
 
+/// Domain-separated code hash that alpha-HIR manifests bind to.
+pub fn wasm_code_hash(wasm_bytes: &[u8]) -> [u8; 32] {
+    hash32(DOMAIN_WASM_HASH, wasm_bytes)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct WasmBuildOutput {
     pub wasm_bytes: Vec<u8>,
@@ -137,7 +142,7 @@ pub fn build_restricted_wasm_with_payload(payload: &[u8]) -> WasmBuildOutput {
     module.section(&code);
 
     let wasm_bytes = module.finish();
-    let code_hash = hash32(DOMAIN_WASM_HASH, &wasm_bytes);
+    let code_hash = wasm_code_hash(&wasm_bytes);
     WasmBuildOutput {
         wasm_bytes,
         code_hash,
@@ -6,6 +6,8 @@ use discos_client::DiscosClient;
 use discos_core::topicid::{compute_topic_id, ClaimMetadata, TopicSignals};
 use serde::{Deserialize, Serialize};
 
+use crate::hex_codec::hex;
+
 const CALIBRATION_BUCKETS: usize = 8;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -124,18 +126,8 @@ impl Lcg64 {
     }
 }
 
-fn hex_encode(bytes: &[u8]) -> String {
-    const HEX: &[u8; 16] = b"0123456789abcdef";
-    let mut s = String::with_capacity(bytes.len() * 2);
-    for b in bytes {
-        s.push(HEX[(b >> 4) as usize] as char);
-        s.push(HEX[(b & 0x0f) as usize] as char);
-    }
-    s
-}
-
 fn hash_hex(input: &[u8]) -> String {
-    hex_encode(&sha256(input))
+    hex(&sha256(input))
 }
 
 fn run_exp11_default() -> Exp11Result {
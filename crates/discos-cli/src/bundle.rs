@@ -0,0 +1,371 @@
+//! `bundle`: self-contained evidence for one capsule.
+//!
+//! An evidence bundle carries everything an auditor needs to check a capsule
+//! without reaching the daemon: the capsule bytes, the inclusion proof, the
+//! signed tree head it is rooted in, an optional consistency proof to a
+//! reference head, the verified revocations the exporter knew of at export
+//! time bound to that head, and the kernel key with its fingerprint.
+//! Each bundled revocation is kernel-signed, but the set is only as complete
+//! as the exporter made it, so a bundle can show a claim revoked but never
+//! show that it was not.
+//! [`EvidenceBundle::verify`] runs entirely on `evidenceos_verifier`.
+
+use std::{
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context};
+use discos_client::gossip::GossipHead;
+use discos_client::{
+    pb, signed_tree_head_from_pb, CapsuleValidity, DiscosClient, RevocationRecord, RevocationSet,
+    RevocationSnapshot, SignedTreeHead,
+};
+use evidenceos_verifier as verifier;
+use serde::{Deserialize, Serialize};
+
+use crate::hex_codec::{hex, unhex};
+use crate::sth_store::prove_consistency;
+
+pub const EVIDENCE_BUNDLE_VERSION: u32 = 1;
+/// Bundle validity comes from the revocations the exporter chose to include.
+pub const VALIDITY_SOURCE: &str = "exporter_snapshot";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EvidenceBundle {
+    pub version: u32,
+    /// Endpoint the capsule was fetched from.
+    pub origin: String,
+    pub exported_unix: u64,
+    pub claim_id_hex: String,
+    pub kernel_pubkey_hex: String,
+    /// SHA-256 of the kernel public key, for checking against a published
+    /// fingerprint when the key itself is not pinned.
+    pub kernel_key_fingerprint_hex: String,
+    pub capsule_hex: String,
+    pub inclusion: BundleInclusion,
+    pub sth: GossipHead,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consistency: Option<BundleConsistency>,
+    /// Revocations known at export time, bound to `sth`.
+    pub revocations: RevocationSnapshot,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BundleInclusion {
+    pub leaf_index: u64,
+    pub tree_size: u64,
+    pub leaf_hash_hex: String,
+    pub audit_path_hex: Vec<String>,
+}
+
+/// Consistency between the bundle head and a reference head, in whichever
+/// direction their sizes dictate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BundleConsistency {
+    pub reference: GossipHead,
+    pub path_hex: Vec<String>,
+}
+
+/// What the verifier trusts the kernel key by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrustAnchor {
+    KernelKey(Vec<u8>),
+    Fingerprint([u8; 32]),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BundleReport {
+    pub claim_id: String,
+    pub origin: String,
+    pub capsule_sha256: String,
+    pub leaf_index: u64,
+    pub tree_size: u64,
+    pub root_hash: String,
+    pub kernel_key_fingerprint: String,
+    pub consistency_reference_tree_size: Option<u64>,
+    pub revocations_checked: usize,
+    /// `revoked` when the snapshot holds a revocation of this claim. `valid`
+    /// only means the exporter's snapshot does not revoke it.
+    pub validity: CapsuleValidity,
+    /// What `validity` was checked against; always [`VALIDITY_SOURCE`].
+    pub validity_source: &'static str,
+}
+
+impl EvidenceBundle {
+    /// Builds a bundle from a fetched capsule. `reference` is a head paired
+    /// with the consistency proof between it and the capsule's head.
+    pub fn new(
+        origin: &str,
+        kernel_pubkey: &[u8],
+        response: &pb::FetchCapsuleResponse,
+        reference: Option<(&SignedTreeHead, &discos_client::ConsistencyProof)>,
+        revocations: &RevocationSet,
+    ) -> anyhow::Result<Self> {
+        let capsule: serde_json::Value =
+            serde_json::from_slice(&response.capsule_bytes).context("capsule is not valid json")?;
+        let claim_id_hex = capsule
+            .get("claim_id_hex")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("capsule has no claim_id_hex"))?
+            .to_string();
+        let inclusion = response
+            .inclusion_proof
+            .as_ref()
+            .context("missing inclusion proof")?;
+        let sth = signed_tree_head_from_pb(
+            response
+                .signed_tree_head
+                .as_ref()
+                .context("missing signed tree head")?,
+        )?;
+        Ok(Self {
+            version: EVIDENCE_BUNDLE_VERSION,
+            origin: origin.to_string(),
+            exported_unix: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            claim_id_hex,
+            kernel_pubkey_hex: hex(kernel_pubkey),
+            kernel_key_fingerprint_hex: hex(&verifier::sha256(kernel_pubkey)),
+            capsule_hex: hex(&response.capsule_bytes),
+            inclusion: BundleInclusion {
+                leaf_index: inclusion.leaf_index,
+                tree_size: inclusion.tree_size,
+                leaf_hash_hex: hex(&inclusion.leaf_hash),
+                audit_path_hex: inclusion.audit_path.iter().map(|n| hex(n)).collect(),
+            },
+            sth: (&sth).into(),
+            consistency: reference.map(|(head, proof)| BundleConsistency {
+                reference: head.into(),
+                path_hex: proof.path.iter().map(|n| hex(n)).collect(),
+            }),
+            revocations: revocations.snapshot(&sth)?,
+        })
+    }
+
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let bytes = fs::read(path).with_context(|| format!("read bundle {}", path.display()))?;
+        let bundle: Self = serde_json::from_slice(&bytes)
+            .with_context(|| format!("parse bundle {}", path.display()))?;
+        anyhow::ensure!(
+            bundle.version == EVIDENCE_BUNDLE_VERSION,
+            "unsupported evidence bundle version {} (expected {EVIDENCE_BUNDLE_VERSION})",
+            bundle.version
+        );
+        Ok(bundle)
+    }
+
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("create bundle dir {}", parent.display()))?;
+        }
+        fs::write(path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("write bundle {}", path.display()))
+    }
+
+    /// Checks every part of the bundle offline.
+    pub fn verify(&self, anchor: &TrustAnchor) -> anyhow::Result<BundleReport> {
+        anyhow::ensure!(
+            self.version == EVIDENCE_BUNDLE_VERSION,
+            "unsupported evidence bundle version {}",
+            self.version
+        );
+        let pubkey = unhex(&self.kernel_pubkey_hex).context("kernel_pubkey_hex")?;
+        let fingerprint = verifier::sha256(&pubkey);
+        anyhow::ensure!(
+            hex(&fingerprint) == self.kernel_key_fingerprint_hex,
+            "kernel key fingerprint does not match the bundled key"
+        );
+        match anchor {
+            TrustAnchor::KernelKey(trusted) => anyhow::ensure!(
+                *trusted == pubkey,
+                "bundle is signed by kernel key {}, not the trusted key",
+                self.kernel_key_fingerprint_hex
+            ),
+            TrustAnchor::Fingerprint(trusted) => anyhow::ensure!(
+                *trusted == fingerprint,
+                "bundle kernel key fingerprint {} is not the trusted fingerprint",
+                self.kernel_key_fingerprint_hex
+            ),
+        }
+
+        let sth = decode_head(&self.sth).context("sth")?;
+        verifier::verify_sth_signature(&sth, &pubkey)?;
+
+        let capsule = unhex(&self.capsule_hex).context("capsule_hex")?;
+        let capsule_json: serde_json::Value =
+            serde_json::from_slice(&capsule).context("capsule is not valid json")?;
+        anyhow::ensure!(
+            capsule_json.get("claim_id_hex").and_then(|v| v.as_str())
+                == Some(self.claim_id_hex.as_str()),
+            "capsule does not belong to claim {}",
+            self.claim_id_hex
+        );
+        let proof = verifier::InclusionProof {
+            leaf_hash: hash32(&self.inclusion.leaf_hash_hex).context("inclusion leaf hash")?,
+            leaf_index: self.inclusion.leaf_index,
+            tree_size: self.inclusion.tree_size,
+            audit_path: hashes(&self.inclusion.audit_path_hex).context("inclusion path")?,
+        };
+        anyhow::ensure!(
+            proof.leaf_hash == verifier::etl_leaf_hash(&capsule),
+            "inclusion proof is for a different leaf than the bundled capsule"
+        );
+        anyhow::ensure!(
+            proof.tree_size == sth.tree_size,
+            "inclusion proof tree size {} does not match signed tree head size {}",
+            proof.tree_size,
+            sth.tree_size
+        );
+        anyhow::ensure!(
+            verifier::verify_inclusion_proof(sth.root_hash, &proof),
+            "inclusion proof verification failed"
+        );
+
+        let consistency_reference_tree_size = match &self.consistency {
+            Some(consistency) => {
+                let reference = decode_head(&consistency.reference).context("reference head")?;
+                verifier::verify_sth_signature(&reference, &pubkey)
+                    .context("reference head signature")?;
+                let (old, new) = if reference.tree_size <= sth.tree_size {
+                    (&reference, &sth)
+                } else {
+                    (&sth, &reference)
+                };
+                let proof = verifier::ConsistencyProof {
+                    old_tree_size: old.tree_size,
+                    new_tree_size: new.tree_size,
+                    path: hashes(&consistency.path_hex).context("consistency path")?,
+                };
+                anyhow::ensure!(
+                    verifier::verify_consistency_proof(old.root_hash, new.root_hash, &proof),
+                    "consistency proof between tree sizes {} and {} failed",
+                    old.tree_size,
+                    new.tree_size
+                );
+                Some(reference.tree_size)
+            }
+            None => None,
+        };
+
+        let entries = revocation_entries(&self.revocations.entries)?;
+        for (entry, record) in entries.iter().zip(&self.revocations.entries) {
+            verifier::verify_revocation_signature(entry, &pubkey)
+                .with_context(|| format!("revocation for claim {}", record.claim_id_hex))?;
+        }
+        anyhow::ensure!(
+            verifier::verify_revocations_snapshot(
+                &entries,
+                &sth,
+                hash32(&self.revocations.digest_hex).context("revocation snapshot digest")?,
+            ),
+            "revocation snapshot digest does not match its entries and tree head"
+        );
+
+        Ok(BundleReport {
+            claim_id: self.claim_id_hex.clone(),
+            origin: self.origin.clone(),
+            capsule_sha256: hex(&verifier::sha256(&capsule)),
+            leaf_index: proof.leaf_index,
+            tree_size: sth.tree_size,
+            root_hash: hex(&sth.root_hash),
+            kernel_key_fingerprint: self.kernel_key_fingerprint_hex.clone(),
+            consistency_reference_tree_size,
+            revocations_checked: entries.len(),
+            validity: self
+                .revocations
+                .validity(&unhex(&self.claim_id_hex).context("claim_id_hex")?),
+            validity_source: VALIDITY_SOURCE,
+        })
+    }
+}
+
+/// Fetches the capsule for `claim_id` and bundles it, with a consistency
+/// proof to `reference` when given. The bundle is verified against
+/// `kernel_pubkey` before it is returned.
+pub async fn export_bundle(
+    client: &mut DiscosClient,
+    origin: &str,
+    kernel_pubkey: &[u8],
+    claim_id: &[u8],
+    reference: Option<&SignedTreeHead>,
+    revocations: &RevocationSet,
+) -> anyhow::Result<EvidenceBundle> {
+    let response = client
+        .fetch_capsule(pb::FetchCapsuleRequest {
+            claim_id: claim_id.to_vec(),
+        })
+        .await?;
+    let sth = signed_tree_head_from_pb(
+        response
+            .signed_tree_head
+            .as_ref()
+            .context("missing signed tree head")?,
+    )?;
+    let proof = match reference {
+        Some(reference) if reference.tree_size <= sth.tree_size => {
+            Some(prove_consistency(client, reference, &sth).await?)
+        }
+        Some(reference) => Some(prove_consistency(client, &sth, reference).await?),
+        None => None,
+    };
+    let bundle = EvidenceBundle::new(
+        origin,
+        kernel_pubkey,
+        &response,
+        reference.zip(proof.as_ref()),
+        revocations,
+    )?;
+    anyhow::ensure!(
+        bundle.claim_id_hex == hex(claim_id),
+        "daemon returned a capsule for claim {}",
+        bundle.claim_id_hex
+    );
+    bundle.verify(&TrustAnchor::KernelKey(kernel_pubkey.to_vec()))?;
+    Ok(bundle)
+}
+
+fn decode_head(head: &GossipHead) -> anyhow::Result<verifier::SignedTreeHead> {
+    Ok(verifier::SignedTreeHead {
+        tree_size: head.tree_size,
+        root_hash: hash32(&head.root_hash_hex)?,
+        signature: unhex(&head.signature_hex)?
+            .try_into()
+            .map_err(|_| anyhow!("signature must be 64 bytes"))?,
+    })
+}
+
+fn hash32(s: &str) -> anyhow::Result<[u8; 32]> {
+    unhex(s)?
+        .try_into()
+        .map_err(|_| anyhow!("hash must be 32 bytes"))
+}
+
+fn hashes(nodes: &[String]) -> anyhow::Result<Vec<[u8; 32]>> {
+    nodes.iter().map(|node| hash32(node)).collect()
+}
+
+fn revocation_entries(
+    records: &[RevocationRecord],
+) -> anyhow::Result<Vec<verifier::RevocationEntry>> {
+    records
+        .iter()
+        .map(|record| {
+            Ok(verifier::RevocationEntry {
+                claim_id: unhex(&record.claim_id_hex)?,
+                reason_code: record.reason_code.clone(),
+                logical_epoch: record.logical_epoch,
+                signature: unhex(&record.signature_hex)?
+                    .try_into()
+                    .map_err(|_| anyhow!("revocation signature must be 64 bytes"))?,
+            })
+        })
+        .collect()
+}
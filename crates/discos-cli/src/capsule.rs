@@ -1,82 +1,142 @@
+//! `capsule`: human-facing summaries of claim capsules.
+//!
+//! Capsules are decoded with [`ClaimCapsule::any_from_value`]: strictly when
+//! they name a schema, so receipts are never read from a malformed v1
+//! capsule, and leniently as a legacy capsule when they do not. The printed
+//! summary is only for display, so it falls back to legacy decoding for any
+//! capsule and says so: fields it did not carry print as `null` and are
+//! listed under `decoding.missing_fields`, next to the reason strict
+//! decoding failed.
+
+use anyhow::Context;
+pub use discos_client::capsule::PolicyOracleReceipt;
+use discos_client::capsule::{CapsuleSchema, ClaimCapsule, LegacyCapsule};
 use serde::Serialize;
 use serde_json::Value;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
-pub struct PolicyOracleReceipt {
-    pub oracle_id: String,
-    pub decision: String,
-    pub reason_code: String,
-    pub wasm_hash_hex: String,
-    pub manifest_hash_hex: String,
+pub struct OracleMetadata {
+    pub oracle_id: Option<String>,
+    pub oracle_resolution_hash: Option<String>,
+    pub oracle_manifest_hash: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
-pub struct OracleMetadata {
-    pub oracle_id: String,
-    pub oracle_resolution_hash: String,
-    pub oracle_manifest_hash: String,
+/// A capsule decoded strictly, or leniently if it has no schema.
+#[derive(Debug, Clone)]
+pub struct DecodedCapsule {
+    pub capsule: LegacyCapsule,
+    /// Why strict decoding failed; `None` when it succeeded.
+    pub strict_error: Option<String>,
 }
 
-pub fn build_capsule_print_summary(capsule: &Value) -> Value {
-    let schema = capsule
-        .get("schema")
-        .cloned()
-        .unwrap_or(Value::String(String::new()));
-    let certified = capsule.get("certified").cloned().unwrap_or(Value::Null);
-    let e_value = capsule.get("e_value").cloned().unwrap_or(Value::Null);
-    let decision = capsule.get("decision").cloned().unwrap_or(Value::Null);
-    let reason_codes = capsule
-        .get("reason_codes")
-        .cloned()
-        .unwrap_or(Value::Array(Vec::new()));
+impl DecodedCapsule {
+    pub fn from_value(capsule: &Value) -> anyhow::Result<Self> {
+        let decoded = ClaimCapsule::any_from_value(capsule).context("capsule does not decode")?;
+        let strict_error = match decoded.capsule.schema {
+            CapsuleSchema::Unversioned => ClaimCapsule::from_value(capsule)
+                .err()
+                .map(|err| err.to_string()),
+            CapsuleSchema::V1 => None,
+        };
+        Ok(Self {
+            capsule: decoded,
+            strict_error,
+        })
+    }
 
-    let receipts = extract_policy_oracle_receipts(capsule);
-    let oracle_metadata = extract_oracle_metadata(capsule);
+    /// Decodes strictly if possible and leniently otherwise, whatever the
+    /// schema; only for showing a capsule, never for trusting its contents.
+    pub fn for_display(capsule: &Value) -> anyhow::Result<Self> {
+        match ClaimCapsule::from_value(capsule) {
+            Ok(capsule) => Ok(Self {
+                capsule: LegacyCapsule {
+                    capsule,
+                    missing_fields: Vec::new(),
+                },
+                strict_error: None,
+            }),
+            Err(strict) => Ok(Self {
+                capsule: ClaimCapsule::legacy_from_value(capsule)
+                    .context("capsule does not decode even as a legacy capsule")?,
+                strict_error: Some(strict.to_string()),
+            }),
+        }
+    }
 
-    serde_json::json!({
-        "capsule": {
-            "schema": schema,
-            "certified": certified,
-            "e_value": e_value,
-            "decision": decision,
-            "reason_codes": reason_codes,
-        },
-        "oracle": oracle_metadata,
-        "policy_oracle_receipts": receipts,
-    })
+    fn present<T: Serialize>(&self, field: &str, value: T) -> Value {
+        if self.capsule.is_missing(field) {
+            Value::Null
+        } else {
+            serde_json::to_value(value).unwrap_or(Value::Null)
+        }
+    }
 }
 
-pub fn extract_policy_oracle_receipts(capsule: &Value) -> Vec<PolicyOracleReceipt> {
-    let Some(receipts) = capsule
-        .get("policy_oracle_receipts")
-        .and_then(serde_json::Value::as_array)
-    else {
-        return Vec::new();
+pub fn build_capsule_print_summary(capsule: &Value) -> anyhow::Result<Value> {
+    let decoded = DecodedCapsule::for_display(capsule)?;
+    let typed = &decoded.capsule.capsule;
+    let decoding = match &decoded.strict_error {
+        None => serde_json::json!({ "mode": "strict", "missing_fields": [] }),
+        Some(error) => serde_json::json!({
+            "mode": "legacy",
+            "missing_fields": decoded.capsule.missing_fields,
+            "strict_error": error,
+        }),
     };
 
-    receipts
+    Ok(serde_json::json!({
+        "capsule": {
+            "schema": typed.schema.id(),
+            "certified": decoded.present("certified", typed.certified),
+            "e_value": decoded.present("e_value", typed.e_value),
+            "decision": decoded.present("decision", &typed.decision),
+            "reason_codes": decoded.present("reason_codes", &typed.reason_codes),
+        },
+        "decoding": decoding,
+        "oracle": oracle_metadata(&decoded.capsule),
+        "policy_oracle_receipts": receipts(&decoded.capsule),
+    }))
+}
+
+/// Receipts as JSON, with fields the capsule did not carry set to `null`.
+fn receipts(decoded: &LegacyCapsule) -> Vec<Value> {
+    decoded
+        .capsule
+        .policy_oracle_receipts
         .iter()
-        .map(|receipt| PolicyOracleReceipt {
-            oracle_id: string_field(receipt, "oracle_id"),
-            decision: string_field(receipt, "decision"),
-            reason_code: string_field(receipt, "reason_code"),
-            wasm_hash_hex: string_field(receipt, "wasm_hash_hex"),
-            manifest_hash_hex: string_field(receipt, "manifest_hash_hex"),
+        .enumerate()
+        .map(|(i, receipt)| {
+            let mut value = serde_json::to_value(receipt).unwrap_or(Value::Null);
+            if let Some(fields) = value.as_object_mut() {
+                for (key, field) in fields.iter_mut() {
+                    if decoded.is_missing(&format!("policy_oracle_receipts[{i}].{key}")) {
+                        *field = Value::Null;
+                    }
+                }
+            }
+            value
         })
         .collect()
 }
 
-pub fn extract_oracle_metadata(capsule: &Value) -> OracleMetadata {
-    OracleMetadata {
-        oracle_id: string_field(capsule, "oracle_id"),
-        oracle_resolution_hash: string_field(capsule, "oracle_resolution_hash"),
-        oracle_manifest_hash: string_field(capsule, "oracle_manifest_hash"),
-    }
+pub fn extract_policy_oracle_receipts(capsule: &Value) -> anyhow::Result<Vec<PolicyOracleReceipt>> {
+    Ok(DecodedCapsule::from_value(capsule)?
+        .capsule
+        .capsule
+        .policy_oracle_receipts)
+}
+
+pub fn extract_oracle_metadata(capsule: &Value) -> anyhow::Result<OracleMetadata> {
+    Ok(oracle_metadata(
+        &DecodedCapsule::from_value(capsule)?.capsule,
+    ))
 }
 
-fn string_field(obj: &Value, key: &str) -> String {
-    obj.get(key)
-        .and_then(serde_json::Value::as_str)
-        .unwrap_or("")
-        .to_owned()
+fn oracle_metadata(decoded: &LegacyCapsule) -> OracleMetadata {
+    let capsule = &decoded.capsule;
+    OracleMetadata {
+        oracle_id: (!decoded.is_missing("oracle_id")).then(|| capsule.oracle_id.clone()),
+        oracle_resolution_hash: capsule.oracle_resolution_hash.clone(),
+        oracle_manifest_hash: capsule.oracle_manifest_hash.clone(),
+    }
 }
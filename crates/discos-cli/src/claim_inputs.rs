@@ -0,0 +1,288 @@
+use std::{fs, path::Path, path::PathBuf};
+
+use anyhow::{anyhow, Context};
+use discos_builder::{
+    build_restricted_wasm, manifest_hash, sha256, wasm_code_hash, AlphaHIRManifest,
+    CausalDSLManifest, PhysHIRManifest,
+};
+use discos_client::{pb, WASM_MODULE_ARTIFACT_KIND};
+use discos_core::{
+    structured_claims::{
+        CbrnStructuredClaim, ClaimKind, Decision, Domain, EnvelopeCheck, Profile, QuantityKind,
+        QuantizedValue, ReasonCode, Scale, SchemaVersion, SiUnit,
+    },
+    structured_profiles::{ProfileRegistry, StructuredClaimProfile},
+    topicid::{canonicalize_output_schema_id, TopicSignals, CANONICAL_OUTPUT_SCHEMA_ID},
+};
+use evidenceos_core::wasm_aspec::verify_restricted_wasm;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::hex_codec::{hex, unhex_32};
+
+pub const WASM_FILE: &str = "wasm.bin";
+pub const ALPHA_HIR_FILE: &str = "alpha_hir.json";
+pub const PHYS_HIR_FILE: &str = "phys_hir.json";
+pub const CAUSAL_DSL_FILE: &str = "causal_dsl.json";
+pub const STRUCTURED_CLAIM_FILE: &str = "structured_claim.json";
+pub const STRUCTURED_CLAIM_CANONICAL_FILE: &str = "structured_claim.canonical";
+
+// Caller-supplied files for `claim create`. Anything left unset falls back to
+// the generated placeholder for that input. (Not a doc comment: clap would use
+// it as the subcommand's help text.)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, clap::Args)]
+#[serde(deny_unknown_fields)]
+pub struct ClaimInputPaths {
+    /// Restricted wasm module.
+    #[arg(long)]
+    pub wasm: Option<PathBuf>,
+    /// Alpha-HIR manifest; must bind the wasm code hash and output schema.
+    #[arg(long)]
+    pub alpha_hir: Option<PathBuf>,
+    /// Phys-HIR manifest; its physical_signature_hash is the phys-HIR signal.
+    #[arg(long)]
+    pub phys_hir: Option<PathBuf>,
+    /// Causal DSL manifest; its dag_hash is the dependency merkle root.
+    #[arg(long)]
+    pub causal_dsl: Option<PathBuf>,
+    /// Structured claim JSON in the output schema's profile; required for
+    /// schemas other than cbrn-sc.v1.
+    #[arg(long)]
+    pub structured_claim: Option<PathBuf>,
+}
+
+impl ClaimInputPaths {
+    /// The inputs `claim create` wrote into a claim directory.
+    pub fn in_dir(dir: &Path) -> Self {
+        Self {
+            wasm: Some(dir.join(WASM_FILE)),
+            alpha_hir: Some(dir.join(ALPHA_HIR_FILE)),
+            phys_hir: Some(dir.join(PHYS_HIR_FILE)),
+            causal_dsl: Some(dir.join(CAUSAL_DSL_FILE)),
+            structured_claim: Some(dir.join(STRUCTURED_CLAIM_FILE)),
+        }
+    }
+
+    /// Resolves relative paths against `base`.
+    pub fn relative_to(&self, base: &Path) -> Self {
+        let join = |path: &Option<PathBuf>| path.as_ref().map(|p| base.join(p));
+        Self {
+            wasm: join(&self.wasm),
+            alpha_hir: join(&self.alpha_hir),
+            phys_hir: join(&self.phys_hir),
+            causal_dsl: join(&self.causal_dsl),
+            structured_claim: join(&self.structured_claim),
+        }
+    }
+}
+
+/// Validated inputs for a claim, with the topic signals derived from them.
+#[derive(Debug, Clone)]
+pub struct ClaimInputs {
+    pub wasm_bytes: Vec<u8>,
+    pub code_hash: [u8; 32],
+    pub alpha: AlphaHIRManifest,
+    pub phys: PhysHIRManifest,
+    pub causal: CausalDSLManifest,
+    /// The structured claim in its profile's JSON form.
+    pub structured_claim: Value,
+    pub canonical_claim: Vec<u8>,
+    pub signals: TopicSignals,
+}
+
+impl ClaimInputs {
+    /// Loads and cross-checks the claim inputs against the built-in profile
+    /// for `output_schema_id`, which must already be canonical.
+    pub fn load(
+        claim_name: &str,
+        output_schema_id: &str,
+        paths: &ClaimInputPaths,
+    ) -> anyhow::Result<Self> {
+        let profiles = ProfileRegistry::builtin();
+        let profile = profiles.resolve(output_schema_id).map_err(|e| anyhow!(e))?;
+        Self::load_with_profile(claim_name, profile, paths)
+    }
+
+    /// Loads and cross-checks the claim inputs, parsing the structured claim
+    /// with `profile`.
+    pub fn load_with_profile(
+        claim_name: &str,
+        profile: &dyn StructuredClaimProfile,
+        paths: &ClaimInputPaths,
+    ) -> anyhow::Result<Self> {
+        let output_schema_id = profile.output_schema_id();
+        let (wasm_bytes, code_hash) = match &paths.wasm {
+            Some(path) => {
+                let bytes =
+                    fs::read(path).with_context(|| format!("read wasm {}", path.display()))?;
+                verify_restricted_wasm(&bytes)
+                    .with_context(|| format!("wasm {} is not restricted", path.display()))?;
+                let code_hash = wasm_code_hash(&bytes);
+                (bytes, code_hash)
+            }
+            None => {
+                let wasm = build_restricted_wasm();
+                (wasm.wasm_bytes, wasm.code_hash)
+            }
+        };
+
+        let alpha = match &paths.alpha_hir {
+            Some(path) => read_manifest::<AlphaHIRManifest>(path)?,
+            None => AlphaHIRManifest {
+                plan_id: claim_name.to_string(),
+                code_hash_hex: hex(&code_hash),
+                oracle_kinds: vec!["oracle_query".into()],
+                output_schema_id: output_schema_id.to_string(),
+                nullspec_id: "nullspec.v1".into(),
+            },
+        };
+        anyhow::ensure!(
+            alpha.code_hash_hex.eq_ignore_ascii_case(&hex(&code_hash)),
+            "alpha-HIR code_hash_hex {} does not match wasm code hash {}",
+            alpha.code_hash_hex,
+            hex(&code_hash)
+        );
+        anyhow::ensure!(
+            canonicalize_output_schema_id(&alpha.output_schema_id) == output_schema_id,
+            "alpha-HIR output_schema_id `{}` does not match `{}`",
+            alpha.output_schema_id,
+            output_schema_id
+        );
+        let semantic_hash = manifest_hash(&alpha)?;
+
+        let phys = match &paths.phys_hir {
+            Some(path) => read_manifest::<PhysHIRManifest>(path)?,
+            None => PhysHIRManifest {
+                physical_signature_hash: hex(&semantic_hash),
+                envelope_ids: vec!["env/default".into()],
+            },
+        };
+        let phys_hir_signature_hash =
+            unhex_32(&phys.physical_signature_hash).context("phys-HIR physical_signature_hash")?;
+
+        let causal = match &paths.causal_dsl {
+            Some(path) => read_manifest::<CausalDSLManifest>(path)?,
+            None => CausalDSLManifest {
+                dag_hash: hex(&manifest_hash(&phys)?),
+                adjustment_sets: vec![vec!["baseline".into()]],
+            },
+        };
+        let dependency_merkle_root = unhex_32(&causal.dag_hash).context("causal DSL dag_hash")?;
+
+        let structured_claim = match &paths.structured_claim {
+            Some(path) => {
+                let bytes = fs::read(path)
+                    .with_context(|| format!("read structured claim {}", path.display()))?;
+                profile
+                    .parse(&bytes)
+                    .map_err(|e| anyhow!("structured claim {}: {e}", path.display()))?
+            }
+            // Only CBRN-SC has a placeholder claim.
+            None if output_schema_id == CANONICAL_OUTPUT_SCHEMA_ID => {
+                serde_json::to_value(default_structured_claim())?
+            }
+            None => {
+                anyhow::bail!("output schema `{output_schema_id}` needs a --structured-claim file")
+            }
+        };
+        profile
+            .validate(&structured_claim)
+            .map_err(|e| anyhow!("structured claim failed validation: {e}"))?;
+        let canonical_claim = profile
+            .canonicalize(&structured_claim)
+            .map_err(|e| anyhow!("failed to canonicalize {output_schema_id} claim: {e}"))?;
+
+        Ok(Self {
+            wasm_bytes,
+            code_hash,
+            alpha,
+            phys,
+            causal,
+            structured_claim,
+            canonical_claim,
+            signals: TopicSignals {
+                semantic_hash: Some(semantic_hash),
+                phys_hir_signature_hash,
+                dependency_merkle_root: Some(dependency_merkle_root),
+            },
+        })
+    }
+
+    /// Artifacts for `CommitArtifacts`: the three manifests, keyed by file
+    /// name, and the wasm module.
+    pub fn artifacts(&self) -> anyhow::Result<Vec<pb::Artifact>> {
+        Ok(vec![
+            pb::Artifact {
+                artifact_hash: manifest_hash(&self.alpha)?.to_vec(),
+                kind: ALPHA_HIR_FILE.to_string(),
+            },
+            pb::Artifact {
+                artifact_hash: manifest_hash(&self.phys)?.to_vec(),
+                kind: PHYS_HIR_FILE.to_string(),
+            },
+            pb::Artifact {
+                artifact_hash: manifest_hash(&self.causal)?.to_vec(),
+                kind: CAUSAL_DSL_FILE.to_string(),
+            },
+            pb::Artifact {
+                artifact_hash: sha256(&self.wasm_bytes).to_vec(),
+                kind: WASM_MODULE_ARTIFACT_KIND.to_string(),
+            },
+        ])
+    }
+
+    /// Writes every input into the claim directory under its well-known name.
+    pub fn write_to(&self, dir: &Path) -> anyhow::Result<()> {
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join(WASM_FILE), &self.wasm_bytes)?;
+        fs::write(
+            dir.join(ALPHA_HIR_FILE),
+            serde_json::to_vec_pretty(&self.alpha)?,
+        )?;
+        fs::write(
+            dir.join(PHYS_HIR_FILE),
+            serde_json::to_vec_pretty(&self.phys)?,
+        )?;
+        fs::write(
+            dir.join(CAUSAL_DSL_FILE),
+            serde_json::to_vec_pretty(&self.causal)?,
+        )?;
+        fs::write(
+            dir.join(STRUCTURED_CLAIM_FILE),
+            serde_json::to_vec_pretty(&self.structured_claim)?,
+        )?;
+        fs::write(
+            dir.join(STRUCTURED_CLAIM_CANONICAL_FILE),
+            &self.canonical_claim,
+        )?;
+        Ok(())
+    }
+}
+
+fn default_structured_claim() -> CbrnStructuredClaim {
+    CbrnStructuredClaim {
+        schema_version: SchemaVersion::V1_0_0,
+        profile: Profile::CbrnSc,
+        domain: Domain::Cbrn,
+        claim_kind: ClaimKind::Assessment,
+        quantities: vec![QuantizedValue {
+            quantity_kind: QuantityKind::Concentration,
+            value_q: 500,
+            scale: Scale::Micro,
+            unit: SiUnit::MolPerM3,
+        }],
+        envelope_id: [0u8; 32],
+        envelope_check: EnvelopeCheck::Match,
+        references: vec![],
+        etl_root: [0u8; 32],
+        envelope_manifest_hash: [0u8; 32],
+        envelope_manifest_version: 1,
+        decision: Decision::Pass,
+        reason_codes: vec![ReasonCode::SensorAgreement],
+    }
+}
+
+fn read_manifest<T: serde::de::DeserializeOwned>(path: &Path) -> anyhow::Result<T> {
+    let bytes = fs::read(path).with_context(|| format!("read manifest {}", path.display()))?;
+    serde_json::from_slice(&bytes).with_context(|| format!("parse manifest {}", path.display()))
+}
@@ -0,0 +1,356 @@
+//! `claim run`: the whole claim lifecycle from one TOML spec.
+//!
+//! Each completed step is checkpointed into the claim directory before the
+//! next one starts, so rerunning the same spec after a crash or transport
+//! failure picks up where the last run stopped instead of creating a second
+//! claim. A step whose response was lost before its checkpoint was written
+//! is sent again with the same idempotency key, derived from the spec digest,
+//! so the daemon answers with the original result. Each step's registry
+//! event is recorded before its checkpoint, so a crash between the two
+//! replays the step rather than leaving the registry behind the checkpoint.
+
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Context};
+use discos_builder::sha256;
+use discos_client::{
+    pb,
+    session::{Committed, Created, Executed, Frozen, Sealed},
+    ClaimSession, DiscosClient,
+};
+use discos_core::topicid::{compute_topic_id, ClaimMetadata};
+use serde::{Deserialize, Serialize};
+
+use crate::claim_inputs::{ClaimInputPaths, ClaimInputs};
+use crate::hex_codec::{hex, unhex};
+use crate::registry::{ClaimEvent, ExecutionRecord, Registry};
+
+pub const RUN_CHECKPOINT_FILE: &str = "run_checkpoint.json";
+pub const CAPSULE_FILE: &str = "capsule.bin";
+
+fn default_output_schema_id() -> String {
+    "cbrn-sc.v1".to_string()
+}
+
+fn default_oracle_id() -> String {
+    "default".to_string()
+}
+
+/// Contents of `claim.toml`. Input paths are relative to the spec file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClaimRunSpec {
+    pub claim_name: String,
+    pub lane: String,
+    pub alpha_micros: u32,
+    pub epoch_config_ref: String,
+    #[serde(default = "default_output_schema_id")]
+    pub output_schema_id: String,
+    pub holdout_ref: String,
+    pub epoch_size: u32,
+    pub oracle_num_symbols: u32,
+    pub access_credit: u64,
+    #[serde(default = "default_oracle_id")]
+    pub oracle_id: String,
+    #[serde(default)]
+    pub inputs: ClaimInputPaths,
+}
+
+impl ClaimRunSpec {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("read claim spec {}", path.display()))?;
+        let mut spec: Self = toml::from_str(&text)
+            .with_context(|| format!("parse claim spec {}", path.display()))?;
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        spec.inputs = spec.inputs.relative_to(base);
+        Ok(spec)
+    }
+
+    /// Digest binding a checkpoint to the spec that produced it.
+    pub fn digest_hex(&self) -> anyhow::Result<String> {
+        Ok(hex(&sha256(&serde_json::to_vec(self)?)))
+    }
+
+    fn metadata(&self) -> ClaimMetadata {
+        ClaimMetadata {
+            lane: self.lane.clone(),
+            alpha_micros: self.alpha_micros,
+            epoch_config_ref: self.epoch_config_ref.clone(),
+            output_schema_id: self.output_schema_id.clone(),
+        }
+    }
+}
+
+/// Lifecycle steps in the order `claim run` performs them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStep {
+    Create,
+    Commit,
+    Freeze,
+    Seal,
+    Execute,
+    FetchCapsule,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunCheckpoint {
+    pub spec_sha256_hex: String,
+    pub claim_id_hex: String,
+    pub topic_id_hex: String,
+    pub local_topic_id_hex: String,
+    pub completed: RunStep,
+    pub execution: Option<ExecutionRecord>,
+}
+
+impl RunCheckpoint {
+    pub fn load(dir: &Path) -> anyhow::Result<Option<Self>> {
+        match fs::read(dir.join(RUN_CHECKPOINT_FILE)) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .context("parse claim run checkpoint"),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err).context("read claim run checkpoint"),
+        }
+    }
+
+    fn advance(&mut self, step: RunStep, dir: &Path) -> anyhow::Result<()> {
+        self.completed = step;
+        let tmp = dir.join(format!("{RUN_CHECKPOINT_FILE}.tmp"));
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?).context("write claim run checkpoint")?;
+        fs::rename(&tmp, dir.join(RUN_CHECKPOINT_FILE)).context("replace claim run checkpoint")
+    }
+
+    fn ids(&self) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
+        Ok((unhex(&self.claim_id_hex)?, unhex(&self.topic_id_hex)?))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClaimRunOutcome {
+    pub claim_id: String,
+    pub topic_id: String,
+    pub local_topic_id: String,
+    pub resumed_after: Option<RunStep>,
+    pub certified: bool,
+    pub e_value: f64,
+    pub capsule_sha256: String,
+    pub etl_index: u64,
+    pub tree_size: u64,
+    pub capsule_path: String,
+}
+
+/// Drives the lifecycle for `spec`, resuming from the checkpoint in `dir` if
+/// one exists. The capsule is verified against `kernel_pubkey` before the run
+/// is reported complete.
+pub async fn run_claim(
+    client: &mut DiscosClient,
+    registry: &mut Registry,
+    spec: &ClaimRunSpec,
+    dir: &Path,
+    endpoint: &str,
+    kernel_pubkey: &[u8],
+) -> anyhow::Result<ClaimRunOutcome> {
+    let spec_digest = spec.digest_hex()?;
+    let client = &mut client
+        .clone()
+        .with_idempotency_scope(format!("discos.claim-run.v1/{spec_digest}"));
+    let name = Some(spec.claim_name.as_str());
+    let existing = RunCheckpoint::load(dir)?;
+    let resumed_after = existing.as_ref().map(|cp| cp.completed);
+
+    let mut checkpoint = match existing {
+        Some(checkpoint) => {
+            anyhow::ensure!(
+                checkpoint.spec_sha256_hex == spec_digest,
+                "claim spec changed since claim {} was created; remove {} to start a new claim",
+                checkpoint.claim_id_hex,
+                dir.join(RUN_CHECKPOINT_FILE).display()
+            );
+            checkpoint
+        }
+        None => {
+            let inputs = ClaimInputs::load(&spec.claim_name, &spec.output_schema_id, &spec.inputs)?;
+            inputs.write_to(dir)?;
+            let topic = compute_topic_id(&spec.metadata(), inputs.signals.clone());
+            let session = ClaimSession::create(
+                client,
+                pb::CreateClaimV2Request {
+                    claim_name: spec.claim_name.clone(),
+                    metadata: Some(pb::ClaimMetadataV2 {
+                        lane: spec.lane.clone(),
+                        alpha_micros: spec.alpha_micros,
+                        epoch_config_ref: spec.epoch_config_ref.clone(),
+                        output_schema_id: spec.output_schema_id.clone(),
+                    }),
+                    signals: Some(pb::TopicSignalsV2 {
+                        semantic_hash: topic
+                            .signals
+                            .semantic_hash
+                            .map(|h| h.to_vec())
+                            .unwrap_or_default(),
+                        phys_hir_signature_hash: topic.signals.phys_hir_signature_hash.to_vec(),
+                        dependency_merkle_root: topic
+                            .signals
+                            .dependency_merkle_root
+                            .map(|h| h.to_vec())
+                            .unwrap_or_default(),
+                    }),
+                    holdout_ref: spec.holdout_ref.clone(),
+                    epoch_size: spec.epoch_size.into(),
+                    oracle_num_symbols: spec.oracle_num_symbols,
+                    access_credit: spec.access_credit,
+                    oracle_id: spec.oracle_id.clone(),
+                    nullspec_id: String::new(),
+                    dp_epsilon_budget: None,
+                    dp_delta_budget: None,
+                },
+            )
+            .await
+            .with_context(|| {
+                format!("create_claim_v2 failed for oracle_id `{}`", spec.oracle_id)
+            })?;
+            let mut checkpoint = RunCheckpoint {
+                spec_sha256_hex: spec_digest,
+                claim_id_hex: hex(session.claim_id()),
+                topic_id_hex: hex(session.topic_id()),
+                local_topic_id_hex: topic.topic_id_hex,
+                completed: RunStep::Create,
+                execution: None,
+            };
+            registry.record(
+                &checkpoint.claim_id_hex,
+                name,
+                ClaimEvent::Created {
+                    topic_id_hex: checkpoint.topic_id_hex.clone(),
+                    local_topic_id_hex: checkpoint.local_topic_id_hex.clone(),
+                    endpoint: endpoint.to_string(),
+                },
+            )?;
+            checkpoint.advance(RunStep::Create, dir)?;
+            checkpoint
+        }
+    };
+    let (claim_id, topic_id) = checkpoint.ids()?;
+
+    if checkpoint.completed < RunStep::Commit {
+        // Commit exactly what create wrote, not whatever the spec points at now.
+        let inputs = ClaimInputs::load(
+            &spec.claim_name,
+            &spec.output_schema_id,
+            &ClaimInputPaths::in_dir(dir),
+        )?;
+        ClaimSession::<Created>::resume(client, claim_id.clone(), topic_id.clone())?
+            .commit(inputs.artifacts()?, inputs.wasm_bytes.clone())
+            .await
+            .context("commit_artifacts failed")?;
+        registry.record(
+            &checkpoint.claim_id_hex,
+            name,
+            ClaimEvent::Committed {
+                wasm_hash_hex: hex(&sha256(&inputs.wasm_bytes)),
+            },
+        )?;
+        checkpoint.advance(RunStep::Commit, dir)?;
+    }
+
+    if checkpoint.completed < RunStep::Freeze {
+        ClaimSession::<Committed>::resume(client, claim_id.clone(), topic_id.clone())?
+            .freeze()
+            .await
+            .context("freeze failed")?;
+        registry.record(&checkpoint.claim_id_hex, name, ClaimEvent::Frozen)?;
+        checkpoint.advance(RunStep::Freeze, dir)?;
+    }
+
+    if checkpoint.completed < RunStep::Seal {
+        ClaimSession::<Frozen>::resume(client, claim_id.clone(), topic_id.clone())?
+            .seal()
+            .await
+            .context("seal failed")?;
+        registry.record(&checkpoint.claim_id_hex, name, ClaimEvent::Sealed)?;
+        checkpoint.advance(RunStep::Seal, dir)?;
+    }
+
+    if checkpoint.completed < RunStep::Execute {
+        let executed = ClaimSession::<Sealed>::resume(client, claim_id.clone(), topic_id.clone())?
+            .execute()
+            .await
+            .context("execute_claim_v2 failed")?;
+        let response = executed.execution();
+        let execution = ExecutionRecord {
+            certified: response.certified,
+            e_value: response.e_value,
+            canonical_output_hex: hex(&response.canonical_output),
+        };
+        checkpoint.execution = Some(execution.clone());
+        registry.record(
+            &checkpoint.claim_id_hex,
+            name,
+            ClaimEvent::Executed {
+                certified: execution.certified,
+                e_value: execution.e_value,
+                canonical_output_hex: execution.canonical_output_hex,
+            },
+        )?;
+        checkpoint.advance(RunStep::Execute, dir)?;
+    }
+
+    let execution = checkpoint
+        .execution
+        .clone()
+        .ok_or_else(|| anyhow!("claim run checkpoint is missing the execution result"))?;
+    let capsule_path = dir.join(CAPSULE_FILE);
+    // A checkpoint can still be ahead of the registry if it was written by an
+    // older build that advanced before recording; fetching again is harmless.
+    let capsule_recorded = registry
+        .get(&checkpoint.claim_id_hex)
+        .is_some_and(|record| record.capsule.is_some());
+    if checkpoint.completed < RunStep::FetchCapsule || !capsule_recorded {
+        let session: ClaimSession<'_, Executed> = ClaimSession::resume_executed(
+            client,
+            claim_id,
+            topic_id,
+            pb::ExecuteClaimV2Response {
+                certified: execution.certified,
+                e_value: execution.e_value,
+                canonical_output: unhex(&execution.canonical_output_hex)?,
+                ..Default::default()
+            },
+        )?;
+        let verified = session
+            .fetch_capsule(kernel_pubkey, None)
+            .await
+            .context("capsule verification failed")?;
+        fs::write(&capsule_path, &verified.capsule.capsule_bytes)?;
+        registry.record(
+            &checkpoint.claim_id_hex,
+            name,
+            ClaimEvent::CapsuleFetched {
+                capsule_sha256_hex: hex(&sha256(&verified.capsule.capsule_bytes)),
+                etl_index: verified.capsule.etl_index,
+                tree_size: verified.capsule.tree_size,
+                capsule_path: capsule_path.display().to_string(),
+            },
+        )?;
+        checkpoint.advance(RunStep::FetchCapsule, dir)?;
+    }
+
+    let record = registry
+        .get(&checkpoint.claim_id_hex)
+        .and_then(|record| record.capsule.clone())
+        .ok_or_else(|| anyhow!("claim registry has no capsule for a completed run"))?;
+    Ok(ClaimRunOutcome {
+        claim_id: checkpoint.claim_id_hex,
+        topic_id: checkpoint.topic_id_hex,
+        local_topic_id: checkpoint.local_topic_id_hex,
+        resumed_after,
+        certified: execution.certified,
+        e_value: execution.e_value,
+        capsule_sha256: record.capsule_sha256_hex,
+        etl_index: record.etl_index,
+        tree_size: record.tree_size,
+        capsule_path: record.capsule_path,
+    })
+}
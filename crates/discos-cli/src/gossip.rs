@@ -0,0 +1,120 @@
+//! `gossip`: exchange signed tree heads with other operators.
+//!
+//! An imported bundle is cross-checked head by head against the endpoint we
+//! talk to. Every conflict is written to its own signed fork-evidence file so
+//! it can be handed to the other operator or a third party.
+
+use std::path::{Path, PathBuf};
+
+use discos_client::gossip::{
+    cross_check, load_or_create_reporter_key, CrossCheck, ForkEvidence, SthBundle,
+};
+use discos_client::{pb, verify_signed_tree_head_response, DiscosClient, SignedTreeHead};
+use serde::Serialize;
+
+use crate::hex_codec::hex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeadStatus {
+    Consistent,
+    Ahead,
+    Forked,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImportedHead {
+    pub tree_size: u64,
+    pub root_hash: String,
+    pub status: HeadStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub evidence: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImportReport {
+    pub origin: String,
+    pub local_tree_size: u64,
+    pub local_root_hash: String,
+    pub heads: Vec<ImportedHead>,
+}
+
+impl ImportReport {
+    pub fn forks(&self) -> usize {
+        self.heads
+            .iter()
+            .filter(|head| head.status == HeadStatus::Forked)
+            .count()
+    }
+}
+
+/// Current head of the endpoint behind `client`, checked against
+/// `kernel_pubkey`.
+pub async fn current_head(
+    client: &mut DiscosClient,
+    kernel_pubkey: &[u8],
+) -> anyhow::Result<SignedTreeHead> {
+    let response = client
+        .get_signed_tree_head(pb::GetSignedTreeHeadRequest {})
+        .await?;
+    Ok(verify_signed_tree_head_response(&response, kernel_pubkey)?)
+}
+
+/// Cross-checks every head in `bundle` against `endpoint`. Conflicting heads
+/// are written to `evidence_dir` as fork evidence signed with the reporter
+/// key at `reporter_key`, which is created on first use.
+pub async fn import_bundle(
+    client: &mut DiscosClient,
+    endpoint: &str,
+    kernel_pubkey: &[u8],
+    bundle: &SthBundle,
+    evidence_dir: &Path,
+    reporter_key: &Path,
+) -> anyhow::Result<ImportReport> {
+    let remote_heads = bundle.verified_heads(kernel_pubkey)?;
+    let local = current_head(client, kernel_pubkey).await?;
+    let mut heads = Vec::with_capacity(remote_heads.len());
+    for remote in remote_heads {
+        let mut imported = ImportedHead {
+            tree_size: remote.tree_size,
+            root_hash: hex(&remote.root_hash),
+            status: HeadStatus::Consistent,
+            reason: None,
+            evidence: None,
+        };
+        match cross_check(client, &local, &remote).await? {
+            CrossCheck::Consistent => {}
+            CrossCheck::Ahead => imported.status = HeadStatus::Ahead,
+            CrossCheck::Forked { reason, proof } => {
+                let evidence = ForkEvidence::new(
+                    kernel_pubkey,
+                    endpoint,
+                    &local,
+                    &bundle.origin,
+                    &remote,
+                    &reason,
+                    proof.as_ref(),
+                    &load_or_create_reporter_key(reporter_key)?,
+                );
+                let path = evidence_dir.join(format!(
+                    "fork-{}-{}.json",
+                    remote.tree_size,
+                    hex(&remote.root_hash[..8])
+                ));
+                evidence.write(&path)?;
+                imported.status = HeadStatus::Forked;
+                imported.reason = Some(reason);
+                imported.evidence = Some(path);
+            }
+        }
+        heads.push(imported);
+    }
+    Ok(ImportReport {
+        origin: bundle.origin.clone(),
+        local_tree_size: local.tree_size,
+        local_root_hash: hex(&local.root_hash),
+        heads,
+    })
+}
@@ -0,0 +1,22 @@
+//! `hex_codec`: the one hex encoding used for ids, hashes and keys.
+//!
+//! Decoding is strict: only hex digits, an even count, and surrounding
+//! whitespace from command-line arguments ignored.
+
+use anyhow::anyhow;
+
+/// Lowercase hex.
+pub fn hex(bytes: &[u8]) -> String {
+    ::hex::encode(bytes)
+}
+
+pub fn unhex(s: &str) -> anyhow::Result<Vec<u8>> {
+    ::hex::decode(s.trim()).map_err(|e| anyhow!("invalid hex `{s}`: {e}"))
+}
+
+/// Decodes a 32-byte hash or key.
+pub fn unhex_32(s: &str) -> anyhow::Result<[u8; 32]> {
+    let bytes = unhex(s)?;
+    <[u8; 32]>::try_from(bytes)
+        .map_err(|bytes| anyhow!("expected 32-byte hex, got {} bytes", bytes.len()))
+}
@@ -1,2 +1,12 @@
 pub mod artifacts;
+pub mod bundle;
 pub mod capsule;
+pub mod claim_inputs;
+pub mod claim_run;
+pub mod gossip;
+pub mod hex_codec;
+pub mod monitor;
+pub mod receipts;
+pub mod registry;
+pub mod sth_store;
+pub mod structured_output;
@@ -17,59 +17,68 @@
     deny(clippy::unwrap_used, clippy::expect_used, clippy::panic)
 )]
 
-use std::{collections::HashMap, fs, path::Path, path::PathBuf};
+use std::{collections::BTreeMap, fs, path::Path, path::PathBuf, time::Duration};
 
 use anyhow::{anyhow, Context};
 use clap::{Parser, Subcommand};
-use discos_builder::{
-    build_restricted_wasm, manifest_hash, sha256, AlphaHIRManifest, CausalDSLManifest,
-    PhysHIRManifest,
-};
+use discos_builder::{manifest_hash, sha256};
 use discos_cli::artifacts::{build_calibration_artifact, run_paper_suite, write_json_file};
-use discos_cli::capsule::build_capsule_print_summary;
+use discos_cli::bundle::{export_bundle, EvidenceBundle, TrustAnchor};
+use discos_cli::capsule::{build_capsule_print_summary, extract_policy_oracle_receipts};
+use discos_cli::claim_inputs::{
+    ClaimInputPaths, ClaimInputs, ALPHA_HIR_FILE, CAUSAL_DSL_FILE, PHYS_HIR_FILE, WASM_FILE,
+};
+use discos_cli::claim_run::{run_claim, ClaimRunSpec};
+use discos_cli::gossip::{current_head, import_bundle};
+use discos_cli::hex_codec::{hex, unhex, unhex_32};
+use discos_cli::monitor::{Monitor, MonitorOptions, DEFAULT_INCLUSION_SAMPLES};
+use discos_cli::receipts::{verify_policy_oracle_receipts, LocalArtifacts};
+use discos_cli::registry::{ClaimEvent, ClaimRecord, ClaimSelector, Registry, ResolvedClaim};
+use discos_cli::sth_store::{verify_extends, HeadCheck, SthStore, STH_STORE_FILE};
+use discos_cli::structured_output::{
+    load_local_claim, local_output_schema_id, CapsuleRef, OutputReport,
+};
+use discos_client::checkpoint::{note_verifier_key, Checkpoint};
+use discos_client::gossip::{
+    fetch_sth_bundle, load_or_create_reporter_key, serve_sth_bundles, ForkEvidence, ForkProof,
+    SthBundle, STH_BUNDLE_HTTP_PATH,
+};
 use discos_client::{
-    pb, verify_consistency, verify_inclusion, verify_sth_signature, ConsistencyProof, DiscosClient,
-    InclusionProof, SignedTreeHead,
+    consistency_proof_from_pb, merkle_leaf_hash, pb, resolve_kernel_key, signed_tree_head_from_pb,
+    verify_consistency, verify_inclusion, verify_inclusion_proof_response,
+    verify_signed_tree_head_response, verify_sth_signature, ConsistencyProof, DiscosClient,
+    InclusionProof, KernelError, KernelKeyStore, KeyCheck, RevocationSet, RevocationWatcher,
+    SignedTreeHead, WASM_MODULE_ARTIFACT_KIND,
 };
 #[cfg(feature = "sim")]
 use discos_core::experiments::exp7b::{run_exp7b, Exp7bConfig};
 use discos_core::{
-    structured_claims::{
-        canonicalize_cbrn_claim, parse_cbrn_claim_json, validate_cbrn_claim, CbrnStructuredClaim,
-        ClaimKind, Decision, Domain, EnvelopeCheck, Profile, QuantityKind, QuantizedValue,
-        ReasonCode, Scale, SchemaVersion, SiUnit,
+    structured_profiles::ProfileRegistry,
+    topicid::{
+        canonicalize_output_schema_id, compute_topic_id, ClaimMetadata, CANONICAL_OUTPUT_SCHEMA_ID,
     },
-    topicid::{canonicalize_output_schema_id, compute_topic_id, ClaimMetadata, TopicSignals},
 };
 use evidenceos_core::safety_policy::{
     enforce_dual_use_policy, ClaimSafetyContext, DualUsePolicyConfig, EnforcementDecision,
 };
 use semver::{Version, VersionReq};
-use tokio_stream::StreamExt;
 use tonic::Code;
 use tracing_subscriber::EnvFilter;
 
-const CACHE_FILE_NAME: &str = "sth_cache.json";
 const DEFAULT_ORACLE_ID: &str = "default";
 const MAX_ORACLE_ID_LEN: usize = 128;
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
-struct CachedSth {
-    tree_size: u64,
-    root_hash: [u8; 32],
-}
-
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Default)]
-struct SthCache {
-    entries: HashMap<String, CachedSth>,
-}
-
 #[derive(Debug, Parser)]
 #[command(name = "discos")]
 #[command(about = "DiscOS untrusted userland client for EvidenceOS")]
 struct Args {
     #[arg(long, default_value = "http://127.0.0.1:50051")]
     endpoint: String,
+    /// Replicas to fail over to, in order, when --endpoint is unavailable. A
+    /// replica is only used once its tree head is verified against the kernel
+    /// key and shown consistent with the last verified head.
+    #[arg(long, env = "DISCOS_FAILOVER_ENDPOINTS", value_delimiter = ',')]
+    failover_endpoint: Vec<String>,
     #[arg(long)]
     tls_ca_cert_pem: Option<PathBuf>,
     #[arg(long)]
@@ -94,10 +103,24 @@ struct Args {
     keepalive_interval_ms: u64,
     #[arg(long, env = "DISCOS_KEEPALIVE_TIMEOUT_MS", default_value_t = discos_client::DEFAULT_KEEPALIVE_TIMEOUT_MS)]
     keepalive_timeout_ms: u64,
+    /// Attempts per RPC, including the first. Transient failures are retried
+    /// with exponential backoff; lifecycle calls carry idempotency keys.
+    #[arg(long, env = "DISCOS_RETRY_MAX_ATTEMPTS", default_value_t = discos_client::retry::DEFAULT_RETRY_MAX_ATTEMPTS)]
+    retry_max_attempts: u32,
     #[arg(long, default_value = "info")]
     log: String,
     #[arg(long, env = "DISCOS_KERNEL_PUBKEY_HEX", default_value = "")]
     kernel_pubkey_hex: String,
+    /// Per-endpoint kernel key pins consulted when --kernel-pubkey-hex is empty.
+    #[arg(
+        long,
+        env = "DISCOS_KEY_STORE",
+        default_value = ".discos/kernel_keys.json"
+    )]
+    key_store: PathBuf,
+    /// Pin the key served by an unpinned endpoint on first use.
+    #[arg(long, default_value_t = false)]
+    tofu: bool,
     #[arg(long, default_value_t = false)]
     allow_protocol_drift: bool,
     #[arg(long, default_value_t = true)]
@@ -129,8 +152,50 @@ enum Command {
         #[command(subcommand)]
         cmd: ClaimCommand,
     },
-    WatchRevocations,
+    Log {
+        #[command(subcommand)]
+        cmd: LogCommand,
+    },
+    WatchRevocations {
+        /// Verified revocation set, reloaded on start and updated per entry.
+        #[arg(long, default_value = ".discos/revocations.json")]
+        store: PathBuf,
+        #[arg(long, default_value_t = discos_client::revocations::DEFAULT_MAX_RECONNECTS)]
+        max_reconnects: u32,
+    },
     ServerInfo,
+    /// Exchange signed tree heads with other operators to detect split views.
+    Gossip {
+        #[command(subcommand)]
+        cmd: GossipCommand,
+    },
+    /// Export and verify offline evidence bundles for capsules.
+    Bundle {
+        #[command(subcommand)]
+        cmd: BundleCommand,
+    },
+    /// Poll the endpoint's signed tree head and alert on any log misbehaviour.
+    Monitor {
+        #[arg(long, default_value_t = 30_000)]
+        interval_ms: u64,
+        /// Holds history.jsonl (every accepted head) and alerts.jsonl.
+        #[arg(long, default_value = ".discos/monitor")]
+        dir: PathBuf,
+        /// Inclusion proofs checked per poll for newly logged local capsules.
+        #[arg(long, default_value_t = DEFAULT_INCLUSION_SAMPLES)]
+        inclusion_samples: usize,
+        /// Shell command run on alert, with DISCOS_ALERT_KIND, DISCOS_ALERT_MESSAGE,
+        /// DISCOS_ALERT_ENDPOINT, DISCOS_ALERT_FILE and DISCOS_ALERT_JSON set.
+        #[arg(long, env = "DISCOS_MONITOR_ALERT_HOOK")]
+        alert_hook: Option<String>,
+        /// Stop after this many successful polls; 0 runs until an alert.
+        #[arg(long, default_value_t = 0)]
+        max_polls: u64,
+    },
+    KernelKey {
+        #[command(subcommand)]
+        cmd: KernelKeyCommand,
+    },
     Scenario {
         #[command(subcommand)]
         cmd: ScenarioCommand,
@@ -142,6 +207,33 @@ enum Command {
     },
 }
 
+#[derive(Debug, Subcommand)]
+enum BundleCommand {
+    /// Fetch a claim's capsule and write it with all proofs needed offline.
+    Export {
+        #[command(flatten)]
+        claim: ClaimSelector,
+        #[arg(long)]
+        out: PathBuf,
+        /// Also prove consistency with the stored tree head of this size
+        /// (see `discos log sth`).
+        #[arg(long)]
+        reference_tree_size: Option<u64>,
+        /// Verified revocation set to snapshot into the bundle.
+        #[arg(long, default_value = ".discos/revocations.json")]
+        revocations: PathBuf,
+    },
+    /// Check a bundle without contacting any daemon. The kernel key is
+    /// trusted via --kernel-pubkey-hex, a key pinned for the bundle's origin,
+    /// or --kernel-fingerprint.
+    Verify {
+        bundle: PathBuf,
+        /// Hex SHA-256 of the trusted kernel public key.
+        #[arg(long)]
+        kernel_fingerprint: Option<String>,
+    },
+}
+
 #[derive(Debug, Subcommand)]
 enum NullspecCommand {
     Calibrate {
@@ -166,6 +258,108 @@ enum PaperSuiteCommand {
     },
 }
 
+#[derive(Debug, Subcommand)]
+enum KernelKeyCommand {
+    /// Pin the endpoint's kernel key; fetched via GetPublicKey when --pubkey-hex is omitted.
+    Pin {
+        #[arg(long)]
+        pubkey_hex: Option<String>,
+        #[arg(long)]
+        key_id_hex: Option<String>,
+        /// Replace an existing pin for a different key.
+        #[arg(long, default_value_t = false)]
+        replace: bool,
+    },
+    /// Announce the key_id (and optionally the exact key) the endpoint will rotate to.
+    Announce {
+        #[arg(long)]
+        key_id_hex: String,
+        #[arg(long)]
+        pubkey_hex: Option<String>,
+    },
+    Show,
+    Unpin,
+}
+
+#[derive(Debug, Subcommand)]
+enum GossipCommand {
+    /// Write the endpoint's current signed tree head as an STH bundle.
+    Export {
+        /// Defaults to stdout.
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Cross-check a bundle from a file or a gossip endpoint against --endpoint.
+    Import {
+        #[arg(
+            long,
+            required_unless_present = "from_url",
+            conflicts_with = "from_url"
+        )]
+        bundle: Option<PathBuf>,
+        /// Gossip endpoint, e.g. http://10.0.0.7:8787/sth-bundle.
+        #[arg(long)]
+        from_url: Option<String>,
+        #[arg(long, default_value = ".discos/forks")]
+        evidence_dir: PathBuf,
+        /// Hex ed25519 seed that signs fork evidence; created on first use.
+        #[arg(
+            long,
+            env = "DISCOS_GOSSIP_REPORTER_KEY",
+            default_value = ".discos/gossip_reporter.key"
+        )]
+        reporter_key: PathBuf,
+    },
+    /// Serve the endpoint's current signed tree head over HTTP.
+    Serve {
+        #[arg(long, default_value = "127.0.0.1:8787")]
+        listen: String,
+    },
+    /// Check a fork-evidence file offline against the kernel key.
+    VerifyEvidence { evidence: PathBuf },
+}
+
+#[derive(Debug, Subcommand)]
+enum LogCommand {
+    Sth,
+    Inclusion {
+        #[command(flatten)]
+        claim: ClaimSelector,
+    },
+    Consistency {
+        #[arg(long)]
+        from: u64,
+        #[arg(long)]
+        to: u64,
+        /// Root of the `--from` tree; defaults to the cached or current signed head of that size.
+        #[arg(long)]
+        from_root_hex: Option<String>,
+        /// Root of the `--to` tree; defaults to the cached or current signed head of that size.
+        #[arg(long)]
+        to_root_hex: Option<String>,
+    },
+    /// Write the current signed tree head as a signed-note checkpoint.
+    Checkpoint {
+        /// Checkpoint origin and kernel key name; defaults to the endpoint without its scheme.
+        #[arg(long)]
+        origin: Option<String>,
+        /// Hex ed25519 seed that also signs the note text, for witnesses that
+        /// only check Ed25519 notes; created on first use.
+        #[arg(long)]
+        note_key: Option<PathBuf>,
+        /// Defaults to stdout.
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Check a checkpoint's kernel signature and reconcile it with the stored heads.
+    ImportCheckpoint {
+        checkpoint: PathBuf,
+        /// Also require a note signature by this verifier key (`name+id+key`).
+        #[arg(long)]
+        note_vkey: Option<String>,
+    },
+}
+
 #[derive(Debug, Subcommand)]
 enum ScenarioCommand {
     List,
@@ -209,34 +403,87 @@ enum ClaimCommand {
         access_credit: u64,
         #[arg(long, default_value = DEFAULT_ORACLE_ID)]
         oracle_id: String,
+        #[command(flatten)]
+        inputs: Box<ClaimInputPaths>,
     },
     Commit {
+        #[command(flatten)]
+        claim: ClaimSelector,
+        /// Defaults to the claim's local `wasm.bin`.
         #[arg(long)]
-        claim_id: String,
-        #[arg(long)]
-        wasm: PathBuf,
+        wasm: Option<PathBuf>,
+        /// Defaults to the claim's local alpha-HIR, phys-HIR and causal DSL manifests.
         #[arg(long)]
         manifests: Vec<PathBuf>,
     },
-    Freeze {
+    CommitWasm {
+        #[command(flatten)]
+        claim: ClaimSelector,
+        /// Defaults to the claim's local `wasm.bin`.
         #[arg(long)]
-        claim_id: String,
+        wasm: Option<PathBuf>,
+        /// Hash of the `wasm_module` artifact sent with `claim commit`; defaults to the file hash.
+        #[arg(long)]
+        artifact_hash_hex: Option<String>,
+    },
+    Freeze {
+        #[command(flatten)]
+        claim: ClaimSelector,
+    },
+    Seal {
+        #[command(flatten)]
+        claim: ClaimSelector,
     },
     Execute {
-        #[arg(long)]
-        claim_id: String,
+        #[command(flatten)]
+        claim: ClaimSelector,
     },
     FetchCapsule {
-        #[arg(long)]
-        claim_id: String,
+        #[command(flatten)]
+        claim: ClaimSelector,
         #[arg(long, default_value_t = false)]
         verify_etl: bool,
         #[arg(long, default_value_t = false)]
         print_capsule_json: bool,
+        /// Check the capsule's policy-oracle receipts against the claim's
+        /// local wasm.bin and manifests.
+        #[arg(long, default_value_t = false)]
+        verify_receipts: bool,
+        /// Verified revocation set to check the claim against; the output's
+        /// `validity` then says whether the claim has been revoked.
+        #[arg(long)]
+        revocations: Option<PathBuf>,
     },
     ValidateStructured {
         #[arg(long)]
         input: PathBuf,
+        /// Output schema whose structured claim profile checks the input.
+        #[arg(long, default_value = CANONICAL_OUTPUT_SCHEMA_ID)]
+        output_schema_id: String,
+    },
+    Revoke {
+        #[command(flatten)]
+        claim: ClaimSelector,
+        #[arg(long)]
+        reason: String,
+    },
+    /// Runs create → commit → freeze → seal → execute → fetch-capsule → verify
+    /// from a TOML spec, resuming from the claim directory's checkpoint.
+    Run {
+        #[arg(long)]
+        spec: PathBuf,
+    },
+    /// Lists claims recorded in the local registry.
+    List,
+    /// Shows a claim's lifecycle stage and the next step to resume it.
+    Status {
+        #[command(flatten)]
+        claim: ClaimSelector,
+    },
+    /// Shows a claim's registry record and journal history.
+    Show {
+        #[command(flatten)]
+        claim: ClaimSelector,
     },
 }
 
@@ -268,46 +515,101 @@ fn dual_use_policy_from_args(args: &Args) -> DualUsePolicyConfig {
     }
 }
 
-fn hex_encode(bytes: &[u8]) -> String {
-    const HEX: &[u8; 16] = b"0123456789abcdef";
-    let mut s = String::with_capacity(bytes.len() * 2);
-    for b in bytes {
-        s.push(HEX[(b >> 4) as usize] as char);
-        s.push(HEX[(b & 0x0f) as usize] as char);
+/// Returns the lane the claim must use, or an error if policy rejects it.
+/// `domain` comes from the structured claim profile of `output_schema_id`.
+fn apply_dual_use_policy(
+    policy: &DualUsePolicyConfig,
+    domain: &str,
+    lane: &str,
+    output_schema_id: &str,
+) -> anyhow::Result<String> {
+    match enforce_dual_use_policy(
+        policy,
+        &ClaimSafetyContext {
+            domain,
+            lane,
+            output_schema_id,
+            requests_free_text_output: false,
+        },
+    ) {
+        EnforcementDecision::Allow => Ok(lane.to_string()),
+        EnforcementDecision::ForceHeavyLane { required_lane } => Ok(required_lane.to_string()),
+        EnforcementDecision::Reject { reason } => Err(anyhow!(
+            "dual-use policy rejected claim create request: {reason}"
+        )),
     }
-    s
 }
 
-fn hex_decode_32(s: &str) -> anyhow::Result<[u8; 32]> {
-    let mut out = [0u8; 32];
-    let s = s.trim();
-    anyhow::ensure!(s.len() == 64, "expected 64-char hex hash");
-    for i in 0..32 {
-        out[i] = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).context("invalid hex")?;
-    }
-    Ok(out)
+fn claim_dir(claim_id: &str) -> PathBuf {
+    PathBuf::from(".discos").join("claims").join(claim_id)
+}
+
+/// Decodes a claim's canonical output and checks it against `capsule_bytes`
+/// (when given and the topic is known) and the claim's local structured claim.
+fn structured_output_report(
+    registry: &Registry,
+    claim: &ResolvedClaim,
+    canonical_output: &[u8],
+    capsule_bytes: Option<&[u8]>,
+) -> anyhow::Result<OutputReport> {
+    let claim_id = unhex(&claim.claim_id_hex)?;
+    let topic_id = registry
+        .get(&claim.claim_id_hex)
+        .and_then(|record| record.topic_id_hex.as_deref())
+        .map(unhex)
+        .transpose()?;
+    let capsule = capsule_bytes
+        .zip(topic_id.as_deref())
+        .map(|(capsule_bytes, topic_id)| CapsuleRef {
+            capsule_bytes,
+            claim_id: &claim_id,
+            topic_id,
+        });
+    let dir = claim_dir(claim.dir_name());
+    // Claims created elsewhere have no alpha-HIR here; assume CBRN-SC.
+    let output_schema_id =
+        local_output_schema_id(&dir)?.unwrap_or_else(|| CANONICAL_OUTPUT_SCHEMA_ID.to_string());
+    let profiles = ProfileRegistry::builtin();
+    let profile = profiles
+        .resolve(&output_schema_id)
+        .map_err(|e| anyhow!(e))?;
+    let local = load_local_claim(&dir, profile)?;
+    Ok(OutputReport::build(
+        profile,
+        canonical_output,
+        capsule,
+        local.as_ref(),
+    ))
 }
 
-fn hex_decode_bytes(s: &str) -> anyhow::Result<Vec<u8>> {
-    let s = s.trim();
-    anyhow::ensure!(s.len().is_multiple_of(2), "hex length must be even");
-    let mut out = Vec::with_capacity(s.len() / 2);
-    for i in (0..s.len()).step_by(2) {
-        out.push(u8::from_str_radix(&s[i..i + 2], 16).context("invalid hex")?);
+/// Explicit path if given, otherwise the named claim's file in its claim dir.
+fn local_claim_file(
+    explicit: Option<&PathBuf>,
+    claim: &ResolvedClaim,
+    file: &str,
+) -> anyhow::Result<PathBuf> {
+    match (explicit, &claim.claim_name) {
+        (Some(path), _) => Ok(path.clone()),
+        (None, Some(name)) => Ok(claim_dir(name).join(file)),
+        (None, None) => Err(anyhow!(
+            "claim {} has no local name; pass the {file} path explicitly",
+            claim.claim_id_hex
+        )),
     }
-    Ok(out)
 }
 
-fn claim_dir(claim_id: &str) -> PathBuf {
-    PathBuf::from(".discos").join("claims").join(claim_id)
+fn open_claim_registry() -> anyhow::Result<Registry> {
+    Registry::open(&PathBuf::from(".discos").join("registry"))
 }
 
-fn cache_key(endpoint: &str, kernel_pubkey_hex: &str) -> String {
-    if kernel_pubkey_hex.is_empty() {
-        endpoint.to_owned()
-    } else {
-        format!("{endpoint}#{kernel_pubkey_hex}")
-    }
+fn claim_status_json(record: &ClaimRecord) -> serde_json::Value {
+    serde_json::json!({
+        "claim_name": record.claim_name,
+        "claim_id": record.claim_id_hex,
+        "topic_id": record.topic_id_hex,
+        "stage": record.stage,
+        "next_step": record.stage.next_step(),
+    })
 }
 
 fn transport_is_secure(endpoint: &str, tls_ca_cert_pem: Option<&Path>) -> bool {
@@ -389,7 +691,7 @@ async fn connect_client(args: &Args) -> anyhow::Result<DiscosClient> {
         (Some(token), None, None) => Some(discos_client::ClientAuth::BearerToken(token.clone())),
         (None, Some(key_id), Some(secret_hex)) => Some(discos_client::ClientAuth::HmacSha256 {
             key_id: key_id.clone(),
-            secret: hex_decode_bytes(secret_hex).context("invalid --hmac-secret-hex")?,
+            secret: unhex(secret_hex).context("invalid --hmac-secret-hex")?,
         }),
         (None, Some(_), None) | (None, None, Some(_)) => {
             anyhow::bail!("hmac auth requires both --hmac-key-id and --hmac-secret-hex")
@@ -398,14 +700,44 @@ async fn connect_client(args: &Args) -> anyhow::Result<DiscosClient> {
         (None, None, None) => None,
     };
 
+    let kernel_pubkey = if args.failover_endpoint.is_empty() {
+        None
+    } else {
+        known_kernel_pubkey(args)?
+    };
+    // Replicas have to be consistent with what this machine already verified,
+    // not just with whatever the first healthy one serves.
+    let verified_head = match &kernel_pubkey {
+        Some(pubkey) => {
+            let store = open_sth_store()?;
+            let mut latest: Option<SignedTreeHead> = None;
+            for endpoint in std::iter::once(&args.endpoint).chain(&args.failover_endpoint) {
+                if let Some(head) = store.latest(endpoint, pubkey)? {
+                    if latest
+                        .as_ref()
+                        .is_none_or(|current| head.tree_size > current.tree_size)
+                    {
+                        latest = Some(head);
+                    }
+                }
+            }
+            latest
+        }
+        None => None,
+    };
+
     DiscosClient::connect_with_config(discos_client::ClientConnectConfig {
         endpoint: args.endpoint.clone(),
+        failover_endpoints: args.failover_endpoint.clone(),
+        kernel_pubkey,
+        verified_head,
         tls,
         auth,
         connect_timeout_ms: args.connect_timeout_ms,
         request_timeout_ms: args.request_timeout_ms,
         keepalive_interval_ms: args.keepalive_interval_ms,
         keepalive_timeout_ms: args.keepalive_timeout_ms,
+        retry: discos_client::RetryPolicy::with_max_attempts(args.retry_max_attempts),
     })
     .await
     .map_err(|e| anyhow!(e))
@@ -421,47 +753,150 @@ fn cache_dir() -> PathBuf {
     }
 }
 
-fn cache_file_path() -> PathBuf {
-    cache_dir().join(CACHE_FILE_NAME)
+fn open_sth_store() -> anyhow::Result<SthStore> {
+    SthStore::open(&cache_dir().join(STH_STORE_FILE))
 }
 
-fn load_sth_cache(path: &Path) -> anyhow::Result<SthCache> {
-    if !path.exists() {
-        return Ok(SthCache::default());
-    }
-    let bytes = fs::read(path).with_context(|| format!("read cache {}", path.display()))?;
-    serde_json::from_slice(&bytes).context("parse sth cache json")
+/// Checks a verified head against the STH store, then records it.
+async fn check_and_record_head(
+    store: &mut SthStore,
+    client: &mut DiscosClient,
+    endpoint: &str,
+    kernel_pubkey: &[u8],
+    head: &SignedTreeHead,
+) -> anyhow::Result<HeadCheck> {
+    let check = store.check(client, endpoint, kernel_pubkey, head).await?;
+    store.record(endpoint, kernel_pubkey, head)?;
+    Ok(check)
 }
 
-fn persist_sth_cache(path: &Path, cache: &SthCache) -> anyhow::Result<()> {
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)
-            .with_context(|| format!("create cache dir {}", parent.display()))?;
+fn wasm_hash_for_bytes(wasm_bytes: &[u8]) -> [u8; 32] {
+    sha256(wasm_bytes)
+}
+
+/// Kernel key for `args.endpoint` that is known without asking the daemon:
+/// `--kernel-pubkey-hex` or the pinned key.
+fn known_kernel_pubkey(args: &Args) -> anyhow::Result<Option<Vec<u8>>> {
+    if !args.kernel_pubkey_hex.is_empty() {
+        return Ok(Some(
+            unhex(&args.kernel_pubkey_hex).context("invalid --kernel-pubkey-hex")?,
+        ));
     }
-    let data = serde_json::to_vec_pretty(cache)?;
-    fs::write(path, data).with_context(|| format!("write cache {}", path.display()))?;
-    Ok(())
+    let store = KernelKeyStore::open(&args.key_store)?;
+    Ok(match store.get(&args.endpoint) {
+        Some(pin) => Some(pin.pubkey()?),
+        None => None,
+    })
 }
 
-fn wasm_hash_for_bytes(wasm_bytes: &[u8]) -> [u8; 32] {
-    sha256(wasm_bytes)
+/// Kernel key for `args.endpoint`, also handed to the client so failover
+/// can verify replica tree heads.
+async fn resolve_kernel_pubkey(
+    args: &Args,
+    client: &mut DiscosClient,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let pubkey = resolve_endpoint_kernel_pubkey(args, client).await?;
+    if let Some(pubkey) = &pubkey {
+        client.set_kernel_pubkey(pubkey.clone());
+    }
+    Ok(pubkey)
 }
 
-fn verify_consistency_with_cache(
-    cache: &mut SthCache,
-    key: &str,
-    new_sth: CachedSth,
-    consistency: &ConsistencyProof,
-) -> anyhow::Result<Option<bool>> {
-    let consistency_ok = if let Some(old_sth) = cache.entries.get(key) {
-        let ok = verify_consistency(old_sth.root_hash, new_sth.root_hash, consistency);
-        anyhow::ensure!(ok, "consistency proof verification failed for cached STH");
-        Some(ok)
-    } else {
-        None
+/// `--kernel-pubkey-hex` when given (it must agree with any pin), otherwise
+/// the key store, pinning on first use under `--tofu`.
+async fn resolve_endpoint_kernel_pubkey(
+    args: &Args,
+    client: &mut DiscosClient,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let mut store = KernelKeyStore::open(&args.key_store)?;
+    if !args.kernel_pubkey_hex.is_empty() {
+        let pubkey = unhex(&args.kernel_pubkey_hex).context("invalid --kernel-pubkey-hex")?;
+        if let Some(pin) = store.get(&args.endpoint) {
+            anyhow::ensure!(
+                pin.pubkey()? == pubkey,
+                "--kernel-pubkey-hex conflicts with the key pinned for {} (key_id {})",
+                args.endpoint,
+                pin.key_id_hex
+            );
+        }
+        return Ok(Some(pubkey));
+    }
+    let Some((pubkey, check)) =
+        resolve_kernel_key(client, &mut store, &args.endpoint, args.tofu).await?
+    else {
+        return Ok(None);
     };
-    cache.entries.insert(key.to_owned(), new_sth);
-    Ok(consistency_ok)
+    match check {
+        KeyCheck::Pinned => {}
+        KeyCheck::FirstUse => eprintln!(
+            "WARNING: trusting kernel key {} for {} on first use; it is now pinned in {}.",
+            hex(&pubkey),
+            args.endpoint,
+            store.path().display()
+        ),
+        KeyCheck::Rotated {
+            previous_key_id_hex,
+        } => eprintln!(
+            "kernel key for {} rotated from key_id {} as announced",
+            args.endpoint, previous_key_id_hex
+        ),
+    }
+    Ok(Some(pubkey))
+}
+
+async fn required_kernel_pubkey(args: &Args, client: &mut DiscosClient) -> anyhow::Result<Vec<u8>> {
+    resolve_kernel_pubkey(args, client).await?.ok_or_else(|| {
+        anyhow!(
+            "--kernel-pubkey-hex, a pinned key (discos kernel-key pin) or --tofu is required to verify signed tree heads"
+        )
+    })
+}
+
+async fn fetch_consistency_proof(
+    client: &mut DiscosClient,
+    old_tree_size: u64,
+    new_tree_size: u64,
+) -> anyhow::Result<ConsistencyProof> {
+    let resp = client
+        .get_consistency_proof(pb::GetConsistencyProofRequest {
+            old_tree_size,
+            new_tree_size,
+        })
+        .await?;
+    let proof = consistency_proof_from_pb(
+        resp.consistency_proof
+            .as_ref()
+            .context("missing consistency proof")?,
+    )?;
+    anyhow::ensure!(
+        proof.old_tree_size == old_tree_size && proof.new_tree_size == new_tree_size,
+        "consistency proof covers {}..{}, requested {old_tree_size}..{new_tree_size}",
+        proof.old_tree_size,
+        proof.new_tree_size
+    );
+    Ok(proof)
+}
+
+fn resolve_log_root(
+    tree_size: u64,
+    explicit_hex: Option<&str>,
+    current: &SignedTreeHead,
+    store: &SthStore,
+    endpoint: &str,
+    kernel_pubkey: &[u8],
+) -> anyhow::Result<[u8; 32]> {
+    if let Some(hex) = explicit_hex {
+        return unhex_32(hex);
+    }
+    if current.tree_size == tree_size {
+        return Ok(current.root_hash);
+    }
+    match store.closest_at_or_before(endpoint, kernel_pubkey, tree_size)? {
+        Some(stored) if stored.tree_size == tree_size => Ok(stored.root_hash),
+        _ => Err(anyhow!(
+            "no verified root known for tree size {tree_size}; pass it with --from-root-hex/--to-root-hex"
+        )),
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -527,20 +962,18 @@ struct ScenarioRun {
     exchanges: Vec<ScenarioExchange>,
 }
 
-fn classify_from_status(code: Code, message: &str) -> String {
-    let uppercase = message.to_ascii_uppercase();
-    if code == Code::FailedPrecondition
-        || uppercase.contains("ESCALATE")
-        || uppercase.contains("FROZEN")
+fn classify_from_status(err: &KernelError) -> String {
+    let reason = err.reason().unwrap_or_default();
+    if err.code == Code::FailedPrecondition || reason.starts_with("ESCALATE") || reason == "FROZEN"
     {
         return "REQUIRE_HUMAN".to_string();
     }
-    if code == Code::ResourceExhausted || uppercase.contains("THROTTLE") {
+    if err.code == Code::ResourceExhausted || reason.starts_with("THROTTLE") {
         return "DOWNGRADE".to_string();
     }
-    if code == Code::PermissionDenied
-        || code == Code::Unauthenticated
-        || code == Code::InvalidArgument
+    if err.code == Code::PermissionDenied
+        || err.code == Code::Unauthenticated
+        || err.code == Code::InvalidArgument
     {
         return "DENY".to_string();
     }
@@ -702,11 +1135,14 @@ async fn run_scenario_live(
                     Code::Ok.to_string(),
                     "ALLOW".to_string(),
                 ),
-                Err(status) => (
-                    classify_from_status(status.code(), status.message()),
-                    status.code().to_string(),
-                    status.message().to_string(),
-                ),
+                Err(status) => {
+                    let err = KernelError::from(status);
+                    (
+                        classify_from_status(&err),
+                        err.code.to_string(),
+                        err.message,
+                    )
+                }
             };
 
             if response_rank(&state) > response_rank(&final_state) {
@@ -722,7 +1158,7 @@ async fn run_scenario_live(
                 session_id: session_id.clone(),
                 topic: topic.clone(),
                 agent_id,
-                claim_id_hex: Some(hex_encode(&claim_id)),
+                claim_id_hex: Some(hex(&claim_id)),
                 response_state: state,
                 grpc_code,
                 daemon_message,
@@ -730,7 +1166,7 @@ async fn run_scenario_live(
         }
     }
 
-    let fingerprint = hex_encode(&sha256(&serde_json::to_vec(&exchanges)?));
+    let fingerprint = hex(&sha256(&serde_json::to_vec(&exchanges)?));
     Ok(ScenarioRun {
         scenario_id: spec.id.clone(),
         description: spec.description.clone(),
@@ -962,6 +1398,275 @@ async fn main() -> anyhow::Result<()> {
                 })
             );
         }
+        Command::Gossip {
+            cmd: GossipCommand::VerifyEvidence { ref evidence },
+        } => {
+            let pubkey = known_kernel_pubkey(&args)?.ok_or_else(|| {
+                anyhow!("--kernel-pubkey-hex or a pinned key is required to verify fork evidence")
+            })?;
+            let evidence = ForkEvidence::read(evidence)?;
+            let proof = evidence.verify(&pubkey)?;
+            if proof == ForkProof::ReporterClaim {
+                eprintln!(
+                    "WARNING: the heads differ in size; the failed consistency proof is not kernel-signed, so this fork rests on the reporter's word."
+                );
+            }
+            println!(
+                "{}",
+                serde_json::json!({
+                    "ok": true,
+                    "proof": proof,
+                    "reason": evidence.reason,
+                    "local_origin": evidence.local_origin,
+                    "remote_origin": evidence.remote_origin,
+                    "reporter_pubkey_hex": evidence.reporter_pubkey_hex,
+                })
+            );
+        }
+        Command::Bundle {
+            cmd:
+                BundleCommand::Verify {
+                    ref bundle,
+                    ref kernel_fingerprint,
+                },
+        } => {
+            let bundle = EvidenceBundle::read(bundle)?;
+            let pinned = KernelKeyStore::open(&args.key_store)?
+                .get(&bundle.origin)
+                .map(|pin| pin.pubkey())
+                .transpose()?;
+            let anchor = if !args.kernel_pubkey_hex.is_empty() {
+                TrustAnchor::KernelKey(
+                    unhex(&args.kernel_pubkey_hex).context("invalid --kernel-pubkey-hex")?,
+                )
+            } else if let Some(pubkey) = pinned {
+                TrustAnchor::KernelKey(pubkey)
+            } else if let Some(fingerprint) = kernel_fingerprint {
+                TrustAnchor::Fingerprint(
+                    unhex_32(fingerprint).context("invalid --kernel-fingerprint")?,
+                )
+            } else {
+                anyhow::bail!(
+                    "no trusted kernel key for {}; pass --kernel-pubkey-hex or --kernel-fingerprint, or pin the key",
+                    bundle.origin
+                );
+            };
+            let report = bundle.verify(&anchor)?;
+            println!("{}", serde_json::json!({"ok": true, "report": report}));
+        }
+        Command::Bundle {
+            cmd:
+                BundleCommand::Export {
+                    ref claim,
+                    ref out,
+                    reference_tree_size,
+                    ref revocations,
+                },
+        } => {
+            let claim = open_claim_registry()?.resolve(claim)?;
+            let mut client = connect_client(&args).await?;
+            assert_server_compatibility(&mut client, args.allow_protocol_drift).await?;
+            let pubkey = required_kernel_pubkey(&args, &mut client).await?;
+            let revocations = RevocationSet::load_verified(revocations, &pubkey)?;
+            let reference = match reference_tree_size {
+                Some(size) => Some(
+                    open_sth_store()?
+                        .closest_at_or_before(&args.endpoint, &pubkey, size)?
+                        .filter(|head| head.tree_size == size)
+                        .ok_or_else(|| {
+                            anyhow!("no stored tree head of size {size} for {}", args.endpoint)
+                        })?,
+                ),
+                None => None,
+            };
+            let bundle = export_bundle(
+                &mut client,
+                &args.endpoint,
+                &pubkey,
+                &unhex(&claim.claim_id_hex)?,
+                reference.as_ref(),
+                &revocations,
+            )
+            .await?;
+            bundle.write(out)?;
+            println!(
+                "{}",
+                serde_json::json!({
+                    "bundle": out,
+                    "claim_id": bundle.claim_id_hex,
+                    "tree_size": bundle.sth.tree_size,
+                    "consistency_reference_tree_size": reference.map(|head| head.tree_size),
+                    "revocations": bundle.revocations.entries.len(),
+                })
+            );
+        }
+        Command::Gossip { ref cmd } => {
+            let mut client = connect_client(&args).await?;
+            assert_server_compatibility(&mut client, args.allow_protocol_drift).await?;
+            let pubkey = required_kernel_pubkey(&args, &mut client).await?;
+            match cmd {
+                GossipCommand::Export { out } => {
+                    let head = current_head(&mut client, &pubkey).await?;
+                    let bundle =
+                        SthBundle::new(&args.endpoint, &pubkey, std::slice::from_ref(&head));
+                    match out {
+                        Some(path) => {
+                            bundle.write(path)?;
+                            println!(
+                                "{}",
+                                serde_json::json!({"bundle": path, "tree_size": head.tree_size})
+                            );
+                        }
+                        None => println!("{}", String::from_utf8_lossy(&bundle.to_json()?)),
+                    }
+                }
+                GossipCommand::Import {
+                    bundle,
+                    from_url,
+                    evidence_dir,
+                    reporter_key,
+                } => {
+                    let bundle = match (bundle, from_url) {
+                        (Some(path), _) => SthBundle::read(path)?,
+                        (None, Some(url)) => fetch_sth_bundle(url).await?,
+                        (None, None) => anyhow::bail!("--bundle or --from-url is required"),
+                    };
+                    let report = import_bundle(
+                        &mut client,
+                        &args.endpoint,
+                        &pubkey,
+                        &bundle,
+                        evidence_dir,
+                        reporter_key,
+                    )
+                    .await?;
+                    println!("{}", serde_json::to_value(&report)?);
+                    anyhow::ensure!(
+                        report.forks() == 0,
+                        "{} imported tree head(s) from {} cannot be reconciled with {}; fork evidence written to {}",
+                        report.forks(),
+                        report.origin,
+                        args.endpoint,
+                        evidence_dir.display()
+                    );
+                }
+                GossipCommand::Serve { listen } => {
+                    let listener = tokio::net::TcpListener::bind(listen)
+                        .await
+                        .with_context(|| format!("bind gossip listener {listen}"))?;
+                    eprintln!(
+                        "serving STH bundles at http://{}{}",
+                        listener.local_addr()?,
+                        STH_BUNDLE_HTTP_PATH
+                    );
+                    serve_sth_bundles(listener, || {
+                        let mut client = client.clone();
+                        let pubkey = pubkey.clone();
+                        let origin = args.endpoint.clone();
+                        async move {
+                            let head = verify_signed_tree_head_response(
+                                &client
+                                    .get_signed_tree_head(pb::GetSignedTreeHeadRequest {})
+                                    .await?,
+                                &pubkey,
+                            )?;
+                            Ok(SthBundle::new(&origin, &pubkey, &[head]))
+                        }
+                    })
+                    .await?;
+                }
+                GossipCommand::VerifyEvidence { .. } => unreachable!("handled offline above"),
+            }
+        }
+        Command::Monitor {
+            interval_ms,
+            ref dir,
+            inclusion_samples,
+            ref alert_hook,
+            max_polls,
+        } => {
+            let mut client = connect_client(&args).await?;
+            assert_server_compatibility(&mut client, args.allow_protocol_drift).await?;
+            let kernel_pubkey = required_kernel_pubkey(&args, &mut client).await?;
+            let mut monitor = Monitor::open(MonitorOptions {
+                endpoint: args.endpoint.clone(),
+                kernel_pubkey,
+                dir: dir.clone(),
+                registry: Some(PathBuf::from(".discos").join("registry")),
+                inclusion_samples,
+                hook: alert_hook.clone(),
+            })?;
+            let mut polls = 0;
+            loop {
+                match monitor.poll(&mut client).await {
+                    Ok(report) => println!("{}", serde_json::to_value(&report)?),
+                    Err(alert) => {
+                        println!("{}", serde_json::to_value(&alert)?);
+                        monitor.raise(&alert)?;
+                        anyhow::bail!("monitor alert ({:?}): {}", alert.kind, alert.message);
+                    }
+                }
+                polls += 1;
+                if max_polls != 0 && polls >= max_polls {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(interval_ms)).await;
+            }
+        }
+        Command::KernelKey { ref cmd } => {
+            let mut store = KernelKeyStore::open(&args.key_store)?;
+            match cmd {
+                KernelKeyCommand::Pin {
+                    pubkey_hex,
+                    key_id_hex,
+                    replace,
+                } => {
+                    let (pubkey, key_id) = match pubkey_hex {
+                        Some(pubkey_hex) => (unhex(pubkey_hex)?, Vec::new()),
+                        None => {
+                            let mut client = connect_client(&args).await?;
+                            let served = client.get_public_key().await?;
+                            (served.pubkey, served.key_id)
+                        }
+                    };
+                    let key_id = match key_id_hex {
+                        Some(key_id_hex) => unhex(key_id_hex)?,
+                        None => key_id,
+                    };
+                    let pin = store.pin(&args.endpoint, &pubkey, &key_id, *replace)?;
+                    println!(
+                        "{}",
+                        serde_json::json!({"endpoint": args.endpoint, "pin": pin})
+                    );
+                }
+                KernelKeyCommand::Announce {
+                    key_id_hex,
+                    pubkey_hex,
+                } => {
+                    let pubkey = pubkey_hex.as_deref().map(unhex).transpose()?;
+                    let pin = store.announce_rotation(
+                        &args.endpoint,
+                        &unhex(key_id_hex)?,
+                        pubkey.as_deref(),
+                    )?;
+                    println!(
+                        "{}",
+                        serde_json::json!({"endpoint": args.endpoint, "pin": pin})
+                    );
+                }
+                KernelKeyCommand::Show => {
+                    let pins = store.pins().collect::<BTreeMap<_, _>>();
+                    println!("{}", serde_json::json!({ "pins": pins }));
+                }
+                KernelKeyCommand::Unpin => {
+                    let removed = store.unpin(&args.endpoint)?;
+                    println!(
+                        "{}",
+                        serde_json::json!({"endpoint": args.endpoint, "removed": removed})
+                    );
+                }
+            }
+        }
         Command::Scenario { cmd } => match cmd {
             ScenarioCommand::List => {
                 let specs = load_scenarios(Path::new("docs/scenarios"))?;
@@ -1021,60 +1726,23 @@ async fn main() -> anyhow::Result<()> {
                 oracle_num_symbols,
                 access_credit,
                 oracle_id,
+                inputs,
             } => {
                 validate_oracle_id(&oracle_id)?;
                 let output_schema_id = canonicalize_output_schema_id(&output_schema_id);
-                let mut lane = lane.clone();
-                match enforce_dual_use_policy(
+                let profiles = ProfileRegistry::builtin();
+                let profile = profiles
+                    .resolve(&output_schema_id)
+                    .map_err(|e| anyhow!(e))?;
+                let lane = apply_dual_use_policy(
                     &dual_use_policy,
-                    &ClaimSafetyContext {
-                        domain: "CBRN",
-                        lane: &lane,
-                        output_schema_id: &output_schema_id,
-                        requests_free_text_output: false,
-                    },
-                ) {
-                    EnforcementDecision::Allow => {}
-                    EnforcementDecision::ForceHeavyLane { required_lane } => {
-                        lane = required_lane.to_string();
-                    }
-                    EnforcementDecision::Reject { reason } => {
-                        return Err(anyhow!(
-                            "dual-use policy rejected claim create request: {reason}"
-                        ));
-                    }
-                }
-                let dir = claim_dir(&claim_name);
-                fs::create_dir_all(&dir)?;
-
-                let wasm = build_restricted_wasm();
-                fs::write(dir.join("wasm.bin"), &wasm.wasm_bytes)?;
-                let alpha = AlphaHIRManifest {
-                    plan_id: claim_name.clone(),
-                    code_hash_hex: hex_encode(&wasm.code_hash),
-                    oracle_kinds: vec!["oracle_query".into()],
-                    output_schema_id: output_schema_id.clone(),
-                    nullspec_id: "nullspec.v1".into(),
-                };
-                let phys = PhysHIRManifest {
-                    physical_signature_hash: hex_encode(&manifest_hash(&alpha)?),
-                    envelope_ids: vec!["env/default".into()],
-                };
-                let causal = CausalDSLManifest {
-                    dag_hash: hex_encode(&manifest_hash(&phys)?),
-                    adjustment_sets: vec![vec!["baseline".into()]],
-                };
-                fs::write(
-                    dir.join("alpha_hir.json"),
-                    serde_json::to_vec_pretty(&alpha)?,
-                )?;
-                fs::write(dir.join("phys_hir.json"), serde_json::to_vec_pretty(&phys)?)?;
-                fs::write(
-                    dir.join("causal_dsl.json"),
-                    serde_json::to_vec_pretty(&causal)?,
+                    profile.domain(),
+                    lane,
+                    &output_schema_id,
                 )?;
+                let inputs = ClaimInputs::load_with_profile(claim_name, profile, inputs)?;
+                inputs.write_to(&claim_dir(claim_name))?;
 
-                let phys_hash = hex_decode_32(&phys.physical_signature_hash)?;
                 let topic = compute_topic_id(
                     &ClaimMetadata {
                         lane: lane.clone(),
@@ -1082,41 +1750,9 @@ async fn main() -> anyhow::Result<()> {
                         epoch_config_ref: epoch_config_ref.clone(),
                         output_schema_id: output_schema_id.clone(),
                     },
-                    TopicSignals {
-                        semantic_hash: None,
-                        phys_hir_signature_hash: phys_hash,
-                        dependency_merkle_root: None,
-                    },
+                    inputs.signals.clone(),
                 );
 
-                let c = CbrnStructuredClaim {
-                    schema_version: SchemaVersion::V1_0_0,
-                    profile: Profile::CbrnSc,
-                    domain: Domain::Cbrn,
-                    claim_kind: ClaimKind::Assessment,
-                    quantities: vec![QuantizedValue {
-                        quantity_kind: QuantityKind::Concentration,
-                        value_q: 500,
-                        scale: Scale::Micro,
-                        unit: SiUnit::MolPerM3,
-                    }],
-                    envelope_id: [0u8; 32],
-                    envelope_check: EnvelopeCheck::Match,
-                    references: vec![],
-                    etl_root: [0u8; 32],
-                    envelope_manifest_hash: [0u8; 32],
-                    envelope_manifest_version: 1,
-                    decision: Decision::Pass,
-                    reason_codes: vec![ReasonCode::SensorAgreement],
-                };
-                validate_cbrn_claim(&c)
-                    .map_err(|e| anyhow!("constructed CBRN claim should validate: {e}"))?;
-                fs::write(
-                    dir.join("structured_claim.json"),
-                    canonicalize_cbrn_claim(&c)
-                        .map_err(|e| anyhow!("failed to canonicalize cbrn claim: {e}"))?,
-                )?;
-
                 let mut client = connect_client(&args).await?;
                 assert_server_compatibility(&mut client, args.allow_protocol_drift).await?;
                 let resp = client
@@ -1129,9 +1765,17 @@ async fn main() -> anyhow::Result<()> {
                             output_schema_id: output_schema_id.clone(),
                         }),
                         signals: Some(pb::TopicSignalsV2 {
-                            semantic_hash: vec![],
+                            semantic_hash: topic
+                                .signals
+                                .semantic_hash
+                                .map(|h| h.to_vec())
+                                .unwrap_or_default(),
                             phys_hir_signature_hash: topic.signals.phys_hir_signature_hash.to_vec(),
-                            dependency_merkle_root: vec![],
+                            dependency_merkle_root: topic
+                                .signals
+                                .dependency_merkle_root
+                                .map(|h| h.to_vec())
+                                .unwrap_or_default(),
                         }),
                         holdout_ref: holdout_ref.clone(),
                         epoch_size: (*epoch_size).into(),
@@ -1150,18 +1794,39 @@ async fn main() -> anyhow::Result<()> {
                             e
                         )
                     })?;
+                open_claim_registry()?.record(
+                    &hex(&resp.claim_id),
+                    Some(claim_name),
+                    ClaimEvent::Created {
+                        topic_id_hex: hex(&resp.topic_id),
+                        local_topic_id_hex: topic.topic_id_hex.clone(),
+                        endpoint: args.endpoint.clone(),
+                    },
+                )?;
                 println!(
                     "{}",
-                    serde_json::json!({"claim_id": hex_encode(&resp.claim_id), "topic_id": hex_encode(&resp.topic_id), "local_topic_id": topic.topic_id_hex })
+                    serde_json::json!({"claim_id": hex(&resp.claim_id), "topic_id": hex(&resp.topic_id), "local_topic_id": topic.topic_id_hex })
                 );
             }
             ClaimCommand::Commit {
-                claim_id,
+                claim,
                 wasm,
                 manifests,
             } => {
+                let mut registry = open_claim_registry()?;
+                let claim = registry.resolve(claim)?;
+                let wasm = local_claim_file(wasm.as_ref(), &claim, WASM_FILE)?;
+                let manifests = if manifests.is_empty() && claim.claim_name.is_some() {
+                    [ALPHA_HIR_FILE, PHYS_HIR_FILE, CAUSAL_DSL_FILE]
+                        .iter()
+                        .map(|file| claim_dir(claim.dir_name()).join(file))
+                        .collect()
+                } else {
+                    manifests.clone()
+                };
                 let wasm_bytes =
                     fs::read(&wasm).with_context(|| format!("read wasm {}", wasm.display()))?;
+                let wasm_hash = wasm_hash_for_bytes(&wasm_bytes);
                 let mut artifacts = manifests
                     .iter()
                     .map(|p| {
@@ -1182,12 +1847,12 @@ async fn main() -> anyhow::Result<()> {
                     })
                     .collect::<anyhow::Result<Vec<_>>>()?;
                 artifacts.push(pb::Artifact {
-                    artifact_hash: wasm_hash_for_bytes(&wasm_bytes).to_vec(),
-                    kind: "wasm_module".to_string(),
+                    artifact_hash: wasm_hash.to_vec(),
+                    kind: WASM_MODULE_ARTIFACT_KIND.to_string(),
                 });
                 let mut client = connect_client(&args).await?;
                 assert_server_compatibility(&mut client, args.allow_protocol_drift).await?;
-                let claim_id_bytes = hex_decode_bytes(&claim_id)?;
+                let claim_id_bytes = unhex(&claim.claim_id_hex)?;
                 let commit_resp = client
                     .commit_artifacts(pb::CommitArtifactsRequest {
                         claim_id: claim_id_bytes,
@@ -1195,48 +1860,180 @@ async fn main() -> anyhow::Result<()> {
                         wasm_module: wasm_bytes,
                     })
                     .await?;
+                registry.record(
+                    &claim.claim_id_hex,
+                    claim.claim_name.as_deref(),
+                    ClaimEvent::Committed {
+                        wasm_hash_hex: hex(&wasm_hash),
+                    },
+                )?;
                 println!("{}", serde_json::json!({"state": commit_resp.state}));
             }
-            ClaimCommand::Freeze { claim_id } => {
+            ClaimCommand::CommitWasm {
+                claim,
+                wasm,
+                artifact_hash_hex,
+            } => {
+                let mut registry = open_claim_registry()?;
+                let claim = registry.resolve(claim)?;
+                let wasm = local_claim_file(wasm.as_ref(), &claim, WASM_FILE)?;
+                let wasm_bytes =
+                    fs::read(&wasm).with_context(|| format!("read wasm {}", wasm.display()))?;
+                let wasm_hash = wasm_hash_for_bytes(&wasm_bytes);
+                let artifact_hash = match artifact_hash_hex {
+                    Some(hex) => unhex_32(hex)?,
+                    None => wasm_hash,
+                };
+                let wasm_artifact = pb::Artifact {
+                    artifact_hash: artifact_hash.to_vec(),
+                    kind: WASM_MODULE_ARTIFACT_KIND.to_string(),
+                };
+                let mut client = connect_client(&args).await?;
+                assert_server_compatibility(&mut client, args.allow_protocol_drift).await?;
+                let resp = client
+                    .commit_wasm(
+                        pb::CommitWasmRequest {
+                            claim_id: unhex(&claim.claim_id_hex)?,
+                            wasm_hash: wasm_hash.to_vec(),
+                            wasm_module: wasm_bytes,
+                        },
+                        &wasm_artifact,
+                    )
+                    .await?;
+                registry.record(
+                    &claim.claim_id_hex,
+                    claim.claim_name.as_deref(),
+                    ClaimEvent::WasmCommitted {
+                        wasm_hash_hex: hex(&wasm_hash),
+                    },
+                )?;
+                println!(
+                    "{}",
+                    serde_json::json!({"wasm_hash": hex(&wasm_hash), "state": resp.state})
+                );
+            }
+            ClaimCommand::Freeze { claim } => {
+                let mut registry = open_claim_registry()?;
+                let claim = registry.resolve(claim)?;
                 let mut client = connect_client(&args).await?;
                 assert_server_compatibility(&mut client, args.allow_protocol_drift).await?;
                 let resp = client
                     .freeze(pb::FreezeRequest {
-                        claim_id: hex_decode_bytes(&claim_id)?,
+                        claim_id: unhex(&claim.claim_id_hex)?,
+                    })
+                    .await?;
+                registry.record(
+                    &claim.claim_id_hex,
+                    claim.claim_name.as_deref(),
+                    ClaimEvent::Frozen,
+                )?;
+                println!("{}", serde_json::json!({"state": resp.state}));
+            }
+            ClaimCommand::Seal { claim } => {
+                let mut registry = open_claim_registry()?;
+                let claim = registry.resolve(claim)?;
+                let mut client = connect_client(&args).await?;
+                assert_server_compatibility(&mut client, args.allow_protocol_drift).await?;
+                let resp = client
+                    .seal(pb::SealRequest {
+                        claim_id: unhex(&claim.claim_id_hex)?,
                     })
                     .await?;
+                registry.record(
+                    &claim.claim_id_hex,
+                    claim.claim_name.as_deref(),
+                    ClaimEvent::Sealed,
+                )?;
                 println!("{}", serde_json::json!({"state": resp.state}));
             }
-            ClaimCommand::Execute { claim_id } => {
+            ClaimCommand::Execute { claim } => {
                 ensure_certify_transport_security(&args)?;
+                let mut registry = open_claim_registry()?;
+                let claim = registry.resolve(claim)?;
                 let mut client = connect_client(&args).await?;
                 assert_server_compatibility(&mut client, args.allow_protocol_drift).await?;
                 let resp = client
                     .execute_claim_v2(pb::ExecuteClaimV2Request {
-                        claim_id: hex_decode_bytes(&claim_id)?,
+                        claim_id: unhex(&claim.claim_id_hex)?,
                     })
                     .await?;
+                registry.record(
+                    &claim.claim_id_hex,
+                    claim.claim_name.as_deref(),
+                    ClaimEvent::Executed {
+                        certified: resp.certified,
+                        e_value: resp.e_value,
+                        canonical_output_hex: hex(&resp.canonical_output),
+                    },
+                )?;
+                // Execution already happened; a capsule that cannot be fetched
+                // only costs the cross-check, not the result.
+                let capsule = match client
+                    .fetch_capsule(pb::FetchCapsuleRequest {
+                        claim_id: unhex(&claim.claim_id_hex)?,
+                    })
+                    .await
+                {
+                    Ok(capsule) => Some(capsule.capsule_bytes),
+                    Err(err) => {
+                        eprintln!(
+                            "WARNING: fetch capsule failed; canonical output not checked against it: {err}"
+                        );
+                        None
+                    }
+                };
+                let report = structured_output_report(
+                    &registry,
+                    &claim,
+                    &resp.canonical_output,
+                    capsule.as_deref(),
+                )?;
                 println!(
                     "{}",
-                    serde_json::json!({"certified": resp.certified, "e_value": resp.e_value, "canonical_output_len": resp.canonical_output.len()})
+                    serde_json::json!({"certified": resp.certified, "e_value": resp.e_value, "canonical_output_len": resp.canonical_output.len(), "structured_output": report})
+                );
+                anyhow::ensure!(
+                    !report.capsule_mismatch(),
+                    "canonical output does not match the capsule"
                 );
             }
             ClaimCommand::FetchCapsule {
-                claim_id,
+                claim,
                 verify_etl,
                 print_capsule_json,
+                verify_receipts,
+                revocations,
             } => {
+                let mut registry = open_claim_registry()?;
+                let claim = registry.resolve(claim)?;
                 let mut client = connect_client(&args).await?;
                 assert_server_compatibility(&mut client, args.allow_protocol_drift).await?;
                 let resp = client
                     .fetch_capsule(pb::FetchCapsuleRequest {
-                        claim_id: hex_decode_bytes(&claim_id)?,
+                        claim_id: unhex(&claim.claim_id_hex)?,
                     })
                     .await?;
+                let capsule_bytes = resp.capsule_bytes.clone();
+                let (etl_index, tree_size) = (resp.etl_index, resp.tree_size);
+                let validity = match revocations {
+                    Some(path) => {
+                        let kernel_pubkey = required_kernel_pubkey(&args, &mut client).await?;
+                        let sth = signed_tree_head_from_pb(
+                            resp.signed_tree_head
+                                .as_ref()
+                                .context("missing signed tree head")?,
+                        )?;
+                        verify_sth_signature(&sth, &kernel_pubkey)?;
+                        // Every record was checked on load and the snapshot is
+                        // taken under a verified head, so it needs no re-check.
+                        let snapshot =
+                            RevocationSet::load_verified(path, &kernel_pubkey)?.snapshot(&sth)?;
+                        Some(snapshot.validity(&unhex(&claim.claim_id_hex)?))
+                    }
+                    None => None,
+                };
                 let mut output = if *verify_etl {
-                    let cache_path = cache_file_path();
-                    let cache_entry_key = cache_key(&args.endpoint, &args.kernel_pubkey_hex);
-                    let mut cache = load_sth_cache(&cache_path)?;
+                    let kernel_pubkey = resolve_kernel_pubkey(&args, &mut client).await?;
 
                     let root: [u8; 32] = resp
                         .root_hash
@@ -1279,24 +2076,7 @@ async fn main() -> anyhow::Result<()> {
 
                     anyhow::ensure!(inclusion_ok, "inclusion proof verification failed");
 
-                    let consistency_ok = match verify_consistency_with_cache(
-                        &mut cache,
-                        &cache_entry_key,
-                        CachedSth {
-                            tree_size: resp.tree_size,
-                            root_hash: root,
-                        },
-                        &consistency,
-                    )? {
-                        Some(ok) => ok,
-                        None => {
-                            println!("no prior STH cached; skipping consistency check");
-                            false
-                        }
-                    };
-
-                    if !args.kernel_pubkey_hex.is_empty() {
-                        let pubkey = hex_decode_bytes(&args.kernel_pubkey_hex)?;
+                    let consistency_ok = if let Some(pubkey) = &kernel_pubkey {
                         let sth = SignedTreeHead {
                             tree_size: resp.tree_size,
                             root_hash: root,
@@ -1308,60 +2088,463 @@ async fn main() -> anyhow::Result<()> {
                                 .try_into()
                                 .map_err(|_| anyhow!("sth signature must be 64 bytes"))?,
                         };
-                        verify_sth_signature(&sth, &pubkey)?;
-                    }
-
-                    persist_sth_cache(&cache_path, &cache)?;
+                        verify_sth_signature(&sth, pubkey)?;
+                        let mut store = open_sth_store()?;
+                        // The capsule response proves consistency from the head
+                        // just before this capsule; use it when that head is stored.
+                        if let Some(old) = store
+                            .closest_at_or_before(
+                                &args.endpoint,
+                                pubkey,
+                                consistency.old_tree_size,
+                            )?
+                            .filter(|old| old.tree_size == consistency.old_tree_size)
+                        {
+                            verify_extends(&old, &sth, &consistency)?;
+                        }
+                        match check_and_record_head(
+                            &mut store,
+                            &mut client,
+                            &args.endpoint,
+                            pubkey,
+                            &sth,
+                        )
+                        .await?
+                        {
+                            HeadCheck::First => {
+                                println!("no prior STH stored; skipping consistency check");
+                                false
+                            }
+                            HeadCheck::Known | HeadCheck::Extends { .. } => true,
+                        }
+                    } else {
+                        eprintln!(
+                            "WARNING: no kernel key configured for {}; signed tree head signature was not verified and consistency was not checked.",
+                            args.endpoint
+                        );
+                        false
+                    };
 
                     serde_json::json!({"capsule_len": resp.capsule_bytes.len(), "inclusion_ok": inclusion_ok, "consistency_ok": consistency_ok})
                 } else {
                     serde_json::json!({"capsule_len": resp.capsule_bytes.len(), "etl_index": resp.etl_index})
                 };
 
+                // The capsule is verified by now; keep it before anything that
+                // only inspects its contents can fail.
+                let dir = claim_dir(claim.dir_name());
+                fs::create_dir_all(&dir)?;
+                let capsule_path = dir.join("capsule.bin");
+                fs::write(&capsule_path, &capsule_bytes)?;
+                registry.record(
+                    &claim.claim_id_hex,
+                    claim.claim_name.as_deref(),
+                    ClaimEvent::CapsuleFetched {
+                        capsule_sha256_hex: hex(&sha256(&capsule_bytes)),
+                        etl_index,
+                        tree_size,
+                        capsule_path: capsule_path.display().to_string(),
+                    },
+                )?;
+
                 if *print_capsule_json {
-                    let capsule_json: serde_json::Value =
-                        serde_json::from_slice(&resp.capsule_bytes)
-                            .context("capsule is not valid json")?;
-                    output["capsule_summary"] = build_capsule_print_summary(&capsule_json);
+                    let capsule_json: serde_json::Value = serde_json::from_slice(&capsule_bytes)
+                        .context("capsule is not valid json")?;
+                    output["capsule_summary"] = build_capsule_print_summary(&capsule_json)
+                        .unwrap_or_else(|err| serde_json::json!({ "error": format!("{err:#}") }));
                 }
+                if let Some(validity) = &validity {
+                    output["validity"] = serde_json::to_value(validity)?;
+                }
+                let output_report = registry
+                    .get(&claim.claim_id_hex)
+                    .and_then(|record| record.execution.as_ref())
+                    .map(|execution| {
+                        structured_output_report(
+                            &registry,
+                            &claim,
+                            &unhex(&execution.canonical_output_hex)?,
+                            Some(&capsule_bytes),
+                        )
+                    })
+                    .transpose()?;
+                if let Some(report) = &output_report {
+                    output["structured_output"] = serde_json::to_value(report)?;
+                }
+                let receipt_report = if *verify_receipts {
+                    let capsule_json: serde_json::Value = serde_json::from_slice(&capsule_bytes)
+                        .context("capsule is not valid json")?;
+                    let receipts = extract_policy_oracle_receipts(&capsule_json)?;
+                    let report =
+                        verify_policy_oracle_receipts(&receipts, &LocalArtifacts::in_dir(&dir)?);
+                    output["receipts"] = serde_json::to_value(&report)?;
+                    Some(report)
+                } else {
+                    None
+                };
 
                 println!("{}", output);
+                anyhow::ensure!(
+                    !output_report.is_some_and(|report| report.capsule_mismatch()),
+                    "canonical output recorded at execution does not match the capsule"
+                );
+                if let Some(report) = receipt_report {
+                    anyhow::ensure!(
+                        report.is_ok(),
+                        "{} policy-oracle receipt(s) do not match the committed wasm and manifests",
+                        report.mismatched().count()
+                    );
+                }
+            }
+            ClaimCommand::Revoke { claim, reason } => {
+                anyhow::ensure!(!reason.trim().is_empty(), "--reason must not be empty");
+                let mut registry = open_claim_registry()?;
+                let claim = registry.resolve(claim)?;
+                let mut client = connect_client(&args).await?;
+                assert_server_compatibility(&mut client, args.allow_protocol_drift).await?;
+                let resp = client
+                    .revoke_claim(pb::RevokeClaimRequest {
+                        claim_id: unhex(&claim.claim_id_hex)?,
+                        reason: reason.clone(),
+                    })
+                    .await?;
+                registry.record(
+                    &claim.claim_id_hex,
+                    claim.claim_name.as_deref(),
+                    ClaimEvent::Revoked {
+                        reason: reason.clone(),
+                    },
+                )?;
+                println!(
+                    "{}",
+                    serde_json::json!({"claim_id": claim.claim_id_hex, "reason": reason, "state": resp.state})
+                );
+            }
+            ClaimCommand::Run { spec } => {
+                let mut spec = ClaimRunSpec::load(spec)?;
+                validate_oracle_id(&spec.oracle_id)?;
+                spec.output_schema_id = canonicalize_output_schema_id(&spec.output_schema_id);
+                let profiles = ProfileRegistry::builtin();
+                let profile = profiles
+                    .resolve(&spec.output_schema_id)
+                    .map_err(|e| anyhow!(e))?;
+                spec.lane = apply_dual_use_policy(
+                    &dual_use_policy,
+                    profile.domain(),
+                    &spec.lane,
+                    &spec.output_schema_id,
+                )?;
+                ensure_certify_transport_security(&args)?;
+                let mut client = connect_client(&args).await?;
+                assert_server_compatibility(&mut client, args.allow_protocol_drift).await?;
+                let pubkey = required_kernel_pubkey(&args, &mut client).await?;
+                let mut registry = open_claim_registry()?;
+                let outcome = run_claim(
+                    &mut client,
+                    &mut registry,
+                    &spec,
+                    &claim_dir(&spec.claim_name),
+                    &args.endpoint,
+                    &pubkey,
+                )
+                .await?;
+                println!("{}", serde_json::to_value(&outcome)?);
+            }
+            ClaimCommand::List => {
+                let registry = open_claim_registry()?;
+                let claims = registry.claims().map(claim_status_json).collect::<Vec<_>>();
+                println!("{}", serde_json::json!({ "claims": claims }));
             }
-            ClaimCommand::ValidateStructured { input } => {
-                let bytes = fs::read(&input)
+            ClaimCommand::Status { claim } => {
+                let registry = open_claim_registry()?;
+                let claim = registry.resolve(claim)?;
+                let record = registry.get(&claim.claim_id_hex).with_context(|| {
+                    format!("claim {} is not in the local registry", claim.claim_id_hex)
+                })?;
+                println!("{}", claim_status_json(record));
+            }
+            ClaimCommand::Show { claim } => {
+                let registry = open_claim_registry()?;
+                let claim = registry.resolve(claim)?;
+                let record = registry.get(&claim.claim_id_hex).with_context(|| {
+                    format!("claim {} is not in the local registry", claim.claim_id_hex)
+                })?;
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "record": record,
+                        "history": registry.history(&claim.claim_id_hex)?,
+                    }))?
+                );
+            }
+            ClaimCommand::ValidateStructured {
+                input,
+                output_schema_id,
+            } => {
+                let profiles = ProfileRegistry::builtin();
+                let profile = profiles.resolve(output_schema_id).map_err(|e| anyhow!(e))?;
+                let bytes = fs::read(input)
                     .with_context(|| format!("read structured claim {}", input.display()))?;
-                let claim = parse_cbrn_claim_json(&bytes)
+                let claim = profile
+                    .parse(&bytes)
                     .map_err(|e| anyhow!("invalid structured claim json: {e}"))?;
-                validate_cbrn_claim(&claim)
+                profile
+                    .validate(&claim)
                     .map_err(|e| anyhow!("invalid structured claim semantics: {e}"))?;
-                let canonical = canonicalize_cbrn_claim(&claim)
+                let canonical = profile
+                    .canonicalize(&claim)
                     .map_err(|e| anyhow!("failed to canonicalize structured claim: {e}"))?;
+                let kout = profile
+                    .kout_accounting(&claim)
+                    .map_err(|e| anyhow!("failed to account structured claim: {e}"))?;
                 println!(
                     "{}",
                     serde_json::json!({
                         "ok": true,
+                        "output_schema_id": profile.output_schema_id(),
                         "canonical_len": canonical.len(),
-                        "decision": format!("{:?}", claim.decision).to_lowercase()
+                        "decision": claim.get("decision"),
+                        "kout_bits": kout.kout_bits,
                     })
                 );
             }
         },
-        Command::WatchRevocations => {
+        Command::Log { ref cmd } => {
             let mut client = connect_client(&args).await?;
             assert_server_compatibility(&mut client, args.allow_protocol_drift).await?;
-            let mut stream = client
-                .watch_revocations(pb::WatchRevocationsRequest {})
-                .await?;
-            while let Some(ev) = stream.next().await {
-                let ev = ev?;
-                for entry in ev.entries {
+            let pubkey = required_kernel_pubkey(&args, &mut client).await?;
+            let mut store = open_sth_store()?;
+            match cmd {
+                LogCommand::Sth => {
+                    let sth = verify_signed_tree_head_response(
+                        &client
+                            .get_signed_tree_head(pb::GetSignedTreeHeadRequest {})
+                            .await?,
+                        &pubkey,
+                    )?;
+                    if let Some(latest) = store.latest(&args.endpoint, &pubkey)? {
+                        anyhow::ensure!(
+                            sth.tree_size >= latest.tree_size,
+                            "signed tree head size {} is older than stored size {}",
+                            sth.tree_size,
+                            latest.tree_size
+                        );
+                    }
+                    let check = check_and_record_head(
+                        &mut store,
+                        &mut client,
+                        &args.endpoint,
+                        &pubkey,
+                        &sth,
+                    )
+                    .await?;
+                    let consistency_ok = (check != HeadCheck::First).then_some(true);
+                    println!(
+                        "{}",
+                        serde_json::json!({
+                            "tree_size": sth.tree_size,
+                            "root_hash": hex(&sth.root_hash),
+                            "signature": hex(&sth.signature),
+                            "signature_ok": true,
+                            "consistency_ok": consistency_ok,
+                        })
+                    );
+                }
+                LogCommand::Inclusion { claim } => {
+                    let claim_id = open_claim_registry()?.resolve(claim)?.claim_id_hex;
+                    let resp = client
+                        .get_inclusion_proof(pb::GetInclusionProofRequest {
+                            claim_id: unhex(&claim_id)?,
+                        })
+                        .await?;
+                    let (sth, proof) = verify_inclusion_proof_response(&resp, &pubkey)?;
+                    // The proof alone only shows some leaf is logged; bind it
+                    // to this claim's capsule.
+                    let capsule = client
+                        .fetch_capsule(pb::FetchCapsuleRequest {
+                            claim_id: unhex(&claim_id)?,
+                        })
+                        .await?
+                        .capsule_bytes;
+                    let capsule_json: serde_json::Value =
+                        serde_json::from_slice(&capsule).context("capsule is not valid json")?;
+                    anyhow::ensure!(
+                        capsule_json
+                            .get("claim_id_hex")
+                            .and_then(|v| v.as_str())
+                            .is_some_and(|id| id.eq_ignore_ascii_case(&claim_id)),
+                        "capsule does not belong to claim {claim_id}"
+                    );
+                    anyhow::ensure!(
+                        proof.leaf_hash == merkle_leaf_hash(&capsule),
+                        "inclusion proof is for a different leaf than the claim's capsule"
+                    );
+                    check_and_record_head(&mut store, &mut client, &args.endpoint, &pubkey, &sth)
+                        .await?;
+                    println!(
+                        "{}",
+                        serde_json::json!({
+                            "claim_id": claim_id,
+                            "leaf_hash": hex(&proof.leaf_hash),
+                            "leaf_index": proof.leaf_index,
+                            "tree_size": sth.tree_size,
+                            "root_hash": hex(&sth.root_hash),
+                            "audit_path_len": proof.audit_path.len(),
+                            "inclusion_ok": true,
+                        })
+                    );
+                }
+                LogCommand::Consistency {
+                    from,
+                    to,
+                    from_root_hex,
+                    to_root_hex,
+                } => {
+                    anyhow::ensure!(from <= to, "--from must not exceed --to");
+                    let current = verify_signed_tree_head_response(
+                        &client
+                            .get_signed_tree_head(pb::GetSignedTreeHeadRequest {})
+                            .await?,
+                        &pubkey,
+                    )?;
+                    check_and_record_head(
+                        &mut store,
+                        &mut client,
+                        &args.endpoint,
+                        &pubkey,
+                        &current,
+                    )
+                    .await?;
+                    let known_root = |size: u64, explicit: &Option<String>| {
+                        resolve_log_root(
+                            size,
+                            explicit.as_deref(),
+                            &current,
+                            &store,
+                            &args.endpoint,
+                            &pubkey,
+                        )
+                    };
+                    let old_root = known_root(*from, from_root_hex)?;
+                    let new_root = known_root(*to, to_root_hex)?;
+                    let proof = fetch_consistency_proof(&mut client, *from, *to).await?;
+                    anyhow::ensure!(
+                        verify_consistency(old_root, new_root, &proof),
+                        "consistency proof verification failed"
+                    );
+                    println!(
+                        "{}",
+                        serde_json::json!({
+                            "from": from,
+                            "to": to,
+                            "from_root_hash": hex(&old_root),
+                            "to_root_hash": hex(&new_root),
+                            "path_len": proof.path.len(),
+                            "consistency_ok": true,
+                        })
+                    );
+                }
+                LogCommand::Checkpoint {
+                    origin,
+                    note_key,
+                    out,
+                } => {
+                    let head = current_head(&mut client, &pubkey).await?;
+                    check_and_record_head(&mut store, &mut client, &args.endpoint, &pubkey, &head)
+                        .await?;
+                    let origin = origin.clone().unwrap_or_else(|| {
+                        args.endpoint
+                            .split_once("://")
+                            .map_or(args.endpoint.as_str(), |(_, rest)| rest)
+                            .trim_end_matches('/')
+                            .to_string()
+                    });
+                    let mut checkpoint = Checkpoint::from_sth(&origin, &head, &pubkey)?;
+                    let vkey = match note_key {
+                        Some(path) => {
+                            let key = load_or_create_reporter_key(path)?;
+                            checkpoint.sign(&origin, &key)?;
+                            Some(note_verifier_key(&origin, &key.verifying_key()))
+                        }
+                        None => None,
+                    };
+                    match out {
+                        Some(path) => {
+                            std::fs::write(path, checkpoint.to_note())
+                                .with_context(|| format!("write {}", path.display()))?;
+                            println!(
+                                "{}",
+                                serde_json::json!({
+                                    "checkpoint": path,
+                                    "origin": origin,
+                                    "tree_size": head.tree_size,
+                                    "note_verifier_key": vkey,
+                                })
+                            );
+                        }
+                        None => {
+                            print!("{}", checkpoint.to_note());
+                            if let Some(vkey) = vkey {
+                                eprintln!("note verifier key: {vkey}");
+                            }
+                        }
+                    }
+                }
+                LogCommand::ImportCheckpoint {
+                    checkpoint,
+                    note_vkey,
+                } => {
+                    let note = std::fs::read_to_string(checkpoint)
+                        .with_context(|| format!("read {}", checkpoint.display()))?;
+                    let checkpoint = Checkpoint::parse(&note)?;
+                    let head = checkpoint.verified_head(&pubkey)?;
+                    if let Some(vkey) = note_vkey {
+                        checkpoint.verify_note_signature(vkey)?;
+                    }
+                    let check = check_and_record_head(
+                        &mut store,
+                        &mut client,
+                        &args.endpoint,
+                        &pubkey,
+                        &head,
+                    )
+                    .await?;
                     println!(
                         "{}",
-                        serde_json::json!({"claim_id": hex_encode(&entry.claim_id), "reason": entry.reason, "timestamp_unix": entry.timestamp_unix})
+                        serde_json::json!({
+                            "origin": checkpoint.origin,
+                            "tree_size": head.tree_size,
+                            "root_hash": hex(&head.root_hash),
+                            "signatures": checkpoint.signatures.len(),
+                            "signature_ok": true,
+                            "consistency_ok": (check != HeadCheck::First).then_some(true),
+                        })
                     );
                 }
             }
         }
+        Command::WatchRevocations {
+            ref store,
+            max_reconnects,
+        } => {
+            let mut client = connect_client(&args).await?;
+            assert_server_compatibility(&mut client, args.allow_protocol_drift).await?;
+            let kernel_pubkey = required_kernel_pubkey(&args, &mut client).await?;
+            let set = RevocationSet::load_verified(store, &kernel_pubkey)?;
+            let mut watcher = RevocationWatcher::new(client, &kernel_pubkey, set)?
+                .with_store(store)
+                .with_reconnect(
+                    max_reconnects,
+                    Duration::from_millis(discos_client::revocations::DEFAULT_RECONNECT_DELAY_MS),
+                );
+            loop {
+                let record = watcher.next().await?;
+                println!(
+                    "{}",
+                    serde_json::json!({"claim_id": record.claim_id_hex, "reason": record.reason_code, "logical_epoch": record.logical_epoch, "timestamp_unix": record.timestamp_unix})
+                );
+            }
+        }
     }
 
     Ok(())
@@ -1382,50 +2565,28 @@ mod tests {
     }
 
     #[test]
-    fn sth_cache_first_run_stores_and_skips_consistency_then_second_run_verifies() {
-        let dir = tempfile::tempdir().expect("tempdir should create");
-        let cache_path = dir.path().join(CACHE_FILE_NAME);
-        let key = "http://127.0.0.1:50051";
-
+    fn stored_head_is_extended_only_by_a_matching_consistency_proof() {
         let l0 = merkle_leaf_hash(b"a");
         let l1 = merkle_leaf_hash(b"b");
         let l2 = merkle_leaf_hash(b"c");
 
         let old_root = merkle_node_hash(l0, l1);
         let new_root = merkle_node_hash(old_root, l2);
+        let head = |tree_size, root_hash| SignedTreeHead {
+            tree_size,
+            root_hash,
+            signature: [0u8; 64],
+        };
 
         let proof = ConsistencyProof {
             old_tree_size: 2,
             new_tree_size: 3,
             path: vec![old_root, l2],
         };
-
-        let mut cache = load_sth_cache(&cache_path).expect("cache should load when missing");
-        let first = verify_consistency_with_cache(
-            &mut cache,
-            key,
-            CachedSth {
-                tree_size: 2,
-                root_hash: old_root,
-            },
-            &proof,
-        )
-        .expect("first run should not fail");
-        assert_eq!(first, None);
-        persist_sth_cache(&cache_path, &cache).expect("first cache write should succeed");
-
-        let mut second_cache = load_sth_cache(&cache_path).expect("cache reload should succeed");
-        let second = verify_consistency_with_cache(
-            &mut second_cache,
-            key,
-            CachedSth {
-                tree_size: 3,
-                root_hash: new_root,
-            },
-            &proof,
-        )
-        .expect("second run should verify consistency proof");
-        assert_eq!(second, Some(true));
+        verify_extends(&head(2, old_root), &head(3, new_root), &proof)
+            .expect("proof should show size 3 extends size 2");
+        assert!(verify_extends(&head(2, l0), &head(3, new_root), &proof).is_err());
+        assert!(verify_extends(&head(1, l0), &head(3, new_root), &proof).is_err());
     }
 
     #[test]
@@ -1536,6 +2697,58 @@ feed"
         assert!(is_server_compatible(&info));
     }
 
+    #[test]
+    fn log_root_resolution_prefers_explicit_then_current_then_store() {
+        let daemon = discos_testkit::MockDaemon::new(Default::default()).expect("daemon");
+        let pubkey = daemon.kernel_pubkey();
+        for leaf in [b"a", b"b", b"c", b"d"] {
+            daemon.append_leaf(leaf);
+        }
+        let stored =
+            discos_client::signed_tree_head_from_pb(&daemon.signed_tree_head()).expect("sth");
+        let dir = tempfile::tempdir().expect("tempdir should create");
+        let mut store = SthStore::open(&dir.path().join(STH_STORE_FILE)).expect("store");
+        store.record("k", &pubkey, &stored).expect("record");
+
+        let current = SignedTreeHead {
+            tree_size: 9,
+            root_hash: [9u8; 32],
+            signature: [0u8; 64],
+        };
+        let resolve = |size, explicit: Option<&str>, endpoint| {
+            resolve_log_root(size, explicit, &current, &store, endpoint, &pubkey)
+        };
+
+        let explicit = hex(&[1u8; 32]);
+        assert_eq!(
+            resolve(9, Some(&explicit), "k").expect("explicit"),
+            [1u8; 32]
+        );
+        assert_eq!(resolve(9, None, "k").expect("current"), [9u8; 32]);
+        assert_eq!(resolve(4, None, "k").expect("stored"), stored.root_hash);
+        assert!(resolve(5, None, "k").is_err());
+        assert!(resolve(4, None, "other").is_err());
+    }
+
+    #[test]
+    fn classify_from_status_uses_code_and_reason_token() {
+        let frozen =
+            KernelError::from(tonic::Status::new(Code::Aborted, "FROZEN: claim is frozen"));
+        assert_eq!(classify_from_status(&frozen), "REQUIRE_HUMAN");
+
+        let throttled = KernelError::from(tonic::Status::new(Code::Unavailable, "THROTTLE_TOPIC"));
+        assert_eq!(classify_from_status(&throttled), "DOWNGRADE");
+
+        let exhausted = KernelError::from(tonic::Status::resource_exhausted("budget spent"));
+        assert_eq!(classify_from_status(&exhausted), "DOWNGRADE");
+
+        let denied = KernelError::from(tonic::Status::invalid_argument("bad request"));
+        assert_eq!(classify_from_status(&denied), "DENY");
+
+        let prose = KernelError::from(tonic::Status::internal("claim was not frozen"));
+        assert_eq!(classify_from_status(&prose), "ALLOW");
+    }
+
     #[test]
     fn load_scenarios_reads_json_specs() {
         let dir = tempfile::tempdir().expect("tempdir should create");
@@ -1576,8 +2789,12 @@ feed"
             request_timeout_ms: discos_client::DEFAULT_REQUEST_TIMEOUT_MS,
             keepalive_interval_ms: discos_client::DEFAULT_KEEPALIVE_INTERVAL_MS,
             keepalive_timeout_ms: discos_client::DEFAULT_KEEPALIVE_TIMEOUT_MS,
+            retry_max_attempts: discos_client::retry::DEFAULT_RETRY_MAX_ATTEMPTS,
+            failover_endpoint: Vec::new(),
             log: "info".to_string(),
             kernel_pubkey_hex: "".to_string(),
+            key_store: PathBuf::from(".discos/kernel_keys.json"),
+            tofu: false,
             allow_protocol_drift: false,
             require_structured_outputs: true,
             deny_free_text_outputs: true,
@@ -1612,6 +2829,31 @@ feed"
         assert_eq!(args.keepalive_timeout_ms, 4444);
     }
 
+    #[test]
+    fn log_inclusion_selects_the_claim_by_id_or_name() {
+        let args = Args::parse_from(["discos", "log", "inclusion", "--claim-name", "demo-1"]);
+        let Command::Log {
+            cmd: LogCommand::Inclusion { claim },
+        } = args.cmd
+        else {
+            panic!("expected log inclusion");
+        };
+        assert_eq!(claim.claim_name.as_deref(), Some("demo-1"));
+        assert_eq!(claim.claim_id, None);
+
+        assert!(Args::try_parse_from(["discos", "log", "inclusion"]).is_err());
+        assert!(Args::try_parse_from([
+            "discos",
+            "log",
+            "inclusion",
+            "--claim-id",
+            "aa",
+            "--claim-name",
+            "demo-1",
+        ])
+        .is_err());
+    }
+
     #[test]
     fn cli_timeout_flags_default_to_client_defaults() {
         let args = Args::parse_from(["discos", "health"]);
@@ -0,0 +1,463 @@
+//! `monitor`: continuous transparency-log monitoring.
+//!
+//! Each poll fetches the endpoint's signed tree head, checks its kernel
+//! signature and its consistency with the previous head, and samples
+//! inclusion proofs for capsules of locally registered claims that entered
+//! the log since then. Every accepted head is appended to `history.jsonl`.
+//! The first failure becomes an [`Alert`]: it is appended to `alerts.jsonl`,
+//! handed to the optional hook command, and ends the monitor.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context};
+use discos_client::gossip::GossipHead;
+use discos_client::{
+    consistency_proof_from_pb, merkle_leaf_hash, pb, signed_tree_head_from_pb,
+    verify_consistency_proof, verify_inclusion_proof_response, verify_signed_tree_head_response,
+    verify_sth_signature, DiscosClient, SignedTreeHead,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::hex_codec::{hex, unhex};
+use crate::registry::Registry;
+
+pub const HISTORY_FILE: &str = "history.jsonl";
+pub const ALERTS_FILE: &str = "alerts.jsonl";
+pub const DEFAULT_INCLUSION_SAMPLES: usize = 4;
+
+/// One accepted tree head, as recorded in the history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeadRecord {
+    pub seq: u64,
+    pub observed_unix: u64,
+    pub endpoint: String,
+    pub head: GossipHead,
+    /// Size of the previous head this one was proven consistent with.
+    pub consistent_with: Option<u64>,
+    /// Claims whose inclusion proofs were checked against this head.
+    pub inclusion_sampled: Vec<String>,
+}
+
+/// Append-only history of accepted heads.
+#[derive(Debug)]
+pub struct HeadHistory {
+    path: PathBuf,
+    last: Option<HeadRecord>,
+    len: u64,
+}
+
+impl HeadHistory {
+    /// Opens the history at `path`. The last recorded head must carry a
+    /// valid signature under `kernel_pubkey`; a torn trailing line is
+    /// dropped before the next append.
+    pub fn open(path: &Path, kernel_pubkey: &[u8]) -> anyhow::Result<Self> {
+        let mut history = Self {
+            path: path.to_path_buf(),
+            last: None,
+            len: 0,
+        };
+        history.catch_up()?;
+        if let Some(record) = &history.last {
+            verify_sth_signature(&record.head.decode()?, kernel_pubkey).with_context(|| {
+                format!(
+                    "last head in {} is not signed by the kernel key",
+                    path.display()
+                )
+            })?;
+        }
+        Ok(history)
+    }
+
+    /// Reads complete records appended since the last read, by this or
+    /// another monitor sharing the file.
+    fn catch_up(&mut self) -> anyhow::Result<()> {
+        let bytes = match fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("read monitor history {}", self.path.display()))
+            }
+        };
+        if (bytes.len() as u64) < self.len {
+            self.last = None;
+            self.len = 0;
+        }
+        let start = self.len as usize;
+        for line in bytes[start..].split_inclusive(|b| *b == b'\n') {
+            if line.last() != Some(&b'\n') {
+                break;
+            }
+            self.last =
+                Some(serde_json::from_slice::<HeadRecord>(line).with_context(|| {
+                    format!("corrupt monitor history entry at byte {}", self.len)
+                })?);
+            self.len += line.len() as u64;
+        }
+        Ok(())
+    }
+
+    pub fn last(&self) -> Option<&HeadRecord> {
+        self.last.as_ref()
+    }
+
+    fn append(&mut self, mut record: HeadRecord) -> anyhow::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("create monitor dir {}", parent.display()))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .context("open monitor history")?;
+        file.lock().context("lock monitor history")?;
+        // Keep whatever other monitors appended; only an incomplete last
+        // line is a torn write.
+        self.catch_up()?;
+        if file.metadata()?.len() > self.len {
+            file.set_len(self.len)
+                .context("truncate torn monitor history tail")?;
+        }
+        record.seq = self.last.as_ref().map_or(0, |last| last.seq + 1);
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+        file.write_all(&line).context("append monitor history")?;
+        file.sync_data().context("sync monitor history")?;
+        self.len += line.len() as u64;
+        self.last = Some(record);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    /// The tree head could not be fetched.
+    Unreachable,
+    /// The tree head is not signed by the kernel key.
+    BadSignature,
+    /// The log shrank.
+    Rollback,
+    /// Two signed roots for the same size.
+    Fork,
+    /// No valid consistency proof from the previous head.
+    Inconsistent,
+    /// A sampled capsule is missing from the log or its proof is invalid.
+    InclusionFailed,
+    /// Local history or registry could not be read or written.
+    Storage,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Alert {
+    pub observed_unix: u64,
+    pub endpoint: String,
+    pub kind: AlertKind,
+    pub message: String,
+    pub previous: Option<GossipHead>,
+    pub head: Option<GossipHead>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PollReport {
+    pub tree_size: u64,
+    pub root_hash: String,
+    pub previous_tree_size: Option<u64>,
+    pub inclusion_sampled: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MonitorOptions {
+    pub endpoint: String,
+    pub kernel_pubkey: Vec<u8>,
+    /// Directory holding the history and alert files.
+    pub dir: PathBuf,
+    /// Claim registry whose capsules are sampled; `None` disables sampling.
+    pub registry: Option<PathBuf>,
+    pub inclusion_samples: usize,
+    /// Shell command run with the alert in `DISCOS_ALERT_*` variables.
+    pub hook: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Monitor {
+    options: MonitorOptions,
+    history: HeadHistory,
+}
+
+impl Monitor {
+    pub fn open(options: MonitorOptions) -> anyhow::Result<Self> {
+        let history = HeadHistory::open(&options.dir.join(HISTORY_FILE), &options.kernel_pubkey)?;
+        Ok(Self { options, history })
+    }
+
+    pub fn history(&self) -> &HeadHistory {
+        &self.history
+    }
+
+    /// Fetches and checks the current head. An `Err` is an alert that has
+    /// not been raised yet; see [`Monitor::raise`].
+    pub async fn poll(&mut self, client: &mut DiscosClient) -> Result<PollReport, Alert> {
+        let previous = match self.history.last() {
+            Some(record) => Some(
+                record
+                    .head
+                    .decode()
+                    .map_err(|err| self.alert(AlertKind::Storage, err, None, None))?,
+            ),
+            None => None,
+        };
+        let previous = previous.as_ref();
+
+        let response = client
+            .get_signed_tree_head(pb::GetSignedTreeHeadRequest {})
+            .await
+            .map_err(|err| self.alert(AlertKind::Unreachable, err, previous, None))?;
+        let sth = response.signed_tree_head.as_ref();
+        let head = verify_signed_tree_head_response(&response, &self.options.kernel_pubkey)
+            .map_err(|err| {
+                let unverified = sth.and_then(|sth| signed_tree_head_from_pb(sth).ok());
+                self.alert(AlertKind::BadSignature, err, previous, unverified.as_ref())
+            })?;
+
+        if let Some(previous) = previous {
+            self.check_consistency(client, previous, &head).await?;
+        }
+        let sampled = self.sample_inclusion(client, previous, &head).await?;
+
+        let record = HeadRecord {
+            seq: 0,
+            observed_unix: now_unix(),
+            endpoint: self.options.endpoint.clone(),
+            head: (&head).into(),
+            consistent_with: previous.map(|previous| previous.tree_size),
+            inclusion_sampled: sampled.clone(),
+        };
+        let previous_tree_size = previous.map(|previous| previous.tree_size);
+        if let Err(err) = self.history.append(record) {
+            let message = format!("record head: {err:#}");
+            return Err(self.alert(AlertKind::Storage, message, None, Some(&head)));
+        }
+        Ok(PollReport {
+            tree_size: head.tree_size,
+            root_hash: hex(&head.root_hash),
+            previous_tree_size,
+            inclusion_sampled: sampled,
+        })
+    }
+
+    async fn check_consistency(
+        &self,
+        client: &mut DiscosClient,
+        previous: &SignedTreeHead,
+        head: &SignedTreeHead,
+    ) -> Result<(), Alert> {
+        let alert = |kind, message: String| self.alert(kind, message, Some(previous), Some(head));
+        if head.tree_size < previous.tree_size {
+            return Err(alert(
+                AlertKind::Rollback,
+                format!(
+                    "tree size went back from {} to {}",
+                    previous.tree_size, head.tree_size
+                ),
+            ));
+        }
+        if head.tree_size == previous.tree_size {
+            if head.root_hash != previous.root_hash {
+                return Err(alert(
+                    AlertKind::Fork,
+                    format!("two signed roots for tree size {}", head.tree_size),
+                ));
+            }
+            return Ok(());
+        }
+        if previous.tree_size == 0 {
+            return Ok(());
+        }
+        let proof = client
+            .get_consistency_proof(pb::GetConsistencyProofRequest {
+                old_tree_size: previous.tree_size,
+                new_tree_size: head.tree_size,
+            })
+            .await
+            .map_err(|err| alert(AlertKind::Unreachable, err.to_string()))?
+            .consistency_proof
+            .ok_or_else(|| alert(AlertKind::Inconsistent, "missing consistency proof".into()))?;
+        let proof = consistency_proof_from_pb(&proof)
+            .map_err(|err| alert(AlertKind::Inconsistent, err.to_string()))?;
+        if proof.old_tree_size != previous.tree_size
+            || proof.new_tree_size != head.tree_size
+            || !verify_consistency_proof(previous.root_hash, head.root_hash, &proof)
+        {
+            return Err(alert(
+                AlertKind::Inconsistent,
+                format!(
+                    "no valid consistency proof from size {} to {}",
+                    previous.tree_size, head.tree_size
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks inclusion proofs for up to `inclusion_samples` registered
+    /// capsules whose log index lies between the previous head and `head`.
+    async fn sample_inclusion(
+        &self,
+        client: &mut DiscosClient,
+        previous: Option<&SignedTreeHead>,
+        head: &SignedTreeHead,
+    ) -> Result<Vec<String>, Alert> {
+        let Some(registry) = &self.options.registry else {
+            return Ok(Vec::new());
+        };
+        let alert = |kind, message: String| self.alert(kind, message, previous, Some(head));
+        let registry = Registry::open(registry)
+            .map_err(|err| alert(AlertKind::Storage, format!("{err:#}")))?;
+        let from = previous.map_or(0, |previous| previous.tree_size);
+        let fresh = registry
+            .claims()
+            .filter(|record| {
+                record
+                    .endpoint
+                    .as_deref()
+                    .is_none_or(|endpoint| endpoint == self.options.endpoint)
+            })
+            .filter_map(|record| {
+                let capsule = record.capsule.as_ref()?;
+                (from..head.tree_size)
+                    .contains(&capsule.etl_index)
+                    .then_some((record.claim_id_hex.clone(), capsule.clone()))
+            })
+            .collect::<Vec<_>>();
+
+        let mut sampled = Vec::new();
+        for (claim_id_hex, capsule) in spread(&fresh, self.options.inclusion_samples) {
+            let failed = |message: String| {
+                alert(
+                    AlertKind::InclusionFailed,
+                    format!("claim {claim_id_hex}: {message}"),
+                )
+            };
+            let claim_id = unhex(claim_id_hex).map_err(|err| failed(err.to_string()))?;
+            let response = client
+                .get_inclusion_proof(pb::GetInclusionProofRequest { claim_id })
+                .await
+                .map_err(|err| failed(err.to_string()))?;
+            let (sth, proof) =
+                verify_inclusion_proof_response(&response, &self.options.kernel_pubkey)
+                    .map_err(|err| failed(err.to_string()))?;
+            if proof.leaf_index != capsule.etl_index {
+                return Err(failed(format!(
+                    "capsule is at log index {}, registry says {}",
+                    proof.leaf_index, capsule.etl_index
+                )));
+            }
+            if sth.tree_size == head.tree_size && sth.root_hash != head.root_hash {
+                return Err(failed(format!(
+                    "inclusion proof is rooted in a different tree of size {}",
+                    sth.tree_size
+                )));
+            }
+            if let Ok(bytes) = fs::read(&capsule.capsule_path) {
+                if merkle_leaf_hash(&bytes) != proof.leaf_hash {
+                    return Err(failed(format!(
+                        "logged leaf does not match {}",
+                        capsule.capsule_path
+                    )));
+                }
+            }
+            sampled.push(claim_id_hex.clone());
+        }
+        Ok(sampled)
+    }
+
+    fn alert(
+        &self,
+        kind: AlertKind,
+        message: impl std::fmt::Display,
+        previous: Option<&SignedTreeHead>,
+        head: Option<&SignedTreeHead>,
+    ) -> Alert {
+        Alert {
+            observed_unix: now_unix(),
+            endpoint: self.options.endpoint.clone(),
+            kind,
+            message: message.to_string(),
+            previous: previous.map(GossipHead::from),
+            head: head.map(GossipHead::from),
+        }
+    }
+
+    /// Appends `alert` to the alert file and runs the hook, if configured.
+    pub fn raise(&self, alert: &Alert) -> anyhow::Result<()> {
+        let path = self.options.dir.join(ALERTS_FILE);
+        fs::create_dir_all(&self.options.dir)
+            .with_context(|| format!("create monitor dir {}", self.options.dir.display()))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("open alert file {}", path.display()))?;
+        let mut line = serde_json::to_vec(alert)?;
+        line.push(b'\n');
+        file.write_all(&line).context("append alert")?;
+        file.sync_data().context("sync alert file")?;
+
+        let Some(hook) = &self.options.hook else {
+            return Ok(());
+        };
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(hook)
+            .env("DISCOS_ALERT_KIND", kind_name(alert.kind))
+            .env("DISCOS_ALERT_MESSAGE", &alert.message)
+            .env("DISCOS_ALERT_ENDPOINT", &alert.endpoint)
+            .env("DISCOS_ALERT_FILE", &path)
+            .env(
+                "DISCOS_ALERT_JSON",
+                String::from_utf8_lossy(&line).trim_end(),
+            )
+            .status()
+            .with_context(|| format!("run alert hook `{hook}`"))?;
+        if !status.success() {
+            return Err(anyhow!("alert hook `{hook}` exited with {status}"));
+        }
+        Ok(())
+    }
+}
+
+fn kind_name(kind: AlertKind) -> String {
+    serde_json::to_value(kind)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Up to `n` items spread evenly over `items`, always including the newest.
+fn spread<T>(items: &[T], n: usize) -> Vec<&T> {
+    if n == 0 || items.is_empty() {
+        return Vec::new();
+    }
+    if items.len() <= n {
+        return items.iter().collect();
+    }
+    let last = items.len() - 1;
+    (0..n)
+        .map(|i| &items[last - i * last / (n - 1).max(1)])
+        .collect()
+}
+
+fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,148 @@
+//! `receipts`: check policy-oracle receipts against the local claim workspace.
+//!
+//! A receipt names the wasm code hash and manifest hash the kernel evaluated.
+//! Both are recomputed from what we committed: the wasm module with the
+//! builder's code-hash domain and each manifest with its manifest-hash
+//! domain. A receipt passes only if its wasm hash is ours and its manifest
+//! hash is one of our manifests.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use discos_builder::{manifest_hash, wasm_code_hash};
+use discos_client::capsule::PolicyOracleReceipt;
+use serde::Serialize;
+
+use crate::claim_inputs::{ALPHA_HIR_FILE, CAUSAL_DSL_FILE, PHYS_HIR_FILE, WASM_FILE};
+use crate::hex_codec::hex;
+
+/// Hashes of the artifacts a claim was committed with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalArtifacts {
+    pub wasm_code_hash_hex: String,
+    /// `(file name, manifest hash hex)` per manifest.
+    pub manifests: Vec<(String, String)>,
+}
+
+impl LocalArtifacts {
+    /// The wasm module and manifests `claim create` wrote into `dir`.
+    pub fn in_dir(dir: &Path) -> anyhow::Result<Self> {
+        let manifests = [ALPHA_HIR_FILE, PHYS_HIR_FILE, CAUSAL_DSL_FILE]
+            .iter()
+            .map(|file| dir.join(file))
+            .collect::<Vec<_>>();
+        Self::load(&dir.join(WASM_FILE), &manifests)
+    }
+
+    pub fn load(wasm: &Path, manifests: &[PathBuf]) -> anyhow::Result<Self> {
+        let wasm_bytes = fs::read(wasm).with_context(|| format!("read wasm {}", wasm.display()))?;
+        let manifests = manifests
+            .iter()
+            .map(|path| {
+                let bytes =
+                    fs::read(path).with_context(|| format!("read manifest {}", path.display()))?;
+                let value: serde_json::Value = serde_json::from_slice(&bytes)
+                    .with_context(|| format!("parse manifest {}", path.display()))?;
+                let name = path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string();
+                Ok((name, hex(&manifest_hash(&value)?)))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self {
+            wasm_code_hash_hex: hex(&wasm_code_hash(&wasm_bytes)),
+            manifests,
+        })
+    }
+
+    fn manifest_named_by(&self, hash_hex: &str) -> Option<&str> {
+        self.manifests
+            .iter()
+            .find(|(_, hash)| hash.eq_ignore_ascii_case(hash_hex))
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReceiptMismatch {
+    /// `wasm_hash_hex` or `manifest_hash_hex`.
+    pub field: &'static str,
+    pub receipt: String,
+    /// The local hashes the receipt was compared with.
+    pub expected: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReceiptCheck {
+    pub index: usize,
+    pub oracle_id: String,
+    /// The local manifest the receipt's manifest hash belongs to.
+    pub manifest: Option<String>,
+    pub mismatches: Vec<ReceiptMismatch>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReceiptReport {
+    pub wasm_code_hash: String,
+    pub receipts: Vec<ReceiptCheck>,
+}
+
+impl ReceiptReport {
+    pub fn mismatched(&self) -> impl Iterator<Item = &ReceiptCheck> {
+        self.receipts
+            .iter()
+            .filter(|check| !check.mismatches.is_empty())
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.mismatched().next().is_none()
+    }
+}
+
+pub fn verify_policy_oracle_receipts(
+    receipts: &[PolicyOracleReceipt],
+    local: &LocalArtifacts,
+) -> ReceiptReport {
+    let checks = receipts
+        .iter()
+        .enumerate()
+        .map(|(index, receipt)| {
+            let mut mismatches = Vec::new();
+            if !receipt
+                .wasm_hash_hex
+                .eq_ignore_ascii_case(&local.wasm_code_hash_hex)
+            {
+                mismatches.push(ReceiptMismatch {
+                    field: "wasm_hash_hex",
+                    receipt: receipt.wasm_hash_hex.clone(),
+                    expected: vec![local.wasm_code_hash_hex.clone()],
+                });
+            }
+            let manifest = local.manifest_named_by(&receipt.manifest_hash_hex);
+            if manifest.is_none() {
+                mismatches.push(ReceiptMismatch {
+                    field: "manifest_hash_hex",
+                    receipt: receipt.manifest_hash_hex.clone(),
+                    expected: local
+                        .manifests
+                        .iter()
+                        .map(|(_, hash)| hash.clone())
+                        .collect(),
+                });
+            }
+            ReceiptCheck {
+                index,
+                oracle_id: receipt.oracle_id.clone(),
+                manifest: manifest.map(str::to_string),
+                mismatches,
+            }
+        })
+        .collect();
+    ReceiptReport {
+        wasm_code_hash: local.wasm_code_hash_hex.clone(),
+        receipts: checks,
+    }
+}
@@ -0,0 +1,457 @@
+//! Local claim registry.
+//!
+//! Every claim subcommand appends a [`JournalEntry`] to `journal.jsonl` and
+//! then rewrites `index.json`, which holds the latest [`ClaimRecord`] per
+//! claim id. The journal is the source of truth: entries past the index's
+//! recorded offset are replayed on open, and a torn trailing line left by an
+//! interrupted write is truncated before the next append.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+
+pub const JOURNAL_FILE: &str = "journal.jsonl";
+pub const INDEX_FILE: &str = "index.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimStage {
+    Created,
+    Committed,
+    Frozen,
+    Sealed,
+    Executed,
+    CapsuleFetched,
+    Revoked,
+}
+
+impl ClaimStage {
+    /// The `claim` subcommand that continues an interrupted lifecycle.
+    pub fn next_step(self) -> Option<&'static str> {
+        match self {
+            Self::Created => Some("commit"),
+            Self::Committed => Some("freeze"),
+            Self::Frozen => Some("seal"),
+            Self::Sealed => Some("execute"),
+            Self::Executed => Some("fetch-capsule"),
+            Self::CapsuleFetched | Self::Revoked => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ClaimEvent {
+    Created {
+        topic_id_hex: String,
+        local_topic_id_hex: String,
+        endpoint: String,
+    },
+    Committed {
+        wasm_hash_hex: String,
+    },
+    WasmCommitted {
+        wasm_hash_hex: String,
+    },
+    Frozen,
+    Sealed,
+    Executed {
+        certified: bool,
+        e_value: f64,
+        canonical_output_hex: String,
+    },
+    CapsuleFetched {
+        capsule_sha256_hex: String,
+        etl_index: u64,
+        tree_size: u64,
+        capsule_path: String,
+    },
+    Revoked {
+        reason: String,
+    },
+}
+
+impl ClaimEvent {
+    /// Stage reached by this event; `WasmCommitted` leaves the stage unchanged.
+    pub fn stage(&self) -> Option<ClaimStage> {
+        match self {
+            Self::Created { .. } => Some(ClaimStage::Created),
+            Self::Committed { .. } => Some(ClaimStage::Committed),
+            Self::WasmCommitted { .. } => None,
+            Self::Frozen => Some(ClaimStage::Frozen),
+            Self::Sealed => Some(ClaimStage::Sealed),
+            Self::Executed { .. } => Some(ClaimStage::Executed),
+            Self::CapsuleFetched { .. } => Some(ClaimStage::CapsuleFetched),
+            Self::Revoked { .. } => Some(ClaimStage::Revoked),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub seq: u64,
+    pub timestamp_unix: u64,
+    pub claim_id_hex: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claim_name: Option<String>,
+    #[serde(flatten)]
+    pub event: ClaimEvent,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionRecord {
+    pub certified: bool,
+    pub e_value: f64,
+    pub canonical_output_hex: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapsuleRecord {
+    pub capsule_sha256_hex: String,
+    pub etl_index: u64,
+    pub tree_size: u64,
+    pub capsule_path: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClaimRecord {
+    pub claim_id_hex: String,
+    pub claim_name: Option<String>,
+    pub topic_id_hex: Option<String>,
+    pub local_topic_id_hex: Option<String>,
+    pub endpoint: Option<String>,
+    pub stage: ClaimStage,
+    pub wasm_hash_hex: Option<String>,
+    pub execution: Option<ExecutionRecord>,
+    pub capsule: Option<CapsuleRecord>,
+    pub revocation_reason: Option<String>,
+    pub first_seq: u64,
+    pub last_seq: u64,
+    pub updated_unix: u64,
+}
+
+impl ClaimRecord {
+    fn new(entry: &JournalEntry) -> Self {
+        Self {
+            claim_id_hex: entry.claim_id_hex.clone(),
+            claim_name: None,
+            topic_id_hex: None,
+            local_topic_id_hex: None,
+            endpoint: None,
+            stage: ClaimStage::Created,
+            wasm_hash_hex: None,
+            execution: None,
+            capsule: None,
+            revocation_reason: None,
+            first_seq: entry.seq,
+            last_seq: entry.seq,
+            updated_unix: entry.timestamp_unix,
+        }
+    }
+
+    fn apply(&mut self, entry: &JournalEntry) {
+        if entry.claim_name.is_some() {
+            self.claim_name.clone_from(&entry.claim_name);
+        }
+        if let Some(stage) = entry.event.stage() {
+            self.stage = stage;
+        }
+        self.last_seq = entry.seq;
+        self.updated_unix = entry.timestamp_unix;
+        match &entry.event {
+            ClaimEvent::Created {
+                topic_id_hex,
+                local_topic_id_hex,
+                endpoint,
+            } => {
+                self.topic_id_hex = Some(topic_id_hex.clone());
+                self.local_topic_id_hex = Some(local_topic_id_hex.clone());
+                self.endpoint = Some(endpoint.clone());
+            }
+            ClaimEvent::Committed { wasm_hash_hex }
+            | ClaimEvent::WasmCommitted { wasm_hash_hex } => {
+                self.wasm_hash_hex = Some(wasm_hash_hex.clone());
+            }
+            ClaimEvent::Frozen | ClaimEvent::Sealed => {}
+            ClaimEvent::Executed {
+                certified,
+                e_value,
+                canonical_output_hex,
+            } => {
+                self.execution = Some(ExecutionRecord {
+                    certified: *certified,
+                    e_value: *e_value,
+                    canonical_output_hex: canonical_output_hex.clone(),
+                });
+            }
+            ClaimEvent::CapsuleFetched {
+                capsule_sha256_hex,
+                etl_index,
+                tree_size,
+                capsule_path,
+            } => {
+                self.capsule = Some(CapsuleRecord {
+                    capsule_sha256_hex: capsule_sha256_hex.clone(),
+                    etl_index: *etl_index,
+                    tree_size: *tree_size,
+                    capsule_path: capsule_path.clone(),
+                });
+            }
+            ClaimEvent::Revoked { reason } => {
+                self.revocation_reason = Some(reason.clone());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct RegistryIndex {
+    journal_offset: u64,
+    next_seq: u64,
+    claims: BTreeMap<String, ClaimRecord>,
+}
+
+impl RegistryIndex {
+    fn apply(&mut self, entry: &JournalEntry) {
+        self.claims
+            .entry(entry.claim_id_hex.clone())
+            .or_insert_with(|| ClaimRecord::new(entry))
+            .apply(entry);
+        self.next_seq = self.next_seq.max(entry.seq + 1);
+    }
+}
+
+// Selects a claim by hex id or by the name it was created with. (Not a doc
+// comment: clap would use it as the help text of every flattening subcommand.)
+#[derive(Debug, Clone, Default, clap::Args)]
+#[group(required = true, multiple = false)]
+pub struct ClaimSelector {
+    /// Hex claim id returned by `claim create`.
+    #[arg(long)]
+    pub claim_id: Option<String>,
+    /// Name passed to `claim create`, looked up in the local registry.
+    #[arg(long)]
+    pub claim_name: Option<String>,
+}
+
+/// A claim id resolved through the registry, with its local name if known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedClaim {
+    pub claim_id_hex: String,
+    pub claim_name: Option<String>,
+}
+
+impl ResolvedClaim {
+    /// Directory name under `.discos/claims` for this claim's local files.
+    pub fn dir_name(&self) -> &str {
+        self.claim_name.as_deref().unwrap_or(&self.claim_id_hex)
+    }
+}
+
+#[derive(Debug)]
+pub struct Registry {
+    root: PathBuf,
+    index: RegistryIndex,
+}
+
+impl Registry {
+    /// Opens the registry rooted at `root`, replaying any journal entries the
+    /// index has not seen yet. Nothing is created until the first record.
+    pub fn open(root: &Path) -> anyhow::Result<Self> {
+        let index = match fs::read(root.join(INDEX_FILE)) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => RegistryIndex::default(),
+            Err(err) => return Err(err).context("read claim registry index"),
+        };
+        let mut registry = Self {
+            root: root.to_path_buf(),
+            index,
+        };
+        if registry.catch_up()? {
+            registry.persist_index()?;
+        }
+        Ok(registry)
+    }
+
+    pub fn claims(&self) -> impl Iterator<Item = &ClaimRecord> {
+        self.index.claims.values()
+    }
+
+    pub fn get(&self, claim_id_hex: &str) -> Option<&ClaimRecord> {
+        self.index.claims.get(&claim_id_hex.to_ascii_lowercase())
+    }
+
+    /// Most recently created claim with the given name.
+    pub fn find_by_name(&self, claim_name: &str) -> Option<&ClaimRecord> {
+        self.claims()
+            .filter(|record| record.claim_name.as_deref() == Some(claim_name))
+            .max_by_key(|record| record.first_seq)
+    }
+
+    /// Resolves a selector to a claim id. Raw hex ids need not be registered.
+    pub fn resolve(&self, selector: &ClaimSelector) -> anyhow::Result<ResolvedClaim> {
+        match (&selector.claim_id, &selector.claim_name) {
+            (Some(claim_id), None) => {
+                let claim_id_hex = normalize_claim_id(claim_id)?;
+                let claim_name = self
+                    .get(&claim_id_hex)
+                    .and_then(|record| record.claim_name.clone());
+                Ok(ResolvedClaim {
+                    claim_id_hex,
+                    claim_name,
+                })
+            }
+            (None, Some(claim_name)) => {
+                let record = self.find_by_name(claim_name).ok_or_else(|| {
+                    anyhow!("no claim named `{claim_name}` in the local registry")
+                })?;
+                Ok(ResolvedClaim {
+                    claim_id_hex: record.claim_id_hex.clone(),
+                    claim_name: Some(claim_name.clone()),
+                })
+            }
+            _ => Err(anyhow!(
+                "exactly one of --claim-id or --claim-name is required"
+            )),
+        }
+    }
+
+    /// Appends an event to the journal and updates the index.
+    pub fn record(
+        &mut self,
+        claim_id_hex: &str,
+        claim_name: Option<&str>,
+        event: ClaimEvent,
+    ) -> anyhow::Result<&ClaimRecord> {
+        fs::create_dir_all(&self.root)
+            .with_context(|| format!("create claim registry {}", self.root.display()))?;
+        let mut journal = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.journal_path())
+            .context("open claim registry journal")?;
+        journal.lock().context("lock claim registry journal")?;
+        // Another process may have appended since we opened; anything left
+        // past the last complete line is a torn write and is dropped.
+        self.catch_up()?;
+        if journal.metadata()?.len() > self.index.journal_offset {
+            journal
+                .set_len(self.index.journal_offset)
+                .context("truncate torn claim registry journal tail")?;
+        }
+
+        let claim_id_hex = normalize_claim_id(claim_id_hex)?;
+        let entry = JournalEntry {
+            seq: self.index.next_seq,
+            timestamp_unix: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            claim_id_hex: claim_id_hex.clone(),
+            claim_name: claim_name.map(str::to_string),
+            event,
+        };
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        journal
+            .write_all(&line)
+            .context("append claim registry journal")?;
+        journal.sync_data().context("sync claim registry journal")?;
+
+        self.index.journal_offset += line.len() as u64;
+        self.index.apply(&entry);
+        self.persist_index()?;
+        self.index
+            .claims
+            .get(&claim_id_hex)
+            .ok_or_else(|| anyhow!("claim registry index lost {claim_id_hex}"))
+    }
+
+    /// Journal entries for one claim, oldest first.
+    pub fn history(&self, claim_id_hex: &str) -> anyhow::Result<Vec<JournalEntry>> {
+        let claim_id_hex = claim_id_hex.to_ascii_lowercase();
+        let (entries, _) = self.read_journal(0)?;
+        Ok(entries
+            .into_iter()
+            .filter(|entry| entry.claim_id_hex == claim_id_hex)
+            .collect())
+    }
+
+    fn journal_path(&self) -> PathBuf {
+        self.root.join(JOURNAL_FILE)
+    }
+
+    /// Reads complete journal lines starting at `offset`, returning them with
+    /// the offset just past the last complete line.
+    fn read_journal(&self, offset: u64) -> anyhow::Result<(Vec<JournalEntry>, u64)> {
+        let bytes = match fs::read(self.journal_path()) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok((Vec::new(), 0)),
+            Err(err) => return Err(err).context("read claim registry journal"),
+        };
+        let start = usize::try_from(offset)
+            .ok()
+            .filter(|start| *start <= bytes.len())
+            .ok_or_else(|| anyhow!("claim registry index is ahead of its journal"))?;
+        let mut entries = Vec::new();
+        let mut end = start;
+        for line in bytes[start..].split_inclusive(|b| *b == b'\n') {
+            if line.last() != Some(&b'\n') {
+                break;
+            }
+            let entry = serde_json::from_slice(line)
+                .with_context(|| format!("corrupt claim registry journal entry at byte {end}"))?;
+            entries.push(entry);
+            end += line.len();
+        }
+        Ok((entries, end as u64))
+    }
+
+    /// Applies journal entries past the index offset. Returns whether the
+    /// index changed.
+    fn catch_up(&mut self) -> anyhow::Result<bool> {
+        let journal_len = match fs::metadata(self.journal_path()) {
+            Ok(meta) => meta.len(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(err) => return Err(err).context("stat claim registry journal"),
+        };
+        if self.index.journal_offset > journal_len {
+            self.index = RegistryIndex::default();
+        }
+        if self.index.journal_offset == journal_len {
+            return Ok(false);
+        }
+        let (entries, end) = self.read_journal(self.index.journal_offset)?;
+        for entry in &entries {
+            self.index.apply(entry);
+        }
+        self.index.journal_offset = end;
+        Ok(true)
+    }
+
+    fn persist_index(&self) -> anyhow::Result<()> {
+        fs::create_dir_all(&self.root)?;
+        let tmp = self.root.join(format!("{INDEX_FILE}.tmp"));
+        fs::write(&tmp, serde_json::to_vec_pretty(&self.index)?)
+            .context("write claim registry index")?;
+        fs::rename(&tmp, self.root.join(INDEX_FILE)).context("replace claim registry index")
+    }
+}
+
+fn normalize_claim_id(claim_id: &str) -> anyhow::Result<String> {
+    let claim_id = claim_id.trim();
+    anyhow::ensure!(
+        !claim_id.is_empty()
+            && claim_id.len().is_multiple_of(2)
+            && claim_id.bytes().all(|b| b.is_ascii_hexdigit()),
+        "claim id must be non-empty hex, got `{claim_id}`"
+    );
+    Ok(claim_id.to_ascii_lowercase())
+}
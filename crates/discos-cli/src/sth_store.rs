@@ -0,0 +1,341 @@
+//! `sth_store`: every verified signed tree head, per endpoint and kernel key.
+//!
+//! Heads are appended to one JSONL journal under an exclusive file lock, so
+//! concurrent `discos` invocations can share it. Only heads whose signature
+//! verified against the kernel key are stored, with that signature, so the
+//! history can later be re-checked or handed to a third party. A trailing
+//! line without its newline is a torn write and is dropped on the next
+//! append.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    ops::Bound,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context};
+use discos_client::gossip::GossipHead;
+use discos_client::{
+    consistency_proof_from_pb, pb, verify_consistency_proof, verify_sth_signature,
+    ConsistencyProof, DiscosClient, SignedTreeHead,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::hex_codec::hex;
+
+pub const STH_STORE_FILE: &str = "sth_history.jsonl";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredSth {
+    pub endpoint: String,
+    pub kernel_pubkey_hex: String,
+    pub recorded_unix: u64,
+    #[serde(flatten)]
+    pub head: GossipHead,
+}
+
+/// How a newly seen head relates to the stored history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeadCheck {
+    /// Nothing is stored for this endpoint and key yet.
+    First,
+    /// The same head is already stored.
+    Known,
+    /// Proven to extend the stored head of size `from_tree_size`.
+    Extends { from_tree_size: u64 },
+}
+
+type LogKey = (String, String);
+
+#[derive(Debug)]
+pub struct SthStore {
+    path: PathBuf,
+    offset: u64,
+    heads: BTreeMap<LogKey, BTreeMap<u64, StoredSth>>,
+}
+
+impl SthStore {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let mut store = Self {
+            path: path.to_path_buf(),
+            offset: 0,
+            heads: BTreeMap::new(),
+        };
+        store.catch_up()?;
+        Ok(store)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Stored heads for one log, smallest tree first.
+    pub fn heads(&self, endpoint: &str, kernel_pubkey: &[u8]) -> impl Iterator<Item = &StoredSth> {
+        self.heads
+            .get(&log_key(endpoint, kernel_pubkey))
+            .into_iter()
+            .flat_map(|heads| heads.values())
+    }
+
+    pub fn latest(
+        &self,
+        endpoint: &str,
+        kernel_pubkey: &[u8],
+    ) -> anyhow::Result<Option<SignedTreeHead>> {
+        self.closest_at_or_before(endpoint, kernel_pubkey, u64::MAX)
+    }
+
+    /// The largest stored head with `tree_size <= tree_size`, signature
+    /// re-checked.
+    pub fn closest_at_or_before(
+        &self,
+        endpoint: &str,
+        kernel_pubkey: &[u8],
+        tree_size: u64,
+    ) -> anyhow::Result<Option<SignedTreeHead>> {
+        let stored = self
+            .heads
+            .get(&log_key(endpoint, kernel_pubkey))
+            .and_then(|heads| heads.range(..=tree_size).next_back())
+            .map(|(_, stored)| stored);
+        stored
+            .map(|stored| verified(stored, kernel_pubkey))
+            .transpose()
+    }
+
+    /// The smallest stored head with `tree_size > tree_size`.
+    fn closest_after(
+        &self,
+        endpoint: &str,
+        kernel_pubkey: &[u8],
+        tree_size: u64,
+    ) -> anyhow::Result<Option<SignedTreeHead>> {
+        let stored = self
+            .heads
+            .get(&log_key(endpoint, kernel_pubkey))
+            .and_then(|heads| {
+                heads
+                    .range((Bound::Excluded(tree_size), Bound::Unbounded))
+                    .next()
+            })
+            .map(|(_, stored)| stored);
+        stored
+            .map(|stored| verified(stored, kernel_pubkey))
+            .transpose()
+    }
+
+    /// Checks a verified `head` against the history: it must agree with any
+    /// stored head of the same size, be provably extended by the next larger
+    /// stored head, and provably extend the closest smaller one.
+    pub async fn check(
+        &self,
+        client: &mut DiscosClient,
+        endpoint: &str,
+        kernel_pubkey: &[u8],
+        head: &SignedTreeHead,
+    ) -> anyhow::Result<HeadCheck> {
+        if let Some(later) = self.closest_after(endpoint, kernel_pubkey, head.tree_size)? {
+            prove_consistency(client, head, &later).await?;
+        }
+        match self.closest_at_or_before(endpoint, kernel_pubkey, head.tree_size)? {
+            None => Ok(HeadCheck::First),
+            Some(stored) if stored.tree_size == head.tree_size => {
+                anyhow::ensure!(
+                    stored.root_hash == head.root_hash,
+                    "signed tree head {} for size {} conflicts with stored head {}",
+                    hex(&head.root_hash),
+                    head.tree_size,
+                    hex(&stored.root_hash)
+                );
+                Ok(HeadCheck::Known)
+            }
+            Some(stored) => {
+                prove_consistency(client, &stored, head).await?;
+                Ok(HeadCheck::Extends {
+                    from_tree_size: stored.tree_size,
+                })
+            }
+        }
+    }
+
+    /// Appends `head` after checking its signature. A head already stored is
+    /// not appended again; one that conflicts with a stored head of the same
+    /// size is refused.
+    pub fn record(
+        &mut self,
+        endpoint: &str,
+        kernel_pubkey: &[u8],
+        head: &SignedTreeHead,
+    ) -> anyhow::Result<()> {
+        verify_sth_signature(head, kernel_pubkey)
+            .context("refusing to store an unverified signed tree head")?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("create STH store dir {}", parent.display()))?;
+        }
+        let mut journal = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("open STH store {}", self.path.display()))?;
+        journal.lock().context("lock STH store")?;
+        // Pick up heads other processes stored since we opened.
+        self.catch_up()?;
+        if journal.metadata()?.len() > self.offset {
+            journal
+                .set_len(self.offset)
+                .context("truncate torn STH store tail")?;
+        }
+
+        let key = log_key(endpoint, kernel_pubkey);
+        if let Some(stored) = self
+            .heads
+            .get(&key)
+            .and_then(|heads| heads.get(&head.tree_size))
+        {
+            anyhow::ensure!(
+                stored.head.root_hash_hex == hex(&head.root_hash),
+                "signed tree head {} for size {} conflicts with stored head {}",
+                hex(&head.root_hash),
+                head.tree_size,
+                stored.head.root_hash_hex
+            );
+            return Ok(());
+        }
+
+        let stored = StoredSth {
+            endpoint: endpoint.to_string(),
+            kernel_pubkey_hex: key.1.clone(),
+            recorded_unix: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            head: head.into(),
+        };
+        let mut line = serde_json::to_vec(&stored)?;
+        line.push(b'\n');
+        journal.write_all(&line).context("append STH store")?;
+        journal.sync_data().context("sync STH store")?;
+        self.offset += line.len() as u64;
+        self.heads
+            .entry(key)
+            .or_default()
+            .insert(head.tree_size, stored);
+        Ok(())
+    }
+
+    /// Applies complete journal lines past `offset`.
+    fn catch_up(&mut self) -> anyhow::Result<()> {
+        let bytes = match fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => {
+                return Err(err).with_context(|| format!("read STH store {}", self.path.display()))
+            }
+        };
+        let start = usize::try_from(self.offset)
+            .ok()
+            .filter(|start| *start <= bytes.len())
+            .ok_or_else(|| anyhow!("STH store {} shrank while open", self.path.display()))?;
+        let mut end = start;
+        for line in bytes[start..].split_inclusive(|b| *b == b'\n') {
+            if line.last() != Some(&b'\n') {
+                break;
+            }
+            let stored: StoredSth = serde_json::from_slice(line)
+                .with_context(|| format!("corrupt STH store entry at byte {end}"))?;
+            self.heads
+                .entry((stored.endpoint.clone(), stored.kernel_pubkey_hex.clone()))
+                .or_default()
+                .insert(stored.head.tree_size, stored);
+            end += line.len();
+        }
+        self.offset = end as u64;
+        Ok(())
+    }
+}
+
+/// Fetches a consistency proof from `old` to `new` and checks it against
+/// both roots.
+pub async fn prove_consistency(
+    client: &mut DiscosClient,
+    old: &SignedTreeHead,
+    new: &SignedTreeHead,
+) -> anyhow::Result<ConsistencyProof> {
+    anyhow::ensure!(
+        old.tree_size <= new.tree_size,
+        "cannot prove tree size {} extends larger tree size {}",
+        new.tree_size,
+        old.tree_size
+    );
+    if old.tree_size == 0 || old.tree_size == new.tree_size {
+        // Nothing to fetch: every tree extends the empty one, and equal sizes
+        // must simply agree.
+        let proof = ConsistencyProof {
+            old_tree_size: old.tree_size,
+            new_tree_size: new.tree_size,
+            path: Vec::new(),
+        };
+        anyhow::ensure!(
+            old.tree_size == 0 || old.root_hash == new.root_hash,
+            "two signed roots for tree size {}",
+            new.tree_size
+        );
+        return Ok(proof);
+    }
+    let response = client
+        .get_consistency_proof(pb::GetConsistencyProofRequest {
+            old_tree_size: old.tree_size,
+            new_tree_size: new.tree_size,
+        })
+        .await?;
+    let proof = consistency_proof_from_pb(
+        response
+            .consistency_proof
+            .as_ref()
+            .context("missing consistency proof")?,
+    )?;
+    verify_extends(old, new, &proof)?;
+    Ok(proof)
+}
+
+/// Checks that `proof` shows `new` extends `old`.
+pub fn verify_extends(
+    old: &SignedTreeHead,
+    new: &SignedTreeHead,
+    proof: &ConsistencyProof,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        proof.old_tree_size == old.tree_size && proof.new_tree_size == new.tree_size,
+        "consistency proof covers {}..{}, expected {}..{}",
+        proof.old_tree_size,
+        proof.new_tree_size,
+        old.tree_size,
+        new.tree_size
+    );
+    anyhow::ensure!(
+        verify_consistency_proof(old.root_hash, new.root_hash, proof),
+        "tree head of size {} is not consistent with stored head of size {}",
+        new.tree_size,
+        old.tree_size
+    );
+    Ok(())
+}
+
+fn log_key(endpoint: &str, kernel_pubkey: &[u8]) -> LogKey {
+    (endpoint.to_string(), hex(kernel_pubkey))
+}
+
+fn verified(stored: &StoredSth, kernel_pubkey: &[u8]) -> anyhow::Result<SignedTreeHead> {
+    let head = stored.head.decode()?;
+    verify_sth_signature(&head, kernel_pubkey).with_context(|| {
+        format!(
+            "stored head of size {} is no longer signed by the kernel key",
+            head.tree_size
+        )
+    })?;
+    Ok(head)
+}
@@ -0,0 +1,183 @@
+//! `structured_output`: what the kernel's canonical output says.
+//!
+//! The canonical output of an execution is decoded back into a structured
+//! claim by the profile of the claim's output schema, checked against the
+//! capsule's structured output hash when a capsule is at hand, and compared
+//! field by field with the claim prepared locally by `claim create`.
+//! Differences from the local claim are reported rather than treated as
+//! failures: the kernel may legitimately fill in fields such as the decision.
+
+use std::path::Path;
+
+use anyhow::Context;
+use discos_builder::AlphaHIRManifest;
+use discos_client::canonical_output_matches_capsule;
+use discos_core::structured_profiles::StructuredClaimProfile;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::claim_inputs::{ALPHA_HIR_FILE, STRUCTURED_CLAIM_FILE};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldDiff {
+    /// Dotted path such as `quantities[0].value_q`.
+    pub field: String,
+    pub local: Value,
+    pub kernel: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CapsuleCheck {
+    pub matches: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LocalCheck {
+    pub matches: bool,
+    pub diffs: Vec<FieldDiff>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OutputReport {
+    /// `None` when the canonical output does not decode.
+    pub structured_claim: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decode_error: Option<String>,
+    /// Present when a capsule was available to check against.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capsule: Option<CapsuleCheck>,
+    /// Present when the output decoded and a local claim was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local: Option<LocalCheck>,
+}
+
+/// The capsule a canonical output is checked against, with the ids it must
+/// carry.
+#[derive(Debug, Clone, Copy)]
+pub struct CapsuleRef<'a> {
+    pub capsule_bytes: &'a [u8],
+    pub claim_id: &'a [u8],
+    pub topic_id: &'a [u8],
+}
+
+impl OutputReport {
+    pub fn build(
+        profile: &dyn StructuredClaimProfile,
+        canonical_output: &[u8],
+        capsule: Option<CapsuleRef<'_>>,
+        local: Option<&Value>,
+    ) -> Self {
+        let (structured_claim, decode_error) = match profile.decode_canonical(canonical_output) {
+            Ok(claim) => (Some(claim), None),
+            Err(err) => (None, Some(err)),
+        };
+        let capsule = capsule.map(|capsule| {
+            match canonical_output_matches_capsule(
+                canonical_output,
+                capsule.capsule_bytes,
+                capsule.claim_id,
+                capsule.topic_id,
+            ) {
+                Ok(()) => CapsuleCheck {
+                    matches: true,
+                    error: None,
+                },
+                Err(err) => CapsuleCheck {
+                    matches: false,
+                    error: Some(err.to_string()),
+                },
+            }
+        });
+        let local = local.zip(structured_claim.as_ref()).map(|(local, kernel)| {
+            let diffs = diff_claims(local, kernel);
+            LocalCheck {
+                matches: diffs.is_empty(),
+                diffs,
+            }
+        });
+        Self {
+            structured_claim,
+            decode_error,
+            capsule,
+            local,
+        }
+    }
+
+    pub fn capsule_mismatch(&self) -> bool {
+        self.capsule.as_ref().is_some_and(|check| !check.matches)
+    }
+}
+
+/// The structured claim `claim create` wrote into `dir`, if any, parsed with
+/// `profile`.
+pub fn load_local_claim(
+    dir: &Path,
+    profile: &dyn StructuredClaimProfile,
+) -> anyhow::Result<Option<Value>> {
+    let path = dir.join(STRUCTURED_CLAIM_FILE);
+    let Some(bytes) = read_if_present(&path)? else {
+        return Ok(None);
+    };
+    profile
+        .parse(&bytes)
+        .map(Some)
+        .map_err(|err| anyhow::anyhow!("structured claim {}: {err}", path.display()))
+}
+
+/// The output schema id bound by the alpha-HIR `claim create` wrote into
+/// `dir`, if any.
+pub fn local_output_schema_id(dir: &Path) -> anyhow::Result<Option<String>> {
+    let path = dir.join(ALPHA_HIR_FILE);
+    let Some(bytes) = read_if_present(&path)? else {
+        return Ok(None);
+    };
+    let alpha: AlphaHIRManifest =
+        serde_json::from_slice(&bytes).with_context(|| format!("parse {}", path.display()))?;
+    Ok(Some(alpha.output_schema_id))
+}
+
+fn read_if_present(path: &Path) -> anyhow::Result<Option<Vec<u8>>> {
+    match std::fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).with_context(|| format!("read {}", path.display())),
+    }
+}
+
+/// Fields whose values differ between the two claims, leaves only.
+pub fn diff_claims(local: &Value, kernel: &Value) -> Vec<FieldDiff> {
+    let mut diffs = Vec::new();
+    diff_values("", local, kernel, &mut diffs);
+    diffs
+}
+
+fn diff_values(path: &str, local: &Value, kernel: &Value, diffs: &mut Vec<FieldDiff>) {
+    match (local, kernel) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, a_value) in a {
+                let field = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                diff_values(&field, a_value, b.get(key).unwrap_or(&Value::Null), diffs);
+            }
+        }
+        // Hashes serialize as arrays of bytes; report them whole.
+        (Value::Array(a), Value::Array(b))
+            if a.len() == b.len() && a.iter().any(|v| v.is_object() || v.is_array()) =>
+        {
+            for (i, (a_value, b_value)) in a.iter().zip(b).enumerate() {
+                diff_values(&format!("{path}[{i}]"), a_value, b_value, diffs);
+            }
+        }
+        _ if local != kernel => diffs.push(FieldDiff {
+            field: path.to_string(),
+            local: local.clone(),
+            kernel: kernel.clone(),
+        }),
+        _ => {}
+    }
+}
@@ -0,0 +1,148 @@
+use discos_cli::bundle::{export_bundle, EvidenceBundle, TrustAnchor};
+use discos_client::{
+    sha256, signed_tree_head_from_pb, CapsuleValidity, DiscosClient, RevocationSet, SignedTreeHead,
+};
+use discos_testkit::{run_sample_claim, sample_create_request, MockDaemon, MockDaemonConfig};
+
+async fn start() -> (MockDaemon, String, DiscosClient) {
+    let daemon = MockDaemon::new(MockDaemonConfig::default()).expect("daemon");
+    let endpoint = daemon.spawn().await.expect("spawn daemon");
+    let client = DiscosClient::connect(&endpoint).await.expect("connect");
+    (daemon, endpoint, client)
+}
+
+async fn run_claim(client: &mut DiscosClient, daemon: &MockDaemon, name: &str) -> Vec<u8> {
+    run_sample_claim(
+        client,
+        sample_create_request(name),
+        &daemon.kernel_pubkey(),
+        None,
+    )
+    .await
+    .expect("capsule")
+    .claim_id
+}
+
+fn current_head(daemon: &MockDaemon) -> SignedTreeHead {
+    signed_tree_head_from_pb(&daemon.signed_tree_head()).expect("sth")
+}
+
+/// A bundle for `alpha` with a reference head from before it was logged and
+/// a snapshot holding one revocation of another claim.
+async fn exported() -> (MockDaemon, EvidenceBundle) {
+    let (daemon, endpoint, mut client) = start().await;
+    let pubkey = daemon.kernel_pubkey();
+    daemon.append_leaf(b"earlier");
+    let reference = current_head(&daemon);
+    let alpha = run_claim(&mut client, &daemon, "alpha").await;
+    let beta = run_claim(&mut client, &daemon, "beta").await;
+    let mut revocations = RevocationSet::default();
+    let entry = daemon.revoke(&beta, "RETRACTED").expect("revoke");
+    revocations.apply(&entry, &pubkey).expect("apply");
+
+    let bundle = export_bundle(
+        &mut client,
+        &endpoint,
+        &pubkey,
+        &alpha,
+        Some(&reference),
+        &revocations,
+    )
+    .await
+    .expect("export");
+    (daemon, bundle)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn exported_bundle_verifies_offline_after_a_round_trip() {
+    let (daemon, bundle) = exported().await;
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("alpha.bundle.json");
+    bundle.write(&path).expect("write");
+    let bundle = EvidenceBundle::read(&path).expect("read");
+
+    let pubkey = daemon.kernel_pubkey();
+    let report = bundle
+        .verify(&TrustAnchor::KernelKey(pubkey.to_vec()))
+        .expect("verify by key");
+    assert_eq!(report.leaf_index, 1);
+    assert_eq!(report.consistency_reference_tree_size, Some(1));
+    assert_eq!(report.revocations_checked, 1);
+    assert_eq!(report.validity, CapsuleValidity::Valid);
+    assert_eq!(
+        report.kernel_key_fingerprint,
+        bundle.kernel_key_fingerprint_hex
+    );
+
+    let fingerprint = sha256(&pubkey);
+    assert_eq!(
+        bundle
+            .verify(&TrustAnchor::Fingerprint(fingerprint))
+            .expect("verify by fingerprint"),
+        report
+    );
+    assert!(bundle
+        .verify(&TrustAnchor::KernelKey(daemon.rogue_pubkey().to_vec()))
+        .is_err());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn tampered_bundles_are_rejected() {
+    let (daemon, bundle) = exported().await;
+    let anchor = TrustAnchor::KernelKey(daemon.kernel_pubkey().to_vec());
+    bundle.verify(&anchor).expect("untouched bundle verifies");
+
+    let mut capsule = bundle.clone();
+    capsule.capsule_hex.replace_range(0..2, "5b");
+    assert!(capsule.verify(&anchor).is_err());
+
+    let mut claim = bundle.clone();
+    claim.claim_id_hex = "00".repeat(32);
+    assert!(claim.verify(&anchor).is_err());
+
+    let mut reference = bundle.clone();
+    if let Some(consistency) = reference.consistency.as_mut() {
+        consistency.reference.root_hash_hex = "00".repeat(32);
+    }
+    assert!(reference.verify(&anchor).is_err());
+
+    let mut dropped = bundle.clone();
+    dropped.revocations.entries.clear();
+    assert!(dropped.verify(&anchor).is_err());
+
+    let mut reason = bundle.clone();
+    reason.revocations.entries[0].reason_code = "OTHER".into();
+    assert!(reason.verify(&anchor).is_err());
+
+    let mut version = bundle.clone();
+    version.version += 1;
+    assert!(version.verify(&anchor).is_err());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn bundles_for_revoked_claims_verify_as_revoked() {
+    let (daemon, endpoint, mut client) = start().await;
+    let pubkey = daemon.kernel_pubkey();
+    let claim = run_claim(&mut client, &daemon, "gamma").await;
+    let mut revocations = RevocationSet::default();
+    let entry = daemon.revoke(&claim, "RETRACTED").expect("revoke");
+    revocations.apply(&entry, &pubkey).expect("apply");
+
+    let bundle = export_bundle(&mut client, &endpoint, &pubkey, &claim, None, &revocations)
+        .await
+        .expect("export");
+    let report = bundle
+        .verify(&TrustAnchor::KernelKey(pubkey.to_vec()))
+        .expect("revoked bundles still verify");
+    assert_eq!(
+        report.validity,
+        CapsuleValidity::Revoked {
+            reason_code: "RETRACTED".to_string(),
+            logical_epoch: entry.logical_epoch,
+        }
+    );
+    let json = serde_json::to_value(&report).expect("json");
+    assert_eq!(json["validity"]["status"], "revoked");
+    assert_eq!(json["validity"]["reason_code"], "RETRACTED");
+    assert_eq!(json["validity_source"], "exporter_snapshot");
+}
@@ -24,17 +24,33 @@ fn extracts_and_formats_policy_oracle_receipts() {
         ]
     });
 
-    let receipts = extract_policy_oracle_receipts(&capsule);
+    // Receipts are only read from a strictly valid v1 capsule, and this one
+    // lacks the ids and hashes; without its schema it decodes as legacy.
+    let err = extract_policy_oracle_receipts(&capsule).expect_err("malformed v1");
+    assert!(format!("{err:#}").contains("claim_id_hex"), "{err:#}");
+    assert!(extract_oracle_metadata(&capsule).is_err());
+    let mut unversioned = capsule.clone();
+    unversioned
+        .as_object_mut()
+        .expect("object")
+        .remove("schema");
+    let receipts = extract_policy_oracle_receipts(&unversioned).expect("receipts");
     assert_eq!(receipts.len(), 1);
     assert_eq!(receipts[0].oracle_id, "super-judge-1");
 
-    let oracle = extract_oracle_metadata(&capsule);
-    assert_eq!(oracle.oracle_id, "acme.safety.v1");
-    assert_eq!(oracle.oracle_resolution_hash, "cc33");
-    assert_eq!(oracle.oracle_manifest_hash, "dd44");
+    let oracle = extract_oracle_metadata(&unversioned).expect("oracle");
+    assert_eq!(oracle.oracle_id.as_deref(), Some("acme.safety.v1"));
+    assert_eq!(oracle.oracle_resolution_hash.as_deref(), Some("cc33"));
+    assert_eq!(oracle.oracle_manifest_hash.as_deref(), Some("dd44"));
 
-    let summary = build_capsule_print_summary(&capsule);
+    // The summary still shows the v1 capsule and says why it is not strict.
+    let summary = build_capsule_print_summary(&capsule).expect("summary");
     assert_eq!(summary["capsule"]["schema"], "evidenceos.claim-capsule.v1");
+    assert_eq!(summary["decoding"]["mode"], "legacy");
+    let strict_error = summary["decoding"]["strict_error"]
+        .as_str()
+        .expect("strict error");
+    assert!(strict_error.contains("claim_id_hex"), "{strict_error}");
     assert_eq!(summary["capsule"]["decision"], "defer");
     assert_eq!(summary["oracle"]["oracle_id"], "acme.safety.v1");
     assert_eq!(
@@ -51,12 +67,104 @@ fn missing_policy_oracle_receipts_is_backward_compatible() {
         "decision": "allow"
     });
 
-    let receipts = extract_policy_oracle_receipts(&capsule);
+    assert!(extract_policy_oracle_receipts(&capsule).is_err());
+    let mut unversioned = capsule.clone();
+    unversioned
+        .as_object_mut()
+        .expect("object")
+        .remove("schema");
+    let receipts = extract_policy_oracle_receipts(&unversioned).expect("receipts");
     assert!(receipts.is_empty());
 
-    let summary = build_capsule_print_summary(&capsule);
+    let summary = build_capsule_print_summary(&capsule).expect("summary");
     assert_eq!(summary["policy_oracle_receipts"], serde_json::json!([]));
-    assert_eq!(summary["oracle"]["oracle_id"], "");
-    assert_eq!(summary["oracle"]["oracle_resolution_hash"], "");
-    assert_eq!(summary["oracle"]["oracle_manifest_hash"], "");
+    assert_eq!(summary["oracle"]["oracle_id"], serde_json::Value::Null);
+    assert_eq!(
+        summary["oracle"]["oracle_resolution_hash"],
+        serde_json::Value::Null
+    );
+    assert_eq!(
+        summary["oracle"]["oracle_manifest_hash"],
+        serde_json::Value::Null
+    );
+    assert_eq!(summary["capsule"]["e_value"], serde_json::Value::Null);
+    assert_eq!(summary["capsule"]["certified"], true);
+    assert_eq!(summary["decoding"]["mode"], "legacy");
+    let missing = summary["decoding"]["missing_fields"]
+        .as_array()
+        .expect("missing fields");
+    assert!(missing.contains(&serde_json::json!("e_value")));
+    assert!(missing.contains(&serde_json::json!("oracle_id")));
+    assert!(!missing.contains(&serde_json::json!("decision")));
+    assert!(!missing.contains(&serde_json::json!("schema")));
+    assert!(summary["decoding"]["strict_error"].is_string());
+}
+
+#[test]
+fn well_formed_capsules_decode_strictly_and_mistyped_fields_are_errors() {
+    let mut capsule = serde_json::json!({
+        "schema": "evidenceos.claim-capsule.v1",
+        "claim_id_hex": "11".repeat(32),
+        "topic_id_hex": "22".repeat(32),
+        "structured_output_hash_hex": "33".repeat(32),
+        "wasm_hash_hex": null,
+        "oracle_id": "builtin.accuracy",
+        "certified": false,
+        "e_value": 0.5,
+        "decision": "defer",
+        "reason_codes": [],
+        "policy_oracle_receipts": [{
+            "oracle_id": "super-judge-1",
+            "decision": "veto",
+            "wasm_hash_hex": "aa".repeat(32),
+            "manifest_hash_hex": "bb".repeat(32)
+        }]
+    });
+    // A v1 capsule missing a field is malformed: its receipts are not read,
+    // but the summary still shows it, decoded leniently.
+    let err = extract_policy_oracle_receipts(&capsule).expect_err("malformed v1");
+    assert!(format!("{err:#}").contains("reason_code"), "{err:#}");
+    let summary = build_capsule_print_summary(&capsule).expect("summary");
+    assert_eq!(summary["decoding"]["mode"], "legacy");
+    assert_eq!(
+        summary["decoding"]["missing_fields"],
+        serde_json::json!(["policy_oracle_receipts[0].reason_code"])
+    );
+    assert!(summary["decoding"]["strict_error"]
+        .as_str()
+        .is_some_and(|error| error.contains("reason_code")));
+    let mut unversioned = capsule.clone();
+    unversioned
+        .as_object_mut()
+        .expect("object")
+        .remove("schema");
+    let summary = build_capsule_print_summary(&unversioned).expect("summary");
+    assert_eq!(summary["decoding"]["mode"], "legacy");
+    assert_eq!(
+        summary["decoding"]["missing_fields"],
+        serde_json::json!(["schema", "policy_oracle_receipts[0].reason_code"])
+    );
+    assert_eq!(
+        summary["policy_oracle_receipts"][0]["reason_code"],
+        serde_json::Value::Null
+    );
+
+    capsule["policy_oracle_receipts"][0]["reason_code"] = "SJ_VETO".into();
+    let summary = build_capsule_print_summary(&capsule).expect("summary");
+    assert_eq!(summary["decoding"]["mode"], "strict");
+    assert_eq!(summary["capsule"]["e_value"], 0.5);
+    assert_eq!(summary["oracle"]["oracle_id"], "builtin.accuracy");
+
+    // A field outside the v1 schema fails strict decoding too.
+    let mut extended = capsule.clone();
+    extended["kernel_build"] = "v2.3".into();
+    let summary = build_capsule_print_summary(&extended).expect("summary");
+    assert_eq!(summary["decoding"]["mode"], "legacy");
+    assert!(summary["decoding"]["strict_error"]
+        .as_str()
+        .is_some_and(|error| error.contains("kernel_build")));
+    assert_eq!(summary["capsule"]["e_value"], 0.5);
+
+    capsule["e_value"] = "0.5".into();
+    assert!(build_capsule_print_summary(&capsule).is_err());
 }
@@ -0,0 +1,136 @@
+use std::{fs, path::PathBuf};
+
+use discos_builder::{build_restricted_wasm, manifest_hash, AlphaHIRManifest};
+use discos_cli::claim_inputs::{
+    ClaimInputPaths, ClaimInputs, STRUCTURED_CLAIM_CANONICAL_FILE, WASM_FILE,
+};
+use discos_core::structured_profiles::ProfileRegistry;
+
+fn vector_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../../test_vectors/structured_claims/valid")
+        .join(name)
+}
+
+#[test]
+fn default_inputs_produce_all_topic_signals() {
+    let inputs = ClaimInputs::load("demo", "cbrn-sc.v1", &ClaimInputPaths::default())
+        .expect("default inputs");
+    assert_eq!(inputs.code_hash, build_restricted_wasm().code_hash);
+    assert_eq!(
+        inputs.signals.semantic_hash,
+        Some(manifest_hash(&inputs.alpha).expect("alpha hash"))
+    );
+    assert!(inputs.signals.dependency_merkle_root.is_some());
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    inputs.write_to(dir.path()).expect("write inputs");
+    assert_eq!(
+        fs::read(dir.path().join(WASM_FILE)).expect("wasm"),
+        inputs.wasm_bytes
+    );
+    assert_eq!(
+        fs::read(dir.path().join(STRUCTURED_CLAIM_CANONICAL_FILE)).expect("canonical"),
+        inputs.canonical_claim
+    );
+}
+
+#[test]
+fn structured_claim_file_is_canonicalized() {
+    let inputs = ClaimInputs::load(
+        "demo",
+        "cbrn-sc.v1",
+        &ClaimInputPaths {
+            structured_claim: Some(vector_path("heavy_min.json")),
+            ..ClaimInputPaths::default()
+        },
+    )
+    .expect("vector claim");
+    let expected = fs::read_to_string(vector_path("heavy_min.hex")).expect("hex vector");
+    let actual: String = inputs
+        .canonical_claim
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    assert_eq!(actual, expected.trim());
+}
+
+#[test]
+fn alpha_hir_must_bind_wasm_code_hash() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let alpha = AlphaHIRManifest {
+        plan_id: "demo".into(),
+        code_hash_hex: "00".repeat(32),
+        oracle_kinds: vec!["oracle_query".into()],
+        output_schema_id: "cbrn-sc.v1".into(),
+        nullspec_id: "nullspec.v1".into(),
+    };
+    let path = dir.path().join("alpha_hir.json");
+    fs::write(&path, serde_json::to_vec(&alpha).expect("alpha json")).expect("write alpha");
+
+    let err = ClaimInputs::load(
+        "demo",
+        "cbrn-sc.v1",
+        &ClaimInputPaths {
+            alpha_hir: Some(path),
+            ..ClaimInputPaths::default()
+        },
+    )
+    .expect_err("unbound alpha-HIR must be rejected");
+    assert!(err.to_string().contains("code_hash_hex"));
+}
+
+#[test]
+fn rejects_unrestricted_wasm_and_malformed_dag_hash() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let wasm = dir.path().join("module.wasm");
+    fs::write(&wasm, b"\0asm\x01\0\0\0").expect("write wasm");
+    assert!(ClaimInputs::load(
+        "demo",
+        "cbrn-sc.v1",
+        &ClaimInputPaths {
+            wasm: Some(wasm),
+            ..ClaimInputPaths::default()
+        },
+    )
+    .is_err());
+
+    let causal = dir.path().join("causal_dsl.json");
+    fs::write(&causal, br#"{"dag_hash":"abcd","adjustment_sets":[]}"#).expect("write causal");
+    assert!(ClaimInputs::load(
+        "demo",
+        "cbrn-sc.v1",
+        &ClaimInputPaths {
+            causal_dsl: Some(causal),
+            ..ClaimInputPaths::default()
+        },
+    )
+    .is_err());
+}
+
+#[test]
+fn structured_claim_is_parsed_by_the_schema_profile() {
+    let err = ClaimInputs::load("demo", "other.v1", &ClaimInputPaths::default())
+        .expect_err("unknown schema must be rejected");
+    assert!(err.to_string().contains("no structured claim profile"));
+
+    let registry = ProfileRegistry::builtin();
+    let profile = registry.resolve("cbrn_sc.v1").expect("alias");
+    let inputs = ClaimInputs::load_with_profile(
+        "demo",
+        profile,
+        &ClaimInputPaths {
+            structured_claim: Some(vector_path("heavy_min.json")),
+            ..ClaimInputPaths::default()
+        },
+    )
+    .expect("vector claim");
+    assert_eq!(inputs.alpha.output_schema_id, "cbrn-sc.v1");
+    assert_eq!(inputs.structured_claim["decision"], "heavy");
+    assert_eq!(
+        profile
+            .decode_canonical(&inputs.canonical_claim)
+            .expect("decode"),
+        inputs.structured_claim
+    );
+}
@@ -0,0 +1,137 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+use discos_cli::registry::{
+    ClaimEvent, ClaimSelector, ClaimStage, Registry, INDEX_FILE, JOURNAL_FILE,
+};
+
+const CLAIM_A: &str = "aa00aa00aa00aa00aa00aa00aa00aa00aa00aa00aa00aa00aa00aa00aa00aa00";
+const CLAIM_B: &str = "bb11bb11bb11bb11bb11bb11bb11bb11bb11bb11bb11bb11bb11bb11bb11bb11";
+
+fn created() -> ClaimEvent {
+    ClaimEvent::Created {
+        topic_id_hex: "22".repeat(32),
+        local_topic_id_hex: "22".repeat(32),
+        endpoint: "http://127.0.0.1:50051".into(),
+    }
+}
+
+#[test]
+fn records_lifecycle_and_survives_reopen() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let mut registry = Registry::open(dir.path()).expect("open");
+    registry
+        .record(CLAIM_A, Some("demo"), created())
+        .expect("created");
+    registry
+        .record(
+            CLAIM_A,
+            None,
+            ClaimEvent::Committed {
+                wasm_hash_hex: "33".repeat(32),
+            },
+        )
+        .expect("committed");
+    registry
+        .record(CLAIM_A, None, ClaimEvent::Frozen)
+        .expect("frozen");
+
+    let registry = Registry::open(dir.path()).expect("reopen");
+    let record = registry.get(CLAIM_A).expect("record");
+    assert_eq!(record.claim_name.as_deref(), Some("demo"));
+    assert_eq!(record.stage, ClaimStage::Frozen);
+    assert_eq!(record.stage.next_step(), Some("seal"));
+    assert_eq!(
+        registry.history(CLAIM_A).expect("history").len(),
+        3,
+        "every subcommand appends one journal entry"
+    );
+}
+
+#[test]
+fn resolves_names_to_latest_claim_and_accepts_unregistered_ids() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let mut registry = Registry::open(dir.path()).expect("open");
+    registry
+        .record(CLAIM_A, Some("demo"), created())
+        .expect("first");
+    registry
+        .record(CLAIM_B, Some("demo"), created())
+        .expect("second");
+
+    let by_name = registry
+        .resolve(&ClaimSelector {
+            claim_name: Some("demo".into()),
+            ..ClaimSelector::default()
+        })
+        .expect("by name");
+    assert_eq!(by_name.claim_id_hex, CLAIM_B);
+
+    let by_id = registry
+        .resolve(&ClaimSelector {
+            claim_id: Some(CLAIM_A.to_ascii_uppercase()),
+            ..ClaimSelector::default()
+        })
+        .expect("by id");
+    assert_eq!(by_id.claim_id_hex, CLAIM_A);
+    assert_eq!(by_id.dir_name(), "demo");
+
+    let unregistered = registry
+        .resolve(&ClaimSelector {
+            claim_id: Some("cc".repeat(32)),
+            ..ClaimSelector::default()
+        })
+        .expect("raw id");
+    assert_eq!(unregistered.claim_name, None);
+
+    assert!(registry
+        .resolve(&ClaimSelector {
+            claim_name: Some("missing".into()),
+            ..ClaimSelector::default()
+        })
+        .is_err());
+    assert!(registry
+        .resolve(&ClaimSelector {
+            claim_id: Some("not-hex".into()),
+            ..ClaimSelector::default()
+        })
+        .is_err());
+}
+
+#[test]
+fn rebuilds_index_from_journal_and_drops_torn_tail() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let mut registry = Registry::open(dir.path()).expect("open");
+    registry
+        .record(CLAIM_A, Some("demo"), created())
+        .expect("created");
+    drop(registry);
+
+    fs::remove_file(dir.path().join(INDEX_FILE)).expect("remove index");
+    OpenOptions::new()
+        .append(true)
+        .open(dir.path().join(JOURNAL_FILE))
+        .expect("open journal")
+        .write_all(br#"{"seq":1,"timestamp_unix":0,"claim_id_hex":"#)
+        .expect("torn write");
+
+    let mut registry = Registry::open(dir.path()).expect("reopen");
+    assert_eq!(
+        registry.get(CLAIM_A).map(|r| r.stage),
+        Some(ClaimStage::Created)
+    );
+    registry
+        .record(CLAIM_A, None, ClaimEvent::Sealed)
+        .expect("append after torn tail");
+
+    let journal = fs::read_to_string(dir.path().join(JOURNAL_FILE)).expect("journal");
+    assert_eq!(journal.lines().count(), 2);
+    for line in journal.lines() {
+        serde_json::from_str::<serde_json::Value>(line).expect("complete journal line");
+    }
+    let reopened = Registry::open(dir.path()).expect("reopen again");
+    assert_eq!(
+        reopened.get(CLAIM_A).map(|r| r.stage),
+        Some(ClaimStage::Sealed)
+    );
+}
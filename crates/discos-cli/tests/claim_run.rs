@@ -0,0 +1,186 @@
+use std::fs;
+
+use discos_cli::claim_run::{run_claim, ClaimRunSpec, RunCheckpoint, RunStep};
+use discos_cli::registry::Registry;
+use discos_client::DiscosClient;
+use discos_testkit::{ClaimStage, Fault, MockDaemon, MockDaemonConfig, Rpc};
+use tonic::Status;
+
+const SPEC: &str = r#"
+claim_name = "demo-1"
+lane = "cbrn"
+alpha_micros = 50000
+epoch_config_ref = "epoch/v1"
+holdout_ref = "holdout/default"
+epoch_size = 1024
+oracle_num_symbols = 1024
+access_credit = 100000
+
+[inputs]
+structured_claim = "claims/heavy_min.json"
+"#;
+
+#[test]
+fn spec_applies_defaults_and_resolves_inputs_against_spec_dir() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("claim.toml");
+    fs::write(&path, SPEC).expect("write spec");
+
+    let spec = ClaimRunSpec::load(&path).expect("spec");
+    assert_eq!(spec.output_schema_id, "cbrn-sc.v1");
+    assert_eq!(spec.oracle_id, "default");
+    assert_eq!(
+        spec.inputs.structured_claim,
+        Some(dir.path().join("claims/heavy_min.json"))
+    );
+    assert_eq!(spec.inputs.wasm, None);
+    assert_eq!(
+        spec.digest_hex().expect("digest"),
+        ClaimRunSpec::load(&path)
+            .expect("reload")
+            .digest_hex()
+            .expect("digest")
+    );
+}
+
+#[test]
+fn spec_rejects_unknown_fields() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("claim.toml");
+    fs::write(&path, format!("{SPEC}\nextra = true\n")).expect("write spec");
+    assert!(ClaimRunSpec::load(&path).is_err());
+
+    fs::write(&path, SPEC.replace("structured_claim", "structured")).expect("write spec");
+    assert!(ClaimRunSpec::load(&path).is_err());
+}
+
+#[test]
+fn missing_checkpoint_means_fresh_run() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    assert_eq!(RunCheckpoint::load(dir.path()).expect("load"), None);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn rerun_after_lost_responses_resumes_the_same_claim() {
+    let daemon = MockDaemon::new(MockDaemonConfig::default()).expect("daemon");
+    let endpoint = daemon.spawn().await.expect("spawn daemon");
+    let mut client = DiscosClient::connect(&endpoint).await.expect("connect");
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("claim.toml");
+    let spec = SPEC.split("[inputs]").next().expect("spec head");
+    fs::write(&path, spec).expect("write spec");
+    let spec = ClaimRunSpec::load(&path).expect("spec");
+    let claim_dir = dir.path().join("demo-1");
+    let mut registry = Registry::open(&dir.path().join("registry")).expect("registry");
+    let pubkey = daemon.kernel_pubkey();
+    let lost = || Fault::DropResponse(Status::unavailable("connection reset"));
+
+    // The daemon registers the claim but the response never arrives, so no
+    // checkpoint is written.
+    daemon.fail_next(Rpc::CreateClaimV2, lost());
+    run_claim(
+        &mut client,
+        &mut registry,
+        &spec,
+        &claim_dir,
+        &endpoint,
+        &pubkey,
+    )
+    .await
+    .expect_err("create response lost");
+    assert_eq!(daemon.claim_count(), 1);
+    assert_eq!(RunCheckpoint::load(&claim_dir).expect("load"), None);
+
+    // The rerun gets the original claim back, then loses a freeze response
+    // after the daemon froze it.
+    daemon.fail_next(Rpc::Freeze, lost());
+    run_claim(
+        &mut client,
+        &mut registry,
+        &spec,
+        &claim_dir,
+        &endpoint,
+        &pubkey,
+    )
+    .await
+    .expect_err("freeze response lost");
+    assert_eq!(daemon.claim_count(), 1);
+    let checkpoint = RunCheckpoint::load(&claim_dir)
+        .expect("load")
+        .expect("checkpoint");
+    assert_eq!(checkpoint.completed, RunStep::Commit);
+    let claim_id = (0..checkpoint.claim_id_hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&checkpoint.claim_id_hex[i..i + 2], 16).expect("hex"))
+        .collect::<Vec<_>>();
+    assert_eq!(daemon.claim_stage(&claim_id), Some(ClaimStage::Frozen));
+
+    let outcome = run_claim(
+        &mut client,
+        &mut registry,
+        &spec,
+        &claim_dir,
+        &endpoint,
+        &pubkey,
+    )
+    .await
+    .expect("resumed run");
+    assert_eq!(outcome.resumed_after, Some(RunStep::Commit));
+    assert_eq!(outcome.claim_id, checkpoint.claim_id_hex);
+    assert_eq!(daemon.claim_count(), 1);
+    assert_eq!(daemon.calls(Rpc::CreateClaimV2), 2);
+    assert_eq!(daemon.calls(Rpc::Freeze), 2);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn rerun_recovers_when_the_checkpoint_is_ahead_of_the_registry() {
+    let daemon = MockDaemon::new(MockDaemonConfig::default()).expect("daemon");
+    let endpoint = daemon.spawn().await.expect("spawn daemon");
+    let mut client = DiscosClient::connect(&endpoint).await.expect("connect");
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("claim.toml");
+    let spec = SPEC.split("[inputs]").next().expect("spec head");
+    fs::write(&path, spec).expect("write spec");
+    let spec = ClaimRunSpec::load(&path).expect("spec");
+    let claim_dir = dir.path().join("demo-1");
+    let pubkey = daemon.kernel_pubkey();
+
+    let mut registry = Registry::open(&dir.path().join("registry")).expect("registry");
+    let first = run_claim(
+        &mut client,
+        &mut registry,
+        &spec,
+        &claim_dir,
+        &endpoint,
+        &pubkey,
+    )
+    .await
+    .expect("first run");
+    let checkpoint = RunCheckpoint::load(&claim_dir)
+        .expect("load")
+        .expect("checkpoint");
+    assert_eq!(checkpoint.completed, RunStep::FetchCapsule);
+
+    // The checkpoint says the run finished, but none of its events reached
+    // this registry, as after a crash between checkpoint and journal write.
+    let mut registry = Registry::open(&dir.path().join("lost-registry")).expect("registry");
+    let outcome = run_claim(
+        &mut client,
+        &mut registry,
+        &spec,
+        &claim_dir,
+        &endpoint,
+        &pubkey,
+    )
+    .await
+    .expect("rerun recovers the capsule");
+    assert_eq!(outcome.resumed_after, Some(RunStep::FetchCapsule));
+    assert_eq!(outcome.claim_id, first.claim_id);
+    assert_eq!(outcome.capsule_sha256, first.capsule_sha256);
+    assert_eq!(outcome.etl_index, first.etl_index);
+    let record = registry.get(&first.claim_id).expect("claim recorded");
+    assert!(record.capsule.is_some());
+    assert_eq!(daemon.claim_count(), 1);
+    assert_eq!(daemon.calls(Rpc::CreateClaimV2), 1);
+    assert_eq!(daemon.calls(Rpc::FetchCapsule), 2);
+}
@@ -0,0 +1,52 @@
+use discos_cli::gossip::{import_bundle, HeadStatus};
+use discos_client::gossip::{ForkEvidence, SthBundle};
+use discos_client::{signed_tree_head_from_pb, DiscosClient};
+use discos_testkit::{MockDaemon, MockDaemonConfig};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn import_reports_each_head_and_writes_fork_evidence() {
+    let local = MockDaemon::new(MockDaemonConfig::default()).expect("daemon");
+    local.append_leaf(b"a");
+    let shared = signed_tree_head_from_pb(&local.signed_tree_head()).expect("sth");
+    local.append_leaf(b"b");
+    let endpoint = local.spawn().await.expect("spawn daemon");
+    let mut client = DiscosClient::connect(&endpoint).await.expect("connect");
+
+    let other = MockDaemon::new(MockDaemonConfig::default()).expect("daemon");
+    other.append_leaf(b"x");
+    other.append_leaf(b"y");
+    let forked = signed_tree_head_from_pb(&other.signed_tree_head()).expect("sth");
+    other.append_leaf(b"z");
+    let ahead = signed_tree_head_from_pb(&other.signed_tree_head()).expect("sth");
+
+    let pubkey = local.kernel_pubkey();
+    let bundle = SthBundle::new("http://other", &pubkey, &[shared, forked, ahead]);
+    let dir = tempfile::tempdir().expect("tempdir");
+    let report = import_bundle(
+        &mut client,
+        &endpoint,
+        &pubkey,
+        &bundle,
+        &dir.path().join("forks"),
+        &dir.path().join("reporter.key"),
+    )
+    .await
+    .expect("import");
+
+    let statuses = report.heads.iter().map(|h| h.status).collect::<Vec<_>>();
+    assert_eq!(
+        statuses,
+        vec![
+            HeadStatus::Consistent,
+            HeadStatus::Forked,
+            HeadStatus::Ahead
+        ]
+    );
+    assert_eq!(report.forks(), 1);
+    assert_eq!(report.local_tree_size, 2);
+    let path = report.heads[1].evidence.as_ref().expect("evidence path");
+    let evidence = ForkEvidence::read(path).expect("evidence");
+    evidence.verify(&pubkey).expect("evidence verifies");
+    assert_eq!(evidence.local_origin, endpoint);
+    assert_eq!(evidence.remote_origin, "http://other");
+}
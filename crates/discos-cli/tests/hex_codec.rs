@@ -0,0 +1,13 @@
+use discos_cli::hex_codec::{hex, unhex, unhex_32};
+
+#[test]
+fn decoding_accepts_only_hex_digits() {
+    assert_eq!(unhex(" 00ff10\n").expect("trimmed"), vec![0x00, 0xff, 0x10]);
+    assert_eq!(hex(&[0x00, 0xff, 0x10]), "00ff10");
+    for bad in ["+f", "-1", "0", "zz", "0x00"] {
+        assert!(unhex(bad).is_err(), "{bad}");
+    }
+    assert_eq!(unhex_32(&"ab".repeat(32)).expect("32 bytes"), [0xab; 32]);
+    assert!(unhex_32(&"ab".repeat(31)).is_err());
+    assert!(unhex_32(&format!("+f{}", "ab".repeat(31))).is_err());
+}
@@ -0,0 +1,190 @@
+use std::fs;
+use std::path::Path;
+
+use discos_cli::monitor::{AlertKind, Monitor, MonitorOptions, ALERTS_FILE, HISTORY_FILE};
+use discos_cli::registry::{ClaimEvent, Registry};
+use discos_client::{sha256, DiscosClient};
+use discos_testkit::{
+    run_sample_claim, sample_create_request, Fault, MockDaemon, MockDaemonConfig, Rpc,
+};
+
+async fn start() -> (MockDaemon, String, DiscosClient) {
+    let daemon = MockDaemon::new(MockDaemonConfig::default()).expect("daemon");
+    let endpoint = daemon.spawn().await.expect("spawn daemon");
+    let client = DiscosClient::connect(&endpoint).await.expect("connect");
+    (daemon, endpoint, client)
+}
+
+fn options(daemon: &MockDaemon, endpoint: &str, dir: &Path) -> MonitorOptions {
+    MonitorOptions {
+        endpoint: endpoint.to_string(),
+        kernel_pubkey: daemon.kernel_pubkey().to_vec(),
+        dir: dir.join("monitor"),
+        registry: Some(dir.join("registry")),
+        inclusion_samples: 4,
+        hook: None,
+    }
+}
+
+/// Runs a claim to its capsule and records it in the local registry, the way
+/// `discos claim fetch-capsule` does.
+async fn run_claim(
+    client: &mut DiscosClient,
+    daemon: &MockDaemon,
+    dir: &Path,
+    name: &str,
+) -> String {
+    let verified = run_sample_claim(
+        client,
+        sample_create_request(name),
+        &daemon.kernel_pubkey(),
+        None,
+    )
+    .await
+    .expect("capsule");
+
+    let claim_id_hex = hex(&verified.claim_id);
+    let capsule_path = dir.join(format!("{name}.capsule.json"));
+    fs::write(&capsule_path, &verified.capsule.capsule_bytes).expect("write capsule");
+    Registry::open(&dir.join("registry"))
+        .expect("registry")
+        .record(
+            &claim_id_hex,
+            Some(name),
+            ClaimEvent::CapsuleFetched {
+                capsule_sha256_hex: hex(&sha256(&verified.capsule.capsule_bytes)),
+                etl_index: verified.capsule.etl_index,
+                tree_size: verified.capsule.etl_index + 1,
+                capsule_path: capsule_path.display().to_string(),
+            },
+        )
+        .expect("record");
+    claim_id_hex
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn lines(path: &Path) -> Vec<serde_json::Value> {
+    fs::read_to_string(path)
+        .expect("read")
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("json line"))
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn polls_record_consistent_heads_and_sample_new_capsules() {
+    let (daemon, endpoint, mut client) = start().await;
+    let dir = tempfile::tempdir().expect("tempdir");
+    let alpha = run_claim(&mut client, &daemon, dir.path(), "alpha").await;
+
+    let mut monitor = Monitor::open(options(&daemon, &endpoint, dir.path())).expect("monitor");
+    let first = monitor.poll(&mut client).await.expect("first poll");
+    assert_eq!(first.tree_size, 1);
+    assert_eq!(first.previous_tree_size, None);
+    assert_eq!(first.inclusion_sampled, vec![alpha]);
+
+    let beta = run_claim(&mut client, &daemon, dir.path(), "beta").await;
+    daemon.append_leaf(b"unrelated");
+    let second = monitor.poll(&mut client).await.expect("second poll");
+    assert_eq!(second.tree_size, 3);
+    assert_eq!(second.previous_tree_size, Some(1));
+    assert_eq!(second.inclusion_sampled, vec![beta]);
+
+    let unchanged = monitor.poll(&mut client).await.expect("third poll");
+    assert!(unchanged.inclusion_sampled.is_empty());
+
+    let history = lines(&dir.path().join("monitor").join(HISTORY_FILE));
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[2]["seq"], 2);
+    assert_eq!(history[2]["consistent_with"], 3);
+
+    let reopened = Monitor::open(options(&daemon, &endpoint, dir.path())).expect("reopen");
+    let last = reopened.history().last().expect("last head");
+    assert_eq!((last.seq, last.head.tree_size), (2, 3));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn forged_head_raises_fork_alert_and_runs_hook() {
+    let (daemon, endpoint, mut client) = start().await;
+    daemon.append_leaf(b"a");
+    let dir = tempfile::tempdir().expect("tempdir");
+    let hook_out = dir.path().join("hook.out");
+    let mut options = options(&daemon, &endpoint, dir.path());
+    options.hook = Some(format!(
+        "printf '%s' \"$DISCOS_ALERT_KIND\" > '{}'",
+        hook_out.display()
+    ));
+    let mut monitor = Monitor::open(options).expect("monitor");
+    monitor.poll(&mut client).await.expect("first poll");
+
+    daemon.fail_next(Rpc::GetSignedTreeHead, Fault::ForgeProof);
+    let alert = monitor.poll(&mut client).await.expect_err("fork");
+    assert_eq!(alert.kind, AlertKind::Fork);
+    assert_eq!(alert.previous.as_ref().map(|h| h.tree_size), Some(1));
+    monitor.raise(&alert).expect("raise");
+
+    let alerts = lines(&dir.path().join("monitor").join(ALERTS_FILE));
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0]["kind"], "fork");
+    assert_eq!(fs::read_to_string(&hook_out).expect("hook ran"), "fork");
+    assert_eq!(
+        lines(&dir.path().join("monitor").join(HISTORY_FILE)).len(),
+        1,
+        "rejected heads are not recorded"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn bad_consistency_proof_and_foreign_signature_alert() {
+    let (daemon, endpoint, mut client) = start().await;
+    daemon.append_leaf(b"a");
+    let dir = tempfile::tempdir().expect("tempdir");
+    let mut monitor = Monitor::open(options(&daemon, &endpoint, dir.path())).expect("monitor");
+    monitor.poll(&mut client).await.expect("first poll");
+
+    daemon.append_leaf(b"b");
+    daemon.fail_next(Rpc::GetConsistencyProof, Fault::ForgeProof);
+    let alert = monitor.poll(&mut client).await.expect_err("inconsistent");
+    assert_eq!(alert.kind, AlertKind::Inconsistent);
+
+    daemon.fail_next(Rpc::GetSignedTreeHead, Fault::SwapKey);
+    let alert = monitor.poll(&mut client).await.expect_err("bad signature");
+    assert_eq!(alert.kind, AlertKind::BadSignature);
+    assert!(alert.head.is_some());
+
+    monitor
+        .poll(&mut client)
+        .await
+        .expect("recovers once faults clear");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn monitors_sharing_a_dir_keep_each_others_history() {
+    let (daemon, endpoint, mut client) = start().await;
+    daemon.append_leaf(b"a");
+    let dir = tempfile::tempdir().expect("tempdir");
+    let mut first = Monitor::open(options(&daemon, &endpoint, dir.path())).expect("first");
+    let mut second = Monitor::open(options(&daemon, &endpoint, dir.path())).expect("second");
+    first.poll(&mut client).await.expect("first poll");
+
+    // A torn line left by a crash is dropped, the other monitor's head is not.
+    let path = dir.path().join("monitor").join(HISTORY_FILE);
+    let mut file = fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .expect("open history");
+    std::io::Write::write_all(&mut file, b"{\"seq\":").expect("torn line");
+    drop(file);
+
+    daemon.append_leaf(b"b");
+    second.poll(&mut client).await.expect("second poll");
+    let history = lines(&path);
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0]["head"]["tree_size"], 1);
+    assert_eq!(history[1]["seq"], 1);
+    assert_eq!(history[1]["head"]["tree_size"], 2);
+    assert_eq!(second.history().last().map(|last| last.seq), Some(1));
+}
@@ -0,0 +1,88 @@
+use discos_builder::{manifest_hash, wasm_code_hash};
+use discos_cli::capsule::PolicyOracleReceipt;
+use discos_cli::claim_inputs::{ClaimInputPaths, ClaimInputs, ALPHA_HIR_FILE, WASM_FILE};
+use discos_cli::receipts::{verify_policy_oracle_receipts, LocalArtifacts};
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn receipt(
+    oracle_id: &str,
+    wasm_hash_hex: String,
+    manifest_hash_hex: String,
+) -> PolicyOracleReceipt {
+    PolicyOracleReceipt {
+        oracle_id: oracle_id.to_string(),
+        decision: "pass".to_string(),
+        reason_code: "SJ_PASS".to_string(),
+        wasm_hash_hex,
+        manifest_hash_hex,
+    }
+}
+
+#[test]
+fn receipts_must_name_the_committed_wasm_and_a_committed_manifest() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let inputs =
+        ClaimInputs::load("alpha", "cbrn-sc.v1", &ClaimInputPaths::default()).expect("inputs");
+    inputs.write_to(dir.path()).expect("write");
+    let local = LocalArtifacts::in_dir(dir.path()).expect("local artifacts");
+
+    let code_hash = hex(&wasm_code_hash(&inputs.wasm_bytes));
+    let alpha_hash = hex(&manifest_hash(&inputs.alpha).expect("hash"));
+    let causal_hash = hex(&manifest_hash(&inputs.causal).expect("hash"));
+    assert_eq!(local.wasm_code_hash_hex, code_hash);
+
+    let receipts = vec![
+        receipt("judge-ok", code_hash.to_uppercase(), alpha_hash.clone()),
+        receipt("judge-causal", code_hash.clone(), causal_hash),
+        receipt("judge-wasm", hex(&[7; 32]), alpha_hash.clone()),
+        receipt("judge-both", hex(&[7; 32]), hex(&[8; 32])),
+    ];
+    let report = verify_policy_oracle_receipts(&receipts, &local);
+    assert!(!report.is_ok());
+    assert_eq!(report.receipts[0].manifest.as_deref(), Some(ALPHA_HIR_FILE));
+    assert_eq!(
+        report.receipts[1].manifest.as_deref(),
+        Some("causal_dsl.json")
+    );
+
+    let mismatched = report.mismatched().collect::<Vec<_>>();
+    assert_eq!(mismatched.len(), 2);
+    assert_eq!(mismatched[0].oracle_id, "judge-wasm");
+    assert_eq!(mismatched[0].mismatches.len(), 1);
+    assert_eq!(mismatched[0].mismatches[0].field, "wasm_hash_hex");
+    assert_eq!(mismatched[0].mismatches[0].expected, vec![code_hash]);
+    let fields = mismatched[1]
+        .mismatches
+        .iter()
+        .map(|mismatch| mismatch.field)
+        .collect::<Vec<_>>();
+    assert_eq!(fields, vec!["wasm_hash_hex", "manifest_hash_hex"]);
+    assert_eq!(mismatched[1].mismatches[1].expected.len(), 3);
+
+    assert!(verify_policy_oracle_receipts(&receipts[..2], &local).is_ok());
+}
+
+#[test]
+fn receipts_are_checked_against_the_plain_wasm_bytes_not_the_artifact_hash() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let inputs =
+        ClaimInputs::load("alpha", "cbrn-sc.v1", &ClaimInputPaths::default()).expect("inputs");
+    inputs.write_to(dir.path()).expect("write");
+    let local = LocalArtifacts::in_dir(dir.path()).expect("local artifacts");
+    let alpha_hash = hex(&manifest_hash(&inputs.alpha).expect("hash"));
+
+    let artifact_hash = hex(&discos_builder::sha256(&inputs.wasm_bytes));
+    let report =
+        verify_policy_oracle_receipts(&[receipt("judge", artifact_hash, alpha_hash)], &local);
+    assert!(!report.is_ok(), "undomained sha256 must not pass");
+
+    std::fs::write(dir.path().join(WASM_FILE), b"\0asm\x01\0\0\0").expect("swap wasm");
+    let swapped = LocalArtifacts::in_dir(dir.path()).expect("local artifacts");
+    assert_ne!(swapped.wasm_code_hash_hex, local.wasm_code_hash_hex);
+
+    std::fs::remove_file(dir.path().join(ALPHA_HIR_FILE)).expect("remove");
+    assert!(LocalArtifacts::in_dir(dir.path()).is_err());
+}
@@ -0,0 +1,171 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+use discos_cli::sth_store::{HeadCheck, SthStore, STH_STORE_FILE};
+use discos_client::{pb, signed_tree_head_from_pb, DiscosClient, SignedTreeHead};
+use discos_testkit::{Fault, MockDaemon, MockDaemonConfig, Rpc};
+
+const ENDPOINT: &str = "http://log.example";
+
+async fn start() -> (MockDaemon, DiscosClient) {
+    let daemon = MockDaemon::new(MockDaemonConfig::default()).expect("daemon");
+    let endpoint = daemon.spawn().await.expect("spawn daemon");
+    let client = DiscosClient::connect(&endpoint).await.expect("connect");
+    (daemon, client)
+}
+
+fn grow(daemon: &MockDaemon, leaves: usize) -> SignedTreeHead {
+    for _ in 0..leaves {
+        daemon.append_leaf(format!("leaf-{}", daemon.tree_size()).as_bytes());
+    }
+    signed_tree_head_from_pb(&daemon.signed_tree_head()).expect("sth")
+}
+
+fn line_count(store: &SthStore) -> usize {
+    fs::read_to_string(store.path())
+        .expect("read")
+        .lines()
+        .count()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn heads_are_checked_recorded_and_queried_by_size() {
+    let (daemon, mut client) = start().await;
+    let pubkey = daemon.kernel_pubkey();
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join(STH_STORE_FILE);
+    let mut store = SthStore::open(&path).expect("store");
+
+    let h2 = grow(&daemon, 2);
+    let check = store.check(&mut client, ENDPOINT, &pubkey, &h2).await;
+    assert_eq!(check.expect("first"), HeadCheck::First);
+    store.record(ENDPOINT, &pubkey, &h2).expect("record");
+
+    let h5 = grow(&daemon, 3);
+    let check = store.check(&mut client, ENDPOINT, &pubkey, &h5).await;
+    assert_eq!(
+        check.expect("extends"),
+        HeadCheck::Extends { from_tree_size: 2 }
+    );
+    store.record(ENDPOINT, &pubkey, &h5).expect("record");
+    store.record(ENDPOINT, &pubkey, &h5).expect("record again");
+    assert_eq!(line_count(&store), 2, "known heads are not appended twice");
+
+    let check = store.check(&mut client, ENDPOINT, &pubkey, &h5).await;
+    assert_eq!(check.expect("known"), HeadCheck::Known);
+
+    let store = SthStore::open(&path).expect("reopen");
+    let closest = |size| {
+        store
+            .closest_at_or_before(ENDPOINT, &pubkey, size)
+            .expect("query")
+            .map(|head| head.tree_size)
+    };
+    assert_eq!(closest(1), None);
+    assert_eq!(closest(4), Some(2));
+    assert_eq!(closest(5), Some(5));
+    assert_eq!(closest(100), Some(5));
+    let stored = store.heads(ENDPOINT, &pubkey).collect::<Vec<_>>();
+    assert_eq!(stored[1].head.signature_hex.len(), 128);
+    assert!(store
+        .closest_at_or_before("http://other", &pubkey, 100)
+        .expect("query")
+        .is_none());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn older_heads_are_proven_against_later_stored_heads() {
+    let (daemon, mut client) = start().await;
+    let pubkey = daemon.kernel_pubkey();
+    let dir = tempfile::tempdir().expect("tempdir");
+    let mut store = SthStore::open(&dir.path().join(STH_STORE_FILE)).expect("store");
+
+    let h1 = grow(&daemon, 1);
+    let h3 = grow(&daemon, 2);
+    let h6 = grow(&daemon, 3);
+    store.record(ENDPOINT, &pubkey, &h1).expect("record");
+    store.record(ENDPOINT, &pubkey, &h6).expect("record");
+
+    let check = store.check(&mut client, ENDPOINT, &pubkey, &h3).await;
+    assert_eq!(
+        check.expect("between stored heads"),
+        HeadCheck::Extends { from_tree_size: 1 }
+    );
+
+    daemon.fail_next(Rpc::GetConsistencyProof, Fault::ForgeProof);
+    let err = store
+        .check(&mut client, ENDPOINT, &pubkey, &h3)
+        .await
+        .expect_err("forged proof");
+    assert!(err.to_string().contains("not consistent"), "{err:#}");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn conflicting_heads_are_refused() {
+    let (daemon, mut client) = start().await;
+    let pubkey = daemon.kernel_pubkey();
+    let dir = tempfile::tempdir().expect("tempdir");
+    let mut store = SthStore::open(&dir.path().join(STH_STORE_FILE)).expect("store");
+    let head = grow(&daemon, 2);
+    store.record(ENDPOINT, &pubkey, &head).expect("record");
+
+    daemon.fail_next(Rpc::GetSignedTreeHead, Fault::ForgeProof);
+    let forged = client
+        .get_signed_tree_head(pb::GetSignedTreeHeadRequest {})
+        .await
+        .expect("sth")
+        .signed_tree_head
+        .expect("sth");
+    let forged = signed_tree_head_from_pb(&forged).expect("sth");
+    assert_eq!(forged.tree_size, head.tree_size);
+
+    assert!(store
+        .check(&mut client, ENDPOINT, &pubkey, &forged)
+        .await
+        .is_err());
+    assert!(store.record(ENDPOINT, &pubkey, &forged).is_err());
+
+    let unsigned = SignedTreeHead {
+        signature: [0u8; 64],
+        ..grow(&daemon, 1)
+    };
+    assert!(store.record(ENDPOINT, &pubkey, &unsigned).is_err());
+    assert_eq!(line_count(&store), 1);
+}
+
+#[test]
+fn concurrent_writers_share_the_journal_and_torn_tails_are_dropped() {
+    let daemon = MockDaemon::new(MockDaemonConfig::default()).expect("daemon");
+    let pubkey = daemon.kernel_pubkey();
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join(STH_STORE_FILE);
+
+    let mut first = SthStore::open(&path).expect("store");
+    let mut second = SthStore::open(&path).expect("store");
+    first
+        .record(ENDPOINT, &pubkey, &grow(&daemon, 1))
+        .expect("record");
+    second
+        .record(ENDPOINT, &pubkey, &grow(&daemon, 1))
+        .expect("record");
+    assert_eq!(second.heads(ENDPOINT, &pubkey).count(), 2);
+
+    OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .expect("open")
+        .write_all(b"{\"endpoint\":")
+        .expect("torn write");
+    let mut third = SthStore::open(&path).expect("store with torn tail");
+    assert_eq!(third.heads(ENDPOINT, &pubkey).count(), 2);
+    third
+        .record(ENDPOINT, &pubkey, &grow(&daemon, 1))
+        .expect("record");
+
+    let reopened = SthStore::open(&path).expect("reopen");
+    let sizes = reopened
+        .heads(ENDPOINT, &pubkey)
+        .map(|stored| stored.head.tree_size)
+        .collect::<Vec<_>>();
+    assert_eq!(sizes, vec![1, 2, 3]);
+}
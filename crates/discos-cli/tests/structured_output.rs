@@ -0,0 +1,120 @@
+use discos_cli::claim_inputs::{ClaimInputPaths, ClaimInputs};
+use discos_cli::structured_output::{
+    diff_claims, load_local_claim, local_output_schema_id, CapsuleRef, OutputReport,
+};
+use discos_core::structured_claims::{
+    canonicalize_cbrn_claim, CbrnStructuredClaim, Decision, ReasonCode,
+};
+use discos_core::structured_profiles::CbrnScProfile;
+
+const CLAIM_ID: [u8; 32] = [0x11; 32];
+const TOPIC_ID: [u8; 32] = [0x22; 32];
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn capsule_for(output: &[u8]) -> Vec<u8> {
+    serde_json::to_vec(&serde_json::json!({
+        "structured_output_hash_hex": hex(&discos_client::sha256(output)),
+        "claim_id_hex": hex(&CLAIM_ID),
+        "topic_id_hex": hex(&TOPIC_ID),
+    }))
+    .expect("capsule json")
+}
+
+fn capsule_ref(capsule_bytes: &[u8]) -> CapsuleRef<'_> {
+    CapsuleRef {
+        capsule_bytes,
+        claim_id: &CLAIM_ID,
+        topic_id: &TOPIC_ID,
+    }
+}
+
+#[test]
+fn kernel_output_is_decoded_and_diffed_against_the_local_claim() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let inputs =
+        ClaimInputs::load("alpha", "cbrn-sc.v1", &ClaimInputPaths::default()).expect("inputs");
+    inputs.write_to(dir.path()).expect("write");
+    assert_eq!(
+        local_output_schema_id(dir.path()).expect("schema id"),
+        Some("cbrn-sc.v1".to_string())
+    );
+    let local = load_local_claim(dir.path(), &CbrnScProfile)
+        .expect("load")
+        .expect("local claim");
+
+    let report = OutputReport::build(
+        &CbrnScProfile,
+        &inputs.canonical_claim,
+        Some(capsule_ref(&capsule_for(&inputs.canonical_claim))),
+        Some(&local),
+    );
+    assert_eq!(report.structured_claim.as_ref(), Some(&local));
+    assert!(report.capsule.as_ref().expect("capsule check").matches);
+    assert!(report.local.as_ref().expect("local check").matches);
+
+    let mut kernel: CbrnStructuredClaim = serde_json::from_value(local.clone()).expect("typed");
+    kernel.decision = Decision::Reject;
+    kernel.quantities[0].value_q += 1;
+    kernel.reason_codes.push(ReasonCode::BelowThreshold);
+    kernel.etl_root = [9; 32];
+    let output = canonicalize_cbrn_claim(&kernel).expect("canonical");
+    let report = OutputReport::build(&CbrnScProfile, &output, None, Some(&local));
+    assert!(report.capsule.is_none());
+    let local_check = report.local.expect("local check");
+    assert!(!local_check.matches);
+    let fields = local_check
+        .diffs
+        .iter()
+        .map(|diff| diff.field.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        fields,
+        vec![
+            "decision",
+            "etl_root",
+            "quantities[0].value_q",
+            "reason_codes"
+        ]
+    );
+    assert_eq!(local_check.diffs[0].local, "pass");
+    assert_eq!(local_check.diffs[0].kernel, "reject");
+    assert_eq!(diff_claims(&local, &local), Vec::new());
+}
+
+#[test]
+fn capsule_mismatches_and_undecodable_output_are_reported() {
+    let inputs =
+        ClaimInputs::load("alpha", "cbrn-sc.v1", &ClaimInputPaths::default()).expect("inputs");
+
+    let report = OutputReport::build(
+        &CbrnScProfile,
+        &inputs.canonical_claim,
+        Some(capsule_ref(&capsule_for(b"other output"))),
+        Some(&inputs.structured_claim),
+    );
+    assert!(report.capsule_mismatch());
+    assert!(report.local.expect("local check").matches);
+
+    let opaque = b"{\"claim_name\":\"alpha\"}";
+    let report = OutputReport::build(
+        &CbrnScProfile,
+        opaque,
+        Some(capsule_ref(&capsule_for(opaque))),
+        Some(&inputs.structured_claim),
+    );
+    assert!(report.structured_claim.is_none());
+    assert!(report.decode_error.is_some());
+    assert!(!report.capsule_mismatch());
+    assert!(report.local.is_none());
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    assert!(load_local_claim(dir.path(), &CbrnScProfile)
+        .expect("load")
+        .is_none());
+    assert!(local_output_schema_id(dir.path())
+        .expect("schema id")
+        .is_none());
+}
@@ -0,0 +1,343 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Typed claim capsules.
+//!
+//! [`ClaimCapsule::decode`] is strict: the `schema` must be a version this
+//! crate knows, every required field must be present with the right type,
+//! hashes must be 32 bytes and unknown fields are rejected. Capsules written
+//! before a field existed go through [`ClaimCapsule::decode_legacy`] instead,
+//! which fills absent fields with defaults and lists them in
+//! [`LegacyCapsule::missing_fields`], so an old capsule and a malformed one
+//! can be told apart. Fields of the wrong type are an error in both modes.
+//! [`ClaimCapsule::decode_any`] picks the mode from the capsule itself: only
+//! a capsule without a `schema` is old, so one that names a schema and fails
+//! strict decoding is malformed, never legacy.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::ClientError;
+
+pub const CAPSULE_SCHEMA_V1: &str = "evidenceos.claim-capsule.v1";
+
+const CAPSULE_FIELDS: &[&str] = &[
+    "schema",
+    "claim_id_hex",
+    "topic_id_hex",
+    "structured_output_hash_hex",
+    "wasm_hash_hex",
+    "oracle_id",
+    "oracle_resolution_hash",
+    "oracle_manifest_hash",
+    "certified",
+    "e_value",
+    "decision",
+    "reason_codes",
+    "policy_oracle_receipts",
+];
+
+const RECEIPT_FIELDS: &[&str] = &[
+    "oracle_id",
+    "decision",
+    "reason_code",
+    "wasm_hash_hex",
+    "manifest_hash_hex",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapsuleSchema {
+    /// No `schema` field; only accepted by [`ClaimCapsule::decode_legacy`].
+    Unversioned,
+    V1,
+}
+
+impl CapsuleSchema {
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            CAPSULE_SCHEMA_V1 => Some(Self::V1),
+            _ => None,
+        }
+    }
+
+    pub fn id(self) -> Option<&'static str> {
+        match self {
+            Self::Unversioned => None,
+            Self::V1 => Some(CAPSULE_SCHEMA_V1),
+        }
+    }
+}
+
+/// Verdict of one policy oracle consulted while the capsule was issued.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyOracleReceipt {
+    pub oracle_id: String,
+    pub decision: String,
+    pub reason_code: String,
+    pub wasm_hash_hex: String,
+    pub manifest_hash_hex: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClaimCapsule {
+    pub schema: CapsuleSchema,
+    pub claim_id_hex: String,
+    pub topic_id_hex: String,
+    pub structured_output_hash_hex: String,
+    /// `null` when the claim was executed without a wasm module.
+    pub wasm_hash_hex: Option<String>,
+    pub oracle_id: String,
+    pub oracle_resolution_hash: Option<String>,
+    pub oracle_manifest_hash: Option<String>,
+    pub certified: bool,
+    pub e_value: f64,
+    pub decision: String,
+    pub reason_codes: Vec<String>,
+    pub policy_oracle_receipts: Vec<PolicyOracleReceipt>,
+}
+
+/// A capsule decoded leniently, with the fields it did not carry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegacyCapsule {
+    /// Absent fields hold their type's default here.
+    pub capsule: ClaimCapsule,
+    /// Dotted paths such as `e_value` or `policy_oracle_receipts[0].decision`.
+    pub missing_fields: Vec<String>,
+}
+
+impl LegacyCapsule {
+    pub fn is_missing(&self, field: &str) -> bool {
+        self.missing_fields.iter().any(|missing| missing == field)
+    }
+}
+
+impl ClaimCapsule {
+    pub fn decode(bytes: &[u8]) -> Result<Self, ClientError> {
+        Self::from_value(&parse(bytes)?)
+    }
+
+    pub fn decode_legacy(bytes: &[u8]) -> Result<LegacyCapsule, ClientError> {
+        Self::legacy_from_value(&parse(bytes)?)
+    }
+
+    pub fn decode_any(bytes: &[u8]) -> Result<LegacyCapsule, ClientError> {
+        Self::any_from_value(&parse(bytes)?)
+    }
+
+    pub fn from_value(value: &Value) -> Result<Self, ClientError> {
+        let obj = object(value, "capsule")?;
+        reject_unknown(obj, CAPSULE_FIELDS, "")?;
+        let (capsule, missing) = decode_fields(obj, true)?;
+        if !missing.is_empty() {
+            return Err(malformed(format!(
+                "missing required fields: {}",
+                missing.join(", ")
+            )));
+        }
+        capsule.validate()?;
+        Ok(capsule)
+    }
+
+    /// Strict decoding for a capsule with a `schema`, legacy decoding for
+    /// one without.
+    pub fn any_from_value(value: &Value) -> Result<LegacyCapsule, ClientError> {
+        if object(value, "capsule")?.contains_key("schema") {
+            Ok(LegacyCapsule {
+                capsule: Self::from_value(value)?,
+                missing_fields: Vec::new(),
+            })
+        } else {
+            Self::legacy_from_value(value)
+        }
+    }
+
+    pub fn legacy_from_value(value: &Value) -> Result<LegacyCapsule, ClientError> {
+        let (capsule, missing_fields) = decode_fields(object(value, "capsule")?, false)?;
+        Ok(LegacyCapsule {
+            capsule,
+            missing_fields,
+        })
+    }
+
+    fn validate(&self) -> Result<(), ClientError> {
+        hash32("claim_id_hex", &self.claim_id_hex)?;
+        hash32("topic_id_hex", &self.topic_id_hex)?;
+        hash32(
+            "structured_output_hash_hex",
+            &self.structured_output_hash_hex,
+        )?;
+        for (field, value) in [
+            ("wasm_hash_hex", &self.wasm_hash_hex),
+            ("oracle_resolution_hash", &self.oracle_resolution_hash),
+            ("oracle_manifest_hash", &self.oracle_manifest_hash),
+        ] {
+            if let Some(value) = value {
+                hash32(field, value)?;
+            }
+        }
+        if !self.e_value.is_finite() || self.e_value < 0.0 {
+            return Err(malformed(format!(
+                "e_value must be a non-negative number, got {}",
+                self.e_value
+            )));
+        }
+        if self.decision.is_empty() {
+            return Err(malformed("decision is empty".to_string()));
+        }
+        for (i, receipt) in self.policy_oracle_receipts.iter().enumerate() {
+            let prefix = format!("policy_oracle_receipts[{i}]");
+            hash32(&format!("{prefix}.wasm_hash_hex"), &receipt.wasm_hash_hex)?;
+            hash32(
+                &format!("{prefix}.manifest_hash_hex"),
+                &receipt.manifest_hash_hex,
+            )?;
+            if receipt.decision.is_empty() {
+                return Err(malformed(format!("{prefix}.decision is empty")));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn decode_fields(
+    obj: &Map<String, Value>,
+    strict: bool,
+) -> Result<(ClaimCapsule, Vec<String>), ClientError> {
+    let mut fields = Fields {
+        obj,
+        prefix: String::new(),
+        missing: Vec::new(),
+    };
+    let schema = match fields.optional::<String>("schema")? {
+        Some(id) => CapsuleSchema::from_id(&id)
+            .ok_or_else(|| malformed(format!("unsupported capsule schema {id:?}")))?,
+        None => {
+            fields.missing.push("schema".to_string());
+            CapsuleSchema::Unversioned
+        }
+    };
+
+    let mut receipts = Vec::new();
+    let mut missing_receipt_fields = Vec::new();
+    if let Some(values) = fields.optional::<Vec<Value>>("policy_oracle_receipts")? {
+        for (i, value) in values.iter().enumerate() {
+            let prefix = format!("policy_oracle_receipts[{i}]");
+            let obj = object(value, &prefix)?;
+            if strict {
+                reject_unknown(obj, RECEIPT_FIELDS, &format!("{prefix}."))?;
+            }
+            let mut receipt_fields = Fields {
+                obj,
+                prefix: format!("{prefix}."),
+                missing: Vec::new(),
+            };
+            receipts.push(PolicyOracleReceipt {
+                oracle_id: receipt_fields.required("oracle_id")?,
+                decision: receipt_fields.required("decision")?,
+                reason_code: receipt_fields.required("reason_code")?,
+                wasm_hash_hex: receipt_fields.required("wasm_hash_hex")?,
+                manifest_hash_hex: receipt_fields.required("manifest_hash_hex")?,
+            });
+            missing_receipt_fields.extend(receipt_fields.missing);
+        }
+    }
+
+    let capsule = ClaimCapsule {
+        schema,
+        claim_id_hex: fields.required("claim_id_hex")?,
+        topic_id_hex: fields.required("topic_id_hex")?,
+        structured_output_hash_hex: fields.required("structured_output_hash_hex")?,
+        wasm_hash_hex: fields.optional("wasm_hash_hex")?,
+        oracle_id: fields.required("oracle_id")?,
+        oracle_resolution_hash: fields.optional("oracle_resolution_hash")?,
+        oracle_manifest_hash: fields.optional("oracle_manifest_hash")?,
+        certified: fields.required("certified")?,
+        e_value: fields.required("e_value")?,
+        decision: fields.required("decision")?,
+        reason_codes: fields.required("reason_codes")?,
+        policy_oracle_receipts: receipts,
+    };
+    let mut missing = fields.missing;
+    missing.extend(missing_receipt_fields);
+    Ok((capsule, missing))
+}
+
+/// Typed access to one JSON object, collecting absent required fields.
+struct Fields<'a> {
+    obj: &'a Map<String, Value>,
+    prefix: String,
+    missing: Vec<String>,
+}
+
+impl Fields<'_> {
+    /// An absent or `null` field is recorded as missing and defaulted.
+    fn required<T: DeserializeOwned + Default>(&mut self, key: &str) -> Result<T, ClientError> {
+        match self.optional(key)? {
+            Some(value) => Ok(value),
+            None => {
+                self.missing.push(format!("{}{key}", self.prefix));
+                Ok(T::default())
+            }
+        }
+    }
+
+    fn optional<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, ClientError> {
+        match self.obj.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => T::deserialize(value)
+                .map(Some)
+                .map_err(|e| malformed(format!("field {}{key}: {e}", self.prefix))),
+        }
+    }
+}
+
+fn parse(bytes: &[u8]) -> Result<Value, ClientError> {
+    serde_json::from_slice(bytes)
+        .map_err(|e| malformed(format!("capsule_bytes is not valid JSON: {e}")))
+}
+
+fn object<'a>(value: &'a Value, what: &str) -> Result<&'a Map<String, Value>, ClientError> {
+    value
+        .as_object()
+        .ok_or_else(|| malformed(format!("{what} is not a JSON object")))
+}
+
+fn reject_unknown(
+    obj: &Map<String, Value>,
+    known: &[&str],
+    prefix: &str,
+) -> Result<(), ClientError> {
+    let unknown = obj
+        .keys()
+        .filter(|key| !known.contains(&key.as_str()))
+        .map(|key| format!("{prefix}{key}"))
+        .collect::<Vec<_>>();
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        Err(malformed(format!("unknown fields: {}", unknown.join(", "))))
+    }
+}
+
+fn hash32(field: &str, value: &str) -> Result<(), ClientError> {
+    match hex::decode(value) {
+        Ok(bytes) if bytes.len() == 32 => Ok(()),
+        _ => Err(malformed(format!("{field} must be 32 bytes of hex"))),
+    }
+}
+
+fn malformed(message: String) -> ClientError {
+    ClientError::VerificationFailed(format!("malformed capsule: {message}"))
+}
@@ -0,0 +1,317 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Signed tree heads as signed-note checkpoints.
+//!
+//! A checkpoint (C2SP `tlog-checkpoint`) is the note body
+//!
+//! ```text
+//! <origin>
+//! <tree size>
+//! <base64 root hash>
+//! ```
+//!
+//! followed by a blank line and one `— <key name> <base64(key id || sig)>`
+//! line per signature (C2SP `signed-note`). Off-the-shelf witnesses and
+//! monitors consume this format, and the kernel tree hashes leaves and nodes
+//! as RFC 9162 does. Consistency proofs are not quite RFC 9162 proofs,
+//! though: when the old size is a power of two, EvidenceOS prepends the old
+//! root to the path, where RFC 9162 omits it. A generic verifier has to drop
+//! that first node, or ask DiscOS to check the proof, before it can follow
+//! consistency between heads on its own.
+//!
+//! Kernel signatures cover `sth_signature_digest` rather than the note text,
+//! so they are not Ed25519 note signatures. They travel under key IDs derived
+//! with [`KERNEL_STH_SIGNATURE_TYPE`], which other note verifiers skip as an
+//! unknown type. Tools that only check Ed25519 notes can instead trust the
+//! exporting operator, who signs the note text with their own key; see
+//! [`note_verifier_key`].
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use sha2::{Digest, Sha256};
+
+use crate::{verify_sth_signature, ClientError, SignedTreeHead};
+
+/// Signed-note signature type of Ed25519 signatures over the note text.
+pub const ED25519_SIGNATURE_TYPE: u8 = 0x01;
+/// Signed-note signature type under which kernel STH signatures are carried.
+/// The key ID also covers [`KERNEL_STH_KEY_TAG`], so it cannot collide with
+/// other users of the extension type.
+pub const KERNEL_STH_SIGNATURE_TYPE: u8 = 0xff;
+pub const KERNEL_STH_KEY_TAG: &[u8] = b"evidenceos.sth-signature.v1";
+
+const SIGNATURE_PREFIX: &str = "\u{2014} ";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteSignature {
+    pub key_name: String,
+    pub key_id: [u8; 4],
+    pub signature: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub origin: String,
+    pub tree_size: u64,
+    pub root_hash: [u8; 32],
+    /// Extra body lines after the root hash, kept so that signatures over
+    /// the body still verify.
+    pub extensions: Vec<String>,
+    pub signatures: Vec<NoteSignature>,
+}
+
+fn invalid(what: impl std::fmt::Display) -> ClientError {
+    ClientError::InvalidInput(format!("checkpoint {what}"))
+}
+
+fn key_id(key_name: &str, signature_type: u8, key_material: &[&[u8]]) -> [u8; 4] {
+    let mut hasher = Sha256::new();
+    hasher.update(key_name.as_bytes());
+    hasher.update(b"\n");
+    hasher.update([signature_type]);
+    for part in key_material {
+        hasher.update(part);
+    }
+    let digest = hasher.finalize();
+    [digest[0], digest[1], digest[2], digest[3]]
+}
+
+/// Key ID of the kernel key `kernel_pubkey` under `key_name`.
+pub fn kernel_key_id(key_name: &str, kernel_pubkey: &[u8]) -> [u8; 4] {
+    key_id(
+        key_name,
+        KERNEL_STH_SIGNATURE_TYPE,
+        &[KERNEL_STH_KEY_TAG, kernel_pubkey],
+    )
+}
+
+/// Verifier key (`<name>+<key id>+<base64 key>`) that witnesses are
+/// configured with to accept notes signed by `key`.
+pub fn note_verifier_key(key_name: &str, key: &VerifyingKey) -> String {
+    let mut encoded = vec![ED25519_SIGNATURE_TYPE];
+    encoded.extend_from_slice(key.as_bytes());
+    format!(
+        "{key_name}+{}+{}",
+        hex::encode(key_id(key_name, ED25519_SIGNATURE_TYPE, &[key.as_bytes()])),
+        BASE64.encode(encoded)
+    )
+}
+
+fn check_key_name(key_name: &str) -> Result<(), ClientError> {
+    if key_name.is_empty() || key_name.contains(|c: char| c == '+' || c.is_whitespace()) {
+        return Err(invalid(format!(
+            "key name {key_name:?} must be non-empty without spaces or '+'"
+        )));
+    }
+    Ok(())
+}
+
+impl Checkpoint {
+    /// Checkpoint for `sth` that carries its kernel signature under key name
+    /// `origin`. The signature is checked first.
+    pub fn from_sth(
+        origin: &str,
+        sth: &SignedTreeHead,
+        kernel_pubkey: &[u8],
+    ) -> Result<Self, ClientError> {
+        check_key_name(origin)?;
+        verify_sth_signature(sth, kernel_pubkey)?;
+        Ok(Self {
+            origin: origin.to_string(),
+            tree_size: sth.tree_size,
+            root_hash: sth.root_hash,
+            extensions: Vec::new(),
+            signatures: vec![NoteSignature {
+                key_name: origin.to_string(),
+                key_id: kernel_key_id(origin, kernel_pubkey),
+                signature: sth.signature.to_vec(),
+            }],
+        })
+    }
+
+    /// The signed note body, ending in a newline.
+    pub fn body(&self) -> String {
+        let mut body = format!(
+            "{}\n{}\n{}\n",
+            self.origin,
+            self.tree_size,
+            BASE64.encode(self.root_hash)
+        );
+        for line in &self.extensions {
+            body.push_str(line);
+            body.push('\n');
+        }
+        body
+    }
+
+    /// Adds an Ed25519 note signature by `key` over the body.
+    pub fn sign(&mut self, key_name: &str, key: &SigningKey) -> Result<(), ClientError> {
+        check_key_name(key_name)?;
+        let signature = key.sign(self.body().as_bytes());
+        self.signatures.push(NoteSignature {
+            key_name: key_name.to_string(),
+            key_id: key_id(
+                key_name,
+                ED25519_SIGNATURE_TYPE,
+                &[key.verifying_key().as_bytes()],
+            ),
+            signature: signature.to_bytes().to_vec(),
+        });
+        Ok(())
+    }
+
+    pub fn to_note(&self) -> String {
+        let mut note = self.body();
+        note.push('\n');
+        for sig in &self.signatures {
+            let mut blob = sig.key_id.to_vec();
+            blob.extend_from_slice(&sig.signature);
+            note.push_str(&format!(
+                "{SIGNATURE_PREFIX}{} {}\n",
+                sig.key_name,
+                BASE64.encode(blob)
+            ));
+        }
+        note
+    }
+
+    pub fn parse(note: &str) -> Result<Self, ClientError> {
+        let split = note
+            .find("\n\n")
+            .ok_or_else(|| invalid("has no signature block"))?;
+        let (body, signatures) = (&note[..split], &note[split + 2..]);
+
+        let mut lines = body.split('\n');
+        let origin = lines
+            .next()
+            .filter(|origin| !origin.is_empty())
+            .ok_or_else(|| invalid("origin is empty"))?;
+        let tree_size = lines
+            .next()
+            .filter(|size| size == &"0" || !size.starts_with('0'))
+            .and_then(|size| size.parse::<u64>().ok())
+            .ok_or_else(|| invalid("tree size must be a decimal number"))?;
+        let root_hash = lines
+            .next()
+            .and_then(|root| BASE64.decode(root).ok())
+            .and_then(|root| root.try_into().ok())
+            .ok_or_else(|| invalid("root hash must be 32 base64-encoded bytes"))?;
+        let extensions = lines.map(str::to_string).collect::<Vec<_>>();
+        if extensions.iter().any(String::is_empty) {
+            return Err(invalid("body has an empty line"));
+        }
+
+        let signatures = signatures
+            .strip_suffix('\n')
+            .ok_or_else(|| invalid("must end with a newline"))?
+            .split('\n')
+            .map(parse_signature_line)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            origin: origin.to_string(),
+            tree_size,
+            root_hash,
+            extensions,
+            signatures,
+        })
+    }
+
+    /// The head behind the checkpoint, if one of its signatures is a valid
+    /// kernel signature under `kernel_pubkey`. Other signatures, such as
+    /// witness cosignatures, are ignored.
+    pub fn verified_head(&self, kernel_pubkey: &[u8]) -> Result<SignedTreeHead, ClientError> {
+        self.signatures
+            .iter()
+            .filter(|sig| sig.key_id == kernel_key_id(&sig.key_name, kernel_pubkey))
+            .find_map(|sig| {
+                let sth = SignedTreeHead {
+                    tree_size: self.tree_size,
+                    root_hash: self.root_hash,
+                    signature: sig.signature.as_slice().try_into().ok()?,
+                };
+                verify_sth_signature(&sth, kernel_pubkey).ok()?;
+                Some(sth)
+            })
+            .ok_or_else(|| {
+                ClientError::VerificationFailed(format!(
+                    "checkpoint for {} has no valid kernel signature",
+                    self.origin
+                ))
+            })
+    }
+
+    /// Checks that the note carries a valid signature by the Ed25519
+    /// verifier key `vkey`, in the form [`note_verifier_key`] returns.
+    pub fn verify_note_signature(&self, vkey: &str) -> Result<(), ClientError> {
+        let mut parts = vkey.splitn(3, '+');
+        let (Some(key_name), Some(id_hex), Some(key_b64)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(ClientError::InvalidInput(format!(
+                "malformed verifier key {vkey:?}"
+            )));
+        };
+        let key = BASE64
+            .decode(key_b64)
+            .ok()
+            .and_then(|bytes| match bytes.split_first() {
+                Some((&ED25519_SIGNATURE_TYPE, key)) => <[u8; 32]>::try_from(key).ok(),
+                _ => None,
+            })
+            .and_then(|key| VerifyingKey::from_bytes(&key).ok())
+            .ok_or_else(|| {
+                ClientError::InvalidInput(format!("verifier key {key_name} is not an Ed25519 key"))
+            })?;
+        let id = key_id(key_name, ED25519_SIGNATURE_TYPE, &[key.as_bytes()]);
+        if hex::encode(id) != id_hex {
+            return Err(ClientError::InvalidInput(format!(
+                "verifier key {key_name} has the wrong key id"
+            )));
+        }
+        let body = self.body();
+        let signed = self.signatures.iter().any(|sig| {
+            sig.key_name == key_name
+                && sig.key_id == id
+                && Signature::from_slice(&sig.signature)
+                    .is_ok_and(|signature| key.verify(body.as_bytes(), &signature).is_ok())
+        });
+        if !signed {
+            return Err(ClientError::VerificationFailed(format!(
+                "checkpoint is not signed by {key_name}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn parse_signature_line(line: &str) -> Result<NoteSignature, ClientError> {
+    let (key_name, blob) = line
+        .strip_prefix(SIGNATURE_PREFIX)
+        .and_then(|rest| rest.split_once(' '))
+        .ok_or_else(|| invalid(format!("malformed signature line {line:?}")))?;
+    check_key_name(key_name)?;
+    let blob = BASE64
+        .decode(blob)
+        .ok()
+        .filter(|blob| blob.len() > 4)
+        .ok_or_else(|| invalid(format!("malformed signature by {key_name}")))?;
+    Ok(NoteSignature {
+        key_name: key_name.to_string(),
+        key_id: [blob[0], blob[1], blob[2], blob[3]],
+        signature: blob[4..].to_vec(),
+    })
+}
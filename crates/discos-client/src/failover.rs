@@ -0,0 +1,300 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ordered replica set behind a [`DiscosClient`](crate::DiscosClient).
+//!
+//! The client talks to one active replica. When a call fails with
+//! `Unavailable` (or never reaches the daemon) the remaining replicas are
+//! health-checked in order with `Health` and `GetServerInfo`, and the first
+//! healthy one becomes active. Before switching, the candidate's signed tree
+//! head is checked against the last verified head: it must carry a valid
+//! kernel signature, must not be behind, and must prove consistency with it.
+//! A lagging or forked replica is refused and the refusal is reported rather
+//! than silently skipped.
+
+use std::collections::BTreeSet;
+use std::sync::{Mutex, MutexGuard};
+
+use tonic::{Code, Request};
+
+use crate::{
+    consistency_proof_from_pb, pb, verify_consistency_proof, verify_signed_tree_head_response,
+    ClientError, GrpcClient, SignedTreeHead,
+};
+
+#[derive(Debug)]
+pub(crate) struct Replica {
+    pub endpoint: String,
+    pub grpc: GrpcClient,
+}
+
+#[derive(Debug, Default)]
+struct FailoverState {
+    active: usize,
+    kernel_pubkey: Option<Vec<u8>>,
+    verified_head: Option<SignedTreeHead>,
+}
+
+#[derive(Debug)]
+pub(crate) struct ReplicaSet {
+    replicas: Vec<Replica>,
+    state: Mutex<FailoverState>,
+}
+
+/// Why a replica could not be used.
+enum Rejection {
+    /// It did not answer the health check.
+    Unhealthy(ClientError),
+    /// It answered, but its tree head cannot be reconciled with ours.
+    Inconsistent(String),
+}
+
+/// Whether `err` means the active replica is unreachable rather than that it
+/// rejected the request.
+pub(crate) fn triggers_failover(err: &ClientError) -> bool {
+    match err {
+        ClientError::Transport(_) => true,
+        ClientError::Kernel(kernel) => kernel.code == Code::Unavailable,
+        ClientError::InvalidInput(_) | ClientError::VerificationFailed(_) => false,
+    }
+}
+
+impl ReplicaSet {
+    pub fn new(replicas: Vec<Replica>, kernel_pubkey: Option<Vec<u8>>) -> Self {
+        Self {
+            replicas,
+            state: Mutex::new(FailoverState {
+                kernel_pubkey,
+                ..FailoverState::default()
+            }),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, FailoverState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn len(&self) -> usize {
+        self.replicas.len()
+    }
+
+    pub fn endpoints(&self) -> Vec<String> {
+        self.replicas.iter().map(|r| r.endpoint.clone()).collect()
+    }
+
+    /// Index and gRPC handle of the replica calls currently go to.
+    pub fn active(&self) -> (usize, GrpcClient) {
+        let active = self.state().active;
+        (active, self.replicas[active].grpc.clone())
+    }
+
+    pub fn active_endpoint(&self) -> String {
+        self.replicas[self.state().active].endpoint.clone()
+    }
+
+    pub fn set_kernel_pubkey(&self, kernel_pubkey: Vec<u8>) {
+        self.state().kernel_pubkey = Some(kernel_pubkey);
+    }
+
+    pub fn verified_head(&self) -> Option<SignedTreeHead> {
+        self.state().verified_head.clone()
+    }
+
+    /// Keeps the largest verified head seen so far.
+    pub fn record_verified_head(&self, head: SignedTreeHead) {
+        let mut state = self.state();
+        if state
+            .verified_head
+            .as_ref()
+            .is_none_or(|current| head.tree_size > current.tree_size)
+        {
+            state.verified_head = Some(head);
+        }
+    }
+
+    /// Makes the first healthy replica, in configured order, active.
+    pub async fn select_initial(&self) -> Result<(), ClientError> {
+        let mut tried = BTreeSet::new();
+        let mut down = Vec::new();
+        match self
+            .switch(0..self.replicas.len(), &mut tried, &mut down, false)
+            .await?
+        {
+            true => Ok(()),
+            false => Err(ClientError::Transport(format!(
+                "no healthy endpoint: {}",
+                down.join("; ")
+            ))),
+        }
+    }
+
+    /// Moves off replica `failed` to the next healthy, consistent replica
+    /// that has not been tried during this call. Returns `Ok(false)` when
+    /// every remaining replica is down, and an error when the only ones that
+    /// answered had tree heads that cannot be reconciled with ours.
+    pub async fn fail_over(
+        &self,
+        failed: usize,
+        tried: &mut BTreeSet<usize>,
+    ) -> Result<bool, ClientError> {
+        tried.insert(failed);
+        let active = self.state().active;
+        if !tried.contains(&active) {
+            // Another handle on this client already moved on.
+            return Ok(true);
+        }
+        let count = self.replicas.len();
+        let order = (1..count).map(|offset| (failed + offset) % count);
+        self.switch(order, tried, &mut Vec::new(), true).await
+    }
+
+    /// Activates the first replica in `order` that passes [`Self::check`],
+    /// noting unreachable ones in `down`.
+    async fn switch(
+        &self,
+        order: impl Iterator<Item = usize>,
+        tried: &mut BTreeSet<usize>,
+        down: &mut Vec<String>,
+        require_head: bool,
+    ) -> Result<bool, ClientError> {
+        let mut refusals = Vec::new();
+        for index in order {
+            if !tried.insert(index) {
+                continue;
+            }
+            match self.check(index, require_head).await {
+                Ok(head) => {
+                    self.state().active = index;
+                    if let Some(head) = head {
+                        self.record_verified_head(head);
+                    }
+                    return Ok(true);
+                }
+                Err(Rejection::Unhealthy(err)) => {
+                    down.push(format!("{}: {err}", self.replicas[index].endpoint));
+                }
+                Err(Rejection::Inconsistent(reason)) => {
+                    refusals.push(format!("{}: {reason}", self.replicas[index].endpoint));
+                }
+            }
+        }
+        if refusals.is_empty() {
+            Ok(false)
+        } else {
+            Err(ClientError::VerificationFailed(format!(
+                "refusing inconsistent replicas: {}",
+                refusals.join("; ")
+            )))
+        }
+    }
+
+    /// Health-checks replica `index` and, when a kernel key is known, checks
+    /// its tree head against the last verified one.
+    async fn check(
+        &self,
+        index: usize,
+        require_head: bool,
+    ) -> Result<Option<SignedTreeHead>, Rejection> {
+        let mut grpc = self.replicas[index].grpc.clone();
+        let unhealthy = |status| Rejection::Unhealthy(ClientError::from(status));
+        grpc.health(Request::new(pb::HealthRequest {}))
+            .await
+            .map_err(unhealthy)?;
+        grpc.get_server_info(Request::new(pb::GetServerInfoRequest {}))
+            .await
+            .map_err(unhealthy)?;
+
+        let (kernel_pubkey, baseline) = {
+            let state = self.state();
+            (state.kernel_pubkey.clone(), state.verified_head.clone())
+        };
+        let Some(kernel_pubkey) = kernel_pubkey else {
+            return if require_head {
+                Err(Rejection::Inconsistent(
+                    "no kernel public key to verify its tree head".to_string(),
+                ))
+            } else {
+                Ok(None)
+            };
+        };
+
+        let response = grpc
+            .get_signed_tree_head(Request::new(pb::GetSignedTreeHeadRequest {}))
+            .await
+            .map_err(unhealthy)?
+            .into_inner();
+        let head = verify_signed_tree_head_response(&response, &kernel_pubkey)
+            .map_err(|e| Rejection::Inconsistent(e.to_string()))?;
+        if let Some(baseline) = baseline {
+            check_extends(&mut grpc, &baseline, &head).await?;
+        }
+        Ok(Some(head))
+    }
+}
+
+/// Checks that `head` is `baseline` or a consistent extension of it.
+async fn check_extends(
+    grpc: &mut GrpcClient,
+    baseline: &SignedTreeHead,
+    head: &SignedTreeHead,
+) -> Result<(), Rejection> {
+    if head.tree_size < baseline.tree_size {
+        return Err(Rejection::Inconsistent(format!(
+            "tree head at size {} is behind the last verified size {}",
+            head.tree_size, baseline.tree_size
+        )));
+    }
+    if head.tree_size == baseline.tree_size {
+        if head.root_hash != baseline.root_hash {
+            return Err(Rejection::Inconsistent(format!(
+                "root at size {} differs from the last verified root",
+                head.tree_size
+            )));
+        }
+        return Ok(());
+    }
+    if baseline.tree_size == 0 {
+        return Ok(());
+    }
+    let proof = grpc
+        .get_consistency_proof(Request::new(pb::GetConsistencyProofRequest {
+            old_tree_size: baseline.tree_size,
+            new_tree_size: head.tree_size,
+        }))
+        .await
+        .map_err(|status| {
+            Rejection::Inconsistent(format!(
+                "no consistency proof from size {}: {}",
+                baseline.tree_size,
+                ClientError::from(status)
+            ))
+        })?
+        .into_inner()
+        .consistency_proof
+        .ok_or_else(|| Rejection::Inconsistent("missing consistency proof".to_string()))?;
+    let proof =
+        consistency_proof_from_pb(&proof).map_err(|e| Rejection::Inconsistent(e.to_string()))?;
+    if proof.old_tree_size != baseline.tree_size
+        || proof.new_tree_size != head.tree_size
+        || !verify_consistency_proof(baseline.root_hash, head.root_hash, &proof)
+    {
+        return Err(Rejection::Inconsistent(format!(
+            "tree head at size {} is not consistent with the last verified head at size {}",
+            head.tree_size, baseline.tree_size
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,539 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Signed tree head gossip.
+//!
+//! Consistency checks against a client's own history cannot catch a kernel
+//! that shows each operator a different log. Operators therefore exchange
+//! [`SthBundle`]s, as files or over a small HTTP endpoint, and
+//! [`cross_check`] every imported head against their own daemon with
+//! `GetConsistencyProof`. Two validly signed heads that cannot be reconciled
+//! are written up as [`ForkEvidence`], signed by the reporting operator.
+//!
+//! Heads of equal size with different roots are self-evident forks. For heads
+//! of different sizes the evidence records the proof the daemon served,
+//! which anyone can re-check, but only the reporter's signature attests that
+//! this was the daemon's answer, so [`ForkEvidence::verify`] reports such
+//! evidence as a [`ForkProof::ReporterClaim`].
+
+use std::fs;
+use std::future::Future;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::{
+    consistency_proof_from_pb, pb, sha256_domain, verify_consistency_proof, verify_sth_signature,
+    ClientError, ConsistencyProof, DiscosClient, SignedTreeHead,
+};
+
+pub const STH_BUNDLE_VERSION: u32 = 1;
+pub const FORK_EVIDENCE_VERSION: u32 = 1;
+/// Path the gossip HTTP endpoint serves bundles under.
+pub const STH_BUNDLE_HTTP_PATH: &str = "/sth-bundle";
+
+const FORK_EVIDENCE_DOMAIN: &[u8] = b"discos.fork-evidence.v1";
+const MAX_HTTP_REQUEST_BYTES: usize = 8 * 1024;
+const MAX_HTTP_RESPONSE_BYTES: u64 = 1024 * 1024;
+/// How long a connection may take to send its request head before it is
+/// dropped, so one idle client cannot hold up the accept loop, and how long
+/// fetching a bundle may take in total.
+const HTTP_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A signed tree head in hex, as exchanged between operators.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GossipHead {
+    pub tree_size: u64,
+    pub root_hash_hex: String,
+    pub signature_hex: String,
+}
+
+impl From<&SignedTreeHead> for GossipHead {
+    fn from(sth: &SignedTreeHead) -> Self {
+        Self {
+            tree_size: sth.tree_size,
+            root_hash_hex: hex::encode(sth.root_hash),
+            signature_hex: hex::encode(sth.signature),
+        }
+    }
+}
+
+impl GossipHead {
+    pub fn decode(&self) -> Result<SignedTreeHead, ClientError> {
+        let invalid = |what: &str| ClientError::InvalidInput(format!("gossip head {what}"));
+        Ok(SignedTreeHead {
+            tree_size: self.tree_size,
+            root_hash: hex::decode(&self.root_hash_hex)
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| invalid("root_hash_hex must be 32 hex-encoded bytes"))?,
+            signature: hex::decode(&self.signature_hex)
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| invalid("signature_hex must be 64 hex-encoded bytes"))?,
+        })
+    }
+}
+
+/// Tree heads one operator observed from a kernel.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SthBundle {
+    pub version: u32,
+    /// Endpoint the heads were fetched from.
+    pub origin: String,
+    pub kernel_pubkey_hex: String,
+    pub exported_unix: u64,
+    pub heads: Vec<GossipHead>,
+}
+
+impl SthBundle {
+    pub fn new(origin: &str, kernel_pubkey: &[u8], heads: &[SignedTreeHead]) -> Self {
+        Self {
+            version: STH_BUNDLE_VERSION,
+            origin: origin.to_string(),
+            kernel_pubkey_hex: hex::encode(kernel_pubkey),
+            exported_unix: now_unix(),
+            heads: heads.iter().map(GossipHead::from).collect(),
+        }
+    }
+
+    /// Decodes the heads, requiring that the bundle is for `kernel_pubkey`
+    /// and that every head carries a valid signature under it.
+    pub fn verified_heads(&self, kernel_pubkey: &[u8]) -> Result<Vec<SignedTreeHead>, ClientError> {
+        if self.version != STH_BUNDLE_VERSION {
+            return Err(ClientError::InvalidInput(format!(
+                "unsupported sth bundle version {}",
+                self.version
+            )));
+        }
+        if self.kernel_pubkey_hex != hex::encode(kernel_pubkey) {
+            return Err(ClientError::VerificationFailed(format!(
+                "sth bundle from {} is for kernel key {}, not ours",
+                self.origin, self.kernel_pubkey_hex
+            )));
+        }
+        self.heads
+            .iter()
+            .map(|head| {
+                let sth = head.decode()?;
+                verify_sth_signature(&sth, kernel_pubkey)?;
+                Ok(sth)
+            })
+            .collect()
+    }
+
+    pub fn to_json(&self) -> Result<Vec<u8>, ClientError> {
+        serde_json::to_vec_pretty(self)
+            .map_err(|err| ClientError::InvalidInput(format!("encode sth bundle: {err}")))
+    }
+
+    pub fn from_json(bytes: &[u8]) -> Result<Self, ClientError> {
+        serde_json::from_slice(bytes)
+            .map_err(|err| ClientError::InvalidInput(format!("parse sth bundle: {err}")))
+    }
+
+    pub fn read(path: &Path) -> Result<Self, ClientError> {
+        let bytes = fs::read(path).map_err(|err| {
+            ClientError::InvalidInput(format!("read sth bundle {}: {err}", path.display()))
+        })?;
+        Self::from_json(&bytes)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), ClientError> {
+        write_file(path, &self.to_json()?)
+    }
+}
+
+/// Outcome of checking an imported head against the local daemon.
+#[derive(Debug, Clone)]
+pub enum CrossCheck {
+    /// The heads are the same or one provably extends the other.
+    Consistent,
+    /// The imported head is beyond the local log; check again later.
+    Ahead,
+    /// The heads cannot be reconciled.
+    Forked {
+        reason: String,
+        /// The proof the local daemon served, when there was one.
+        proof: Option<ConsistencyProof>,
+    },
+}
+
+/// Checks `remote`, an imported head, against `local`, the latest head
+/// verified from the daemon behind `client`. Both must already carry valid
+/// kernel signatures.
+pub async fn cross_check(
+    client: &mut DiscosClient,
+    local: &SignedTreeHead,
+    remote: &SignedTreeHead,
+) -> Result<CrossCheck, ClientError> {
+    if remote.tree_size == local.tree_size {
+        return Ok(if remote.root_hash == local.root_hash {
+            CrossCheck::Consistent
+        } else {
+            CrossCheck::Forked {
+                reason: format!("two signed roots for tree size {}", remote.tree_size),
+                proof: None,
+            }
+        });
+    }
+    if remote.tree_size > local.tree_size {
+        return Ok(CrossCheck::Ahead);
+    }
+    if remote.tree_size == 0 {
+        return Ok(CrossCheck::Consistent);
+    }
+    let proof = client
+        .get_consistency_proof(pb::GetConsistencyProofRequest {
+            old_tree_size: remote.tree_size,
+            new_tree_size: local.tree_size,
+        })
+        .await?
+        .consistency_proof
+        .ok_or_else(|| ClientError::VerificationFailed("missing consistency proof".into()))?;
+    let proof = consistency_proof_from_pb(&proof)?;
+    if proof.old_tree_size == remote.tree_size
+        && proof.new_tree_size == local.tree_size
+        && verify_consistency_proof(remote.root_hash, local.root_hash, &proof)
+    {
+        return Ok(CrossCheck::Consistent);
+    }
+    Ok(CrossCheck::Forked {
+        reason: format!(
+            "no valid consistency proof from size {} to {}",
+            remote.tree_size, local.tree_size
+        ),
+        proof: Some(proof),
+    })
+}
+
+/// How much a verified [`ForkEvidence`] proves on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ForkProof {
+    /// Two kernel-signed roots for one tree size; the kernel's own
+    /// signatures prove the fork.
+    SelfEvident,
+    /// Heads of different sizes whose recorded path does not join them. The
+    /// path is not kernel-signed, so this is only the reporter's claim that
+    /// the daemon served no valid proof.
+    ReporterClaim,
+}
+
+/// Two kernel-signed heads that could not be reconciled, signed by the
+/// operator who found them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForkEvidence {
+    pub version: u32,
+    pub kernel_pubkey_hex: String,
+    pub local_origin: String,
+    pub local: GossipHead,
+    pub remote_origin: String,
+    pub remote: GossipHead,
+    pub reason: String,
+    /// Path of the consistency proof the local daemon served, if any.
+    #[serde(default)]
+    pub consistency_path_hex: Option<Vec<String>>,
+    pub detected_unix: u64,
+    pub reporter_pubkey_hex: String,
+    pub signature_hex: String,
+}
+
+impl ForkEvidence {
+    /// Builds evidence for a [`CrossCheck::Forked`] outcome and signs it with
+    /// `reporter`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        kernel_pubkey: &[u8],
+        local_origin: &str,
+        local: &SignedTreeHead,
+        remote_origin: &str,
+        remote: &SignedTreeHead,
+        reason: &str,
+        proof: Option<&ConsistencyProof>,
+        reporter: &SigningKey,
+    ) -> Self {
+        let mut evidence = Self {
+            version: FORK_EVIDENCE_VERSION,
+            kernel_pubkey_hex: hex::encode(kernel_pubkey),
+            local_origin: local_origin.to_string(),
+            local: local.into(),
+            remote_origin: remote_origin.to_string(),
+            remote: remote.into(),
+            reason: reason.to_string(),
+            consistency_path_hex: proof.map(|proof| proof.path.iter().map(hex::encode).collect()),
+            detected_unix: now_unix(),
+            reporter_pubkey_hex: hex::encode(reporter.verifying_key().to_bytes()),
+            signature_hex: String::new(),
+        };
+        evidence.signature_hex = hex::encode(reporter.sign(&evidence.signing_digest()).to_bytes());
+        evidence
+    }
+
+    /// Digest the reporter signs: the evidence with an empty signature.
+    pub fn signing_digest(&self) -> [u8; 32] {
+        let unsigned = Self {
+            signature_hex: String::new(),
+            ..self.clone()
+        };
+        // Serializing a plain struct of strings and integers cannot fail.
+        let body = serde_json::to_vec(&unsigned).unwrap_or_default();
+        sha256_domain(FORK_EVIDENCE_DOMAIN, &body)
+    }
+
+    /// Checks the reporter signature, both kernel signatures and that the
+    /// heads really conflict: equal sizes with different roots, or a recorded
+    /// proof that fails to join them. Only the first is
+    /// [`ForkProof::SelfEvident`]; anyone could record a path that fails.
+    pub fn verify(&self, kernel_pubkey: &[u8]) -> Result<ForkProof, ClientError> {
+        let failed = |msg: &str| ClientError::VerificationFailed(format!("fork evidence {msg}"));
+        if self.version != FORK_EVIDENCE_VERSION {
+            return Err(ClientError::InvalidInput(format!(
+                "unsupported fork evidence version {}",
+                self.version
+            )));
+        }
+        if self.kernel_pubkey_hex != hex::encode(kernel_pubkey) {
+            return Err(failed("is for a different kernel key"));
+        }
+        let reporter: [u8; 32] = hex::decode(&self.reporter_pubkey_hex)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| failed("reporter_pubkey_hex must be 32 hex-encoded bytes"))?;
+        let reporter = VerifyingKey::from_bytes(&reporter)
+            .map_err(|_| failed("has an invalid reporter key"))?;
+        let signature: [u8; 64] = hex::decode(&self.signature_hex)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| failed("signature_hex must be 64 hex-encoded bytes"))?;
+        reporter
+            .verify(&self.signing_digest(), &Signature::from_bytes(&signature))
+            .map_err(|_| failed("reporter signature is invalid"))?;
+
+        let local = self.local.decode()?;
+        let remote = self.remote.decode()?;
+        verify_sth_signature(&local, kernel_pubkey)?;
+        verify_sth_signature(&remote, kernel_pubkey)?;
+        if local.tree_size == remote.tree_size {
+            return if local.root_hash != remote.root_hash {
+                Ok(ForkProof::SelfEvident)
+            } else {
+                Err(failed("heads are identical"))
+            };
+        }
+        let (old, new) = if local.tree_size < remote.tree_size {
+            (&local, &remote)
+        } else {
+            (&remote, &local)
+        };
+        let path = self
+            .consistency_path_hex
+            .as_ref()
+            .ok_or_else(|| failed("has heads of different sizes but no consistency proof"))?
+            .iter()
+            .map(|node| {
+                hex::decode(node)
+                    .ok()
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or_else(|| failed("consistency path node must be 32 hex-encoded bytes"))
+            })
+            .collect::<Result<Vec<[u8; 32]>, _>>()?;
+        let proof = ConsistencyProof {
+            old_tree_size: old.tree_size,
+            new_tree_size: new.tree_size,
+            path,
+        };
+        if verify_consistency_proof(old.root_hash, new.root_hash, &proof) {
+            return Err(failed("proof reconciles the heads"));
+        }
+        Ok(ForkProof::ReporterClaim)
+    }
+
+    pub fn read(path: &Path) -> Result<Self, ClientError> {
+        let bytes = fs::read(path).map_err(|err| {
+            ClientError::InvalidInput(format!("read fork evidence {}: {err}", path.display()))
+        })?;
+        serde_json::from_slice(&bytes)
+            .map_err(|err| ClientError::InvalidInput(format!("parse fork evidence: {err}")))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), ClientError> {
+        let bytes = serde_json::to_vec_pretty(self)
+            .map_err(|err| ClientError::InvalidInput(format!("encode fork evidence: {err}")))?;
+        write_file(path, &bytes)
+    }
+}
+
+/// Loads the operator key that signs fork evidence from `path`, a hex
+/// ed25519 seed, creating it on first use.
+pub fn load_or_create_reporter_key(path: &Path) -> Result<SigningKey, ClientError> {
+    match fs::read_to_string(path) {
+        Ok(seed_hex) => {
+            let seed: [u8; 32] = hex::decode(seed_hex.trim())
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| {
+                    ClientError::InvalidInput(format!(
+                        "reporter key {} must hold a 32-byte hex seed",
+                        path.display()
+                    ))
+                })?;
+            Ok(SigningKey::from_bytes(&seed))
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            let key = SigningKey::from_bytes(&rand::random::<[u8; 32]>());
+            write_secret_file(path, hex::encode(key.to_bytes()).as_bytes())?;
+            Ok(key)
+        }
+        Err(err) => Err(ClientError::InvalidInput(format!(
+            "read reporter key {}: {err}",
+            path.display()
+        ))),
+    }
+}
+
+/// Serves `GET /sth-bundle` on `listener`, answering each request with a
+/// fresh bundle from `bundle`. Runs until accepting fails.
+pub async fn serve_sth_bundles<F, Fut>(listener: TcpListener, mut bundle: F) -> std::io::Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<SthBundle, ClientError>>,
+{
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let read = tokio::time::timeout(HTTP_REQUEST_TIMEOUT, read_request_head(&mut stream));
+        let Ok(Ok(request)) = read.await else {
+            continue;
+        };
+        let mut parts = request.split_whitespace();
+        let response = match (parts.next(), parts.next()) {
+            (Some("GET"), Some(STH_BUNDLE_HTTP_PATH)) => {
+                match bundle().await.and_then(|b| b.to_json()) {
+                    Ok(body) => http_response("200 OK", "application/json", &body),
+                    Err(err) => {
+                        http_response("502 Bad Gateway", "text/plain", err.to_string().as_bytes())
+                    }
+                }
+            }
+            _ => http_response("404 Not Found", "text/plain", b"not found"),
+        };
+        // A client that hung up early only loses its own response.
+        let _ = stream.write_all(&response).await;
+        let _ = stream.shutdown().await;
+    }
+}
+
+/// Fetches a bundle from a gossip endpoint such as
+/// `http://127.0.0.1:8787/sth-bundle`; a bare `http://host:port` gets the
+/// default path.
+pub async fn fetch_sth_bundle(url: &str) -> Result<SthBundle, ClientError> {
+    tokio::time::timeout(HTTP_REQUEST_TIMEOUT, request_sth_bundle(url))
+        .await
+        .map_err(|_| ClientError::Transport(format!("gossip {url}: timed out")))?
+}
+
+async fn request_sth_bundle(url: &str) -> Result<SthBundle, ClientError> {
+    let rest = url.strip_prefix("http://").ok_or_else(|| {
+        ClientError::InvalidInput(format!("gossip url must start with http://: {url}"))
+    })?;
+    let (authority, path) = match rest.find('/') {
+        Some(at) if at + 1 < rest.len() => (&rest[..at], &rest[at..]),
+        Some(at) => (&rest[..at], STH_BUNDLE_HTTP_PATH),
+        None => (rest, STH_BUNDLE_HTTP_PATH),
+    };
+    let transport = |err: std::io::Error| ClientError::Transport(format!("gossip {url}: {err}"));
+    let mut stream = TcpStream::connect(authority).await.map_err(transport)?;
+    let request = format!("GET {path} HTTP/1.1\r\nHost: {authority}\r\nConnection: close\r\n\r\n");
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(transport)?;
+    let mut response = Vec::new();
+    stream
+        .take(MAX_HTTP_RESPONSE_BYTES)
+        .read_to_end(&mut response)
+        .await
+        .map_err(transport)?;
+
+    let split = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or_else(|| ClientError::Transport(format!("gossip {url}: malformed response")))?;
+    let head = String::from_utf8_lossy(&response[..split]);
+    let status = head.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(ClientError::Transport(format!("gossip {url}: {status}")));
+    }
+    SthBundle::from_json(&response[split + 4..])
+}
+
+async fn read_request_head(stream: &mut TcpStream) -> std::io::Result<String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buf.windows(4).any(|window| window == b"\r\n\r\n") {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 || buf.len() + n > MAX_HTTP_REQUEST_BYTES {
+            return Err(std::io::ErrorKind::InvalidData.into());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+fn http_response(status: &str, content_type: &str, body: &[u8]) -> Vec<u8> {
+    let mut response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )
+    .into_bytes();
+    response.extend_from_slice(body);
+    response
+}
+
+fn write_file(path: &Path, bytes: &[u8]) -> Result<(), ClientError> {
+    let io_err =
+        |err: std::io::Error| ClientError::InvalidInput(format!("write {}: {err}", path.display()));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(io_err)?;
+    }
+    fs::write(path, bytes).map_err(io_err)
+}
+
+/// Like [`write_file`], but the file is created owner-only and never
+/// replaces an existing one.
+fn write_secret_file(path: &Path, bytes: &[u8]) -> Result<(), ClientError> {
+    let io_err =
+        |err: std::io::Error| ClientError::InvalidInput(format!("write {}: {err}", path.display()));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(io_err)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path).map_err(io_err)?;
+    std::io::Write::write_all(&mut file, bytes).map_err(io_err)?;
+    file.sync_all().map_err(io_err)
+}
+
+fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,356 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Kernel signing-key store keyed by endpoint.
+//!
+//! Keys are pinned explicitly by an operator or on first use (TOFU). Once an
+//! endpoint has a pin, any other key served by `GetPublicKey` is rejected
+//! unless it matches a rotation announced beforehand by `key_id`; the
+//! announced key then replaces the pin and the old one is retired.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tonic::Code;
+
+use crate::{sha256, ClientError, DiscosClient};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PinSource {
+    Explicit,
+    Tofu,
+    Rotation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnnouncedRotation {
+    pub key_id_hex: String,
+    /// When set, the rotated key must also match this exact public key.
+    pub pubkey_hex: Option<String>,
+    pub announced_unix: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetiredKernelKey {
+    pub pubkey_hex: String,
+    pub key_id_hex: String,
+    pub retired_unix: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinnedKernelKey {
+    pub pubkey_hex: String,
+    pub key_id_hex: String,
+    pub source: PinSource,
+    pub pinned_unix: u64,
+    #[serde(default)]
+    pub announced_rotation: Option<AnnouncedRotation>,
+    #[serde(default)]
+    pub retired: Vec<RetiredKernelKey>,
+}
+
+impl PinnedKernelKey {
+    pub fn pubkey(&self) -> Result<Vec<u8>, ClientError> {
+        hex::decode(&self.pubkey_hex)
+            .map_err(|_| ClientError::InvalidInput("pinned pubkey_hex is not hex".to_string()))
+    }
+}
+
+/// Outcome of checking a served key against the store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyCheck {
+    /// The served key matches the existing pin.
+    Pinned,
+    /// No pin existed; the served key was pinned on first use.
+    FirstUse,
+    /// The served key matched an announced rotation and is now pinned.
+    Rotated { previous_key_id_hex: String },
+}
+
+/// Key id used when the daemon does not report one.
+pub fn kernel_key_id(pubkey: &[u8]) -> [u8; 32] {
+    sha256(pubkey)
+}
+
+fn key_id_hex(pubkey: &[u8], key_id: &[u8]) -> String {
+    if key_id.is_empty() {
+        hex::encode(kernel_key_id(pubkey))
+    } else {
+        hex::encode(key_id)
+    }
+}
+
+fn check_pubkey_len(pubkey: &[u8]) -> Result<(), ClientError> {
+    if pubkey.len() != 32 {
+        return Err(ClientError::InvalidInput(
+            "ed25519 pubkey must be 32 bytes".into(),
+        ));
+    }
+    Ok(())
+}
+
+fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct KeyStoreFile {
+    endpoints: BTreeMap<String, PinnedKernelKey>,
+}
+
+#[derive(Debug, Clone)]
+pub struct KernelKeyStore {
+    path: PathBuf,
+    state: KeyStoreFile,
+}
+
+impl KernelKeyStore {
+    /// Opens the store at `path`; a missing file is an empty store.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, ClientError> {
+        let path = path.into();
+        let state = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|err| {
+                ClientError::InvalidInput(format!("parse key store {}: {err}", path.display()))
+            })?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => KeyStoreFile::default(),
+            Err(err) => {
+                return Err(ClientError::InvalidInput(format!(
+                    "read key store {}: {err}",
+                    path.display()
+                )))
+            }
+        };
+        Ok(Self { path, state })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self, endpoint: &str) -> Option<&PinnedKernelKey> {
+        self.state.endpoints.get(endpoint)
+    }
+
+    pub fn pins(&self) -> impl Iterator<Item = (&str, &PinnedKernelKey)> {
+        self.state
+            .endpoints
+            .iter()
+            .map(|(endpoint, pin)| (endpoint.as_str(), pin))
+    }
+
+    /// Pins `pubkey` for `endpoint`. Replacing a different existing pin
+    /// requires `replace`, so a typo cannot silently swap trust roots.
+    pub fn pin(
+        &mut self,
+        endpoint: &str,
+        pubkey: &[u8],
+        key_id: &[u8],
+        replace: bool,
+    ) -> Result<&PinnedKernelKey, ClientError> {
+        check_pubkey_len(pubkey)?;
+        let pubkey_hex = hex::encode(pubkey);
+        if let Some(existing) = self.state.endpoints.get(endpoint) {
+            if existing.pubkey_hex != pubkey_hex && !replace {
+                return Err(ClientError::VerificationFailed(format!(
+                    "endpoint {endpoint} already pinned to key {}",
+                    existing.key_id_hex
+                )));
+            }
+        }
+        self.insert(endpoint, pubkey, key_id, PinSource::Explicit)
+    }
+
+    pub fn unpin(&mut self, endpoint: &str) -> Result<Option<PinnedKernelKey>, ClientError> {
+        let removed = self.state.endpoints.remove(endpoint);
+        if removed.is_some() {
+            self.persist()?;
+        }
+        Ok(removed)
+    }
+
+    /// Records that `endpoint` will rotate to the key identified by `key_id`.
+    pub fn announce_rotation(
+        &mut self,
+        endpoint: &str,
+        key_id: &[u8],
+        pubkey: Option<&[u8]>,
+    ) -> Result<&PinnedKernelKey, ClientError> {
+        if key_id.is_empty() {
+            return Err(ClientError::InvalidInput(
+                "rotation key_id must not be empty".to_string(),
+            ));
+        }
+        if let Some(pubkey) = pubkey {
+            check_pubkey_len(pubkey)?;
+        }
+        let pin = self.state.endpoints.get_mut(endpoint).ok_or_else(|| {
+            ClientError::InvalidInput(format!("endpoint {endpoint} has no pinned key to rotate"))
+        })?;
+        pin.announced_rotation = Some(AnnouncedRotation {
+            key_id_hex: hex::encode(key_id),
+            pubkey_hex: pubkey.map(hex::encode),
+            announced_unix: now_unix(),
+        });
+        self.persist()?;
+        self.get(endpoint).ok_or_else(|| {
+            ClientError::InvalidInput(format!("endpoint {endpoint} has no pinned key"))
+        })
+    }
+
+    /// Checks the key an endpoint is serving. Without a pin the key is only
+    /// accepted (and pinned) when `tofu` is set; with a pin, a different key
+    /// is accepted only as an announced rotation.
+    pub fn check(
+        &mut self,
+        endpoint: &str,
+        pubkey: &[u8],
+        key_id: &[u8],
+        tofu: bool,
+    ) -> Result<KeyCheck, ClientError> {
+        check_pubkey_len(pubkey)?;
+        let pubkey_hex = hex::encode(pubkey);
+        let served_key_id_hex = key_id_hex(pubkey, key_id);
+        let Some(pin) = self.state.endpoints.get(endpoint) else {
+            if !tofu {
+                return Err(ClientError::VerificationFailed(format!(
+                    "no kernel key pinned for {endpoint}"
+                )));
+            }
+            self.insert(endpoint, pubkey, key_id, PinSource::Tofu)?;
+            return Ok(KeyCheck::FirstUse);
+        };
+        if pin.pubkey_hex == pubkey_hex {
+            return Ok(KeyCheck::Pinned);
+        }
+        let announced = pin.announced_rotation.as_ref().is_some_and(|rotation| {
+            rotation.key_id_hex == served_key_id_hex
+                && rotation
+                    .pubkey_hex
+                    .as_ref()
+                    .is_none_or(|expected| *expected == pubkey_hex)
+        });
+        if !announced {
+            return Err(ClientError::VerificationFailed(format!(
+                "kernel key for {endpoint} changed from {} to {} without an announced rotation",
+                pin.key_id_hex, served_key_id_hex
+            )));
+        }
+        let previous = pin.clone();
+        let mut retired = previous.retired;
+        retired.push(RetiredKernelKey {
+            pubkey_hex: previous.pubkey_hex,
+            key_id_hex: previous.key_id_hex.clone(),
+            retired_unix: now_unix(),
+        });
+        self.state.endpoints.insert(
+            endpoint.to_string(),
+            PinnedKernelKey {
+                pubkey_hex,
+                key_id_hex: served_key_id_hex,
+                source: PinSource::Rotation,
+                pinned_unix: now_unix(),
+                announced_rotation: None,
+                retired,
+            },
+        );
+        self.persist()?;
+        Ok(KeyCheck::Rotated {
+            previous_key_id_hex: previous.key_id_hex,
+        })
+    }
+
+    fn insert(
+        &mut self,
+        endpoint: &str,
+        pubkey: &[u8],
+        key_id: &[u8],
+        source: PinSource,
+    ) -> Result<&PinnedKernelKey, ClientError> {
+        let retired = self
+            .state
+            .endpoints
+            .remove(endpoint)
+            .map(|pin| pin.retired)
+            .unwrap_or_default();
+        self.state.endpoints.insert(
+            endpoint.to_string(),
+            PinnedKernelKey {
+                pubkey_hex: hex::encode(pubkey),
+                key_id_hex: key_id_hex(pubkey, key_id),
+                source,
+                pinned_unix: now_unix(),
+                announced_rotation: None,
+                retired,
+            },
+        );
+        self.persist()?;
+        self.get(endpoint).ok_or_else(|| {
+            ClientError::InvalidInput(format!("endpoint {endpoint} has no pinned key"))
+        })
+    }
+
+    fn persist(&self) -> Result<(), ClientError> {
+        let io_err =
+            |err: std::io::Error| ClientError::InvalidInput(format!("persist key store: {err}"));
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(io_err)?;
+        }
+        let bytes = serde_json::to_vec_pretty(&self.state)
+            .map_err(|err| ClientError::InvalidInput(format!("encode key store: {err}")))?;
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, bytes).map_err(io_err)?;
+        fs::rename(&tmp, &self.path).map_err(io_err)
+    }
+}
+
+/// Resolves the kernel key for `endpoint` through the store.
+///
+/// Returns `None` when nothing is pinned and `tofu` is off. Otherwise the
+/// daemon's `GetPublicKey` answer is checked against the pin; a daemon that
+/// does not implement the RPC is trusted under its existing pin only.
+pub async fn resolve_kernel_key(
+    client: &mut DiscosClient,
+    store: &mut KernelKeyStore,
+    endpoint: &str,
+    tofu: bool,
+) -> Result<Option<(Vec<u8>, KeyCheck)>, ClientError> {
+    let pinned = store
+        .get(endpoint)
+        .map(PinnedKernelKey::pubkey)
+        .transpose()?;
+    if pinned.is_none() && !tofu {
+        return Ok(None);
+    }
+    let served = match client.get_public_key().await {
+        Ok(served) => served,
+        Err(ClientError::Kernel(err)) if err.code == Code::Unimplemented => {
+            return match pinned {
+                Some(pubkey) => Ok(Some((pubkey, KeyCheck::Pinned))),
+                None => Err(ClientError::VerificationFailed(format!(
+                    "{endpoint} does not serve GetPublicKey; pin its key explicitly"
+                ))),
+            };
+        }
+        Err(err) => return Err(err),
+    };
+    let check = store.check(endpoint, &served.pubkey, &served.key_id, tofu)?;
+    Ok(Some((served.pubkey, check)))
+}
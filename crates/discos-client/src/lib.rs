@@ -17,6 +17,7 @@
     deny(clippy::unwrap_used, clippy::expect_used, clippy::panic)
 )]
 
+use std::collections::{BTreeMap, BTreeSet};
 use std::sync::{
     atomic::{AtomicU64, Ordering},
     Arc,
@@ -25,7 +26,9 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use ed25519_dalek::{Signature, Verifier, VerifyingKey};
 use evidenceos_auth_protocol::build_hmac_headers;
+use evidenceos_core::wasm_aspec::verify_restricted_wasm;
 use evidenceos_verifier as verifier;
+use prost::Message;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use thiserror::Error;
@@ -33,13 +36,29 @@ use tonic::{
     metadata::MetadataValue,
     service::Interceptor,
     transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity},
-    Request, Status,
+    Code, Request, Status,
 };
 
 pub mod pb {
     pub use evidenceos_protocol::pb::v2::*;
 }
 
+pub mod capsule;
+pub mod checkpoint;
+mod failover;
+pub mod gossip;
+pub mod keystore;
+pub mod retry;
+pub mod revocations;
+pub mod session;
+
+pub use keystore::{resolve_kernel_key, KernelKeyStore, KeyCheck, PinnedKernelKey};
+pub use retry::{RetryDecision, RetryPolicy, IDEMPOTENCY_KEY_METADATA_KEY};
+pub use revocations::{
+    CapsuleValidity, RevocationRecord, RevocationSet, RevocationSnapshot, RevocationWatcher,
+};
+pub use session::{ClaimSession, VerifiedCapsule};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ErrorCode {
     Transport,
@@ -57,7 +76,7 @@ pub enum ClientError {
     #[error("verification failed: {0}")]
     VerificationFailed(String),
     #[error("kernel error: {0}")]
-    Kernel(String),
+    Kernel(KernelError),
 }
 
 impl ClientError {
@@ -69,6 +88,102 @@ impl ClientError {
             ClientError::Kernel(_) => ErrorCode::Kernel,
         }
     }
+
+    pub fn kernel(&self) -> Option<&KernelError> {
+        match self {
+            ClientError::Kernel(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<Status> for ClientError {
+    fn from(status: Status) -> Self {
+        ClientError::Kernel(KernelError::from(status))
+    }
+}
+
+/// Metadata key the daemon uses to carry its stable reason token.
+pub const REASON_METADATA_KEY: &str = "x-evidenceos-reason";
+
+/// A non-OK `tonic::Status` returned by the kernel, kept in structured form.
+///
+/// `reason` is the daemon's stable reason token (for example
+/// `UNSIGNED_NULLSPEC` or `FROZEN`). It is read from the
+/// `x-evidenceos-reason` metadata entry when present and otherwise from a
+/// leading upper-case token of the status message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KernelError {
+    pub code: Code,
+    pub reason: Option<String>,
+    pub message: String,
+    pub details: Vec<u8>,
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl KernelError {
+    pub fn reason(&self) -> Option<&str> {
+        self.reason.as_deref()
+    }
+
+    pub fn has_reason(&self, token: &str) -> bool {
+        self.reason() == Some(token)
+    }
+
+    pub fn metadata_value(&self, key: &str) -> Option<&str> {
+        self.metadata.get(key).map(String::as_str)
+    }
+}
+
+impl std::fmt::Display for KernelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}: {}", self.code, self.message)
+    }
+}
+
+impl From<Status> for KernelError {
+    fn from(status: Status) -> Self {
+        let metadata = status
+            .metadata()
+            .clone()
+            .into_headers()
+            .iter()
+            .filter_map(|(key, value)| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|v| (key.as_str().to_string(), v.to_string()))
+            })
+            .collect::<BTreeMap<_, _>>();
+        let reason = metadata
+            .get(REASON_METADATA_KEY)
+            .filter(|token| is_reason_token(token))
+            .cloned()
+            .or_else(|| leading_reason_token(status.message()));
+        Self {
+            code: status.code(),
+            reason,
+            message: status.message().to_string(),
+            details: status.details().to_vec(),
+            metadata,
+        }
+    }
+}
+
+fn is_reason_token(token: &str) -> bool {
+    token.len() >= 3
+        && token.starts_with(|c: char| c.is_ascii_uppercase())
+        && token
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn leading_reason_token(message: &str) -> Option<String> {
+    let token = message
+        .trim_start()
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .next()?;
+    is_reason_token(token).then(|| token.to_string())
 }
 
 #[derive(Debug, Clone)]
@@ -88,12 +203,22 @@ pub enum ClientAuth {
 #[derive(Debug, Clone)]
 pub struct ClientConnectConfig {
     pub endpoint: String,
+    /// Replicas to fail over to, in order, when `endpoint` is unavailable.
+    pub failover_endpoints: Vec<String>,
+    /// Kernel key used to verify a replica's tree head before failing over
+    /// to it. Without one, failover is refused.
+    pub kernel_pubkey: Option<Vec<u8>>,
+    /// Tree head the caller has already verified, e.g. from a local store.
+    /// Replicas must be consistent with it, so a rolled-back replica is
+    /// refused even before the client has seen a head of its own.
+    pub verified_head: Option<SignedTreeHead>,
     pub tls: Option<ClientTlsOptions>,
     pub auth: Option<ClientAuth>,
     pub connect_timeout_ms: u64,
     pub request_timeout_ms: u64,
     pub keepalive_interval_ms: u64,
     pub keepalive_timeout_ms: u64,
+    pub retry: RetryPolicy,
 }
 
 pub const DEFAULT_CONNECT_TIMEOUT_MS: u64 = 5_000;
@@ -105,13 +230,66 @@ impl ClientConnectConfig {
     pub fn with_endpoint(endpoint: impl Into<String>) -> Self {
         Self {
             endpoint: endpoint.into(),
+            failover_endpoints: Vec::new(),
+            kernel_pubkey: None,
+            verified_head: None,
             tls: None,
             auth: None,
             connect_timeout_ms: DEFAULT_CONNECT_TIMEOUT_MS,
             request_timeout_ms: DEFAULT_REQUEST_TIMEOUT_MS,
             keepalive_interval_ms: DEFAULT_KEEPALIVE_INTERVAL_MS,
             keepalive_timeout_ms: DEFAULT_KEEPALIVE_TIMEOUT_MS,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Config for an ordered replica set: the first endpoint is preferred and
+    /// the rest are failover targets.
+    pub fn with_endpoints<I, S>(endpoints: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut endpoints = endpoints.into_iter().map(Into::into);
+        let mut config = Self::with_endpoint(endpoints.next().unwrap_or_default());
+        config.failover_endpoints = endpoints.collect();
+        config
+    }
+
+    fn endpoint_builder(&self, url: &str) -> Result<Endpoint, ClientError> {
+        let mut endpoint = Endpoint::from_shared(url.to_string())
+            .map_err(|e| ClientError::InvalidInput(format!("invalid endpoint: {e}")))?;
+
+        endpoint = endpoint
+            .connect_timeout(Duration::from_millis(self.connect_timeout_ms))
+            .timeout(Duration::from_millis(self.request_timeout_ms))
+            .http2_keep_alive_interval(Duration::from_millis(self.keepalive_interval_ms))
+            .keep_alive_timeout(Duration::from_millis(self.keepalive_timeout_ms))
+            .keep_alive_while_idle(true)
+            .tcp_keepalive(Some(Duration::from_millis(self.keepalive_interval_ms)));
+
+        if let Some(tls) = self.tls.clone() {
+            let mut tls_config =
+                ClientTlsConfig::new().ca_certificate(Certificate::from_pem(tls.ca_cert_pem));
+            if let Some(domain_name) = tls.domain_name {
+                tls_config = tls_config.domain_name(domain_name);
+            }
+            match (tls.client_cert_pem, tls.client_key_pem) {
+                (Some(cert), Some(key)) => {
+                    tls_config = tls_config.identity(Identity::from_pem(cert, key));
+                }
+                (None, None) => {}
+                _ => {
+                    return Err(ClientError::InvalidInput(
+                        "mTLS requires both client_cert_pem and client_key_pem".to_string(),
+                    ));
+                }
+            }
+            endpoint = endpoint
+                .tls_config(tls_config)
+                .map_err(|e| ClientError::InvalidInput(format!("invalid tls config: {e}")))?;
         }
+        Ok(endpoint)
     }
 }
 
@@ -187,9 +365,21 @@ impl Interceptor for AuthInterceptor {
 
 type InterceptedChannel = tonic::service::interceptor::InterceptedService<Channel, AuthInterceptor>;
 
+type GrpcClient = pb::evidence_os_client::EvidenceOsClient<InterceptedChannel>;
+
+/// Whether a unary call changes kernel state and so needs an idempotency key
+/// before it can be retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CallKind {
+    Query,
+    Mutation,
+}
+
 #[derive(Debug, Clone)]
 pub struct DiscosClient {
-    inner: pb::evidence_os_client::EvidenceOsClient<InterceptedChannel>,
+    replicas: Arc<failover::ReplicaSet>,
+    retry: RetryPolicy,
+    idempotency_scope: Option<String>,
 }
 
 impl DiscosClient {
@@ -197,182 +387,335 @@ impl DiscosClient {
         Self::connect_with_config(ClientConnectConfig::with_endpoint(endpoint)).await
     }
 
+    /// Connects to `config.endpoint`. With failover endpoints configured,
+    /// every endpoint gets a lazily connected channel and the first one that
+    /// passes a health check becomes active.
     pub async fn connect_with_config(config: ClientConnectConfig) -> Result<Self, ClientError> {
-        let mut endpoint = Endpoint::from_shared(config.endpoint)
-            .map_err(|e| ClientError::InvalidInput(format!("invalid endpoint: {e}")))?;
+        let interceptor = AuthInterceptor::new(config.auth.clone());
+        let grpc = |channel| {
+            pb::evidence_os_client::EvidenceOsClient::with_interceptor(channel, interceptor.clone())
+        };
 
-        endpoint = endpoint
-            .connect_timeout(Duration::from_millis(config.connect_timeout_ms))
-            .timeout(Duration::from_millis(config.request_timeout_ms))
-            .http2_keep_alive_interval(Duration::from_millis(config.keepalive_interval_ms))
-            .keep_alive_timeout(Duration::from_millis(config.keepalive_timeout_ms))
-            .keep_alive_while_idle(true)
-            .tcp_keepalive(Some(Duration::from_millis(config.keepalive_interval_ms)));
+        if config.failover_endpoints.is_empty() {
+            let channel = config
+                .endpoint_builder(&config.endpoint)?
+                .connect()
+                .await
+                .map_err(|e| ClientError::Transport(e.to_string()))?;
+            let replica = failover::Replica {
+                endpoint: config.endpoint.clone(),
+                grpc: grpc(channel),
+            };
+            let replicas = failover::ReplicaSet::new(vec![replica], config.kernel_pubkey);
+            if let Some(head) = config.verified_head {
+                replicas.record_verified_head(head);
+            }
+            return Ok(Self {
+                replicas: Arc::new(replicas),
+                retry: config.retry,
+                idempotency_scope: None,
+            });
+        }
 
-        if let Some(tls) = config.tls {
-            let mut tls_config =
-                ClientTlsConfig::new().ca_certificate(Certificate::from_pem(tls.ca_cert_pem));
-            if let Some(domain_name) = tls.domain_name {
-                tls_config = tls_config.domain_name(domain_name);
+        let replicas = std::iter::once(&config.endpoint)
+            .chain(&config.failover_endpoints)
+            .map(|url| {
+                Ok(failover::Replica {
+                    endpoint: url.clone(),
+                    grpc: grpc(config.endpoint_builder(url)?.connect_lazy()),
+                })
+            })
+            .collect::<Result<Vec<_>, ClientError>>()?;
+        let replicas = failover::ReplicaSet::new(replicas, config.kernel_pubkey);
+        if let Some(head) = config.verified_head {
+            replicas.record_verified_head(head);
+        }
+        replicas.select_initial().await?;
+        Ok(Self {
+            replicas: Arc::new(replicas),
+            retry: config.retry,
+            idempotency_scope: None,
+        })
+    }
+
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry
+    }
+
+    /// Derives mutation idempotency keys from `scope`, the method and the
+    /// request instead of generating a fresh one per call. A later process
+    /// repeating the same call under the same scope then sends the same key,
+    /// so a request whose response was lost is not applied twice. Calls
+    /// meant to take effect more than once need different scopes.
+    pub fn with_idempotency_scope(mut self, scope: impl Into<String>) -> Self {
+        self.idempotency_scope = Some(scope.into());
+        self
+    }
+
+    /// Endpoint calls currently go to.
+    pub fn endpoint(&self) -> String {
+        self.replicas.active_endpoint()
+    }
+
+    /// All configured endpoints, in failover order.
+    pub fn endpoints(&self) -> Vec<String> {
+        self.replicas.endpoints()
+    }
+
+    /// Sets the kernel key replica tree heads are verified against before a
+    /// failover; shared with every clone of this client.
+    pub fn set_kernel_pubkey(&self, kernel_pubkey: Vec<u8>) {
+        self.replicas.set_kernel_pubkey(kernel_pubkey);
+    }
+
+    /// Largest tree head verified through this client so far. A replica is
+    /// only failed over to if its head is consistent with this one.
+    pub fn verified_head(&self) -> Option<SignedTreeHead> {
+        self.replicas.verified_head()
+    }
+
+    /// Records a tree head the caller has verified, e.g. from a capsule.
+    /// Smaller heads than the current one are ignored.
+    pub fn record_verified_head(&self, head: SignedTreeHead) {
+        self.replicas.record_verified_head(head);
+    }
+
+    /// Runs a unary call under the retry policy. Mutations get one
+    /// idempotency key that is reused on every attempt; see
+    /// [`Self::with_idempotency_scope`] for keys that outlive the call. When the active
+    /// replica is unavailable the call moves to the next consistent replica
+    /// without waiting for a backoff.
+    async fn unary<Req, Resp, F, Fut>(
+        &self,
+        method: &'static str,
+        kind: CallKind,
+        req: Req,
+        call: F,
+    ) -> Result<Resp, ClientError>
+    where
+        Req: Message + Clone,
+        F: Fn(GrpcClient, Request<Req>) -> Fut,
+        Fut: std::future::Future<Output = Result<tonic::Response<Resp>, Status>>,
+    {
+        let key = match kind {
+            CallKind::Mutation => Some(
+                MetadataValue::try_from(retry::idempotency_key(
+                    self.idempotency_scope.as_deref(),
+                    method,
+                    &req.encode_to_vec(),
+                ))
+                .map_err(|e| ClientError::InvalidInput(format!("invalid idempotency key: {e}")))?,
+            ),
+            CallKind::Query => None,
+        };
+        let mut attempt = 1;
+        let mut tried = BTreeSet::new();
+        loop {
+            let mut request = Request::new(req.clone());
+            if let Some(key) = &key {
+                request
+                    .metadata_mut()
+                    .insert(IDEMPOTENCY_KEY_METADATA_KEY, key.clone());
             }
-            match (tls.client_cert_pem, tls.client_key_pem) {
-                (Some(cert), Some(key)) => {
-                    tls_config = tls_config.identity(Identity::from_pem(cert, key));
-                }
-                (None, None) => {}
-                _ => {
-                    return Err(ClientError::InvalidInput(
-                        "mTLS requires both client_cert_pem and client_key_pem".to_string(),
-                    ));
-                }
+            let (replica, grpc) = self.replicas.active();
+            let err = match call(grpc, request).await {
+                Ok(resp) => return Ok(resp.into_inner()),
+                Err(status) => ClientError::from(status),
+            };
+            if self.replicas.len() > 1
+                && failover::triggers_failover(&err)
+                && self.replicas.fail_over(replica, &mut tried).await?
+            {
+                continue;
             }
-            endpoint = endpoint
-                .tls_config(tls_config)
-                .map_err(|e| ClientError::InvalidInput(format!("invalid tls config: {e}")))?;
+            let Some(delay) = self.retry.delay_for(&err, attempt) else {
+                return Err(err);
+            };
+            tokio::time::sleep(delay).await;
+            attempt += 1;
         }
-
-        let channel = endpoint
-            .connect()
-            .await
-            .map_err(|e| ClientError::Transport(e.to_string()))?;
-        let interceptor = AuthInterceptor::new(config.auth);
-        let inner =
-            pb::evidence_os_client::EvidenceOsClient::with_interceptor(channel, interceptor);
-        Ok(Self { inner })
     }
 
     pub async fn health(&mut self) -> Result<pb::HealthResponse, ClientError> {
-        self.inner
-            .health(pb::HealthRequest {})
-            .await
-            .map_err(|e| ClientError::Kernel(e.to_string()))
-            .map(|r| r.into_inner())
+        self.unary(
+            "health",
+            CallKind::Query,
+            pb::HealthRequest {},
+            |mut c, r| async move { c.health(r).await },
+        )
+        .await
     }
 
     pub async fn create_claim_v2(
         &mut self,
         req: pb::CreateClaimV2Request,
     ) -> Result<pb::CreateClaimV2Response, ClientError> {
-        self.inner
-            .create_claim_v2(req)
-            .await
-            .map(|r| r.into_inner())
-            .map_err(|e| ClientError::Kernel(e.to_string()))
+        self.unary(
+            "create_claim_v2",
+            CallKind::Mutation,
+            req,
+            |mut c, r| async move { c.create_claim_v2(r).await },
+        )
+        .await
     }
 
     pub async fn commit_artifacts(
         &mut self,
         req: pb::CommitArtifactsRequest,
     ) -> Result<pb::CommitArtifactsResponse, ClientError> {
-        self.inner
-            .commit_artifacts(req)
-            .await
-            .map(|r| r.into_inner())
-            .map_err(|e| ClientError::Kernel(e.to_string()))
+        self.unary(
+            "commit_artifacts",
+            CallKind::Mutation,
+            req,
+            |mut c, r| async move { c.commit_artifacts(r).await },
+        )
+        .await
+    }
+
+    pub async fn commit_wasm(
+        &mut self,
+        req: pb::CommitWasmRequest,
+        wasm_artifact: &pb::Artifact,
+    ) -> Result<pb::CommitWasmResponse, ClientError> {
+        verify_wasm_commit(&req, wasm_artifact)?;
+        self.unary(
+            "commit_wasm",
+            CallKind::Mutation,
+            req,
+            |mut c, r| async move { c.commit_wasm(r).await },
+        )
+        .await
     }
 
     pub async fn freeze(
         &mut self,
         req: pb::FreezeRequest,
     ) -> Result<pb::FreezeResponse, ClientError> {
-        self.inner
-            .freeze(req)
-            .await
-            .map(|r| r.into_inner())
-            .map_err(|e| ClientError::Kernel(e.to_string()))
+        self.unary("freeze", CallKind::Mutation, req, |mut c, r| async move {
+            c.freeze(r).await
+        })
+        .await
+    }
+
+    pub async fn seal(&mut self, req: pb::SealRequest) -> Result<pb::SealResponse, ClientError> {
+        self.unary("seal", CallKind::Mutation, req, |mut c, r| async move {
+            c.seal(r).await
+        })
+        .await
     }
 
     pub async fn execute_claim_v2(
         &mut self,
         req: pb::ExecuteClaimV2Request,
     ) -> Result<pb::ExecuteClaimV2Response, ClientError> {
-        self.inner
-            .execute_claim_v2(req)
-            .await
-            .map(|r| r.into_inner())
-            .map_err(|e| ClientError::Kernel(e.to_string()))
+        self.unary(
+            "execute_claim_v2",
+            CallKind::Mutation,
+            req,
+            |mut c, r| async move { c.execute_claim_v2(r).await },
+        )
+        .await
     }
 
     pub async fn fetch_capsule(
         &mut self,
         req: pb::FetchCapsuleRequest,
     ) -> Result<pb::FetchCapsuleResponse, ClientError> {
-        self.inner
-            .fetch_capsule(req)
-            .await
-            .map(|r| r.into_inner())
-            .map_err(|e| ClientError::Kernel(e.to_string()))
+        self.unary(
+            "fetch_capsule",
+            CallKind::Query,
+            req,
+            |mut c, r| async move { c.fetch_capsule(r).await },
+        )
+        .await
     }
 
     pub async fn get_signed_tree_head(
         &mut self,
         req: pb::GetSignedTreeHeadRequest,
     ) -> Result<pb::GetSignedTreeHeadResponse, ClientError> {
-        self.inner
-            .get_signed_tree_head(req)
-            .await
-            .map(|r| r.into_inner())
-            .map_err(|e| ClientError::Kernel(e.to_string()))
+        self.unary(
+            "get_signed_tree_head",
+            CallKind::Query,
+            req,
+            |mut c, r| async move { c.get_signed_tree_head(r).await },
+        )
+        .await
     }
 
     pub async fn get_inclusion_proof(
         &mut self,
         req: pb::GetInclusionProofRequest,
     ) -> Result<pb::GetInclusionProofResponse, ClientError> {
-        self.inner
-            .get_inclusion_proof(req)
-            .await
-            .map(|r| r.into_inner())
-            .map_err(|e| ClientError::Kernel(e.to_string()))
+        self.unary(
+            "get_inclusion_proof",
+            CallKind::Query,
+            req,
+            |mut c, r| async move { c.get_inclusion_proof(r).await },
+        )
+        .await
     }
 
     pub async fn get_consistency_proof(
         &mut self,
         req: pb::GetConsistencyProofRequest,
     ) -> Result<pb::GetConsistencyProofResponse, ClientError> {
-        self.inner
-            .get_consistency_proof(req)
-            .await
-            .map(|r| r.into_inner())
-            .map_err(|e| ClientError::Kernel(e.to_string()))
+        self.unary(
+            "get_consistency_proof",
+            CallKind::Query,
+            req,
+            |mut c, r| async move { c.get_consistency_proof(r).await },
+        )
+        .await
     }
 
     pub async fn revoke_claim(
         &mut self,
         req: pb::RevokeClaimRequest,
     ) -> Result<pb::RevokeClaimResponse, ClientError> {
-        self.inner
-            .revoke_claim(req)
-            .await
-            .map(|r| r.into_inner())
-            .map_err(|e| ClientError::Kernel(e.to_string()))
+        self.unary(
+            "revoke_claim",
+            CallKind::Mutation,
+            req,
+            |mut c, r| async move { c.revoke_claim(r).await },
+        )
+        .await
     }
 
     pub async fn watch_revocations(
         &mut self,
         req: pb::WatchRevocationsRequest,
     ) -> Result<tonic::Streaming<pb::WatchRevocationsResponse>, ClientError> {
-        self.inner
-            .watch_revocations(req)
+        let (_, mut grpc) = self.replicas.active();
+        grpc.watch_revocations(req)
             .await
             .map(|r| r.into_inner())
-            .map_err(|e| ClientError::Kernel(e.to_string()))
+            .map_err(ClientError::from)
     }
 
     pub async fn get_server_info(&mut self) -> Result<pb::GetServerInfoResponse, ClientError> {
-        self.inner
-            .get_server_info(pb::GetServerInfoRequest {})
-            .await
-            .map(|r| r.into_inner())
-            .map_err(|e| ClientError::Kernel(e.to_string()))
+        self.unary(
+            "get_server_info",
+            CallKind::Query,
+            pb::GetServerInfoRequest {},
+            |mut c, r| async move { c.get_server_info(r).await },
+        )
+        .await
     }
 
     pub async fn get_public_key(&mut self) -> Result<pb::GetPublicKeyResponse, ClientError> {
-        self.inner
-            .get_public_key(pb::GetPublicKeyRequest { key_id: Vec::new() })
-            .await
-            .map(|r| r.into_inner())
-            .map_err(|e| ClientError::Kernel(e.to_string()))
+        self.unary(
+            "get_public_key",
+            CallKind::Query,
+            pb::GetPublicKeyRequest { key_id: Vec::new() },
+            |mut c, r| async move { c.get_public_key(r).await },
+        )
+        .await
     }
 }
 
@@ -387,6 +730,7 @@ mod tests {
         assert_eq!(config.request_timeout_ms, DEFAULT_REQUEST_TIMEOUT_MS);
         assert_eq!(config.keepalive_interval_ms, DEFAULT_KEEPALIVE_INTERVAL_MS);
         assert_eq!(config.keepalive_timeout_ms, DEFAULT_KEEPALIVE_TIMEOUT_MS);
+        assert_eq!(config.retry.max_attempts, 1);
     }
 }
 
@@ -404,6 +748,38 @@ pub fn validate_claim_and_topic_ids(claim_id: &[u8], topic_id: &[u8]) -> Result<
     Ok(())
 }
 
+pub const WASM_MODULE_ARTIFACT_KIND: &str = "wasm_module";
+
+/// Local pre-checks for `CommitWasm`: the module must pass the restricted
+/// wasm profile, and `wasm_hash` must be the sha256 of the module and equal
+/// the hash of the `wasm_module` artifact committed for the claim.
+pub fn verify_wasm_commit(
+    req: &pb::CommitWasmRequest,
+    wasm_artifact: &pb::Artifact,
+) -> Result<(), ClientError> {
+    if wasm_artifact.kind != WASM_MODULE_ARTIFACT_KIND {
+        return Err(ClientError::InvalidInput(format!(
+            "expected `{WASM_MODULE_ARTIFACT_KIND}` artifact, got `{}`",
+            wasm_artifact.kind
+        )));
+    }
+    verify_restricted_wasm(&req.wasm_module)
+        .map_err(|e| ClientError::InvalidInput(format!("wasm module rejected: {e}")))?;
+
+    let module_hash = sha256(&req.wasm_module);
+    if req.wasm_hash.as_slice() != module_hash {
+        return Err(ClientError::InvalidInput(
+            "wasm_hash does not match sha256 of wasm_module".to_string(),
+        ));
+    }
+    if wasm_artifact.artifact_hash.as_slice() != module_hash {
+        return Err(ClientError::InvalidInput(
+            "wasm_module does not match the committed wasm_module artifact hash".to_string(),
+        ));
+    }
+    Ok(())
+}
+
 #[derive(Debug, Deserialize)]
 struct CapsuleView {
     structured_output_hash_hex: String,
@@ -479,7 +855,7 @@ pub struct ConsistencyProof {
     pub path: Vec<[u8; 32]>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SignedRevocation {
     pub claim_id: Vec<u8>,
     pub reason_code: String,
@@ -570,31 +946,61 @@ pub fn verify_capsule_response(
     let root_hash: [u8; 32] = response.root_hash.as_slice().try_into().map_err(|_| {
         ClientError::VerificationFailed("etl_root_hash must be 32 bytes".to_string())
     })?;
-    let proof = InclusionProof {
-        leaf_hash,
-        leaf_index: inclusion.leaf_index,
-        tree_size: inclusion.tree_size,
-        audit_path: inclusion
-            .audit_path
-            .iter()
-            .map(|n| {
-                n.as_slice().try_into().map_err(|_| {
-                    ClientError::VerificationFailed(
-                        "inclusion audit path node must be 32 bytes".to_string(),
-                    )
-                })
-            })
-            .collect::<Result<Vec<[u8; 32]>, ClientError>>()?,
-    };
+    let proof = inclusion_proof_from_pb(inclusion)?;
     if !verify_inclusion_proof(root_hash, &proof) {
         return Err(ClientError::VerificationFailed(
             "inclusion proof verification failed".to_string(),
         ));
     }
 
-    let sth = SignedTreeHead {
+    let sth = capsule_tree_head(response)?;
+    verify_sth_signature(&sth, server_pubkey)?;
+
+    if let (Some(prev), Some(consistency)) = (previous_sth, response.consistency_proof.as_ref()) {
+        let consistency_proof = consistency_proof_from_pb(consistency)?;
+        if !verify_consistency_proof(prev.root_hash, sth.root_hash, &consistency_proof) {
+            return Err(ClientError::VerificationFailed(
+                "consistency proof verification failed".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// [`verify_capsule_response`], then checks `revocations` against the
+/// capsule's tree head and reports whether its claim has been revoked.
+pub fn verify_capsule_response_with_revocations(
+    response: &pb::FetchCapsuleResponse,
+    structured_output: &[u8],
+    expected_claim_id: &[u8],
+    expected_topic_id: &[u8],
+    server_pubkey: &[u8],
+    previous_sth: Option<&SignedTreeHead>,
+    revocations: &RevocationSnapshot,
+) -> Result<CapsuleValidity, ClientError> {
+    verify_capsule_response(
+        response,
+        structured_output,
+        expected_claim_id,
+        expected_topic_id,
+        server_pubkey,
+        previous_sth,
+    )?;
+    revocations.verify(&capsule_tree_head(response)?, server_pubkey)?;
+    Ok(revocations.validity(expected_claim_id))
+}
+
+/// Tree head a `FetchCapsule` response is signed under: the response's own
+/// size and root with the signature from its `signed_tree_head`.
+pub(crate) fn capsule_tree_head(
+    response: &pb::FetchCapsuleResponse,
+) -> Result<SignedTreeHead, ClientError> {
+    Ok(SignedTreeHead {
         tree_size: response.tree_size,
-        root_hash,
+        root_hash: response.root_hash.as_slice().try_into().map_err(|_| {
+            ClientError::VerificationFailed("etl_root_hash must be 32 bytes".to_string())
+        })?,
         signature: response
             .signed_tree_head
             .as_ref()
@@ -605,33 +1011,112 @@ pub fn verify_capsule_response(
             .map_err(|_| {
                 ClientError::VerificationFailed("sth_signature must be 64 bytes".to_string())
             })?,
-    };
-    verify_sth_signature(&sth, server_pubkey)?;
+    })
+}
 
-    if let (Some(prev), Some(consistency)) = (previous_sth, response.consistency_proof.as_ref()) {
-        let consistency_proof = ConsistencyProof {
-            old_tree_size: consistency.old_tree_size,
-            new_tree_size: consistency.new_tree_size,
-            path: consistency
-                .path
-                .iter()
-                .map(|n| {
-                    n.as_slice().try_into().map_err(|_| {
-                        ClientError::VerificationFailed(
-                            "consistency path node must be 32 bytes".to_string(),
-                        )
-                    })
-                })
-                .collect::<Result<Vec<[u8; 32]>, ClientError>>()?,
-        };
-        if !verify_consistency_proof(prev.root_hash, sth.root_hash, &consistency_proof) {
-            return Err(ClientError::VerificationFailed(
-                "consistency proof verification failed".to_string(),
-            ));
-        }
-    }
+fn hash32_field(bytes: &[u8], what: &str) -> Result<[u8; 32], ClientError> {
+    bytes
+        .try_into()
+        .map_err(|_| ClientError::VerificationFailed(format!("{what} must be 32 bytes")))
+}
 
-    Ok(())
+pub fn signed_tree_head_from_pb(sth: &pb::SignedTreeHead) -> Result<SignedTreeHead, ClientError> {
+    Ok(SignedTreeHead {
+        tree_size: sth.tree_size,
+        root_hash: hash32_field(&sth.root_hash, "sth root_hash")?,
+        signature: sth.signature.as_slice().try_into().map_err(|_| {
+            ClientError::VerificationFailed("sth_signature must be 64 bytes".to_string())
+        })?,
+    })
+}
+
+pub fn signed_revocation_from_pb(
+    entry: &pb::RevocationEntry,
+) -> Result<SignedRevocation, ClientError> {
+    Ok(SignedRevocation {
+        claim_id: entry.claim_id.clone(),
+        reason_code: entry.reason.clone(),
+        logical_epoch: entry.logical_epoch,
+        signature: entry.signature.as_slice().try_into().map_err(|_| {
+            ClientError::VerificationFailed("revocation signature must be 64 bytes".to_string())
+        })?,
+    })
+}
+
+pub fn inclusion_proof_from_pb(
+    proof: &pb::MerkleInclusionProof,
+) -> Result<InclusionProof, ClientError> {
+    Ok(InclusionProof {
+        leaf_hash: hash32_field(&proof.leaf_hash, "inclusion leaf_hash")?,
+        leaf_index: proof.leaf_index,
+        tree_size: proof.tree_size,
+        audit_path: proof
+            .audit_path
+            .iter()
+            .map(|n| hash32_field(n, "inclusion audit path node"))
+            .collect::<Result<Vec<_>, _>>()?,
+    })
+}
+
+pub fn consistency_proof_from_pb(
+    proof: &pb::MerkleConsistencyProof,
+) -> Result<ConsistencyProof, ClientError> {
+    Ok(ConsistencyProof {
+        old_tree_size: proof.old_tree_size,
+        new_tree_size: proof.new_tree_size,
+        path: proof
+            .path
+            .iter()
+            .map(|n| hash32_field(n, "consistency path node"))
+            .collect::<Result<Vec<_>, _>>()?,
+    })
+}
+
+/// Decodes a `GetSignedTreeHead` response and checks its signature.
+pub fn verify_signed_tree_head_response(
+    response: &pb::GetSignedTreeHeadResponse,
+    kernel_pubkey: &[u8],
+) -> Result<SignedTreeHead, ClientError> {
+    let sth = signed_tree_head_from_pb(
+        response
+            .signed_tree_head
+            .as_ref()
+            .ok_or_else(|| ClientError::VerificationFailed("missing signed tree head".into()))?,
+    )?;
+    verify_sth_signature(&sth, kernel_pubkey)?;
+    Ok(sth)
+}
+
+/// Checks the tree head signature of a `GetInclusionProof` response and that
+/// the proof is rooted in that head.
+pub fn verify_inclusion_proof_response(
+    response: &pb::GetInclusionProofResponse,
+    kernel_pubkey: &[u8],
+) -> Result<(SignedTreeHead, InclusionProof), ClientError> {
+    let sth = signed_tree_head_from_pb(
+        response
+            .signed_tree_head
+            .as_ref()
+            .ok_or_else(|| ClientError::VerificationFailed("missing signed tree head".into()))?,
+    )?;
+    verify_sth_signature(&sth, kernel_pubkey)?;
+    let proof = inclusion_proof_from_pb(
+        response
+            .inclusion_proof
+            .as_ref()
+            .ok_or_else(|| ClientError::VerificationFailed("missing inclusion proof".into()))?,
+    )?;
+    if proof.tree_size != sth.tree_size {
+        return Err(ClientError::VerificationFailed(
+            "inclusion proof tree_size does not match signed tree head".into(),
+        ));
+    }
+    if !verify_inclusion_proof(sth.root_hash, &proof) {
+        return Err(ClientError::VerificationFailed(
+            "inclusion proof verification failed".into(),
+        ));
+    }
+    Ok((sth, proof))
 }
 
 pub fn verify_inclusion(root: [u8; 32], proof: &InclusionProof) -> bool {
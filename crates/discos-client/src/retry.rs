@@ -0,0 +1,201 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Retry policy for unary RPCs.
+//!
+//! Failures are classified by status code. Transport errors and
+//! `Unavailable`, `DeadlineExceeded` and `Aborted` are retried with
+//! exponential backoff and jitter. `ResourceExhausted` is only retried when
+//! the daemon attaches a retry-after hint: without one it reports a spent
+//! topic budget, which waiting does not fix. Everything else fails at once.
+//!
+//! Lifecycle calls that change kernel state carry an idempotency key in
+//! [`IDEMPOTENCY_KEY_METADATA_KEY`]. The key is generated once per call and
+//! sent unchanged on every attempt, so a daemon that already applied the
+//! request answers with the original result instead of, say, registering a
+//! second claim. A client with an idempotency scope derives the key from the
+//! scope and request instead, so the guarantee also covers a rerun by a
+//! later process.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tonic::Code;
+
+use crate::{sha256, ClientError, KernelError};
+
+pub const IDEMPOTENCY_KEY_METADATA_KEY: &str = "x-idempotency-key";
+/// Retry-after hint in milliseconds.
+pub const RETRY_AFTER_MS_METADATA_KEY: &str = "x-evidenceos-retry-after-ms";
+/// Retry-after hint in whole seconds, as in HTTP.
+pub const RETRY_AFTER_METADATA_KEY: &str = "retry-after";
+
+pub const DEFAULT_RETRY_MAX_ATTEMPTS: u32 = 1;
+pub const DEFAULT_RETRY_INITIAL_BACKOFF_MS: u64 = 200;
+pub const DEFAULT_RETRY_MAX_BACKOFF_MS: u64 = 5_000;
+pub const DEFAULT_RETRY_MULTIPLIER: f64 = 2.0;
+pub const DEFAULT_RETRY_JITTER: f64 = 0.5;
+pub const DEFAULT_RETRY_MAX_RETRY_AFTER_MS: u64 = 30_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Total attempts per call, including the first; `1` disables retries.
+    pub max_attempts: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    pub multiplier: f64,
+    /// Fraction of each backoff that is randomised away, in `0.0..=1.0`.
+    pub jitter: f64,
+    /// Longest retry-after hint that is honoured; longer hints fail the call.
+    pub max_retry_after_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_RETRY_MAX_ATTEMPTS,
+            initial_backoff_ms: DEFAULT_RETRY_INITIAL_BACKOFF_MS,
+            max_backoff_ms: DEFAULT_RETRY_MAX_BACKOFF_MS,
+            multiplier: DEFAULT_RETRY_MULTIPLIER,
+            jitter: DEFAULT_RETRY_JITTER,
+            max_retry_after_ms: DEFAULT_RETRY_MAX_RETRY_AFTER_MS,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Default backoff settings with up to `max_attempts` attempts per call.
+    pub fn with_max_attempts(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+
+    /// Backoff before retry number `retry` (1 for the first retry), without
+    /// jitter.
+    pub fn base_backoff(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(63) as i32;
+        let millis = self.initial_backoff_ms as f64 * self.multiplier.max(1.0).powi(exponent);
+        Duration::from_millis(millis.min(self.max_backoff_ms as f64) as u64)
+    }
+
+    /// Backoff before retry number `retry`, with jitter applied.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let jitter = self.jitter.clamp(0.0, 1.0) * jitter_fraction();
+        self.base_backoff(retry).mul_f64(1.0 - jitter)
+    }
+
+    /// How long to wait before retrying after `err` at attempt `attempt`
+    /// (1-based), or `None` if the call should fail now.
+    pub fn delay_for(&self, err: &ClientError, attempt: u32) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        match classify(err) {
+            RetryDecision::Retry => Some(self.backoff(attempt)),
+            RetryDecision::RetryAfter(hint)
+                if hint <= Duration::from_millis(self.max_retry_after_ms) =>
+            {
+                Some(hint)
+            }
+            RetryDecision::RetryAfter(_) | RetryDecision::Fail => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryDecision {
+    /// Transient failure; retry after the policy's backoff.
+    Retry,
+    /// Throttled; retry once the daemon's hint has elapsed.
+    RetryAfter(Duration),
+    Fail,
+}
+
+pub fn classify(err: &ClientError) -> RetryDecision {
+    match err {
+        ClientError::Transport(_) => RetryDecision::Retry,
+        ClientError::Kernel(kernel) => match kernel.code {
+            Code::Unavailable | Code::DeadlineExceeded | Code::Aborted => RetryDecision::Retry,
+            Code::ResourceExhausted => retry_after_hint(kernel)
+                .map(RetryDecision::RetryAfter)
+                .unwrap_or(RetryDecision::Fail),
+            _ => RetryDecision::Fail,
+        },
+        ClientError::InvalidInput(_) | ClientError::VerificationFailed(_) => RetryDecision::Fail,
+    }
+}
+
+/// Reads a retry-after hint from status metadata, or from a
+/// `retry_after_ms` field when the status message is a JSON object.
+pub fn retry_after_hint(kernel: &KernelError) -> Option<Duration> {
+    if let Some(ms) = kernel
+        .metadata_value(RETRY_AFTER_MS_METADATA_KEY)
+        .and_then(|v| v.trim().parse::<u64>().ok())
+    {
+        return Some(Duration::from_millis(ms));
+    }
+    if let Some(secs) = kernel
+        .metadata_value(RETRY_AFTER_METADATA_KEY)
+        .and_then(|v| v.trim().parse::<u64>().ok())
+    {
+        return Some(Duration::from_secs(secs));
+    }
+    serde_json::from_str::<serde_json::Value>(&kernel.message)
+        .ok()?
+        .get("retry_after_ms")?
+        .as_u64()
+        .map(Duration::from_millis)
+}
+
+static CALL_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+fn unique_material() -> Vec<u8> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let mut material = nanos.to_be_bytes().to_vec();
+    material.extend_from_slice(&CALL_COUNTER.fetch_add(1, Ordering::Relaxed).to_be_bytes());
+    material.extend_from_slice(&std::process::id().to_be_bytes());
+    material
+}
+
+/// Uniform-ish value in `[0, 1)`; only used to spread out retries.
+fn jitter_fraction() -> f64 {
+    let digest = sha256(&unique_material());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    (u64::from_be_bytes(bytes) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Key for one logical call of `method` with the encoded `request`: fresh
+/// per call without a `scope`, and the same for every call with one.
+pub(crate) fn idempotency_key(scope: Option<&str>, method: &str, request: &[u8]) -> String {
+    let mut material = method.as_bytes().to_vec();
+    material.push(0);
+    material.extend_from_slice(&sha256(request));
+    match scope {
+        Some(scope) => {
+            material.push(1);
+            material.extend_from_slice(scope.as_bytes());
+        }
+        None => {
+            material.push(0);
+            material.extend_from_slice(&unique_material());
+        }
+    }
+    hex::encode(&sha256(&material)[..16])
+}
@@ -0,0 +1,438 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Verified revocation feed.
+//!
+//! [`RevocationWatcher`] consumes `WatchRevocations`, checks every entry
+//! against the pinned kernel key and folds it into a [`RevocationSet`] that
+//! can be persisted and reloaded. `WatchRevocationsRequest` carries no
+//! cursor, so after a reconnect the daemon replays its feed and entries the
+//! set already holds are skipped; anything older than the last logical epoch
+//! that is *not* already known is treated as an out-of-order entry.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tonic::Code;
+
+use crate::{
+    pb, signed_revocation_from_pb, verifier, verify_revocation_signature, ClientError,
+    DiscosClient, SignedRevocation, SignedTreeHead,
+};
+
+pub const DEFAULT_MAX_RECONNECTS: u32 = 5;
+pub const DEFAULT_RECONNECT_DELAY_MS: u64 = 1_000;
+
+/// A revocation whose kernel signature has been checked.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevocationRecord {
+    pub claim_id_hex: String,
+    pub reason_code: String,
+    pub logical_epoch: u64,
+    /// Daemon wall-clock time; not covered by the signature.
+    pub timestamp_unix: u64,
+    pub signature_hex: String,
+}
+
+impl RevocationRecord {
+    fn verified(entry: &pb::RevocationEntry, kernel_pubkey: &[u8]) -> Result<Self, ClientError> {
+        let signed = signed_revocation_from_pb(entry)?;
+        verify(&signed, kernel_pubkey)?;
+        Ok(Self {
+            claim_id_hex: hex::encode(&signed.claim_id),
+            reason_code: signed.reason_code,
+            logical_epoch: signed.logical_epoch,
+            timestamp_unix: entry.timestamp_unix,
+            signature_hex: hex::encode(signed.signature),
+        })
+    }
+
+    pub fn to_signed(&self) -> Result<SignedRevocation, ClientError> {
+        let claim_id = hex::decode(&self.claim_id_hex).map_err(|_| {
+            ClientError::InvalidInput("revocation claim_id_hex is not hex".to_string())
+        })?;
+        let signature = hex::decode(&self.signature_hex)
+            .ok()
+            .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+            .ok_or_else(|| {
+                ClientError::InvalidInput("revocation signature_hex must be 64 bytes".to_string())
+            })?;
+        Ok(SignedRevocation {
+            claim_id,
+            reason_code: self.reason_code.clone(),
+            logical_epoch: self.logical_epoch,
+            signature,
+        })
+    }
+
+    fn to_verifier_entry(&self) -> Result<verifier::RevocationEntry, ClientError> {
+        let signed = self.to_signed()?;
+        Ok(verifier::RevocationEntry {
+            claim_id: signed.claim_id,
+            reason_code: signed.reason_code,
+            logical_epoch: signed.logical_epoch,
+            signature: signed.signature,
+        })
+    }
+
+    fn same_revocation(&self, other: &Self) -> bool {
+        self.reason_code == other.reason_code
+            && self.logical_epoch == other.logical_epoch
+            && self.signature_hex == other.signature_hex
+    }
+}
+
+fn verify(revocation: &SignedRevocation, kernel_pubkey: &[u8]) -> Result<(), ClientError> {
+    verify_revocation_signature(revocation, kernel_pubkey).map_err(|err| match err {
+        ClientError::VerificationFailed(_) => ClientError::VerificationFailed(format!(
+            "forged revocation for claim {} at logical epoch {}",
+            hex::encode(&revocation.claim_id),
+            revocation.logical_epoch
+        )),
+        other => other,
+    })
+}
+
+/// Verified revocations keyed by claim id, plus the highest logical epoch seen.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevocationSet {
+    last_logical_epoch: Option<u64>,
+    entries: BTreeMap<String, RevocationRecord>,
+}
+
+impl RevocationSet {
+    /// Loads a persisted set and re-verifies every entry against the pinned
+    /// key, so a tampered file is rejected rather than trusted. A missing
+    /// file yields an empty set.
+    pub fn load_verified(path: &Path, kernel_pubkey: &[u8]) -> Result<Self, ClientError> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => {
+                return Err(ClientError::InvalidInput(format!(
+                    "read revocation set {}: {err}",
+                    path.display()
+                )))
+            }
+        };
+        let stored: Self = serde_json::from_slice(&bytes).map_err(|err| {
+            ClientError::InvalidInput(format!("parse revocation set {}: {err}", path.display()))
+        })?;
+        let mut max_epoch = None;
+        for (claim_id_hex, record) in &stored.entries {
+            if *claim_id_hex != record.claim_id_hex {
+                return Err(ClientError::VerificationFailed(format!(
+                    "revocation set entry {claim_id_hex} is keyed under the wrong claim"
+                )));
+            }
+            verify(&record.to_signed()?, kernel_pubkey)?;
+            max_epoch = max_epoch.max(Some(record.logical_epoch));
+        }
+        if max_epoch != stored.last_logical_epoch {
+            return Err(ClientError::VerificationFailed(
+                "revocation set logical epoch does not match its entries".to_string(),
+            ));
+        }
+        Ok(stored)
+    }
+
+    pub fn persist(&self, path: &Path) -> Result<(), ClientError> {
+        let io_err = |err: std::io::Error| {
+            ClientError::InvalidInput(format!("persist revocation set: {err}"))
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(io_err)?;
+        }
+        let bytes = serde_json::to_vec_pretty(self)
+            .map_err(|err| ClientError::InvalidInput(format!("encode revocation set: {err}")))?;
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, bytes).map_err(io_err)?;
+        fs::rename(&tmp, path).map_err(io_err)
+    }
+
+    pub fn last_logical_epoch(&self) -> Option<u64> {
+        self.last_logical_epoch
+    }
+
+    pub fn get(&self, claim_id: &[u8]) -> Option<&RevocationRecord> {
+        self.entries.get(&hex::encode(claim_id))
+    }
+
+    pub fn is_revoked(&self, claim_id: &[u8]) -> bool {
+        self.get(claim_id).is_some()
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = &RevocationRecord> {
+        self.entries.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Binds the current entries to `sth`.
+    pub fn snapshot(&self, sth: &SignedTreeHead) -> Result<RevocationSnapshot, ClientError> {
+        let entries = self.entries.values().cloned().collect::<Vec<_>>();
+        let verifier_entries = entries
+            .iter()
+            .map(RevocationRecord::to_verifier_entry)
+            .collect::<Result<Vec<_>, _>>()?;
+        let digest = verifier::revocations_snapshot_digest(&verifier_entries, &verifier_head(sth));
+        Ok(RevocationSnapshot {
+            entries,
+            digest_hex: hex::encode(digest),
+        })
+    }
+
+    /// Verifies and inserts a streamed entry. Returns `Ok(false)` for an exact
+    /// replay of a known revocation; forged, conflicting or out-of-order
+    /// entries are errors.
+    pub fn apply(
+        &mut self,
+        entry: &pb::RevocationEntry,
+        kernel_pubkey: &[u8],
+    ) -> Result<bool, ClientError> {
+        let record = RevocationRecord::verified(entry, kernel_pubkey)?;
+        if let Some(known) = self.entries.get(&record.claim_id_hex) {
+            return if known.same_revocation(&record) {
+                Ok(false)
+            } else {
+                Err(ClientError::VerificationFailed(format!(
+                    "conflicting revocations for claim {}",
+                    record.claim_id_hex
+                )))
+            };
+        }
+        if let Some(last) = self.last_logical_epoch {
+            if record.logical_epoch < last {
+                return Err(ClientError::VerificationFailed(format!(
+                    "out-of-order revocation for claim {}: logical epoch {} after {}",
+                    record.claim_id_hex, record.logical_epoch, last
+                )));
+            }
+        }
+        self.last_logical_epoch = Some(record.logical_epoch);
+        self.entries.insert(record.claim_id_hex.clone(), record);
+        Ok(true)
+    }
+}
+
+/// Whether a capsule that verified may still be relied on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CapsuleValidity {
+    Valid,
+    /// The capsule verifies, but the kernel has since revoked its claim.
+    Revoked {
+        reason_code: String,
+        logical_epoch: u64,
+    },
+}
+
+/// Revocations bound to one signed tree head by
+/// `revocations_snapshot_digest`, so a verifier can tell which revocations
+/// the snapshot's builder claims were known at that head. The digest is not
+/// keyed: whoever builds the snapshot can leave entries out and recompute it,
+/// so a snapshot proves the revocations it holds, not that there are no
+/// others.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RevocationSnapshot {
+    pub entries: Vec<RevocationRecord>,
+    pub digest_hex: String,
+}
+
+impl RevocationSnapshot {
+    /// Checks every entry's kernel signature and that the digest binds
+    /// exactly these entries to `sth`.
+    pub fn verify(&self, sth: &SignedTreeHead, kernel_pubkey: &[u8]) -> Result<(), ClientError> {
+        let entries = self
+            .entries
+            .iter()
+            .map(|record| {
+                verify(&record.to_signed()?, kernel_pubkey)?;
+                record.to_verifier_entry()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let digest = hex::decode(&self.digest_hex)
+            .ok()
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .ok_or_else(|| {
+                ClientError::InvalidInput(
+                    "revocation snapshot digest_hex must be 32 bytes".to_string(),
+                )
+            })?;
+        if !verifier::verify_revocations_snapshot(&entries, &verifier_head(sth), digest) {
+            return Err(ClientError::VerificationFailed(
+                "revocation snapshot digest does not match its entries and tree head".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The validity of `claim_id` under this snapshot. For a snapshot read
+    /// from elsewhere, only meaningful after [`RevocationSnapshot::verify`].
+    pub fn validity(&self, claim_id: &[u8]) -> CapsuleValidity {
+        let claim_id_hex = hex::encode(claim_id);
+        match self
+            .entries
+            .iter()
+            .find(|record| record.claim_id_hex.eq_ignore_ascii_case(&claim_id_hex))
+        {
+            Some(record) => CapsuleValidity::Revoked {
+                reason_code: record.reason_code.clone(),
+                logical_epoch: record.logical_epoch,
+            },
+            None => CapsuleValidity::Valid,
+        }
+    }
+}
+
+fn verifier_head(sth: &SignedTreeHead) -> verifier::SignedTreeHead {
+    verifier::SignedTreeHead {
+        tree_size: sth.tree_size,
+        root_hash: sth.root_hash,
+        signature: sth.signature,
+    }
+}
+
+/// Verified, reconnecting consumer of `WatchRevocations`.
+#[derive(Debug)]
+pub struct RevocationWatcher {
+    client: DiscosClient,
+    kernel_pubkey: Vec<u8>,
+    set: RevocationSet,
+    store_path: Option<PathBuf>,
+    max_reconnects: u32,
+    reconnect_delay: Duration,
+    stream: Option<tonic::Streaming<pb::WatchRevocationsResponse>>,
+    pending: VecDeque<pb::RevocationEntry>,
+}
+
+impl RevocationWatcher {
+    pub fn new(
+        client: DiscosClient,
+        kernel_pubkey: &[u8],
+        set: RevocationSet,
+    ) -> Result<Self, ClientError> {
+        if kernel_pubkey.len() != 32 {
+            return Err(ClientError::InvalidInput(
+                "ed25519 pubkey must be 32 bytes".into(),
+            ));
+        }
+        Ok(Self {
+            client,
+            kernel_pubkey: kernel_pubkey.to_vec(),
+            set,
+            store_path: None,
+            max_reconnects: DEFAULT_MAX_RECONNECTS,
+            reconnect_delay: Duration::from_millis(DEFAULT_RECONNECT_DELAY_MS),
+            stream: None,
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Persists the set to `path` after every new revocation.
+    pub fn with_store(mut self, path: impl Into<PathBuf>) -> Self {
+        self.store_path = Some(path.into());
+        self
+    }
+
+    /// Consecutive reconnect attempts allowed before giving up, and the pause
+    /// between them.
+    pub fn with_reconnect(mut self, max_reconnects: u32, delay: Duration) -> Self {
+        self.max_reconnects = max_reconnects;
+        self.reconnect_delay = delay;
+        self
+    }
+
+    pub fn revocations(&self) -> &RevocationSet {
+        &self.set
+    }
+
+    /// Waits for the next revocation not already in the set. Transport
+    /// failures and stream ends trigger a reconnect; verification failures
+    /// are returned immediately.
+    pub async fn next(&mut self) -> Result<RevocationRecord, ClientError> {
+        let mut failures = 0u32;
+        loop {
+            while let Some(entry) = self.pending.pop_front() {
+                if self.set.apply(&entry, &self.kernel_pubkey)? {
+                    if let Some(path) = &self.store_path {
+                        self.set.persist(path)?;
+                    }
+                    let claim_id_hex = hex::encode(&entry.claim_id);
+                    return self.set.entries.get(&claim_id_hex).cloned().ok_or_else(|| {
+                        ClientError::VerificationFailed(format!(
+                            "revocation for {claim_id_hex} missing after insert"
+                        ))
+                    });
+                }
+            }
+
+            let outcome = match self.stream.as_mut() {
+                Some(stream) => stream.message().await.map_err(ClientError::from),
+                None => match self
+                    .client
+                    .watch_revocations(pb::WatchRevocationsRequest {})
+                    .await
+                {
+                    Ok(stream) => {
+                        self.stream = Some(stream);
+                        continue;
+                    }
+                    Err(err) => Err(err),
+                },
+            };
+            let reason = match outcome {
+                Ok(Some(resp)) => {
+                    failures = 0;
+                    self.pending.extend(resp.entries);
+                    continue;
+                }
+                Ok(None) => ClientError::Transport("revocation stream closed".to_string()),
+                Err(err) if is_reconnectable(&err) => err,
+                Err(err) => return Err(err),
+            };
+            self.stream = None;
+            failures += 1;
+            if failures > self.max_reconnects {
+                return Err(reason);
+            }
+            tokio::time::sleep(self.reconnect_delay).await;
+        }
+    }
+}
+
+fn is_reconnectable(err: &ClientError) -> bool {
+    match err {
+        ClientError::Transport(_) => true,
+        ClientError::Kernel(kernel) => matches!(
+            kernel.code,
+            Code::Unavailable
+                | Code::Unknown
+                | Code::Cancelled
+                | Code::DeadlineExceeded
+                | Code::Aborted
+        ),
+        ClientError::InvalidInput(_) | ClientError::VerificationFailed(_) => false,
+    }
+}
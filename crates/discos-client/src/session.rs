@@ -0,0 +1,282 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Typestate driver for the v2 claim lifecycle.
+//!
+//! A [`ClaimSession`] can only move forward through
+//! `Created → Committed → Frozen → [Sealed →] Executed`, and consuming the
+//! executed session with [`ClaimSession::fetch_capsule`] verifies the capsule
+//! before handing it back. Ordering is only enforced within one session
+//! value: out-of-order calls on it do not compile, but
+//! [`ClaimSession::resume`] can build a session in any state, and then the
+//! kernel is what rejects a call the claim is not ready for.
+
+use crate::{
+    capsule_tree_head, pb, sha256, validate_claim_and_topic_ids, verify_capsule_response,
+    ClientError, DiscosClient, SignedTreeHead,
+};
+
+/// Claim registered with the kernel; artifacts not yet committed.
+#[derive(Debug, Clone, Copy)]
+pub struct Created;
+/// Artifacts and wasm module committed.
+#[derive(Debug, Clone, Copy)]
+pub struct Committed;
+/// Claim frozen; ready to seal or execute.
+#[derive(Debug, Clone, Copy)]
+pub struct Frozen;
+/// Claim sealed; ready to execute.
+#[derive(Debug, Clone, Copy)]
+pub struct Sealed;
+/// Claim executed; holds the kernel's execution response.
+#[derive(Debug, Clone)]
+pub struct Executed {
+    response: pb::ExecuteClaimV2Response,
+}
+
+/// Lifecycle states that carry no data and can be re-entered from stored ids.
+pub trait ResumableState: sealed::Sealed {
+    fn state() -> Self;
+}
+
+impl ResumableState for Created {
+    fn state() -> Self {
+        Created
+    }
+}
+
+impl ResumableState for Committed {
+    fn state() -> Self {
+        Committed
+    }
+}
+
+impl ResumableState for Frozen {
+    fn state() -> Self {
+        Frozen
+    }
+}
+
+impl ResumableState for Sealed {
+    fn state() -> Self {
+        Sealed
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for super::Created {}
+    impl Sealed for super::Committed {}
+    impl Sealed for super::Frozen {}
+    impl Sealed for super::Sealed {}
+}
+
+#[derive(Debug)]
+pub struct ClaimSession<'c, S> {
+    client: &'c mut DiscosClient,
+    claim_id: Vec<u8>,
+    topic_id: Vec<u8>,
+    state: S,
+}
+
+/// Capsule returned at the end of a session, already verified against the
+/// execution output, the claim/topic ids and the kernel's signed tree head.
+#[derive(Debug, Clone)]
+pub struct VerifiedCapsule {
+    pub claim_id: Vec<u8>,
+    pub topic_id: Vec<u8>,
+    pub execution: pb::ExecuteClaimV2Response,
+    pub capsule: pb::FetchCapsuleResponse,
+}
+
+impl<'c, S> ClaimSession<'c, S> {
+    pub fn claim_id(&self) -> &[u8] {
+        &self.claim_id
+    }
+
+    pub fn topic_id(&self) -> &[u8] {
+        &self.topic_id
+    }
+
+    fn into_state<T>(self, state: T) -> ClaimSession<'c, T> {
+        ClaimSession {
+            client: self.client,
+            claim_id: self.claim_id,
+            topic_id: self.topic_id,
+            state,
+        }
+    }
+
+    async fn run_execute(self) -> Result<ClaimSession<'c, Executed>, ClientError> {
+        let response = self
+            .client
+            .execute_claim_v2(pb::ExecuteClaimV2Request {
+                claim_id: self.claim_id.clone(),
+            })
+            .await?;
+        Ok(self.into_state(Executed { response }))
+    }
+}
+
+impl<'c, S: ResumableState> ClaimSession<'c, S> {
+    /// Re-enters a lifecycle at a known state, e.g. after a process restart.
+    pub fn resume(
+        client: &'c mut DiscosClient,
+        claim_id: Vec<u8>,
+        topic_id: Vec<u8>,
+    ) -> Result<Self, ClientError> {
+        validate_claim_and_topic_ids(&claim_id, &topic_id)?;
+        Ok(Self {
+            client,
+            claim_id,
+            topic_id,
+            state: S::state(),
+        })
+    }
+}
+
+impl<'c> ClaimSession<'c, Created> {
+    pub async fn create(
+        client: &'c mut DiscosClient,
+        req: pb::CreateClaimV2Request,
+    ) -> Result<Self, ClientError> {
+        let resp = client.create_claim_v2(req).await?;
+        validate_claim_and_topic_ids(&resp.claim_id, &resp.topic_id)?;
+        Ok(Self {
+            client,
+            claim_id: resp.claim_id,
+            topic_id: resp.topic_id,
+            state: Created,
+        })
+    }
+
+    pub async fn commit(
+        self,
+        artifacts: Vec<pb::Artifact>,
+        wasm_module: Vec<u8>,
+    ) -> Result<ClaimSession<'c, Committed>, ClientError> {
+        self.client
+            .commit_artifacts(pb::CommitArtifactsRequest {
+                claim_id: self.claim_id.clone(),
+                artifacts,
+                wasm_module,
+            })
+            .await?;
+        Ok(self.into_state(Committed))
+    }
+}
+
+impl<'c> ClaimSession<'c, Committed> {
+    pub async fn commit_wasm(
+        &mut self,
+        wasm_module: Vec<u8>,
+        wasm_artifact: &pb::Artifact,
+    ) -> Result<pb::CommitWasmResponse, ClientError> {
+        let wasm_hash = sha256(&wasm_module).to_vec();
+        self.client
+            .commit_wasm(
+                pb::CommitWasmRequest {
+                    claim_id: self.claim_id.clone(),
+                    wasm_hash,
+                    wasm_module,
+                },
+                wasm_artifact,
+            )
+            .await
+    }
+
+    pub async fn freeze(self) -> Result<ClaimSession<'c, Frozen>, ClientError> {
+        self.client
+            .freeze(pb::FreezeRequest {
+                claim_id: self.claim_id.clone(),
+            })
+            .await?;
+        Ok(self.into_state(Frozen))
+    }
+}
+
+impl<'c> ClaimSession<'c, Frozen> {
+    pub async fn seal(self) -> Result<ClaimSession<'c, Sealed>, ClientError> {
+        self.client
+            .seal(pb::SealRequest {
+                claim_id: self.claim_id.clone(),
+            })
+            .await?;
+        Ok(self.into_state(Sealed))
+    }
+
+    pub async fn execute(self) -> Result<ClaimSession<'c, Executed>, ClientError> {
+        self.run_execute().await
+    }
+}
+
+impl<'c> ClaimSession<'c, Sealed> {
+    pub async fn execute(self) -> Result<ClaimSession<'c, Executed>, ClientError> {
+        self.run_execute().await
+    }
+}
+
+impl<'c> ClaimSession<'c, Executed> {
+    /// Re-enters an executed lifecycle from a stored execution response so the
+    /// capsule can still be fetched and verified against its output.
+    pub fn resume_executed(
+        client: &'c mut DiscosClient,
+        claim_id: Vec<u8>,
+        topic_id: Vec<u8>,
+        response: pb::ExecuteClaimV2Response,
+    ) -> Result<Self, ClientError> {
+        validate_claim_and_topic_ids(&claim_id, &topic_id)?;
+        Ok(Self {
+            client,
+            claim_id,
+            topic_id,
+            state: Executed { response },
+        })
+    }
+
+    pub fn execution(&self) -> &pb::ExecuteClaimV2Response {
+        &self.state.response
+    }
+
+    /// Fetches the capsule and runs [`verify_capsule_response`] against the
+    /// execution's canonical output before returning it.
+    pub async fn fetch_capsule(
+        self,
+        server_pubkey: &[u8],
+        previous_sth: Option<&SignedTreeHead>,
+    ) -> Result<VerifiedCapsule, ClientError> {
+        let capsule = self
+            .client
+            .fetch_capsule(pb::FetchCapsuleRequest {
+                claim_id: self.claim_id.clone(),
+            })
+            .await?;
+        verify_capsule_response(
+            &capsule,
+            &self.state.response.canonical_output,
+            &self.claim_id,
+            &self.topic_id,
+            server_pubkey,
+            previous_sth,
+        )?;
+        self.client
+            .record_verified_head(capsule_tree_head(&capsule)?);
+        Ok(VerifiedCapsule {
+            claim_id: self.claim_id,
+            topic_id: self.topic_id,
+            execution: self.state.response,
+            capsule,
+        })
+    }
+}
@@ -0,0 +1,110 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use discos_client::{
+    signed_tree_head_from_pb, verify_capsule_response_with_revocations, CapsuleValidity,
+    DiscosClient, RevocationSet, VerifiedCapsule,
+};
+use discos_testkit::{run_sample_claim, sample_create_request, MockDaemon, MockDaemonConfig};
+
+async fn verified_capsule(client: &mut DiscosClient, daemon: &MockDaemon) -> VerifiedCapsule {
+    run_sample_claim(
+        client,
+        sample_create_request("revocable"),
+        &daemon.kernel_pubkey(),
+        None,
+    )
+    .await
+    .expect("capsule")
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn revoked_claims_verify_as_valid_but_revoked() {
+    let daemon = MockDaemon::new(MockDaemonConfig::default()).expect("daemon");
+    let endpoint = daemon.spawn().await.expect("spawn daemon");
+    let mut client = DiscosClient::connect(&endpoint).await.expect("connect");
+    let pubkey = daemon.kernel_pubkey();
+    let verified = verified_capsule(&mut client, &daemon).await;
+    let head = signed_tree_head_from_pb(
+        verified
+            .capsule
+            .signed_tree_head
+            .as_ref()
+            .expect("signed tree head"),
+    )
+    .expect("sth");
+    let verify = |set: &RevocationSet| {
+        let snapshot = set.snapshot(&head).expect("snapshot");
+        verify_capsule_response_with_revocations(
+            &verified.capsule,
+            &verified.execution.canonical_output,
+            &verified.claim_id,
+            &verified.topic_id,
+            &pubkey,
+            None,
+            &snapshot,
+        )
+    };
+
+    let mut set = RevocationSet::default();
+    assert_eq!(verify(&set).expect("valid"), CapsuleValidity::Valid);
+
+    let entry = daemon
+        .revoke(&verified.claim_id, "RETRACTED")
+        .expect("revoke");
+    set.apply(&entry, &pubkey).expect("apply");
+    assert_eq!(
+        verify(&set).expect("revoked"),
+        CapsuleValidity::Revoked {
+            reason_code: "RETRACTED".to_string(),
+            logical_epoch: entry.logical_epoch,
+        }
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn snapshots_are_bound_to_their_entries_and_tree_head() {
+    let daemon = MockDaemon::new(MockDaemonConfig::default()).expect("daemon");
+    let endpoint = daemon.spawn().await.expect("spawn daemon");
+    let mut client = DiscosClient::connect(&endpoint).await.expect("connect");
+    let pubkey = daemon.kernel_pubkey();
+    let verified = verified_capsule(&mut client, &daemon).await;
+    let mut set = RevocationSet::default();
+    let entry = daemon
+        .revoke(&verified.claim_id, "RETRACTED")
+        .expect("revoke");
+    set.apply(&entry, &pubkey).expect("apply");
+
+    let head = signed_tree_head_from_pb(&daemon.signed_tree_head()).expect("sth");
+    let snapshot = set.snapshot(&head).expect("snapshot");
+    snapshot.verify(&head, &pubkey).expect("untouched snapshot");
+
+    let mut dropped = snapshot.clone();
+    dropped.entries.clear();
+    assert!(dropped.verify(&head, &pubkey).is_err());
+    assert_eq!(
+        dropped.validity(&verified.claim_id),
+        CapsuleValidity::Valid,
+        "an unverified snapshot can hide a revocation"
+    );
+
+    let mut reason = snapshot.clone();
+    reason.entries[0].reason_code = "OTHER".to_string();
+    assert!(reason.verify(&head, &pubkey).is_err());
+
+    daemon.append_leaf(b"later");
+    let later = signed_tree_head_from_pb(&daemon.signed_tree_head()).expect("sth");
+    assert!(snapshot.verify(&later, &pubkey).is_err());
+    assert!(snapshot.verify(&head, &daemon.rogue_pubkey()).is_err());
+}
@@ -0,0 +1,153 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use discos_client::capsule::{CapsuleSchema, ClaimCapsule, CAPSULE_SCHEMA_V1};
+use serde_json::{json, Value};
+
+fn v1_capsule() -> Value {
+    json!({
+        "schema": CAPSULE_SCHEMA_V1,
+        "claim_id_hex": "11".repeat(32),
+        "topic_id_hex": "22".repeat(32),
+        "structured_output_hash_hex": "33".repeat(32),
+        "wasm_hash_hex": "44".repeat(32),
+        "oracle_id": "acme.safety.v1",
+        "oracle_resolution_hash": "55".repeat(32),
+        "oracle_manifest_hash": "66".repeat(32),
+        "certified": true,
+        "e_value": 21.5,
+        "decision": "allow",
+        "reason_codes": ["SJ_PASS"],
+        "policy_oracle_receipts": [{
+            "oracle_id": "super-judge-1",
+            "decision": "pass",
+            "reason_code": "SJ_PASS",
+            "wasm_hash_hex": "aa".repeat(32),
+            "manifest_hash_hex": "bb".repeat(32)
+        }]
+    })
+}
+
+type Mutation = fn(&mut Value);
+
+fn encode(value: &Value) -> Vec<u8> {
+    serde_json::to_vec(value).expect("encode")
+}
+
+#[test]
+fn strict_decoding_types_every_field() {
+    let capsule = ClaimCapsule::decode(&encode(&v1_capsule())).expect("strict");
+    assert_eq!(capsule.schema, CapsuleSchema::V1);
+    assert_eq!(capsule.schema.id(), Some(CAPSULE_SCHEMA_V1));
+    assert!(capsule.certified);
+    assert_eq!(capsule.e_value, 21.5);
+    assert_eq!(capsule.decision, "allow");
+    assert_eq!(
+        capsule.oracle_resolution_hash.as_deref(),
+        Some("55".repeat(32).as_str())
+    );
+    assert_eq!(capsule.policy_oracle_receipts.len(), 1);
+    assert_eq!(capsule.policy_oracle_receipts[0].reason_code, "SJ_PASS");
+
+    let legacy = ClaimCapsule::decode_legacy(&encode(&v1_capsule())).expect("legacy");
+    assert!(legacy.missing_fields.is_empty());
+    assert_eq!(legacy.capsule, capsule);
+}
+
+#[test]
+fn strict_decoding_rejects_what_legacy_decoding_reports() {
+    let mut old = v1_capsule();
+    let fields = old.as_object_mut().expect("object");
+    for key in ["schema", "e_value", "oracle_resolution_hash"] {
+        fields.remove(key);
+    }
+    old["policy_oracle_receipts"][0]
+        .as_object_mut()
+        .expect("receipt")
+        .remove("manifest_hash_hex");
+
+    let err = ClaimCapsule::decode(&encode(&old)).expect_err("strict");
+    assert!(err.to_string().contains("e_value"), "{err}");
+
+    let legacy = ClaimCapsule::decode_legacy(&encode(&old)).expect("legacy");
+    assert_eq!(legacy.capsule.schema, CapsuleSchema::Unversioned);
+    assert_eq!(
+        legacy.missing_fields,
+        vec![
+            "schema",
+            "e_value",
+            "policy_oracle_receipts[0].manifest_hash_hex"
+        ]
+    );
+    assert!(legacy.is_missing("e_value"));
+    assert!(!legacy.is_missing("oracle_resolution_hash"));
+    assert_eq!(legacy.capsule.oracle_resolution_hash, None);
+    assert_eq!(legacy.capsule.decision, "allow");
+}
+
+#[test]
+fn malformed_capsules_are_rejected_in_strict_mode() {
+    let cases: [(&str, Mutation); 7] = [
+        ("unknown schema", |c| {
+            c["schema"] = "evidenceos.claim-capsule.v9".into()
+        }),
+        ("unknown field", |c| c["verdict"] = "allow".into()),
+        ("short hash", |c| c["claim_id_hex"] = "11".into()),
+        ("bad receipt hash", |c| {
+            c["policy_oracle_receipts"][0]["wasm_hash_hex"] = "zz".into()
+        }),
+        ("negative e-value", |c| c["e_value"] = json!(-1.0)),
+        ("empty decision", |c| c["decision"] = "".into()),
+        ("mistyped certified", |c| c["certified"] = "yes".into()),
+    ];
+    for (name, mutate) in cases {
+        let mut capsule = v1_capsule();
+        mutate(&mut capsule);
+        assert!(
+            ClaimCapsule::decode(&encode(&capsule)).is_err(),
+            "{name} decoded strictly"
+        );
+    }
+
+    let mut mistyped = v1_capsule();
+    mistyped["reason_codes"] = "SJ_PASS".into();
+    assert!(ClaimCapsule::decode_legacy(&encode(&mistyped)).is_err());
+    let mut future = v1_capsule();
+    future["schema"] = "evidenceos.claim-capsule.v9".into();
+    assert!(ClaimCapsule::decode_legacy(&encode(&future)).is_err());
+    assert!(ClaimCapsule::decode_legacy(b"[]").is_err());
+}
+
+#[test]
+fn only_unversioned_capsules_fall_back_to_legacy_decoding() {
+    let capsule = ClaimCapsule::decode_any(&encode(&v1_capsule())).expect("v1");
+    assert_eq!(capsule.capsule.schema, CapsuleSchema::V1);
+    assert!(capsule.missing_fields.is_empty());
+
+    // Claims to be v1 but lacks a field v1 requires: malformed, not old.
+    let mut malformed = v1_capsule();
+    malformed.as_object_mut().expect("object").remove("e_value");
+    let err = ClaimCapsule::decode_any(&encode(&malformed)).expect_err("malformed v1");
+    assert!(err.to_string().contains("e_value"), "{err}");
+    malformed["e_value"] = json!(-1.0);
+    assert!(ClaimCapsule::decode_any(&encode(&malformed)).is_err());
+
+    let mut old = v1_capsule();
+    let fields = old.as_object_mut().expect("object");
+    fields.remove("schema");
+    fields.remove("e_value");
+    let legacy = ClaimCapsule::decode_any(&encode(&old)).expect("legacy");
+    assert_eq!(legacy.capsule.schema, CapsuleSchema::Unversioned);
+    assert_eq!(legacy.missing_fields, vec!["schema", "e_value"]);
+}
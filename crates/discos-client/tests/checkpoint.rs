@@ -0,0 +1,112 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use discos_client::checkpoint::{note_verifier_key, Checkpoint};
+use discos_client::SignedTreeHead;
+use ed25519_dalek::{Signer, SigningKey, VerifyingKey};
+use evidenceos_verifier::sth_signature_digest;
+
+const ORIGIN: &str = "kernel.example/etl";
+
+fn kernel_head(kernel: &SigningKey) -> SignedTreeHead {
+    let root_hash = [0x5a; 32];
+    SignedTreeHead {
+        tree_size: 42,
+        root_hash,
+        signature: kernel.sign(&sth_signature_digest(42, root_hash)).to_bytes(),
+    }
+}
+
+#[test]
+fn exported_checkpoint_round_trips_through_the_note_format() {
+    let kernel = SigningKey::from_bytes(&[7; 32]);
+    let operator = SigningKey::from_bytes(&[9; 32]);
+    let kernel_pubkey = kernel.verifying_key().to_bytes();
+    let sth = kernel_head(&kernel);
+
+    let mut checkpoint = Checkpoint::from_sth(ORIGIN, &sth, &kernel_pubkey).unwrap();
+    checkpoint.sign(ORIGIN, &operator).unwrap();
+    let note = checkpoint.to_note();
+    let mut lines = note.lines();
+    assert_eq!(lines.next(), Some(ORIGIN));
+    assert_eq!(lines.next(), Some("42"));
+    assert_eq!(lines.next(), Some(BASE64.encode([0x5a; 32]).as_str()));
+    assert_eq!(lines.next(), Some(""));
+    assert!(lines.all(|line| line.starts_with("\u{2014} kernel.example/etl ")));
+
+    let parsed = Checkpoint::parse(&note).unwrap();
+    assert_eq!(parsed, checkpoint);
+    assert_eq!(parsed.to_note(), note);
+    let head = parsed.verified_head(&kernel_pubkey).unwrap();
+    assert_eq!(
+        (head.tree_size, head.root_hash, head.signature),
+        (sth.tree_size, sth.root_hash, sth.signature)
+    );
+    let vkey = note_verifier_key(ORIGIN, &operator.verifying_key());
+    parsed.verify_note_signature(&vkey).unwrap();
+
+    let stranger = SigningKey::from_bytes(&[3; 32]);
+    assert!(parsed
+        .verified_head(&stranger.verifying_key().to_bytes())
+        .is_err());
+    assert!(parsed
+        .verify_note_signature(&note_verifier_key(ORIGIN, &stranger.verifying_key()))
+        .is_err());
+
+    let tampered = note.replacen("\n42\n", "\n43\n", 1);
+    let tampered = Checkpoint::parse(&tampered).unwrap();
+    assert!(tampered.verified_head(&kernel_pubkey).is_err());
+    assert!(tampered.verify_note_signature(&vkey).is_err());
+}
+
+#[test]
+fn unknown_signature_lines_are_kept_and_malformed_notes_rejected() {
+    let kernel = SigningKey::from_bytes(&[7; 32]);
+    let kernel_pubkey = kernel.verifying_key().to_bytes();
+    let checkpoint = Checkpoint::from_sth(ORIGIN, &kernel_head(&kernel), &kernel_pubkey).unwrap();
+    let cosigned = format!(
+        "{}\u{2014} witness.example/w1 {}\n",
+        checkpoint.to_note(),
+        BASE64.encode([1u8; 4 + 72])
+    );
+    let parsed = Checkpoint::parse(&cosigned).unwrap();
+    assert_eq!(parsed.signatures.len(), 2);
+    assert!(parsed.verified_head(&kernel_pubkey).is_ok());
+
+    let body = checkpoint.body();
+    for note in [
+        body.clone(),
+        format!("{body}\n"),
+        format!("{body}\n\u{2014} {ORIGIN} AAAA\n"),
+        body.replacen("\n42\n", "\n042\n", 1) + "\n",
+        format!("{ORIGIN}\n42\nnot-base64\n\n"),
+    ] {
+        assert!(Checkpoint::parse(&note).is_err(), "{note:?}");
+    }
+}
+
+#[test]
+fn verifier_keys_use_signed_note_key_ids() {
+    // Example key from the Go `golang.org/x/mod/sumdb/note` documentation.
+    let encoded = BASE64
+        .decode("ARpc2QcUPDhMQegwxbzhKqiBfsVkmqq/LDE4izWy10TW")
+        .unwrap();
+    let key = VerifyingKey::from_bytes(&encoded[1..].try_into().unwrap()).unwrap();
+    assert_eq!(
+        note_verifier_key("PeterNeumann", &key),
+        "PeterNeumann+c74f20a3+ARpc2QcUPDhMQegwxbzhKqiBfsVkmqq/LDE4izWy10TW"
+    );
+}
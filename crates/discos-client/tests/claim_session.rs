@@ -0,0 +1,148 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use discos_client::{
+    pb, session::Frozen, sha256, ClaimSession, ClientError, DiscosClient, ErrorCode,
+};
+use discos_testkit::{
+    sample_create_request, Fault, MockDaemon, MockDaemonConfig, Rpc, SAMPLE_WASM,
+};
+
+const CANONICAL_OUTPUT: &[u8] = b"canonical-output";
+
+async fn start() -> (MockDaemon, DiscosClient) {
+    let daemon = MockDaemon::new(MockDaemonConfig {
+        canonical_output: Some(CANONICAL_OUTPUT.to_vec()),
+        ..MockDaemonConfig::default()
+    })
+    .expect("daemon");
+    let endpoint = daemon.spawn().await.expect("spawn daemon");
+    let client = DiscosClient::connect(&endpoint).await.expect("connect");
+    (daemon, client)
+}
+
+fn artifacts() -> Vec<pb::Artifact> {
+    vec![pb::Artifact {
+        artifact_hash: sha256(SAMPLE_WASM).to_vec(),
+        kind: "wasm_module".to_string(),
+    }]
+}
+
+/// Claim and topic id of a claim taken as far as `Frozen`.
+async fn frozen_claim(client: &mut DiscosClient) -> (Vec<u8>, Vec<u8>) {
+    let session = ClaimSession::create(client, sample_create_request("alpha"))
+        .await
+        .expect("create")
+        .commit(artifacts(), SAMPLE_WASM.to_vec())
+        .await
+        .expect("commit")
+        .freeze()
+        .await
+        .expect("freeze");
+    (session.claim_id().to_vec(), session.topic_id().to_vec())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn session_runs_full_lifecycle_and_verifies_capsule() {
+    let (daemon, mut client) = start().await;
+
+    let session = ClaimSession::create(&mut client, sample_create_request("alpha"))
+        .await
+        .expect("create");
+    let topic_id = session.topic_id().to_vec();
+    assert_eq!(session.claim_id().len(), 32);
+    let session = session
+        .commit(artifacts(), SAMPLE_WASM.to_vec())
+        .await
+        .expect("commit");
+    let session = session.freeze().await.expect("freeze");
+    let session = session.seal().await.expect("seal");
+    let session = session.execute().await.expect("execute");
+    assert!(session.execution().certified);
+
+    let capsule = session
+        .fetch_capsule(&daemon.kernel_pubkey(), None)
+        .await
+        .expect("verified capsule");
+    assert_eq!(capsule.topic_id, topic_id);
+    assert_eq!(capsule.execution.canonical_output, CANONICAL_OUTPUT);
+    for rpc in [
+        Rpc::CreateClaimV2,
+        Rpc::CommitArtifacts,
+        Rpc::Freeze,
+        Rpc::Seal,
+        Rpc::ExecuteClaimV2,
+        Rpc::FetchCapsule,
+    ] {
+        assert_eq!(daemon.calls(rpc), 1, "{rpc:?}");
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn session_rejects_malformed_claim_id_from_kernel() {
+    let (daemon, mut client) = start().await;
+    daemon.fail_next(Rpc::CreateClaimV2, Fault::ShortIds);
+
+    let err = ClaimSession::create(&mut client, sample_create_request("alpha"))
+        .await
+        .expect_err("short claim id must be rejected");
+    assert_eq!(err.code(), ErrorCode::InvalidInput);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn resumed_session_fails_closed_on_mismatched_capsule() {
+    let (daemon, mut client) = start().await;
+    let (claim_id, topic_id) = frozen_claim(&mut client).await;
+
+    let session = ClaimSession::<Frozen>::resume(&mut client, claim_id.clone(), topic_id.clone())
+        .expect("resume");
+    session.execute().await.expect("execute");
+    let session = ClaimSession::resume_executed(
+        &mut client,
+        claim_id,
+        topic_id,
+        pb::ExecuteClaimV2Response {
+            canonical_output: b"other-output".to_vec(),
+            ..Default::default()
+        },
+    )
+    .expect("resume executed");
+    let err = session
+        .fetch_capsule(&daemon.kernel_pubkey(), None)
+        .await
+        .expect_err("capsule bound to another output must fail");
+    assert!(matches!(err, ClientError::VerificationFailed(_)));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn executed_session_resumes_from_stored_response() {
+    let (daemon, mut client) = start().await;
+    let (claim_id, topic_id) = frozen_claim(&mut client).await;
+    let execution = ClaimSession::<Frozen>::resume(&mut client, claim_id.clone(), topic_id.clone())
+        .expect("resume")
+        .execute()
+        .await
+        .expect("execute")
+        .execution()
+        .clone();
+
+    let session = ClaimSession::resume_executed(&mut client, claim_id, topic_id, execution)
+        .expect("resume executed");
+    session
+        .fetch_capsule(&daemon.kernel_pubkey(), None)
+        .await
+        .expect("verified capsule");
+    assert_eq!(daemon.calls(Rpc::ExecuteClaimV2), 1);
+    assert_eq!(daemon.calls(Rpc::FetchCapsule), 1);
+}
@@ -0,0 +1,76 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use discos_client::{pb, sha256, verify_wasm_commit, ErrorCode, WASM_MODULE_ARTIFACT_KIND};
+
+const RESTRICTED_WAT: &str = r#"
+(module
+  (import "env" "oracle_query" (func (param i32 i32) (result i32)))
+  (import "env" "emit_structured_claim" (func (param i32 i32)))
+  (import "env" "get_logical_epoch" (func (result i64)))
+  (memory (export "memory") 1)
+  (func (export "run")))
+"#;
+
+fn restricted_module() -> Vec<u8> {
+    wat::parse_str(RESTRICTED_WAT).expect("valid wat")
+}
+
+fn commit_request(wasm_module: Vec<u8>) -> pb::CommitWasmRequest {
+    pb::CommitWasmRequest {
+        claim_id: vec![0x11; 32],
+        wasm_hash: sha256(&wasm_module).to_vec(),
+        wasm_module,
+    }
+}
+
+fn artifact_for(wasm_module: &[u8]) -> pb::Artifact {
+    pb::Artifact {
+        artifact_hash: sha256(wasm_module).to_vec(),
+        kind: WASM_MODULE_ARTIFACT_KIND.to_string(),
+    }
+}
+
+#[test]
+fn restricted_module_bound_to_artifact_passes() {
+    let module = restricted_module();
+    let artifact = artifact_for(&module);
+    assert!(verify_wasm_commit(&commit_request(module), &artifact).is_ok());
+}
+
+#[test]
+fn module_outside_restricted_profile_is_rejected() {
+    let module = wat::parse_str(r#"(module (import "wasi" "fd_write" (func)) (memory 1))"#)
+        .expect("valid wat");
+    let artifact = artifact_for(&module);
+    let err = verify_wasm_commit(&commit_request(module), &artifact).expect_err("must reject");
+    assert_eq!(err.code(), ErrorCode::InvalidInput);
+}
+
+#[test]
+fn hash_binding_mismatches_are_rejected() {
+    let module = restricted_module();
+
+    let mut wrong_hash = commit_request(module.clone());
+    wrong_hash.wasm_hash[0] ^= 0x01;
+    assert!(verify_wasm_commit(&wrong_hash, &artifact_for(&module)).is_err());
+
+    let mut other_artifact = artifact_for(&module);
+    other_artifact.artifact_hash[0] ^= 0x01;
+    assert!(verify_wasm_commit(&commit_request(module.clone()), &other_artifact).is_err());
+
+    let mut wrong_kind = artifact_for(&module);
+    wrong_kind.kind = "alpha_hir.json".to_string();
+    assert!(verify_wasm_commit(&commit_request(module), &wrong_kind).is_err());
+}
@@ -0,0 +1,190 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use discos_client::{pb, ClientConnectConfig, ClientError, DiscosClient};
+use discos_testkit::{Fault, MockDaemon, MockDaemonConfig, Rpc};
+use tonic::Status;
+
+const UNREACHABLE: &str = "http://127.0.0.1:1";
+
+async fn replica(leaves: &[&[u8]]) -> (MockDaemon, String) {
+    let daemon = MockDaemon::new(MockDaemonConfig::default()).expect("daemon");
+    for leaf in leaves {
+        daemon.append_leaf(leaf);
+    }
+    let endpoint = daemon.spawn().await.expect("spawn daemon");
+    (daemon, endpoint)
+}
+
+async fn connect(endpoints: &[&str], kernel_pubkey: Option<Vec<u8>>) -> DiscosClient {
+    let mut config = ClientConnectConfig::with_endpoints(endpoints.iter().copied());
+    config.kernel_pubkey = kernel_pubkey;
+    DiscosClient::connect_with_config(config)
+        .await
+        .expect("connect")
+}
+
+fn take_down(daemon: &MockDaemon) {
+    for rpc in [Rpc::Health, Rpc::GetServerInfo, Rpc::GetSignedTreeHead] {
+        daemon.fail_always(rpc, Fault::Status(Status::unavailable("replica down")));
+    }
+}
+
+async fn tree_size(client: &mut DiscosClient) -> Result<u64, ClientError> {
+    Ok(client
+        .get_signed_tree_head(pb::GetSignedTreeHeadRequest {})
+        .await?
+        .signed_tree_head
+        .expect("sth")
+        .tree_size)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn first_healthy_endpoint_is_selected() {
+    let (daemon, endpoint) = replica(&[b"a"]).await;
+    let client = connect(
+        &[UNREACHABLE, &endpoint],
+        Some(daemon.kernel_pubkey().to_vec()),
+    )
+    .await;
+
+    assert_eq!(client.endpoint(), endpoint);
+    assert_eq!(client.endpoints(), vec![UNREACHABLE.to_string(), endpoint]);
+    let head = client.verified_head().expect("verified head");
+    assert_eq!(head.tree_size, 1);
+    assert_eq!(head.root_hash, daemon.root());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn unavailable_fails_over_to_a_consistent_replica() {
+    let (primary, primary_endpoint) = replica(&[b"a"]).await;
+    let (secondary, secondary_endpoint) = replica(&[b"a", b"b"]).await;
+    let mut client = connect(
+        &[&primary_endpoint, &secondary_endpoint],
+        Some(primary.kernel_pubkey().to_vec()),
+    )
+    .await;
+    assert_eq!(client.endpoint(), primary_endpoint);
+
+    take_down(&primary);
+    assert_eq!(tree_size(&mut client).await.expect("failed over"), 2);
+    assert_eq!(client.endpoint(), secondary_endpoint);
+    assert_eq!(client.verified_head().expect("head").tree_size, 2);
+    assert_eq!(secondary.calls(Rpc::GetConsistencyProof), 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn lagging_replica_is_refused() {
+    let (primary, primary_endpoint) = replica(&[b"a", b"b"]).await;
+    let (_secondary, secondary_endpoint) = replica(&[b"a"]).await;
+    let mut client = connect(
+        &[&primary_endpoint, &secondary_endpoint],
+        Some(primary.kernel_pubkey().to_vec()),
+    )
+    .await;
+
+    take_down(&primary);
+    let err = tree_size(&mut client).await.expect_err("lagging replica");
+    assert!(
+        matches!(&err, ClientError::VerificationFailed(msg) if msg.contains("behind")),
+        "{err:?}"
+    );
+    assert_eq!(client.endpoint(), primary_endpoint);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn seeded_head_refuses_a_rolled_back_primary() {
+    let (rolled_back, rolled_back_endpoint) = replica(&[b"a"]).await;
+    let (_current, current_endpoint) = replica(&[b"a", b"b"]).await;
+    let kernel_pubkey = rolled_back.kernel_pubkey().to_vec();
+    let seen = connect(
+        &[&current_endpoint, UNREACHABLE],
+        Some(kernel_pubkey.clone()),
+    )
+    .await
+    .verified_head()
+    .expect("verified head");
+
+    let mut config =
+        ClientConnectConfig::with_endpoints([rolled_back_endpoint.as_str(), &current_endpoint]);
+    config.kernel_pubkey = Some(kernel_pubkey);
+    config.verified_head = Some(seen);
+    let mut client = DiscosClient::connect_with_config(config)
+        .await
+        .expect("connect");
+
+    assert_eq!(client.endpoint(), current_endpoint);
+    assert_eq!(tree_size(&mut client).await.expect("tree size"), 2);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn forked_replica_is_refused() {
+    let (primary, primary_endpoint) = replica(&[b"a"]).await;
+    let (_fork, fork_endpoint) = replica(&[b"x", b"y"]).await;
+    let (_twin, twin_endpoint) = replica(&[b"x"]).await;
+    let mut client = connect(
+        &[&primary_endpoint, &fork_endpoint, &twin_endpoint],
+        Some(primary.kernel_pubkey().to_vec()),
+    )
+    .await;
+
+    take_down(&primary);
+    let err = tree_size(&mut client).await.expect_err("forked replicas");
+    let ClientError::VerificationFailed(msg) = &err else {
+        panic!("{err:?}");
+    };
+    assert!(msg.contains(&fork_endpoint), "{msg}");
+    assert!(msg.contains("not consistent"), "{msg}");
+    assert!(msg.contains(&twin_endpoint), "{msg}");
+    assert!(msg.contains("differs"), "{msg}");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn failover_needs_a_kernel_key() {
+    let (primary, primary_endpoint) = replica(&[b"a"]).await;
+    let (_secondary, secondary_endpoint) = replica(&[b"a"]).await;
+    let mut client = connect(&[&primary_endpoint, &secondary_endpoint], None).await;
+
+    take_down(&primary);
+    let err = tree_size(&mut client).await.expect_err("no kernel key");
+    assert!(
+        matches!(&err, ClientError::VerificationFailed(msg) if msg.contains("kernel public key")),
+        "{err:?}"
+    );
+
+    client.set_kernel_pubkey(primary.kernel_pubkey().to_vec());
+    assert_eq!(tree_size(&mut client).await.expect("failed over"), 1);
+    assert_eq!(client.endpoint(), secondary_endpoint);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn rejected_requests_do_not_fail_over() {
+    let (primary, primary_endpoint) = replica(&[]).await;
+    let (secondary, secondary_endpoint) = replica(&[]).await;
+    let mut client = connect(
+        &[&primary_endpoint, &secondary_endpoint],
+        Some(primary.kernel_pubkey().to_vec()),
+    )
+    .await;
+
+    primary.fail_next(
+        Rpc::GetSignedTreeHead,
+        Fault::Status(Status::failed_precondition("not ready")),
+    );
+    tree_size(&mut client)
+        .await
+        .expect_err("precondition failure");
+    assert_eq!(client.endpoint(), primary_endpoint);
+    assert_eq!(secondary.calls(Rpc::Health), 0);
+}
@@ -0,0 +1,302 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use discos_client::gossip::{
+    cross_check, fetch_sth_bundle, load_or_create_reporter_key, serve_sth_bundles, CrossCheck,
+    ForkEvidence, ForkProof, SthBundle, STH_BUNDLE_HTTP_PATH,
+};
+use discos_client::{signed_tree_head_from_pb, ClientError, DiscosClient, SignedTreeHead};
+use discos_testkit::{MockDaemon, MockDaemonConfig};
+use ed25519_dalek::Signer;
+
+async fn daemon_with(leaves: &[&[u8]]) -> (MockDaemon, DiscosClient) {
+    let daemon = MockDaemon::new(MockDaemonConfig::default()).expect("daemon");
+    for leaf in leaves {
+        daemon.append_leaf(leaf);
+    }
+    let endpoint = daemon.spawn().await.expect("spawn daemon");
+    let client = DiscosClient::connect(&endpoint).await.expect("connect");
+    (daemon, client)
+}
+
+fn head(daemon: &MockDaemon) -> SignedTreeHead {
+    signed_tree_head_from_pb(&daemon.signed_tree_head()).expect("sth")
+}
+
+fn reporter() -> ed25519_dalek::SigningKey {
+    ed25519_dalek::SigningKey::from_bytes(&[3; 32])
+}
+
+#[test]
+fn bundles_round_trip_through_files_and_check_signatures() {
+    let daemon = MockDaemon::new(MockDaemonConfig::default()).expect("daemon");
+    daemon.append_leaf(b"a");
+    let pubkey = daemon.kernel_pubkey();
+    let bundle = SthBundle::new("http://a", &pubkey, &[head(&daemon)]);
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("bundle.json");
+    bundle.write(&path).expect("write");
+    let read = SthBundle::read(&path).expect("read");
+    assert_eq!(read, bundle);
+    assert_eq!(
+        read.verified_heads(&pubkey).expect("verified")[0].tree_size,
+        1
+    );
+
+    let err = read
+        .verified_heads(&daemon.rogue_pubkey())
+        .expect_err("other kernel");
+    assert!(matches!(err, ClientError::VerificationFailed(_)), "{err:?}");
+
+    let mut tampered = read.clone();
+    tampered.heads[0].tree_size = 2;
+    let err = tampered.verified_heads(&pubkey).expect_err("bad signature");
+    assert!(matches!(err, ClientError::VerificationFailed(_)), "{err:?}");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn older_and_equal_heads_are_consistent() {
+    let (local, mut client) = daemon_with(&[b"a"]).await;
+    let older = head(&local);
+    local.append_leaf(b"b");
+    local.append_leaf(b"c");
+    let current = head(&local);
+
+    let check = cross_check(&mut client, &current, &older)
+        .await
+        .expect("check");
+    assert!(matches!(check, CrossCheck::Consistent), "{check:?}");
+    let check = cross_check(&mut client, &current, &current)
+        .await
+        .expect("check");
+    assert!(matches!(check, CrossCheck::Consistent), "{check:?}");
+    let check = cross_check(&mut client, &older, &current)
+        .await
+        .expect("check");
+    assert!(matches!(check, CrossCheck::Ahead), "{check:?}");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn split_views_produce_verifiable_fork_evidence() {
+    let (local, mut client) = daemon_with(&[b"a", b"b", b"c"]).await;
+    let other = MockDaemon::new(MockDaemonConfig::default()).expect("daemon");
+    other.append_leaf(b"a");
+    other.append_leaf(b"x");
+    let pubkey = local.kernel_pubkey();
+    let local_head = head(&local);
+    let remote_head = head(&other);
+
+    let CrossCheck::Forked { reason, proof } = cross_check(&mut client, &local_head, &remote_head)
+        .await
+        .expect("check")
+    else {
+        panic!("split view not detected");
+    };
+    let evidence = ForkEvidence::new(
+        &pubkey,
+        "http://local",
+        &local_head,
+        "http://other",
+        &remote_head,
+        &reason,
+        proof.as_ref(),
+        &reporter(),
+    );
+    assert_eq!(
+        evidence.verify(&pubkey).expect("evidence verifies"),
+        ForkProof::ReporterClaim
+    );
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("fork.json");
+    evidence.write(&path).expect("write");
+    let read = ForkEvidence::read(&path).expect("read");
+    read.verify(&pubkey)
+        .expect("evidence verifies after reload");
+
+    let mut tampered = read.clone();
+    tampered.remote_origin = "http://elsewhere".to_string();
+    assert!(tampered.verify(&pubkey).is_err());
+    assert!(read.verify(&local.rogue_pubkey()).is_err());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn equal_sizes_with_different_roots_are_self_evident() {
+    let (local, mut client) = daemon_with(&[b"a"]).await;
+    let other = MockDaemon::new(MockDaemonConfig::default()).expect("daemon");
+    other.append_leaf(b"x");
+    let pubkey = local.kernel_pubkey();
+
+    let CrossCheck::Forked { reason, proof } =
+        cross_check(&mut client, &head(&local), &head(&other))
+            .await
+            .expect("check")
+    else {
+        panic!("split view not detected");
+    };
+    assert!(proof.is_none());
+    let evidence = ForkEvidence::new(
+        &pubkey,
+        "http://local",
+        &head(&local),
+        "http://other",
+        &head(&other),
+        &reason,
+        None,
+        &reporter(),
+    );
+    assert_eq!(
+        evidence.verify(&pubkey).expect("evidence verifies"),
+        ForkProof::SelfEvident
+    );
+
+    let consistent = ForkEvidence::new(
+        &pubkey,
+        "http://local",
+        &head(&local),
+        "http://other",
+        &head(&local),
+        "made up",
+        None,
+        &reporter(),
+    );
+    assert!(consistent.verify(&pubkey).is_err());
+}
+
+#[test]
+fn failing_path_between_consistent_heads_is_only_a_reporter_claim() {
+    let daemon = MockDaemon::new(MockDaemonConfig::default()).expect("daemon");
+    daemon.append_leaf(b"a");
+    let old = head(&daemon);
+    daemon.append_leaf(b"b");
+    let new = head(&daemon);
+    let pubkey = daemon.kernel_pubkey();
+
+    // The heads are consistent, but nothing stops a reporter recording a
+    // path that fails to join them.
+    let mut evidence = ForkEvidence::new(
+        &pubkey,
+        "http://local",
+        &new,
+        "http://other",
+        &old,
+        "made up",
+        None,
+        &reporter(),
+    );
+    evidence.consistency_path_hex = Some(vec!["00".repeat(32)]);
+    evidence.signature_hex = hex::encode(reporter().sign(&evidence.signing_digest()).to_bytes());
+    assert_eq!(
+        evidence.verify(&pubkey).expect("signatures verify"),
+        ForkProof::ReporterClaim
+    );
+}
+
+#[test]
+fn reporter_key_is_created_once() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("keys/reporter.key");
+    let created = load_or_create_reporter_key(&path).expect("create");
+    let loaded = load_or_create_reporter_key(&path).expect("load");
+    assert_eq!(created.to_bytes(), loaded.to_bytes());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path)
+            .expect("metadata")
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn bundles_are_served_and_fetched_over_http() {
+    let daemon = MockDaemon::new(MockDaemonConfig::default()).expect("daemon");
+    daemon.append_leaf(b"a");
+    let bundle = SthBundle::new("http://a", &daemon.kernel_pubkey(), &[head(&daemon)]);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    let served = bundle.clone();
+    tokio::spawn(serve_sth_bundles(listener, move || {
+        let served = served.clone();
+        async move { Ok(served) }
+    }));
+
+    let fetched = fetch_sth_bundle(&format!("http://{addr}"))
+        .await
+        .expect("fetch default path");
+    assert_eq!(fetched, bundle);
+    let fetched = fetch_sth_bundle(&format!("http://{addr}{STH_BUNDLE_HTTP_PATH}"))
+        .await
+        .expect("fetch");
+    assert_eq!(fetched, bundle);
+    let err = fetch_sth_bundle(&format!("http://{addr}/elsewhere"))
+        .await
+        .expect_err("unknown path");
+    assert!(err.to_string().contains("404"), "{err}");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn idle_connection_does_not_stall_the_server() {
+    let daemon = MockDaemon::new(MockDaemonConfig::default()).expect("daemon");
+    let bundle = SthBundle::new("http://a", &daemon.kernel_pubkey(), &[head(&daemon)]);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    let served = bundle.clone();
+    tokio::spawn(serve_sth_bundles(listener, move || {
+        let served = served.clone();
+        async move { Ok(served) }
+    }));
+
+    let _idle = tokio::net::TcpStream::connect(addr).await.expect("connect");
+    let fetched = tokio::time::timeout(
+        std::time::Duration::from_secs(30),
+        fetch_sth_bundle(&format!("http://{addr}")),
+    )
+    .await
+    .expect("served despite an idle connection")
+    .expect("fetch");
+    assert_eq!(fetched, bundle);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn fetch_gives_up_on_an_endpoint_that_never_answers() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    let silent = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.expect("accept");
+        std::future::pending::<()>().await;
+        drop(stream);
+    });
+
+    let err = tokio::time::timeout(
+        std::time::Duration::from_secs(30),
+        fetch_sth_bundle(&format!("http://{addr}")),
+    )
+    .await
+    .expect("fetch times out on its own")
+    .expect_err("no response");
+    assert!(matches!(err, ClientError::Transport(_)), "{err:?}");
+    assert!(err.to_string().contains("timed out"), "{err}");
+    silent.abort();
+}
@@ -0,0 +1,67 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use discos_client::{ClientError, ErrorCode, KernelError, REASON_METADATA_KEY};
+use tonic::{Code, Status};
+
+#[test]
+fn kernel_error_preserves_code_message_and_details() {
+    let status = Status::with_details(
+        Code::FailedPrecondition,
+        "UNSIGNED_NULLSPEC: nullspec must be signed",
+        b"detail-bytes".to_vec().into(),
+    );
+    let err = ClientError::from(status);
+
+    assert_eq!(err.code(), ErrorCode::Kernel);
+    let kernel = err.kernel().expect("kernel error");
+    assert_eq!(kernel.code, Code::FailedPrecondition);
+    assert_eq!(kernel.reason(), Some("UNSIGNED_NULLSPEC"));
+    assert_eq!(kernel.message, "UNSIGNED_NULLSPEC: nullspec must be signed");
+    assert_eq!(kernel.details, b"detail-bytes");
+}
+
+#[test]
+fn reason_metadata_takes_precedence_over_message() {
+    let mut status = Status::resource_exhausted("topic budget exhausted");
+    status.metadata_mut().insert(
+        REASON_METADATA_KEY,
+        "THROTTLE_TOPIC".parse().expect("ascii"),
+    );
+    status
+        .metadata_mut()
+        .insert("retry-after", "2".parse().expect("ascii"));
+
+    let err = KernelError::from(status);
+    assert_eq!(err.code, Code::ResourceExhausted);
+    assert!(err.has_reason("THROTTLE_TOPIC"));
+    assert_eq!(err.metadata_value("retry-after"), Some("2"));
+}
+
+#[test]
+fn prose_messages_do_not_produce_reason_tokens() {
+    for message in ["unknown oracle_id: x.v1", "", "ID missing", "claim FROZEN"] {
+        let err = KernelError::from(Status::invalid_argument(message));
+        assert_eq!(err.reason(), None, "message {message:?}");
+    }
+}
+
+#[test]
+fn kernel_error_display_keeps_code_name() {
+    let err = ClientError::from(Status::invalid_argument("unknown oracle_id: x.v1"));
+    assert_eq!(
+        err.to_string(),
+        "kernel error: InvalidArgument: unknown oracle_id: x.v1"
+    );
+}
@@ -0,0 +1,106 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use discos_client::keystore::{kernel_key_id, PinSource};
+use discos_client::{ClientError, KernelKeyStore, KeyCheck};
+
+const ENDPOINT: &str = "https://kernel.example:50051";
+
+fn key(byte: u8) -> Vec<u8> {
+    ed25519_dalek::SigningKey::from_bytes(&[byte; 32])
+        .verifying_key()
+        .to_bytes()
+        .to_vec()
+}
+
+#[test]
+fn tofu_pins_on_first_use_and_persists() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("kernel_keys.json");
+    let mut store = KernelKeyStore::open(&path).expect("open");
+
+    assert!(matches!(
+        store.check(ENDPOINT, &key(1), &[], false),
+        Err(ClientError::VerificationFailed(_))
+    ));
+    assert_eq!(
+        store.check(ENDPOINT, &key(1), &[], true).expect("tofu"),
+        KeyCheck::FirstUse
+    );
+
+    let mut reopened = KernelKeyStore::open(&path).expect("reopen");
+    let pin = reopened.get(ENDPOINT).expect("pinned");
+    assert_eq!(pin.source, PinSource::Tofu);
+    assert_eq!(pin.key_id_hex, hex::encode(kernel_key_id(&key(1))));
+    assert_eq!(
+        reopened
+            .check(ENDPOINT, &key(1), &[], false)
+            .expect("check"),
+        KeyCheck::Pinned
+    );
+}
+
+#[test]
+fn unannounced_key_change_is_rejected() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let mut store = KernelKeyStore::open(dir.path().join("kernel_keys.json")).expect("open");
+    store.pin(ENDPOINT, &key(1), b"k1", false).expect("pin");
+
+    let err = store
+        .check(ENDPOINT, &key(2), b"k2", true)
+        .expect_err("key change");
+    assert!(
+        matches!(&err, ClientError::VerificationFailed(msg) if msg.contains("without an announced rotation")),
+        "{err:?}"
+    );
+    assert!(store.pin(ENDPOINT, &key(2), b"k2", false).is_err());
+    assert_eq!(
+        store.get(ENDPOINT).map(|pin| pin.key_id_hex.clone()),
+        Some(hex::encode(b"k1"))
+    );
+}
+
+#[test]
+fn announced_rotation_replaces_pin_and_retires_old_key() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let mut store = KernelKeyStore::open(dir.path().join("kernel_keys.json")).expect("open");
+    store.pin(ENDPOINT, &key(1), b"k1", false).expect("pin");
+    store
+        .announce_rotation(ENDPOINT, b"k2", Some(&key(2)))
+        .expect("announce");
+
+    assert!(
+        store.check(ENDPOINT, &key(3), b"k2", false).is_err(),
+        "announced key_id with a different key must not rotate"
+    );
+    assert_eq!(
+        store
+            .check(ENDPOINT, &key(2), b"k2", false)
+            .expect("rotate"),
+        KeyCheck::Rotated {
+            previous_key_id_hex: hex::encode(b"k1")
+        }
+    );
+
+    let pin = store.get(ENDPOINT).expect("pin");
+    assert_eq!(pin.source, PinSource::Rotation);
+    assert_eq!(pin.pubkey().expect("pubkey"), key(2));
+    assert_eq!(pin.announced_rotation, None);
+    assert_eq!(pin.retired.len(), 1);
+    assert_eq!(pin.retired[0].key_id_hex, hex::encode(b"k1"));
+    assert!(
+        store.check(ENDPOINT, &key(1), b"k1", false).is_err(),
+        "retired key is not trusted again"
+    );
+}
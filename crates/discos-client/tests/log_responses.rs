@@ -0,0 +1,95 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use discos_client::{
+    merkle_leaf_hash, pb, sha256, verify_inclusion_proof_response, verify_signed_tree_head_response,
+};
+use ed25519_dalek::{Signer, SigningKey};
+use evidenceos_verifier as verifier;
+
+fn node(left: [u8; 32], right: [u8; 32]) -> [u8; 32] {
+    let mut bytes = vec![0x01];
+    bytes.extend_from_slice(&left);
+    bytes.extend_from_slice(&right);
+    sha256(&bytes)
+}
+
+fn signed_head(sk: &SigningKey, tree_size: u64, root: [u8; 32]) -> pb::SignedTreeHead {
+    pb::SignedTreeHead {
+        tree_size,
+        root_hash: root.to_vec(),
+        signature: sk
+            .sign(&verifier::sth_signature_digest(tree_size, root))
+            .to_bytes()
+            .to_vec(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn signed_tree_head_response_requires_valid_signature() {
+    let sk = SigningKey::from_bytes(&[5u8; 32]);
+    let pubkey = sk.verifying_key().to_bytes();
+    let mut resp = pb::GetSignedTreeHeadResponse {
+        signed_tree_head: Some(signed_head(&sk, 3, [8u8; 32])),
+    };
+    let sth = verify_signed_tree_head_response(&resp, &pubkey).expect("valid head");
+    assert_eq!(sth.tree_size, 3);
+
+    if let Some(head) = resp.signed_tree_head.as_mut() {
+        head.tree_size = 4;
+    }
+    assert!(verify_signed_tree_head_response(&resp, &pubkey).is_err());
+
+    resp.signed_tree_head = None;
+    assert!(verify_signed_tree_head_response(&resp, &pubkey).is_err());
+}
+
+#[test]
+fn inclusion_response_is_checked_against_signed_root() {
+    let sk = SigningKey::from_bytes(&[5u8; 32]);
+    let pubkey = sk.verifying_key().to_bytes();
+    let a = merkle_leaf_hash(b"a");
+    let b = merkle_leaf_hash(b"b");
+    let root = node(a, b);
+
+    let mut resp = pb::GetInclusionProofResponse {
+        inclusion_proof: Some(pb::MerkleInclusionProof {
+            leaf_hash: b.to_vec(),
+            leaf_index: 1,
+            tree_size: 2,
+            audit_path: vec![a.to_vec()],
+        }),
+        signed_tree_head: Some(signed_head(&sk, 2, root)),
+    };
+    let (sth, proof) = verify_inclusion_proof_response(&resp, &pubkey).expect("valid proof");
+    assert_eq!(sth.root_hash, root);
+    assert_eq!(proof.leaf_index, 1);
+
+    if let Some(proof) = resp.inclusion_proof.as_mut() {
+        proof.leaf_index = 0;
+    }
+    assert!(verify_inclusion_proof_response(&resp, &pubkey).is_err());
+
+    resp.inclusion_proof = Some(pb::MerkleInclusionProof {
+        leaf_hash: a.to_vec(),
+        leaf_index: 0,
+        tree_size: 1,
+        audit_path: Vec::new(),
+    });
+    assert!(
+        verify_inclusion_proof_response(&resp, &pubkey).is_err(),
+        "proof for a different tree size must not verify against the signed head"
+    );
+}
@@ -187,9 +187,9 @@ async fn unknown_oracle_id_returns_actionable_error_without_retries() {
         .expect_err("unknown oracle id should fail");
 
     match err {
-        ClientError::Kernel(message) => {
-            assert!(message.contains("InvalidArgument"));
-            assert!(message.contains("unknown oracle_id: does.not.exist.v1"));
+        ClientError::Kernel(err) => {
+            assert_eq!(err.code, tonic::Code::InvalidArgument);
+            assert!(err.message.contains("unknown oracle_id: does.not.exist.v1"));
         }
         other => panic!("expected kernel error, got {other:?}"),
     }
@@ -0,0 +1,226 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use discos_client::retry::{classify, RETRY_AFTER_MS_METADATA_KEY};
+use discos_client::{ClientConnectConfig, ClientError, DiscosClient, RetryDecision, RetryPolicy};
+use discos_testkit::{sample_create_request, Fault, MockDaemon, MockDaemonConfig, Rpc};
+use tonic::metadata::MetadataValue;
+use tonic::{Code, Status};
+
+fn fast_policy(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        initial_backoff_ms: 5,
+        max_backoff_ms: 20,
+        ..RetryPolicy::default()
+    }
+}
+
+async fn start(policy: RetryPolicy) -> (MockDaemon, DiscosClient) {
+    let daemon = MockDaemon::new(MockDaemonConfig::default()).expect("daemon");
+    let endpoint = daemon.spawn().await.expect("spawn daemon");
+    let client = DiscosClient::connect(&endpoint)
+        .await
+        .expect("connect")
+        .with_retry_policy(policy);
+    (daemon, client)
+}
+
+fn throttled(retry_after_ms: &str) -> Status {
+    let mut status = Status::resource_exhausted("rate limited");
+    status.metadata_mut().insert(
+        RETRY_AFTER_MS_METADATA_KEY,
+        MetadataValue::try_from(retry_after_ms).expect("metadata value"),
+    );
+    status
+}
+
+#[test]
+fn transient_codes_are_retried_and_others_fail() {
+    for code in [Code::Unavailable, Code::DeadlineExceeded, Code::Aborted] {
+        let err = ClientError::from(Status::new(code, "transient"));
+        assert_eq!(classify(&err), RetryDecision::Retry, "{code:?}");
+    }
+    let err = ClientError::from(Status::failed_precondition("bad order"));
+    assert_eq!(classify(&err), RetryDecision::Fail);
+    let err = ClientError::VerificationFailed("bad proof".to_string());
+    assert_eq!(classify(&err), RetryDecision::Fail);
+}
+
+#[test]
+fn resource_exhausted_needs_a_retry_after_hint() {
+    let err = ClientError::from(Status::resource_exhausted("budget spent"));
+    assert_eq!(classify(&err), RetryDecision::Fail);
+
+    let err = ClientError::from(throttled("250"));
+    assert_eq!(
+        classify(&err),
+        RetryDecision::RetryAfter(Duration::from_millis(250))
+    );
+
+    let err = ClientError::from(Status::resource_exhausted(r#"{"retry_after_ms":40}"#));
+    assert_eq!(
+        classify(&err),
+        RetryDecision::RetryAfter(Duration::from_millis(40))
+    );
+}
+
+#[test]
+fn backoff_grows_exponentially_up_to_the_cap() {
+    let policy = RetryPolicy {
+        max_attempts: 10,
+        initial_backoff_ms: 100,
+        max_backoff_ms: 1_000,
+        ..RetryPolicy::default()
+    };
+    assert_eq!(policy.base_backoff(1), Duration::from_millis(100));
+    assert_eq!(policy.base_backoff(3), Duration::from_millis(400));
+    assert_eq!(policy.base_backoff(9), Duration::from_millis(1_000));
+    for retry in 1..=5 {
+        let base = policy.base_backoff(retry);
+        let delay = policy.backoff(retry);
+        assert!(delay <= base && delay >= base / 2, "{delay:?} vs {base:?}");
+    }
+}
+
+#[test]
+fn delay_for_respects_attempt_limit_and_hint_cap() {
+    let policy = RetryPolicy {
+        max_attempts: 3,
+        max_retry_after_ms: 1_000,
+        ..RetryPolicy::default()
+    };
+    let unavailable = ClientError::from(Status::unavailable("down"));
+    assert!(policy.delay_for(&unavailable, 1).is_some());
+    assert_eq!(policy.delay_for(&unavailable, 3), None);
+    assert_eq!(
+        policy.delay_for(&ClientError::from(throttled("60000")), 1),
+        None
+    );
+    assert_eq!(
+        policy.delay_for(&ClientError::from(throttled("500")), 1),
+        Some(Duration::from_millis(500))
+    );
+}
+
+#[test]
+fn connect_config_defaults_to_a_single_attempt() {
+    let config = ClientConnectConfig::with_endpoint("http://127.0.0.1:1");
+    assert_eq!(config.retry, RetryPolicy::default());
+    assert_eq!(config.retry.max_attempts, 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn unavailable_is_retried_until_success() {
+    let (daemon, mut client) = start(fast_policy(3)).await;
+    daemon.fail_next(
+        Rpc::Health,
+        Fault::Status(Status::unavailable("restarting")),
+    );
+    daemon.fail_next(
+        Rpc::Health,
+        Fault::Status(Status::unavailable("restarting")),
+    );
+
+    client.health().await.expect("third attempt succeeds");
+    assert_eq!(daemon.calls(Rpc::Health), 3);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn default_policy_does_not_retry() {
+    let (daemon, mut client) = start(RetryPolicy::default()).await;
+    daemon.fail_next(
+        Rpc::Health,
+        Fault::Status(Status::unavailable("restarting")),
+    );
+
+    let err = client.health().await.expect_err("single attempt");
+    assert_eq!(err.kernel().map(|k| k.code), Some(Code::Unavailable));
+    assert_eq!(daemon.calls(Rpc::Health), 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn retried_mutation_reuses_its_idempotency_key() {
+    let (daemon, mut client) = start(fast_policy(3)).await;
+    daemon.fail_next(
+        Rpc::CreateClaimV2,
+        Fault::DropResponse(Status::unavailable("connection reset")),
+    );
+
+    let created = client
+        .create_claim_v2(sample_create_request("alpha"))
+        .await
+        .expect("retried create");
+    assert_eq!(daemon.calls(Rpc::CreateClaimV2), 2);
+    assert_eq!(daemon.claim_count(), 1);
+    assert!(daemon.claim_stage(&created.claim_id).is_some());
+
+    let again = client
+        .create_claim_v2(sample_create_request("alpha"))
+        .await
+        .expect("new logical call");
+    assert_ne!(again.claim_id, created.claim_id);
+    assert_eq!(daemon.claim_count(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn scoped_idempotency_keys_survive_a_new_client() {
+    let (daemon, client) = start(fast_policy(1)).await;
+    let mut first = client.clone().with_idempotency_scope("run-1");
+    daemon.fail_next(
+        Rpc::CreateClaimV2,
+        Fault::DropResponse(Status::unavailable("connection reset")),
+    );
+    first
+        .create_claim_v2(sample_create_request("alpha"))
+        .await
+        .expect_err("response lost");
+    assert_eq!(daemon.claim_count(), 1);
+
+    let mut rerun = client.clone().with_idempotency_scope("run-1");
+    let created = rerun
+        .create_claim_v2(sample_create_request("alpha"))
+        .await
+        .expect("replayed create");
+    assert_eq!(daemon.claim_count(), 1);
+    assert!(daemon.claim_stage(&created.claim_id).is_some());
+
+    let mut other = client.with_idempotency_scope("run-2");
+    other
+        .create_claim_v2(sample_create_request("alpha"))
+        .await
+        .expect("new scope");
+    assert_eq!(daemon.claim_count(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn throttling_honours_retry_after_but_spent_budget_fails() {
+    let (daemon, mut client) = start(fast_policy(3)).await;
+    daemon.fail_next(Rpc::GetServerInfo, Fault::Status(throttled("30")));
+    client
+        .get_server_info()
+        .await
+        .expect("retried after the hint");
+    assert_eq!(daemon.calls(Rpc::GetServerInfo), 2);
+
+    daemon.fail_next(
+        Rpc::GetServerInfo,
+        Fault::Status(Status::resource_exhausted("topic budget exhausted")),
+    );
+    let err = client.get_server_info().await.expect_err("no hint");
+    assert_eq!(err.kernel().map(|k| k.code), Some(Code::ResourceExhausted));
+    assert_eq!(daemon.calls(Rpc::GetServerInfo), 3);
+}
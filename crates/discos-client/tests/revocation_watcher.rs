@@ -0,0 +1,179 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use discos_client::{pb, ClientError, DiscosClient, RevocationSet, RevocationWatcher};
+use discos_testkit::{sample_create_request, Fault, MockDaemon, MockDaemonConfig, Rpc};
+use ed25519_dalek::{Signer, SigningKey};
+use evidenceos_verifier as verifier;
+use tonic::{Code, Status};
+
+const REASON: &str = "policy_violation";
+
+async fn start() -> (MockDaemon, DiscosClient) {
+    let daemon = MockDaemon::new(MockDaemonConfig::default()).expect("daemon");
+    let endpoint = daemon.spawn().await.expect("spawn daemon");
+    let client = DiscosClient::connect(&endpoint).await.expect("connect");
+    (daemon, client)
+}
+
+async fn create_claim(client: &mut DiscosClient, name: &str) -> Vec<u8> {
+    client
+        .create_claim_v2(sample_create_request(name))
+        .await
+        .expect("create")
+        .claim_id
+}
+
+fn kernel_key() -> SigningKey {
+    MockDaemonConfig::default().signing_key
+}
+
+fn signed_entry(key: &SigningKey, claim_byte: u8, logical_epoch: u64) -> pb::RevocationEntry {
+    let claim_id = vec![claim_byte; 32];
+    let reason = REASON.to_string();
+    let digest = verifier::revocation_entry_digest(&verifier::RevocationEntry {
+        claim_id: claim_id.clone(),
+        reason_code: reason.clone(),
+        logical_epoch,
+        signature: [0u8; 64],
+    });
+    pb::RevocationEntry {
+        claim_id,
+        reason,
+        timestamp_unix: 1_700_000_000 + logical_epoch,
+        logical_epoch,
+        signature: key.sign(&digest).to_bytes().to_vec(),
+    }
+}
+
+fn watcher(daemon: &MockDaemon, client: &DiscosClient, set: RevocationSet) -> RevocationWatcher {
+    RevocationWatcher::new(client.clone(), &daemon.kernel_pubkey(), set)
+        .expect("watcher")
+        .with_reconnect(2, Duration::from_millis(10))
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn reconnects_and_skips_replayed_entries() {
+    let (daemon, mut client) = start().await;
+    let alpha = create_claim(&mut client, "alpha").await;
+    let beta = create_claim(&mut client, "beta").await;
+    daemon.revoke(&alpha, REASON).expect("revoke alpha");
+    let dir = tempfile::tempdir().expect("tempdir");
+    let store = dir.path().join("revocations.json");
+    let mut watcher = watcher(&daemon, &client, RevocationSet::default()).with_store(&store);
+
+    let first = watcher.next().await.expect("first revocation");
+    assert_eq!(first.claim_id_hex, hex::encode(&alpha));
+
+    // The stream ends, the first reconnect is refused, and the second one
+    // replays alpha before beta.
+    daemon.close_watchers();
+    daemon.fail_next(
+        Rpc::WatchRevocations,
+        Fault::Status(Status::unavailable("daemon restarting")),
+    );
+    daemon.revoke(&beta, REASON).expect("revoke beta");
+    let second = watcher.next().await.expect("second revocation");
+    assert_eq!(second.claim_id_hex, hex::encode(&beta));
+    assert_eq!(daemon.calls(Rpc::WatchRevocations), 3);
+    assert_eq!(watcher.revocations().last_logical_epoch(), Some(2));
+
+    let reloaded = RevocationSet::load_verified(&store, &daemon.kernel_pubkey())
+        .expect("reload persisted set");
+    assert_eq!(&reloaded, watcher.revocations());
+    assert!(reloaded.is_revoked(&beta));
+
+    daemon.close_watchers();
+    daemon.fail_always(
+        Rpc::WatchRevocations,
+        Fault::Status(Status::unavailable("daemon down")),
+    );
+    let err = watcher
+        .next()
+        .await
+        .expect_err("reconnect budget exhausted");
+    assert!(
+        matches!(&err, ClientError::Kernel(kernel) if kernel.code == Code::Unavailable),
+        "{err:?}"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn forged_revocation_is_a_hard_error() {
+    let (daemon, mut client) = start().await;
+    let alpha = create_claim(&mut client, "alpha").await;
+    daemon.revoke(&alpha, REASON).expect("revoke");
+    daemon.fail_next(Rpc::WatchRevocations, Fault::SwapKey);
+    let mut watcher = watcher(&daemon, &client, RevocationSet::default());
+
+    let err = watcher.next().await.expect_err("forged entry");
+    assert!(
+        matches!(&err, ClientError::VerificationFailed(msg) if msg.contains("forged")),
+        "{err:?}"
+    );
+    assert_eq!(daemon.calls(Rpc::WatchRevocations), 1);
+    assert!(watcher.revocations().is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn out_of_order_revocation_is_a_hard_error() {
+    let (daemon, mut client) = start().await;
+    let alpha = create_claim(&mut client, "alpha").await;
+    daemon.revoke(&alpha, REASON).expect("revoke");
+    // A set that already holds a later epoch than the daemon's feed.
+    let mut set = RevocationSet::default();
+    set.apply(
+        &signed_entry(&kernel_key(), 0x11, 5),
+        &daemon.kernel_pubkey(),
+    )
+    .expect("apply");
+    let mut watcher = watcher(&daemon, &client, set);
+
+    let err = watcher.next().await.expect_err("epoch went backwards");
+    assert!(
+        matches!(&err, ClientError::VerificationFailed(msg) if msg.contains("out-of-order")),
+        "{err:?}"
+    );
+    assert!(!watcher.revocations().is_revoked(&alpha));
+}
+
+#[test]
+fn tampered_revocation_store_is_rejected() {
+    let key = kernel_key();
+    let pubkey = key.verifying_key().to_bytes();
+    let mut set = RevocationSet::default();
+    assert!(set
+        .apply(&signed_entry(&key, 0x11, 1), &pubkey)
+        .expect("apply"));
+    assert!(!set
+        .apply(&signed_entry(&key, 0x11, 1), &pubkey)
+        .expect("replay"));
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let store = dir.path().join("revocations.json");
+    set.persist(&store).expect("persist");
+    assert_eq!(
+        RevocationSet::load_verified(&store, &pubkey).expect("load"),
+        set
+    );
+
+    let stored = std::fs::read_to_string(&store).expect("read store");
+    std::fs::write(&store, stored.replace("policy_violation", "withdrawn")).expect("tamper");
+    assert!(matches!(
+        RevocationSet::load_verified(&store, &pubkey),
+        Err(ClientError::VerificationFailed(_))
+    ));
+}
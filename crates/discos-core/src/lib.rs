@@ -22,6 +22,7 @@
 
 pub mod evalue;
 pub mod structured_claims;
+pub mod structured_profiles;
 pub mod topicid;
 
 #[cfg(feature = "sim")]
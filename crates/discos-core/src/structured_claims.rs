@@ -34,6 +34,13 @@ impl SchemaVersion {
             Self::V1_0_0 => 0,
         }
     }
+
+    pub const fn from_discriminant(discriminant: u8) -> Option<Self> {
+        match discriminant {
+            0 => Some(Self::V1_0_0),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -52,6 +59,13 @@ impl Profile {
             Self::CbrnSc => 0,
         }
     }
+
+    pub const fn from_discriminant(discriminant: u8) -> Option<Self> {
+        match discriminant {
+            0 => Some(Self::CbrnSc),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -70,6 +84,13 @@ impl Domain {
             Self::Cbrn => 0,
         }
     }
+
+    pub const fn from_discriminant(discriminant: u8) -> Option<Self> {
+        match discriminant {
+            0 => Some(Self::Cbrn),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -88,6 +109,13 @@ impl ClaimKind {
             Self::Assessment => 0,
         }
     }
+
+    pub const fn from_discriminant(discriminant: u8) -> Option<Self> {
+        match discriminant {
+            0 => Some(Self::Assessment),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -110,6 +138,15 @@ impl QuantityKind {
             Self::Activity => 2,
         }
     }
+
+    pub const fn from_discriminant(discriminant: u8) -> Option<Self> {
+        match discriminant {
+            0 => Some(Self::Concentration),
+            1 => Some(Self::DoseRate),
+            2 => Some(Self::Activity),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -138,6 +175,18 @@ impl Scale {
             Self::Femto => 5,
         }
     }
+
+    pub const fn from_discriminant(discriminant: u8) -> Option<Self> {
+        match discriminant {
+            0 => Some(Self::Unit),
+            1 => Some(Self::Milli),
+            2 => Some(Self::Micro),
+            3 => Some(Self::Nano),
+            4 => Some(Self::Pico),
+            5 => Some(Self::Femto),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -168,6 +217,19 @@ impl SiUnit {
             Self::KgPerKgBody => 6,
         }
     }
+
+    pub const fn from_discriminant(discriminant: u8) -> Option<Self> {
+        match discriminant {
+            0 => Some(Self::MolPerM3),
+            1 => Some(Self::KgPerM3),
+            2 => Some(Self::BqPerM3),
+            3 => Some(Self::JPerKg),
+            4 => Some(Self::GrayPerSec),
+            5 => Some(Self::WattPerM2),
+            6 => Some(Self::KgPerKgBody),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -192,6 +254,16 @@ impl Decision {
             Self::Escalate => 3,
         }
     }
+
+    pub const fn from_discriminant(discriminant: u8) -> Option<Self> {
+        match discriminant {
+            0 => Some(Self::Pass),
+            1 => Some(Self::Heavy),
+            2 => Some(Self::Reject),
+            3 => Some(Self::Escalate),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -224,6 +296,20 @@ impl ReasonCode {
             Self::StructuralAnomalyDetected => 7,
         }
     }
+
+    pub const fn from_discriminant(discriminant: u8) -> Option<Self> {
+        match discriminant {
+            0 => Some(Self::SensorAgreement),
+            1 => Some(Self::AboveThreshold),
+            2 => Some(Self::BelowThreshold),
+            3 => Some(Self::IncompleteInputs),
+            4 => Some(Self::MagnitudeEnvelopeExceeded),
+            5 => Some(Self::CalibrationExpired),
+            6 => Some(Self::LineageTainted),
+            7 => Some(Self::StructuralAnomalyDetected),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -246,6 +332,15 @@ impl EnvelopeCheck {
             Self::Mismatch => 2,
         }
     }
+
+    pub const fn from_discriminant(discriminant: u8) -> Option<Self> {
+        match discriminant {
+            0 => Some(Self::Match),
+            1 => Some(Self::Missing),
+            2 => Some(Self::Mismatch),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -396,6 +491,118 @@ pub fn canonicalize_cbrn_claim(claim: &CbrnStructuredClaim) -> Result<Vec<u8>, S
     Ok(out)
 }
 
+/// Strict inverse of [`canonicalize_cbrn_claim`]: accepts exactly the byte
+/// strings it produces, so every decoded claim re-encodes to `bytes` and
+/// passes [`validate_cbrn_claim`].
+///
+/// The CLI's structured output report decodes kernel `canonical_output`
+/// through this function and relies on that strictness: a lenient decoder
+/// would let two different outputs compare equal against a capsule hash or
+/// the local claim.
+pub fn decode_cbrn_claim_canonical(bytes: &[u8]) -> Result<CbrnStructuredClaim, String> {
+    let mut reader = CanonicalReader { bytes, pos: 0 };
+    let schema_version = reader.variant("schema_version", SchemaVersion::from_discriminant)?;
+    let profile = reader.variant("profile", Profile::from_discriminant)?;
+    let domain = reader.variant("domain", Domain::from_discriminant)?;
+    let claim_kind = reader.variant("claim_kind", ClaimKind::from_discriminant)?;
+
+    let quantity_count = reader.count("quantities", 1, MAX_QUANTITIES)?;
+    let mut quantities = Vec::with_capacity(quantity_count);
+    for _ in 0..quantity_count {
+        quantities.push(QuantizedValue {
+            quantity_kind: reader.variant("quantity_kind", QuantityKind::from_discriminant)?,
+            value_q: i64::from_be_bytes(reader.array("value_q")?),
+            scale: reader.variant("scale", Scale::from_discriminant)?,
+            unit: reader.variant("unit", SiUnit::from_discriminant)?,
+        });
+    }
+
+    let envelope_id = reader.array("envelope_id")?;
+    let envelope_check = reader.variant("envelope_check", EnvelopeCheck::from_discriminant)?;
+
+    let reference_count = reader.count("references", 0, MAX_REFERENCES)?;
+    let mut references = Vec::with_capacity(reference_count);
+    for _ in 0..reference_count {
+        references.push(reader.array("reference")?);
+    }
+
+    let etl_root = reader.array("etl_root")?;
+    let envelope_manifest_hash = reader.array("envelope_manifest_hash")?;
+    let envelope_manifest_version = u32::from_be_bytes(reader.array("envelope_manifest_version")?);
+
+    let decision = reader.variant("decision", Decision::from_discriminant)?;
+    let reason_count = reader.count("reason_codes", 1, MAX_REASON_CODES)?;
+    let mut reason_codes = Vec::with_capacity(reason_count);
+    for _ in 0..reason_count {
+        reason_codes.push(reader.variant("reason_code", ReasonCode::from_discriminant)?);
+    }
+
+    if reader.pos != bytes.len() {
+        return Err(format!(
+            "{} trailing bytes after canonical claim",
+            bytes.len() - reader.pos
+        ));
+    }
+
+    let claim = CbrnStructuredClaim {
+        schema_version,
+        profile,
+        domain,
+        claim_kind,
+        quantities,
+        envelope_id,
+        envelope_check,
+        references,
+        etl_root,
+        envelope_manifest_hash,
+        envelope_manifest_version,
+        decision,
+        reason_codes,
+    };
+    validate_cbrn_claim(&claim)?;
+    Ok(claim)
+}
+
+struct CanonicalReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl CanonicalReader<'_> {
+    fn array<const N: usize>(&mut self, field: &str) -> Result<[u8; N], String> {
+        let end = self.pos + N;
+        let chunk = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or_else(|| format!("truncated canonical claim at {field}"))?;
+        self.pos = end;
+        let mut out = [0u8; N];
+        out.copy_from_slice(chunk);
+        Ok(out)
+    }
+
+    fn u8(&mut self, field: &str) -> Result<u8, String> {
+        let [byte] = self.array::<1>(field)?;
+        Ok(byte)
+    }
+
+    /// Element count of a list, checked before anything is allocated for it.
+    fn count(&mut self, field: &str, min: usize, max: usize) -> Result<usize, String> {
+        let count = usize::from(self.u8(field)?);
+        if !(min..=max).contains(&count) {
+            return Err(format!(
+                "{field} count {count} outside {min}..={max} in canonical claim"
+            ));
+        }
+        Ok(count)
+    }
+
+    fn variant<T>(&mut self, field: &str, decode: fn(u8) -> Option<T>) -> Result<T, String> {
+        let discriminant = self.u8(field)?;
+        decode(discriminant).ok_or_else(|| format!("unknown {field} discriminant {discriminant}"))
+    }
+}
+
 pub fn kout_accounting(claim: &CbrnStructuredClaim) -> KoutAccounting {
     let quantity_bits = ceil_log2(QuantityKind::variant_count())
         + 64
@@ -497,6 +704,60 @@ mod tests {
         );
     }
 
+    #[test]
+    fn canonical_bytes_decode_back_to_the_claim() {
+        let claim = with_all_fields_populated();
+        let bytes = canonicalize_cbrn_claim(&claim).expect("canonical");
+        assert_eq!(decode_cbrn_claim_canonical(&bytes).expect("decode"), claim);
+
+        assert!(decode_cbrn_claim_canonical(&bytes[..bytes.len() - 1]).is_err());
+        let mut unknown_unit = bytes.clone();
+        unknown_unit[5 + 1 + 8 + 1] = SiUnit::variant_count() as u8;
+        assert!(decode_cbrn_claim_canonical(&unknown_unit)
+            .expect_err("unknown unit")
+            .contains("unit"));
+    }
+
+    #[test]
+    fn canonical_decoding_is_strict() {
+        let claim = with_all_fields_populated();
+        let bytes = canonicalize_cbrn_claim(&claim).expect("canonical");
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(decode_cbrn_claim_canonical(&trailing)
+            .expect_err("trailing byte")
+            .contains("trailing"));
+
+        // Quantity count, then the reference and reason-code counts.
+        let references_at = 5 + claim.quantities.len() * 11 + 32 + 1;
+        let reasons_at = references_at + 1 + claim.references.len() * 32 + 32 + 32 + 4 + 1;
+        for (at, count) in [
+            (4, 0),
+            (4, MAX_QUANTITIES + 1),
+            (references_at, MAX_REFERENCES + 1),
+            (reasons_at, 0),
+            (reasons_at, MAX_REASON_CODES + 1),
+        ] {
+            let mut out_of_range = bytes.clone();
+            out_of_range[at] = count as u8;
+            assert!(
+                decode_cbrn_claim_canonical(&out_of_range)
+                    .expect_err("count out of range")
+                    .contains("count"),
+                "{at}: {count}"
+            );
+        }
+
+        let mut negative = claim.clone();
+        negative.quantities[0].value_q = 1;
+        let mut negative = canonicalize_cbrn_claim(&negative).expect("canonical");
+        negative[6] = 0x80;
+        assert!(decode_cbrn_claim_canonical(&negative)
+            .expect_err("negative value_q")
+            .contains("non-negative"));
+    }
+
     #[test]
     fn rejects_floats() {
         let json = r#"{
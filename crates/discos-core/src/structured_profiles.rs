@@ -0,0 +1,249 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Structured-claim profiles keyed by canonical output schema id.
+//!
+//! A [`StructuredClaimProfile`] knows how to parse, validate, canonicalize and
+//! decode the claims of one output schema, and what they cost in k_out bits.
+//! Claims cross the trait in their JSON form so callers can handle any
+//! registered schema without knowing its Rust type. [`ProfileRegistry`] maps
+//! canonical schema ids to profiles; CBRN-SC is the only built-in one.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::structured_claims::{
+    canonicalize_cbrn_claim, decode_cbrn_claim_canonical, kout_accounting, parse_cbrn_claim_json,
+    validate_cbrn_claim, CbrnStructuredClaim, KoutAccounting,
+};
+use crate::topicid::{canonicalize_output_schema_id, CANONICAL_OUTPUT_SCHEMA_ID};
+
+pub trait StructuredClaimProfile: Send + Sync {
+    /// Canonical output schema id the profile is registered under.
+    fn output_schema_id(&self) -> &'static str;
+
+    /// Dual-use policy domain the profile's claims belong to, e.g. `CBRN`.
+    fn domain(&self) -> &'static str;
+
+    /// Parses claim JSON, rejecting anything the schema does not describe.
+    fn parse(&self, json: &[u8]) -> Result<Value, String>;
+
+    /// Checks the semantic rules parsing alone does not enforce.
+    fn validate(&self, claim: &Value) -> Result<(), String>;
+
+    fn canonicalize(&self, claim: &Value) -> Result<Vec<u8>, String>;
+
+    /// Inverse of [`Self::canonicalize`]; the decoded claim must validate.
+    fn decode_canonical(&self, bytes: &[u8]) -> Result<Value, String>;
+
+    fn kout_accounting(&self, claim: &Value) -> Result<KoutAccounting, String>;
+}
+
+/// The CBRN-SC v1 profile from [`crate::structured_claims`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CbrnScProfile;
+
+impl CbrnScProfile {
+    fn claim(claim: &Value) -> Result<CbrnStructuredClaim, String> {
+        CbrnStructuredClaim::deserialize(claim).map_err(|e| e.to_string())
+    }
+
+    fn to_value(claim: &CbrnStructuredClaim) -> Result<Value, String> {
+        serde_json::to_value(claim).map_err(|e| e.to_string())
+    }
+}
+
+impl StructuredClaimProfile for CbrnScProfile {
+    fn output_schema_id(&self) -> &'static str {
+        CANONICAL_OUTPUT_SCHEMA_ID
+    }
+
+    fn domain(&self) -> &'static str {
+        "CBRN"
+    }
+
+    fn parse(&self, json: &[u8]) -> Result<Value, String> {
+        Self::to_value(&parse_cbrn_claim_json(json)?)
+    }
+
+    fn validate(&self, claim: &Value) -> Result<(), String> {
+        validate_cbrn_claim(&Self::claim(claim)?)
+    }
+
+    fn canonicalize(&self, claim: &Value) -> Result<Vec<u8>, String> {
+        canonicalize_cbrn_claim(&Self::claim(claim)?)
+    }
+
+    fn decode_canonical(&self, bytes: &[u8]) -> Result<Value, String> {
+        Self::to_value(&decode_cbrn_claim_canonical(bytes)?)
+    }
+
+    fn kout_accounting(&self, claim: &Value) -> Result<KoutAccounting, String> {
+        Ok(kout_accounting(&Self::claim(claim)?))
+    }
+}
+
+/// Structured-claim profiles by canonical output schema id.
+#[derive(Default)]
+pub struct ProfileRegistry {
+    profiles: BTreeMap<String, Box<dyn StructuredClaimProfile>>,
+}
+
+impl ProfileRegistry {
+    /// A registry holding the built-in profiles.
+    pub fn builtin() -> Self {
+        let mut registry = Self::default();
+        registry.profiles.insert(
+            CANONICAL_OUTPUT_SCHEMA_ID.to_string(),
+            Box::new(CbrnScProfile),
+        );
+        registry
+    }
+
+    /// Adds `profile`; a schema id can only be registered once.
+    pub fn register(&mut self, profile: Box<dyn StructuredClaimProfile>) -> Result<(), String> {
+        let schema_id = profile.output_schema_id();
+        if canonicalize_output_schema_id(schema_id) != schema_id {
+            return Err(format!(
+                "profile schema id `{schema_id}` is not a canonical output schema id"
+            ));
+        }
+        if self.profiles.contains_key(schema_id) {
+            return Err(format!(
+                "a structured claim profile for `{schema_id}` is already registered"
+            ));
+        }
+        self.profiles.insert(schema_id.to_string(), profile);
+        Ok(())
+    }
+
+    /// The profile for `output_schema_id`, which may be an alias.
+    pub fn get(&self, output_schema_id: &str) -> Option<&dyn StructuredClaimProfile> {
+        self.profiles
+            .get(&canonicalize_output_schema_id(output_schema_id))
+            .map(|profile| profile.as_ref())
+    }
+
+    pub fn resolve(&self, output_schema_id: &str) -> Result<&dyn StructuredClaimProfile, String> {
+        self.get(output_schema_id).ok_or_else(|| {
+            format!("no structured claim profile for output schema `{output_schema_id}`")
+        })
+    }
+
+    /// Registered canonical schema ids, sorted.
+    pub fn schema_ids(&self) -> impl Iterator<Item = &str> {
+        self.profiles.keys().map(String::as_str)
+    }
+}
+
+impl std::fmt::Debug for ProfileRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.schema_ids()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEAVY_MIN: &[u8] =
+        include_bytes!("../../../test_vectors/structured_claims/valid/heavy_min.json");
+
+    struct FlatProfile;
+
+    impl StructuredClaimProfile for FlatProfile {
+        fn output_schema_id(&self) -> &'static str {
+            "flat.v1"
+        }
+
+        fn domain(&self) -> &'static str {
+            "GENERAL"
+        }
+
+        fn parse(&self, json: &[u8]) -> Result<Value, String> {
+            serde_json::from_slice(json).map_err(|e| e.to_string())
+        }
+
+        fn validate(&self, claim: &Value) -> Result<(), String> {
+            claim
+                .as_u64()
+                .map(|_| ())
+                .ok_or_else(|| "expected an unsigned integer".to_string())
+        }
+
+        fn canonicalize(&self, claim: &Value) -> Result<Vec<u8>, String> {
+            self.validate(claim)?;
+            Ok(claim.as_u64().unwrap_or_default().to_be_bytes().to_vec())
+        }
+
+        fn decode_canonical(&self, bytes: &[u8]) -> Result<Value, String> {
+            let bytes: [u8; 8] = bytes.try_into().map_err(|_| "expected 8 bytes")?;
+            Ok(Value::from(u64::from_be_bytes(bytes)))
+        }
+
+        fn kout_accounting(&self, _claim: &Value) -> Result<KoutAccounting, String> {
+            Ok(KoutAccounting {
+                kout_bits: 64,
+                capacity_bits: 64,
+            })
+        }
+    }
+
+    #[test]
+    fn builtin_cbrn_profile_matches_the_free_functions() {
+        let registry = ProfileRegistry::builtin();
+        let profile = registry.resolve("CBRN_SC.v1").expect("alias resolves");
+        assert_eq!(profile.output_schema_id(), CANONICAL_OUTPUT_SCHEMA_ID);
+        assert_eq!(profile.domain(), "CBRN");
+
+        let typed = parse_cbrn_claim_json(HEAVY_MIN).expect("typed");
+        let claim = profile.parse(HEAVY_MIN).expect("parse");
+        profile.validate(&claim).expect("valid");
+        let canonical = profile.canonicalize(&claim).expect("canonical");
+        assert_eq!(canonical, canonicalize_cbrn_claim(&typed).expect("typed"));
+        assert_eq!(profile.decode_canonical(&canonical).expect("decode"), claim);
+        assert_eq!(
+            profile.kout_accounting(&claim).expect("kout"),
+            kout_accounting(&typed)
+        );
+        assert!(profile.validate(&Value::from(7)).is_err());
+    }
+
+    #[test]
+    fn registry_dispatches_by_schema_id() {
+        let mut registry = ProfileRegistry::builtin();
+        registry.register(Box::new(FlatProfile)).expect("register");
+        assert!(registry
+            .register(Box::new(FlatProfile))
+            .expect_err("duplicate")
+            .contains("already registered"));
+        assert!(registry.register(Box::new(CbrnScProfile)).is_err());
+        assert_eq!(
+            registry.schema_ids().collect::<Vec<_>>(),
+            vec![CANONICAL_OUTPUT_SCHEMA_ID, "flat.v1"]
+        );
+
+        let flat = registry.resolve("flat.v1").expect("flat");
+        let claim = flat.parse(b"42").expect("parse");
+        let canonical = flat.canonicalize(&claim).expect("canonical");
+        assert_eq!(flat.decode_canonical(&canonical).expect("decode"), claim);
+        assert!(registry.get("flat.v2").is_none());
+        assert!(registry
+            .resolve("flat.v2")
+            .err()
+            .is_some_and(|err| err.contains("`flat.v2`")));
+    }
+}
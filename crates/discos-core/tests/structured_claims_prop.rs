@@ -1,8 +1,8 @@
 use discos_core::structured_claims::{
-    canonicalize_cbrn_claim, kout_bits, parse_cbrn_claim_json, validate_cbrn_claim,
-    CbrnStructuredClaim, ClaimKind, Decision, Domain, EnvelopeCheck, Profile, QuantityKind,
-    QuantizedValue, ReasonCode, Scale, SchemaVersion, SiUnit, MAX_QUANTITIES, MAX_REASON_CODES,
-    MAX_REFERENCES,
+    canonicalize_cbrn_claim, decode_cbrn_claim_canonical, kout_bits, parse_cbrn_claim_json,
+    validate_cbrn_claim, CbrnStructuredClaim, ClaimKind, Decision, Domain, EnvelopeCheck, Profile,
+    QuantityKind, QuantizedValue, ReasonCode, Scale, SchemaVersion, SiUnit, MAX_QUANTITIES,
+    MAX_REASON_CODES, MAX_REFERENCES,
 };
 use proptest::prelude::*;
 use serde_json::json;
@@ -152,6 +152,18 @@ proptest! {
         prop_assert_eq!(one, two);
     }
 
+    #[test]
+    fn canonical_bytes_round_trip(claim in arb_claim(), cut in any::<prop::sample::Index>()) {
+        let bytes = canonicalize_cbrn_claim(&claim).expect("canonicalization succeeds");
+        prop_assert_eq!(&decode_cbrn_claim_canonical(&bytes).expect("decodes"), &claim);
+
+        let prefix = &bytes[..cut.index(bytes.len())];
+        prop_assert!(decode_cbrn_claim_canonical(prefix).is_err());
+        let mut extended = bytes.clone();
+        extended.push(bytes[cut.index(bytes.len())]);
+        prop_assert!(decode_cbrn_claim_canonical(&extended).is_err());
+    }
+
     #[test]
     fn kout_bits_monotone_with_additional_fields(claim in arb_claim()) {
         let mut expanded = claim.clone();
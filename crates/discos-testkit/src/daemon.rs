@@ -0,0 +1,873 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Handlers and their helpers all speak `tonic::Status`, like the service trait.
+#![allow(clippy::result_large_err)]
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use discos_core::topicid::{
+    compute_topic_id, ClaimMetadata, TopicBudgetError, TopicBudgetLedger, TopicSignals,
+};
+use ed25519_dalek::{Signer, SigningKey};
+use evidenceos_protocol::pb::v2 as pb;
+use evidenceos_verifier as verifier;
+use prost::Message;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{TcpListenerStream, UnboundedReceiverStream};
+use tonic::{transport::Server, Code, Request, Response, Status};
+
+use crate::faults::{Applied, Fault, FaultPlan, Rpc};
+use crate::merkle::MerkleLog;
+
+pub const DEFAULT_TOPIC_BUDGET_BITS: f64 = 64.0;
+pub const DEFAULT_E_VALUE: f64 = 20.0;
+pub const CAPSULE_SCHEMA: &str = "evidenceos.claim-capsule.v1";
+/// Metadata key DiscOS clients put idempotency keys under.
+pub const IDEMPOTENCY_KEY_METADATA_KEY: &str = "x-idempotency-key";
+
+/// Lifecycle position of a claim; the discriminant is the `state` field the
+/// daemon reports in lifecycle responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i32)]
+pub enum ClaimStage {
+    Created = 1,
+    Committed = 2,
+    Frozen = 3,
+    Sealed = 4,
+    Executed = 5,
+    Revoked = 6,
+}
+
+#[derive(Debug, Clone)]
+pub struct MockDaemonConfig {
+    pub signing_key: SigningKey,
+    /// Key used instead of `signing_key` while a [`Fault::SwapKey`] applies.
+    pub rogue_key: SigningKey,
+    /// Defaults to sha256 of the public key.
+    pub key_id: Option<Vec<u8>>,
+    pub topic_budget_bits: f64,
+    /// Canonical output for every execution. Defaults to a small JSON
+    /// document naming the claim and oracle.
+    pub canonical_output: Option<Vec<u8>>,
+    pub certified: bool,
+    pub e_value: f64,
+    pub server_info: pb::GetServerInfoResponse,
+}
+
+impl Default for MockDaemonConfig {
+    fn default() -> Self {
+        Self {
+            signing_key: SigningKey::from_bytes(&[7u8; 32]),
+            rogue_key: SigningKey::from_bytes(&[0xEE; 32]),
+            key_id: None,
+            topic_budget_bits: DEFAULT_TOPIC_BUDGET_BITS,
+            canonical_output: None,
+            certified: true,
+            e_value: DEFAULT_E_VALUE,
+            server_info: pb::GetServerInfoResponse {
+                protocol_semver: evidenceos_protocol::PROTOCOL_SEMVER.to_string(),
+                proto_hash: evidenceos_protocol::PROTO_SHA256.to_string(),
+                build_git_commit: String::new(),
+                build_time_utc: String::new(),
+                daemon_version: concat!("discos-testkit/", env!("CARGO_PKG_VERSION")).to_string(),
+                feature_flags: Vec::new(),
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Claim {
+    name: String,
+    topic_id: [u8; 32],
+    oracle_id: String,
+    oracle_num_symbols: u32,
+    stage: ClaimStage,
+    wasm_hash: Option<[u8; 32]>,
+    execution: Option<pb::ExecuteClaimV2Response>,
+}
+
+#[derive(Debug)]
+struct State {
+    config: MockDaemonConfig,
+    claims: HashMap<Vec<u8>, Claim>,
+    claims_created: u64,
+    ledger: TopicBudgetLedger,
+    log: MerkleLog,
+    revocations: Vec<pb::RevocationEntry>,
+    watchers: Vec<mpsc::UnboundedSender<Result<pb::WatchRevocationsResponse, Status>>>,
+    /// Encoded responses of applied mutations, by RPC and idempotency key.
+    replies: HashMap<(Rpc, String), Vec<u8>>,
+    faults: FaultPlan,
+}
+
+fn key_id_for(config: &MockDaemonConfig, key: &SigningKey) -> Vec<u8> {
+    match &config.key_id {
+        Some(key_id) if key == &config.signing_key => key_id.clone(),
+        _ => verifier::sha256(key.verifying_key().as_bytes()).to_vec(),
+    }
+}
+
+fn flip(bytes: &mut [u8]) {
+    if let Some(byte) = bytes.first_mut() {
+        *byte ^= 0x01;
+    }
+}
+
+fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn hash32(bytes: &[u8], what: &str) -> Result<[u8; 32], Status> {
+    bytes
+        .try_into()
+        .map_err(|_| Status::invalid_argument(format!("{what} must be 32 bytes")))
+}
+
+fn optional_hash32(bytes: &[u8], what: &str) -> Result<Option<[u8; 32]>, Status> {
+    if bytes.is_empty() {
+        Ok(None)
+    } else {
+        hash32(bytes, what).map(Some)
+    }
+}
+
+fn inclusion_to_pb(proof: verifier::InclusionProof, forge: bool) -> pb::MerkleInclusionProof {
+    let mut proof = pb::MerkleInclusionProof {
+        leaf_hash: proof.leaf_hash.to_vec(),
+        leaf_index: proof.leaf_index,
+        tree_size: proof.tree_size,
+        audit_path: proof.audit_path.iter().map(|h| h.to_vec()).collect(),
+    };
+    if forge {
+        match proof.audit_path.first_mut() {
+            Some(node) => flip(node),
+            None => flip(&mut proof.leaf_hash),
+        }
+    }
+    proof
+}
+
+fn consistency_to_pb(proof: verifier::ConsistencyProof, forge: bool) -> pb::MerkleConsistencyProof {
+    let mut proof = pb::MerkleConsistencyProof {
+        old_tree_size: proof.old_tree_size,
+        new_tree_size: proof.new_tree_size,
+        path: proof.path.iter().map(|h| h.to_vec()).collect(),
+    };
+    if forge {
+        if let Some(node) = proof.path.first_mut() {
+            flip(node);
+        }
+    }
+    proof
+}
+
+impl State {
+    fn key(&self, swap_key: bool) -> &SigningKey {
+        if swap_key {
+            &self.config.rogue_key
+        } else {
+            &self.config.signing_key
+        }
+    }
+
+    fn sign_tree_head(&self, tree_size: u64, root: [u8; 32], swap_key: bool) -> pb::SignedTreeHead {
+        let key = self.key(swap_key);
+        let digest = verifier::sth_signature_digest(tree_size, root);
+        pb::SignedTreeHead {
+            tree_size,
+            root_hash: root.to_vec(),
+            signature: key.sign(&digest).to_bytes().to_vec(),
+            key_id: key_id_for(&self.config, key),
+        }
+    }
+
+    fn current_head(&self, applied: &Applied) -> pb::SignedTreeHead {
+        let mut root = self.log.root();
+        if applied.forge_proof {
+            flip(&mut root);
+        }
+        self.sign_tree_head(self.log.size(), root, applied.swap_key)
+    }
+
+    fn claim_mut(&mut self, claim_id: &[u8]) -> Result<&mut Claim, Status> {
+        self.claims
+            .get_mut(claim_id)
+            .ok_or_else(|| Status::not_found("unknown claim_id"))
+    }
+
+    /// Moves a claim from one of `from` to `to`, or fails the way the daemon
+    /// does for out-of-order lifecycle calls.
+    fn advance(
+        &mut self,
+        claim_id: &[u8],
+        from: &[ClaimStage],
+        to: ClaimStage,
+    ) -> Result<&mut Claim, Status> {
+        let claim = self.claim_mut(claim_id)?;
+        if !from.contains(&claim.stage) {
+            return Err(Status::failed_precondition(format!(
+                "claim is {:?}; cannot move to {to:?}",
+                claim.stage
+            )));
+        }
+        claim.stage = to;
+        Ok(claim)
+    }
+
+    fn create(
+        &mut self,
+        req: pb::CreateClaimV2Request,
+    ) -> Result<pb::CreateClaimV2Response, Status> {
+        let metadata = req
+            .metadata
+            .ok_or_else(|| Status::invalid_argument("metadata is required"))?;
+        let signals = req
+            .signals
+            .ok_or_else(|| Status::invalid_argument("signals are required"))?;
+        if req.claim_name.is_empty() {
+            return Err(Status::invalid_argument("claim_name is required"));
+        }
+        let topic = compute_topic_id(
+            &ClaimMetadata {
+                lane: metadata.lane,
+                alpha_micros: metadata.alpha_micros,
+                epoch_config_ref: metadata.epoch_config_ref,
+                output_schema_id: metadata.output_schema_id,
+            },
+            TopicSignals {
+                semantic_hash: optional_hash32(&signals.semantic_hash, "semantic_hash")?,
+                phys_hir_signature_hash: hash32(
+                    &signals.phys_hir_signature_hash,
+                    "phys_hir_signature_hash",
+                )?,
+                dependency_merkle_root: optional_hash32(
+                    &signals.dependency_merkle_root,
+                    "dependency_merkle_root",
+                )?,
+            },
+        );
+
+        self.claims_created += 1;
+        let mut material = req.claim_name.as_bytes().to_vec();
+        material.push(0);
+        material.extend_from_slice(&topic.topic_id);
+        material.extend_from_slice(&self.claims_created.to_be_bytes());
+        let claim_id = verifier::sha256(&material).to_vec();
+        self.claims.insert(
+            claim_id.clone(),
+            Claim {
+                name: req.claim_name,
+                topic_id: topic.topic_id,
+                oracle_id: req.oracle_id,
+                oracle_num_symbols: req.oracle_num_symbols,
+                stage: ClaimStage::Created,
+                wasm_hash: None,
+                execution: None,
+            },
+        );
+        Ok(pb::CreateClaimV2Response {
+            claim_id,
+            topic_id: topic.topic_id.to_vec(),
+            state: ClaimStage::Created as i32,
+        })
+    }
+
+    fn execute(&mut self, claim_id: &[u8]) -> Result<pb::ExecuteClaimV2Response, Status> {
+        let claim = self.claim_mut(claim_id)?;
+        if !matches!(claim.stage, ClaimStage::Frozen | ClaimStage::Sealed) {
+            return Err(Status::failed_precondition(format!(
+                "claim is {:?}; cannot execute",
+                claim.stage
+            )));
+        }
+        let topic_id = claim.topic_id;
+        // One oracle answer over `n` symbols leaks at most log2(n) bits.
+        let k_bits = f64::from(claim.oracle_num_symbols.max(2)).log2();
+        if let Err(err) = self.ledger.charge(topic_id, k_bits) {
+            let payload = serde_json::json!({
+                "topic_id_hex": hex::encode(topic_id),
+                "k_bits_requested": k_bits,
+                "k_bits_remaining": self.ledger.get_or_create(topic_id).k_bits_remaining(),
+                "state": "FROZEN",
+                "reason": err.to_string(),
+            });
+            return Err(Status::new(Code::ResourceExhausted, payload.to_string()));
+        }
+
+        let certified = self.config.certified;
+        let e_value = self.config.e_value;
+        let claim = self
+            .claims
+            .get(claim_id)
+            .ok_or_else(|| Status::not_found("unknown claim_id"))?;
+        let canonical_output = match &self.config.canonical_output {
+            Some(output) => output.clone(),
+            None => serde_json::json!({
+                "claim_name": claim.name,
+                "oracle_id": claim.oracle_id,
+            })
+            .to_string()
+            .into_bytes(),
+        };
+        let capsule = serde_json::json!({
+            "schema": CAPSULE_SCHEMA,
+            "claim_id_hex": hex::encode(claim_id),
+            "topic_id_hex": hex::encode(topic_id),
+            "structured_output_hash_hex": hex::encode(verifier::sha256(&canonical_output)),
+            "wasm_hash_hex": claim.wasm_hash.map(hex::encode),
+            "oracle_id": claim.oracle_id,
+            "certified": certified,
+            "e_value": e_value,
+            "decision": if certified { "allow" } else { "defer" },
+            "reason_codes": [],
+        })
+        .to_string()
+        .into_bytes();
+
+        let etl_index = self.log.append(&capsule);
+        let response = pb::ExecuteClaimV2Response {
+            certified,
+            e_value,
+            canonical_output,
+            capsule_hash: verifier::etl_leaf_hash(&capsule).to_vec(),
+            etl_index,
+            state: ClaimStage::Executed as i32,
+        };
+        let claim = self.claim_mut(claim_id)?;
+        claim.stage = ClaimStage::Executed;
+        claim.execution = Some(response.clone());
+        Ok(response)
+    }
+
+    fn execution(&self, claim_id: &[u8]) -> Result<pb::ExecuteClaimV2Response, Status> {
+        self.claims
+            .get(claim_id)
+            .ok_or_else(|| Status::not_found("unknown claim_id"))?
+            .execution
+            .clone()
+            .ok_or_else(|| Status::failed_precondition("claim has not been executed"))
+    }
+
+    fn fetch_capsule(
+        &self,
+        claim_id: &[u8],
+        applied: &Applied,
+    ) -> Result<pb::FetchCapsuleResponse, Status> {
+        let execution = self.execution(claim_id)?;
+        let index = execution.etl_index;
+        let tree_size = self.log.size();
+        let capsule_bytes = self
+            .log
+            .leaf(index)
+            .ok_or_else(|| Status::internal("capsule missing from log"))?
+            .to_vec();
+        let inclusion = self
+            .log
+            .inclusion_proof(index, tree_size)
+            .ok_or_else(|| Status::internal("inclusion proof unavailable"))?;
+        // Proves the current head extends the one the log had just before
+        // this capsule was appended.
+        let consistency = self
+            .log
+            .consistency_proof(index, tree_size)
+            .ok_or_else(|| Status::internal("consistency proof unavailable"))?;
+        let root = self.log.root();
+        Ok(pb::FetchCapsuleResponse {
+            capsule_hash: execution.capsule_hash,
+            capsule_bytes,
+            etl_index: index,
+            tree_size,
+            root_hash: root.to_vec(),
+            inclusion_proof: Some(inclusion_to_pb(inclusion, applied.forge_proof)),
+            consistency_proof: Some(consistency_to_pb(consistency, applied.forge_proof)),
+            signed_tree_head: Some(self.sign_tree_head(tree_size, root, applied.swap_key)),
+        })
+    }
+
+    fn revoke(&mut self, claim_id: &[u8], reason: &str) -> Result<pb::RevocationEntry, Status> {
+        let claim = self.claim_mut(claim_id)?;
+        if claim.stage == ClaimStage::Revoked {
+            return Err(Status::already_exists("claim is already revoked"));
+        }
+        claim.stage = ClaimStage::Revoked;
+        let logical_epoch = self.revocations.len() as u64 + 1;
+        let entry = self.sign_revocation(
+            pb::RevocationEntry {
+                claim_id: claim_id.to_vec(),
+                reason: reason.to_string(),
+                timestamp_unix: now_unix(),
+                logical_epoch,
+                signature: Vec::new(),
+            },
+            &Applied::default(),
+        );
+        self.revocations.push(entry.clone());
+        let update = pb::WatchRevocationsResponse {
+            entries: vec![entry.clone()],
+        };
+        self.watchers
+            .retain(|watcher| watcher.send(Ok(update.clone())).is_ok());
+        Ok(entry)
+    }
+
+    fn sign_revocation(
+        &self,
+        mut entry: pb::RevocationEntry,
+        applied: &Applied,
+    ) -> pb::RevocationEntry {
+        let digest = verifier::revocation_entry_digest(&verifier::RevocationEntry {
+            claim_id: entry.claim_id.clone(),
+            reason_code: entry.reason.clone(),
+            logical_epoch: entry.logical_epoch,
+            signature: [0u8; 64],
+        });
+        entry.signature = self.key(applied.swap_key).sign(&digest).to_bytes().to_vec();
+        if applied.forge_proof {
+            flip(&mut entry.signature);
+        }
+        entry
+    }
+}
+
+/// Stateful in-process `EvidenceOS` daemon. Clones share state, so a test
+/// can keep a handle to script faults and inspect the log while the server
+/// runs.
+#[derive(Debug, Clone)]
+pub struct MockDaemon {
+    state: Arc<Mutex<State>>,
+}
+
+impl MockDaemon {
+    pub fn new(config: MockDaemonConfig) -> Result<Self, TopicBudgetError> {
+        let ledger = TopicBudgetLedger::new(config.topic_budget_bits)?;
+        Ok(Self {
+            state: Arc::new(Mutex::new(State {
+                config,
+                claims: HashMap::new(),
+                claims_created: 0,
+                ledger,
+                log: MerkleLog::new(),
+                revocations: Vec::new(),
+                watchers: Vec::new(),
+                replies: HashMap::new(),
+                faults: FaultPlan::default(),
+            })),
+        })
+    }
+
+    /// Serves the daemon on an ephemeral localhost port and returns its
+    /// endpoint URL. The server runs until the runtime shuts down.
+    pub async fn spawn(&self) -> std::io::Result<String> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let daemon = self.clone();
+        tokio::spawn(async move {
+            let _ = Server::builder()
+                .add_service(pb::evidence_os_server::EvidenceOsServer::new(daemon))
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await;
+        });
+        Ok(format!("http://{addr}"))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Queues a fault for the next call of `rpc`; queued faults are consumed
+    /// one per call.
+    pub fn fail_next(&self, rpc: Rpc, fault: Fault) {
+        self.lock().faults.push_next(rpc, fault);
+    }
+
+    /// Applies `fault` to every call of `rpc` until [`Self::clear_faults`].
+    pub fn fail_always(&self, rpc: Rpc, fault: Fault) {
+        self.lock().faults.push_always(rpc, fault);
+    }
+
+    pub fn clear_faults(&self, rpc: Rpc) {
+        self.lock().faults.clear(rpc);
+    }
+
+    /// Calls received for `rpc`, including ones failed by a fault.
+    pub fn calls(&self, rpc: Rpc) -> usize {
+        self.lock().faults.calls(rpc)
+    }
+
+    pub fn kernel_pubkey(&self) -> [u8; 32] {
+        self.lock().config.signing_key.verifying_key().to_bytes()
+    }
+
+    pub fn rogue_pubkey(&self) -> [u8; 32] {
+        self.lock().config.rogue_key.verifying_key().to_bytes()
+    }
+
+    pub fn key_id(&self) -> Vec<u8> {
+        let state = self.lock();
+        key_id_for(&state.config, &state.config.signing_key)
+    }
+
+    /// Replaces the kernel signing key, as a real key rotation would.
+    pub fn rotate_key(&self, signing_key: SigningKey, key_id: Option<Vec<u8>>) {
+        let mut state = self.lock();
+        state.config.signing_key = signing_key;
+        state.config.key_id = key_id;
+    }
+
+    pub fn tree_size(&self) -> u64 {
+        self.lock().log.size()
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.lock().log.root()
+    }
+
+    pub fn signed_tree_head(&self) -> pb::SignedTreeHead {
+        self.lock().current_head(&Applied::default())
+    }
+
+    /// Appends an unrelated leaf, e.g. to grow the log between two heads.
+    pub fn append_leaf(&self, payload: &[u8]) -> u64 {
+        self.lock().log.append(payload)
+    }
+
+    /// Claims registered so far; retried creates with the same idempotency
+    /// key count once.
+    pub fn claim_count(&self) -> usize {
+        self.lock().claims.len()
+    }
+
+    pub fn claim_stage(&self, claim_id: &[u8]) -> Option<ClaimStage> {
+        self.lock().claims.get(claim_id).map(|claim| claim.stage)
+    }
+
+    /// Remaining k-bits for a topic that has at least one claim.
+    pub fn k_bits_remaining(&self, topic_id: &[u8]) -> Option<f64> {
+        let topic_id: [u8; 32] = topic_id.try_into().ok()?;
+        let mut state = self.lock();
+        if !state
+            .claims
+            .values()
+            .any(|claim| claim.topic_id == topic_id)
+        {
+            return None;
+        }
+        Some(state.ledger.get_or_create(topic_id).k_bits_remaining())
+    }
+
+    /// Revokes a claim out of band and pushes the entry to open watchers.
+    pub fn revoke(&self, claim_id: &[u8], reason: &str) -> Result<pb::RevocationEntry, Status> {
+        self.lock().revoke(claim_id, reason)
+    }
+
+    pub fn revocations(&self) -> Vec<pb::RevocationEntry> {
+        self.lock().revocations.clone()
+    }
+
+    /// Ends every open `WatchRevocations` stream.
+    pub fn close_watchers(&self) {
+        self.lock().watchers.clear();
+    }
+
+    async fn begin(&self, rpc: Rpc) -> Result<Applied, Status> {
+        let mut applied = self.lock().faults.take(rpc);
+        if !applied.delay.is_zero() {
+            tokio::time::sleep(applied.delay).await;
+        }
+        match applied.status.take() {
+            Some(status) => Err(status),
+            None => Ok(applied),
+        }
+    }
+
+    /// Runs a state-changing handler. A request carrying an idempotency key
+    /// that was already applied gets the stored response instead, the way a
+    /// daemon deduplicates client retries.
+    fn mutate<Req, Resp>(
+        &self,
+        rpc: Rpc,
+        req: Request<Req>,
+        applied: Applied,
+        handle: impl FnOnce(&mut State, Req) -> Result<Resp, Status>,
+    ) -> Result<Response<Resp>, Status>
+    where
+        Resp: Message + Default,
+    {
+        let key = req
+            .metadata()
+            .get(IDEMPOTENCY_KEY_METADATA_KEY)
+            .and_then(|value| value.to_str().ok())
+            .map(|key| (rpc, key.to_string()));
+        let mut state = self.lock();
+        if let Some(stored) = key.as_ref().and_then(|key| state.replies.get(key)) {
+            let resp = Resp::decode(stored.as_slice())
+                .map_err(|e| Status::internal(format!("stored reply: {e}")))?;
+            return applied.respond(resp);
+        }
+        let resp = handle(&mut state, req.into_inner())?;
+        if let Some(key) = key {
+            state.replies.insert(key, resp.encode_to_vec());
+        }
+        applied.respond(resp)
+    }
+}
+
+#[tonic::async_trait]
+impl pb::evidence_os_server::EvidenceOs for MockDaemon {
+    async fn health(
+        &self,
+        _: Request<pb::HealthRequest>,
+    ) -> Result<Response<pb::HealthResponse>, Status> {
+        self.begin(Rpc::Health).await?.respond(pb::HealthResponse {
+            status: "ok".to_string(),
+        })
+    }
+
+    async fn create_claim_v2(
+        &self,
+        req: Request<pb::CreateClaimV2Request>,
+    ) -> Result<Response<pb::CreateClaimV2Response>, Status> {
+        let applied = self.begin(Rpc::CreateClaimV2).await?;
+        let short_ids = applied.short_ids;
+        self.mutate(Rpc::CreateClaimV2, req, applied, |state, req| {
+            let mut resp = state.create(req)?;
+            if short_ids {
+                resp.claim_id.truncate(16);
+                resp.topic_id.truncate(16);
+            }
+            Ok(resp)
+        })
+    }
+
+    async fn commit_artifacts(
+        &self,
+        req: Request<pb::CommitArtifactsRequest>,
+    ) -> Result<Response<pb::CommitArtifactsResponse>, Status> {
+        let applied = self.begin(Rpc::CommitArtifacts).await?;
+        self.mutate(Rpc::CommitArtifacts, req, applied, |state, req| {
+            if req.artifacts.is_empty() {
+                return Err(Status::invalid_argument("artifacts are required"));
+            }
+            let claim =
+                state.advance(&req.claim_id, &[ClaimStage::Created], ClaimStage::Committed)?;
+            if !req.wasm_module.is_empty() {
+                claim.wasm_hash = Some(verifier::sha256(&req.wasm_module));
+            }
+            Ok(pb::CommitArtifactsResponse {
+                state: ClaimStage::Committed as i32,
+            })
+        })
+    }
+
+    async fn commit_wasm(
+        &self,
+        req: Request<pb::CommitWasmRequest>,
+    ) -> Result<Response<pb::CommitWasmResponse>, Status> {
+        let applied = self.begin(Rpc::CommitWasm).await?;
+        self.mutate(Rpc::CommitWasm, req, applied, |state, req| {
+            let wasm_hash = verifier::sha256(&req.wasm_module);
+            if req.wasm_hash.as_slice() != wasm_hash {
+                return Err(Status::invalid_argument(
+                    "wasm_hash does not match wasm_module",
+                ));
+            }
+            let claim = state.advance(
+                &req.claim_id,
+                &[ClaimStage::Committed],
+                ClaimStage::Committed,
+            )?;
+            claim.wasm_hash = Some(wasm_hash);
+            Ok(pb::CommitWasmResponse {
+                state: ClaimStage::Committed as i32,
+            })
+        })
+    }
+
+    async fn freeze(
+        &self,
+        req: Request<pb::FreezeRequest>,
+    ) -> Result<Response<pb::FreezeResponse>, Status> {
+        let applied = self.begin(Rpc::Freeze).await?;
+        self.mutate(Rpc::Freeze, req, applied, |state, req| {
+            state.advance(&req.claim_id, &[ClaimStage::Committed], ClaimStage::Frozen)?;
+            Ok(pb::FreezeResponse {
+                state: ClaimStage::Frozen as i32,
+            })
+        })
+    }
+
+    async fn seal(
+        &self,
+        req: Request<pb::SealRequest>,
+    ) -> Result<Response<pb::SealResponse>, Status> {
+        let applied = self.begin(Rpc::Seal).await?;
+        self.mutate(Rpc::Seal, req, applied, |state, req| {
+            state.advance(&req.claim_id, &[ClaimStage::Frozen], ClaimStage::Sealed)?;
+            Ok(pb::SealResponse {
+                state: ClaimStage::Sealed as i32,
+            })
+        })
+    }
+
+    async fn execute_claim_v2(
+        &self,
+        req: Request<pb::ExecuteClaimV2Request>,
+    ) -> Result<Response<pb::ExecuteClaimV2Response>, Status> {
+        let applied = self.begin(Rpc::ExecuteClaimV2).await?;
+        self.mutate(Rpc::ExecuteClaimV2, req, applied, |state, req| {
+            state.execute(&req.claim_id)
+        })
+    }
+
+    async fn fetch_capsule(
+        &self,
+        req: Request<pb::FetchCapsuleRequest>,
+    ) -> Result<Response<pb::FetchCapsuleResponse>, Status> {
+        let applied = self.begin(Rpc::FetchCapsule).await?;
+        let resp = self
+            .lock()
+            .fetch_capsule(&req.into_inner().claim_id, &applied)?;
+        applied.respond(resp)
+    }
+
+    async fn get_public_key(
+        &self,
+        req: Request<pb::GetPublicKeyRequest>,
+    ) -> Result<Response<pb::GetPublicKeyResponse>, Status> {
+        let applied = self.begin(Rpc::GetPublicKey).await?;
+        let state = self.lock();
+        let key = state.key(applied.swap_key);
+        let key_id = key_id_for(&state.config, key);
+        let requested = req.into_inner().key_id;
+        if !requested.is_empty() && requested != key_id {
+            return Err(Status::not_found("unknown key_id"));
+        }
+        let resp = pb::GetPublicKeyResponse {
+            pubkey: key.verifying_key().to_bytes().to_vec(),
+            key_id,
+        };
+        applied.respond(resp)
+    }
+
+    async fn get_signed_tree_head(
+        &self,
+        _: Request<pb::GetSignedTreeHeadRequest>,
+    ) -> Result<Response<pb::GetSignedTreeHeadResponse>, Status> {
+        let applied = self.begin(Rpc::GetSignedTreeHead).await?;
+        let sth = self.lock().current_head(&applied);
+        applied.respond(pb::GetSignedTreeHeadResponse {
+            signed_tree_head: Some(sth),
+        })
+    }
+
+    async fn get_inclusion_proof(
+        &self,
+        req: Request<pb::GetInclusionProofRequest>,
+    ) -> Result<Response<pb::GetInclusionProofResponse>, Status> {
+        let applied = self.begin(Rpc::GetInclusionProof).await?;
+        let state = self.lock();
+        let execution = state.execution(&req.into_inner().claim_id)?;
+        let tree_size = state.log.size();
+        let proof = state
+            .log
+            .inclusion_proof(execution.etl_index, tree_size)
+            .ok_or_else(|| Status::internal("inclusion proof unavailable"))?;
+        let resp = pb::GetInclusionProofResponse {
+            inclusion_proof: Some(inclusion_to_pb(proof, applied.forge_proof)),
+            signed_tree_head: Some(state.sign_tree_head(
+                tree_size,
+                state.log.root(),
+                applied.swap_key,
+            )),
+        };
+        applied.respond(resp)
+    }
+
+    async fn get_consistency_proof(
+        &self,
+        req: Request<pb::GetConsistencyProofRequest>,
+    ) -> Result<Response<pb::GetConsistencyProofResponse>, Status> {
+        let applied = self.begin(Rpc::GetConsistencyProof).await?;
+        let req = req.into_inner();
+        let proof = self
+            .lock()
+            .log
+            .consistency_proof(req.old_tree_size, req.new_tree_size)
+            .ok_or_else(|| {
+                Status::invalid_argument(format!(
+                    "no consistency proof from {} to {}",
+                    req.old_tree_size, req.new_tree_size
+                ))
+            })?;
+        let resp = pb::GetConsistencyProofResponse {
+            consistency_proof: Some(consistency_to_pb(proof, applied.forge_proof)),
+        };
+        applied.respond(resp)
+    }
+
+    async fn revoke_claim(
+        &self,
+        req: Request<pb::RevokeClaimRequest>,
+    ) -> Result<Response<pb::RevokeClaimResponse>, Status> {
+        let applied = self.begin(Rpc::RevokeClaim).await?;
+        self.mutate(Rpc::RevokeClaim, req, applied, |state, req| {
+            let entry = state.revoke(&req.claim_id, &req.reason)?;
+            Ok(pb::RevokeClaimResponse {
+                state: ClaimStage::Revoked as i32,
+                timestamp_unix: entry.timestamp_unix,
+            })
+        })
+    }
+
+    type WatchRevocationsStream =
+        UnboundedReceiverStream<Result<pb::WatchRevocationsResponse, Status>>;
+
+    /// Replays every revocation so far, then streams new ones. Forge and
+    /// key-swap faults only affect the replayed backlog.
+    async fn watch_revocations(
+        &self,
+        _: Request<pb::WatchRevocationsRequest>,
+    ) -> Result<Response<Self::WatchRevocationsStream>, Status> {
+        let applied = self.begin(Rpc::WatchRevocations).await?;
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut state = self.lock();
+        if !state.revocations.is_empty() {
+            let entries = state
+                .revocations
+                .iter()
+                .map(|entry| state.sign_revocation(entry.clone(), &applied))
+                .collect();
+            let _ = tx.send(Ok(pb::WatchRevocationsResponse { entries }));
+        }
+        state.watchers.push(tx);
+        Ok(Response::new(UnboundedReceiverStream::new(rx)))
+    }
+
+    async fn get_server_info(
+        &self,
+        _: Request<pb::GetServerInfoRequest>,
+    ) -> Result<Response<pb::GetServerInfoResponse>, Status> {
+        let applied = self.begin(Rpc::GetServerInfo).await?;
+        let resp = self.lock().config.server_info.clone();
+        applied.respond(resp)
+    }
+}
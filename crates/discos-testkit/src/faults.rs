@@ -0,0 +1,139 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Per-RPC fault scripts.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use tonic::Status;
+
+/// RPCs of the `EvidenceOS` service, used to address faults and call counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Rpc {
+    Health,
+    CreateClaimV2,
+    CommitArtifacts,
+    CommitWasm,
+    Freeze,
+    Seal,
+    ExecuteClaimV2,
+    FetchCapsule,
+    GetPublicKey,
+    GetSignedTreeHead,
+    GetInclusionProof,
+    GetConsistencyProof,
+    RevokeClaim,
+    WatchRevocations,
+    GetServerInfo,
+}
+
+#[derive(Debug, Clone)]
+pub enum Fault {
+    /// Sleep before handling the call. Several delays add up.
+    Delay(Duration),
+    /// Fail the call with this status; the daemon state is left untouched.
+    Status(Status),
+    /// Handle the call, then fail it with this status, as if the response
+    /// was lost on the way back.
+    DropResponse(Status),
+    /// Corrupt Merkle material in the response: audit and consistency paths
+    /// get a flipped byte, and bare tree heads are re-signed over a bogus
+    /// root so the signature is valid but the head forks from the log.
+    ForgeProof,
+    /// Sign with the daemon's rogue key instead of the kernel key, and serve
+    /// the rogue key from `GetPublicKey`.
+    SwapKey,
+    /// Cut the claim and topic ids in a `CreateClaimV2` response to 16
+    /// bytes; the claim itself is registered under its full id.
+    ShortIds,
+}
+
+/// Faults that apply to one call.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Applied {
+    pub delay: Duration,
+    pub status: Option<Status>,
+    pub drop_response: Option<Status>,
+    pub forge_proof: bool,
+    pub swap_key: bool,
+    pub short_ids: bool,
+}
+
+impl Applied {
+    /// Wraps a handler's response, or fails the call if the response is
+    /// scripted to be dropped.
+    #[allow(clippy::result_large_err)]
+    pub fn respond<T>(self, resp: T) -> Result<tonic::Response<T>, Status> {
+        match self.drop_response {
+            Some(status) => Err(status),
+            None => Ok(tonic::Response::new(resp)),
+        }
+    }
+
+    fn add(&mut self, fault: &Fault) {
+        match fault {
+            Fault::Delay(delay) => self.delay += *delay,
+            Fault::Status(status) => {
+                self.status.get_or_insert_with(|| status.clone());
+            }
+            Fault::DropResponse(status) => {
+                self.drop_response.get_or_insert_with(|| status.clone());
+            }
+            Fault::ForgeProof => self.forge_proof = true,
+            Fault::SwapKey => self.swap_key = true,
+            Fault::ShortIds => self.short_ids = true,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct FaultPlan {
+    next: HashMap<Rpc, VecDeque<Fault>>,
+    always: HashMap<Rpc, Vec<Fault>>,
+    calls: HashMap<Rpc, usize>,
+}
+
+impl FaultPlan {
+    pub fn push_next(&mut self, rpc: Rpc, fault: Fault) {
+        self.next.entry(rpc).or_default().push_back(fault);
+    }
+
+    pub fn push_always(&mut self, rpc: Rpc, fault: Fault) {
+        self.always.entry(rpc).or_default().push(fault);
+    }
+
+    pub fn clear(&mut self, rpc: Rpc) {
+        self.next.remove(&rpc);
+        self.always.remove(&rpc);
+    }
+
+    pub fn calls(&self, rpc: Rpc) -> usize {
+        self.calls.get(&rpc).copied().unwrap_or(0)
+    }
+
+    /// Counts the call and returns every persistent fault for `rpc` plus
+    /// the next queued one-shot fault, if any.
+    pub fn take(&mut self, rpc: Rpc) -> Applied {
+        *self.calls.entry(rpc).or_default() += 1;
+        let mut applied = Applied::default();
+        for fault in self.always.get(&rpc).into_iter().flatten() {
+            applied.add(fault);
+        }
+        if let Some(fault) = self.next.get_mut(&rpc).and_then(VecDeque::pop_front) {
+            applied.add(&fault);
+        }
+        applied
+    }
+}
@@ -0,0 +1,43 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg_attr(
+    not(test),
+    deny(clippy::unwrap_used, clippy::expect_used, clippy::panic)
+)]
+
+//! In-process `EvidenceOS` daemon for testing DiscOS clients and tools
+//! without an EvidenceOS checkout.
+//!
+//! [`MockDaemon`] keeps real state: claims walk the v2 lifecycle, executions
+//! are charged against per-topic k-bit budgets, capsules are appended to a
+//! Merkle log whose proofs pass `evidenceos_verifier`, tree heads and
+//! revocations are signed with an ed25519 kernel key, and revocations are
+//! streamed to watchers. Faults are scripted per RPC through
+//! [`MockDaemon::fail_next`] and [`MockDaemon::fail_always`].
+//! [`sample_create_request`] and [`run_sample_claim`] drive a claim through
+//! it with `discos-client`.
+
+mod daemon;
+pub mod faults;
+mod lifecycle;
+pub mod merkle;
+
+pub use daemon::{
+    ClaimStage, MockDaemon, MockDaemonConfig, CAPSULE_SCHEMA, DEFAULT_E_VALUE,
+    DEFAULT_TOPIC_BUDGET_BITS, IDEMPOTENCY_KEY_METADATA_KEY,
+};
+pub use faults::{Fault, Rpc};
+pub use lifecycle::{run_sample_claim, sample_create_request, SAMPLE_WASM};
+pub use merkle::MerkleLog;
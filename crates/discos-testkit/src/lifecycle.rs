@@ -0,0 +1,73 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A valid claim and the client calls that take it through the lifecycle.
+
+use discos_client::{
+    pb, sha256, ClaimSession, ClientError, DiscosClient, SignedTreeHead, VerifiedCapsule,
+};
+
+/// Smallest valid wasm module; committed by [`run_sample_claim`].
+pub const SAMPLE_WASM: &[u8] = b"\0asm\x01\0\0\0";
+
+/// A create request [`crate::MockDaemon`] accepts. Claims with the same name
+/// share a topic.
+pub fn sample_create_request(name: &str) -> pb::CreateClaimV2Request {
+    pb::CreateClaimV2Request {
+        claim_name: name.to_string(),
+        metadata: Some(pb::ClaimMetadataV2 {
+            lane: "fast".to_string(),
+            alpha_micros: 50_000,
+            epoch_config_ref: "epoch/default".to_string(),
+            output_schema_id: "cbrn-sc.v1".to_string(),
+        }),
+        signals: Some(pb::TopicSignalsV2 {
+            semantic_hash: vec![1; 32],
+            phys_hir_signature_hash: vec![1; 32],
+            dependency_merkle_root: Vec::new(),
+        }),
+        holdout_ref: "holdout/default".to_string(),
+        epoch_size: 10,
+        oracle_num_symbols: 4,
+        access_credit: 1,
+        oracle_id: "builtin.accuracy".to_string(),
+        ..Default::default()
+    }
+}
+
+/// Creates, commits, freezes, seals and executes `request`, then fetches and
+/// verifies its capsule against `kernel_pubkey`.
+pub async fn run_sample_claim(
+    client: &mut DiscosClient,
+    request: pb::CreateClaimV2Request,
+    kernel_pubkey: &[u8],
+    previous_sth: Option<&SignedTreeHead>,
+) -> Result<VerifiedCapsule, ClientError> {
+    let artifact = pb::Artifact {
+        artifact_hash: sha256(SAMPLE_WASM).to_vec(),
+        kind: "wasm_module".to_string(),
+    };
+    ClaimSession::create(client, request)
+        .await?
+        .commit(vec![artifact], SAMPLE_WASM.to_vec())
+        .await?
+        .freeze()
+        .await?
+        .seal()
+        .await?
+        .execute()
+        .await?
+        .fetch_capsule(kernel_pubkey, previous_sth)
+        .await
+}
@@ -0,0 +1,210 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! In-memory transparency log for the mock daemon.
+//!
+//! Trees are split at the largest power of two below the size, as in
+//! RFC 9162, so every root and proof produced here is accepted by
+//! `evidenceos_verifier`. It is the same construction as
+//! `evidenceos_core::etl::Etl`, without the segment files.
+
+use evidenceos_verifier::{etl_leaf_hash, sha256, ConsistencyProof, InclusionProof};
+
+fn node_hash(left: [u8; 32], right: [u8; 32]) -> [u8; 32] {
+    let mut material = Vec::with_capacity(65);
+    material.push(0x01);
+    material.extend_from_slice(&left);
+    material.extend_from_slice(&right);
+    sha256(&material)
+}
+
+/// Largest power of two strictly below `n`; `n` must be at least 2.
+fn split(n: usize) -> usize {
+    let mut k = 1;
+    while k << 1 < n {
+        k <<= 1;
+    }
+    k
+}
+
+fn subtree_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    match leaves.len() {
+        0 => sha256(&[]),
+        1 => leaves[0],
+        n => {
+            let k = split(n);
+            node_hash(subtree_root(&leaves[..k]), subtree_root(&leaves[k..]))
+        }
+    }
+}
+
+fn audit_path(index: usize, leaves: &[[u8; 32]], out: &mut Vec<[u8; 32]>) {
+    if leaves.len() <= 1 {
+        return;
+    }
+    let k = split(leaves.len());
+    if index < k {
+        audit_path(index, &leaves[..k], out);
+        out.push(subtree_root(&leaves[k..]));
+    } else {
+        audit_path(index - k, &leaves[k..], out);
+        out.push(subtree_root(&leaves[..k]));
+    }
+}
+
+fn subproof(old: usize, leaves: &[[u8; 32]], complete: bool, out: &mut Vec<[u8; 32]>) {
+    let n = leaves.len();
+    if old == n {
+        if !complete {
+            out.push(subtree_root(leaves));
+        }
+        return;
+    }
+    let k = split(n);
+    if old <= k {
+        subproof(old, &leaves[..k], complete, out);
+        out.push(subtree_root(&leaves[k..]));
+    } else {
+        subproof(old - k, &leaves[k..], false, out);
+        out.push(subtree_root(&leaves[..k]));
+    }
+}
+
+/// Append-only log of capsule leaves.
+#[derive(Debug, Clone, Default)]
+pub struct MerkleLog {
+    leaves: Vec<Vec<u8>>,
+    hashes: Vec<[u8; 32]>,
+}
+
+impl MerkleLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `payload` and returns its leaf index.
+    pub fn append(&mut self, payload: &[u8]) -> u64 {
+        self.hashes.push(etl_leaf_hash(payload));
+        self.leaves.push(payload.to_vec());
+        self.hashes.len() as u64 - 1
+    }
+
+    pub fn size(&self) -> u64 {
+        self.hashes.len() as u64
+    }
+
+    pub fn leaf(&self, index: u64) -> Option<&[u8]> {
+        self.leaves.get(index as usize).map(Vec::as_slice)
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        subtree_root(&self.hashes)
+    }
+
+    /// Root of the tree as it was at `tree_size`.
+    pub fn root_at(&self, tree_size: u64) -> Option<[u8; 32]> {
+        self.prefix(tree_size).map(subtree_root)
+    }
+
+    pub fn inclusion_proof(&self, index: u64, tree_size: u64) -> Option<InclusionProof> {
+        let leaves = self.prefix(tree_size)?;
+        let leaf_hash = *leaves.get(index as usize)?;
+        let mut path = Vec::new();
+        audit_path(index as usize, leaves, &mut path);
+        Some(InclusionProof {
+            leaf_hash,
+            leaf_index: index,
+            tree_size,
+            audit_path: path,
+        })
+    }
+
+    /// Proof that the tree at `old_size` is a prefix of the tree at
+    /// `new_size`. When `old_size` is a power of two the old root is
+    /// prepended as the starting node, which is the form the verifier
+    /// expects.
+    pub fn consistency_proof(&self, old_size: u64, new_size: u64) -> Option<ConsistencyProof> {
+        if old_size > new_size {
+            return None;
+        }
+        let leaves = self.prefix(new_size)?;
+        let mut path = Vec::new();
+        if old_size > 0 && old_size < new_size {
+            if old_size.is_power_of_two() {
+                path.push(subtree_root(&leaves[..old_size as usize]));
+            }
+            subproof(old_size as usize, leaves, true, &mut path);
+        }
+        Some(ConsistencyProof {
+            old_tree_size: old_size,
+            new_tree_size: new_size,
+            path,
+        })
+    }
+
+    fn prefix(&self, tree_size: u64) -> Option<&[[u8; 32]]> {
+        self.hashes.get(..usize::try_from(tree_size).ok()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use evidenceos_verifier::{verify_consistency_proof, verify_inclusion_proof};
+
+    fn log_of(n: u64) -> MerkleLog {
+        let mut log = MerkleLog::new();
+        for i in 0..n {
+            log.append(format!("capsule-{i}").as_bytes());
+        }
+        log
+    }
+
+    #[test]
+    fn inclusion_proofs_verify_for_every_leaf_and_size() {
+        let log = log_of(13);
+        for size in 1..=13 {
+            let root = log.root_at(size).expect("root");
+            for index in 0..size {
+                let proof = log.inclusion_proof(index, size).expect("proof");
+                assert!(verify_inclusion_proof(root, &proof), "{index}/{size}");
+            }
+        }
+    }
+
+    #[test]
+    fn consistency_proofs_verify_between_all_sizes() {
+        let log = log_of(13);
+        for new in 1..=13 {
+            let new_root = log.root_at(new).expect("new root");
+            for old in 1..=new {
+                let old_root = log.root_at(old).expect("old root");
+                let proof = log.consistency_proof(old, new).expect("proof");
+                assert!(
+                    verify_consistency_proof(old_root, new_root, &proof),
+                    "{old}->{new}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn out_of_range_requests_yield_none() {
+        let log = log_of(3);
+        assert!(log.inclusion_proof(3, 3).is_none());
+        assert!(log.inclusion_proof(0, 4).is_none());
+        assert!(log.consistency_proof(2, 4).is_none());
+        assert!(log.consistency_proof(3, 2).is_none());
+    }
+}
@@ -0,0 +1,225 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, Instant};
+
+use discos_client::{
+    consistency_proof_from_pb, pb, signed_tree_head_from_pb, verify_consistency_proof,
+    verify_sth_signature, ClientError, DiscosClient, RevocationSet, RevocationWatcher,
+    SignedTreeHead, VerifiedCapsule,
+};
+use discos_testkit::{
+    run_sample_claim, sample_create_request, ClaimStage, Fault, MockDaemon, MockDaemonConfig, Rpc,
+};
+use tonic::{Code, Status};
+
+async fn start(config: MockDaemonConfig) -> (MockDaemon, DiscosClient) {
+    let daemon = MockDaemon::new(config).expect("daemon");
+    let endpoint = daemon.spawn().await.expect("spawn daemon");
+    let client = DiscosClient::connect(&endpoint).await.expect("connect");
+    (daemon, client)
+}
+
+fn capsule_sth(capsule: &VerifiedCapsule) -> SignedTreeHead {
+    signed_tree_head_from_pb(capsule.capsule.signed_tree_head.as_ref().expect("sth")).expect("sth")
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn lifecycle_capsules_verify_and_extend_the_log() {
+    let (daemon, mut client) = start(MockDaemonConfig::default()).await;
+    let pubkey = daemon.kernel_pubkey();
+
+    let first = run_sample_claim(&mut client, sample_create_request("alpha"), &pubkey, None)
+        .await
+        .expect("first claim");
+    let first_sth = capsule_sth(&first);
+    let second = run_sample_claim(
+        &mut client,
+        sample_create_request("beta"),
+        &pubkey,
+        Some(&first_sth),
+    )
+    .await
+    .expect("second claim");
+    daemon.append_leaf(b"unrelated");
+
+    assert_eq!(daemon.tree_size(), 3);
+    assert_eq!(second.capsule.etl_index, 1);
+    assert_eq!(
+        daemon.claim_stage(&second.claim_id),
+        Some(ClaimStage::Executed)
+    );
+    assert_eq!(daemon.k_bits_remaining(&first.topic_id), Some(60.0));
+
+    let head = client
+        .get_signed_tree_head(pb::GetSignedTreeHeadRequest {})
+        .await
+        .expect("sth")
+        .signed_tree_head
+        .expect("sth");
+    let head = signed_tree_head_from_pb(&head).expect("sth");
+    verify_sth_signature(&head, &pubkey).expect("kernel signature");
+    let proof = client
+        .get_consistency_proof(pb::GetConsistencyProofRequest {
+            old_tree_size: first_sth.tree_size,
+            new_tree_size: head.tree_size,
+        })
+        .await
+        .expect("consistency")
+        .consistency_proof
+        .expect("consistency");
+    assert!(verify_consistency_proof(
+        first_sth.root_hash,
+        head.root_hash,
+        &consistency_proof_from_pb(&proof).expect("proof"),
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn out_of_order_lifecycle_calls_fail_precondition() {
+    let (_daemon, mut client) = start(MockDaemonConfig::default()).await;
+    let created = client
+        .create_claim_v2(sample_create_request("alpha"))
+        .await
+        .expect("create");
+    let err = client
+        .execute_claim_v2(pb::ExecuteClaimV2Request {
+            claim_id: created.claim_id,
+        })
+        .await
+        .expect_err("execute before freeze");
+    assert_eq!(err.kernel().map(|k| k.code), Some(Code::FailedPrecondition));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn topic_budget_exhaustion_is_resource_exhausted() {
+    let (daemon, mut client) = start(MockDaemonConfig {
+        topic_budget_bits: 3.0,
+        ..MockDaemonConfig::default()
+    })
+    .await;
+    let pubkey = daemon.kernel_pubkey();
+
+    let first = run_sample_claim(&mut client, sample_create_request("alpha"), &pubkey, None)
+        .await
+        .expect("within budget");
+    let err = run_sample_claim(&mut client, sample_create_request("beta"), &pubkey, None)
+        .await
+        .expect_err("budget exhausted");
+    let kernel = err.kernel().expect("kernel error");
+    assert_eq!(kernel.code, Code::ResourceExhausted);
+    let payload: serde_json::Value = serde_json::from_str(&kernel.message).expect("json payload");
+    assert_eq!(payload["state"], "FROZEN");
+    assert_eq!(payload["topic_id_hex"], hex::encode(&first.topic_id));
+    assert_eq!(daemon.k_bits_remaining(&first.topic_id), Some(1.0));
+    assert_eq!(daemon.tree_size(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn scripted_status_and_delay_faults() {
+    let (daemon, mut client) = start(MockDaemonConfig::default()).await;
+    daemon.fail_next(
+        Rpc::Health,
+        Fault::Status(Status::unavailable("restarting")),
+    );
+    daemon.fail_always(Rpc::Health, Fault::Delay(Duration::from_millis(50)));
+
+    let started = Instant::now();
+    let err = client.health().await.expect_err("scripted failure");
+    assert_eq!(err.kernel().map(|k| k.code), Some(Code::Unavailable));
+    client.health().await.expect("one-shot fault consumed");
+    assert!(started.elapsed() >= Duration::from_millis(100));
+    assert_eq!(daemon.calls(Rpc::Health), 2);
+
+    daemon.clear_faults(Rpc::Health);
+    client.health().await.expect("faults cleared");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn forged_proofs_and_swapped_keys_are_detected() {
+    let (daemon, mut client) = start(MockDaemonConfig::default()).await;
+    let pubkey = daemon.kernel_pubkey();
+
+    daemon.fail_next(Rpc::FetchCapsule, Fault::ForgeProof);
+    let err = run_sample_claim(&mut client, sample_create_request("alpha"), &pubkey, None)
+        .await
+        .expect_err("forged inclusion proof");
+    assert!(matches!(err, ClientError::VerificationFailed(_)), "{err:?}");
+
+    daemon.fail_next(Rpc::FetchCapsule, Fault::SwapKey);
+    let err = run_sample_claim(&mut client, sample_create_request("beta"), &pubkey, None)
+        .await
+        .expect_err("swapped signing key");
+    assert!(matches!(err, ClientError::VerificationFailed(_)), "{err:?}");
+
+    daemon.fail_next(Rpc::GetPublicKey, Fault::SwapKey);
+    let served = client.get_public_key().await.expect("public key");
+    assert_eq!(served.pubkey, daemon.rogue_pubkey().to_vec());
+
+    daemon.fail_next(Rpc::GetSignedTreeHead, Fault::ForgeProof);
+    let forked = client
+        .get_signed_tree_head(pb::GetSignedTreeHeadRequest {})
+        .await
+        .expect("sth")
+        .signed_tree_head
+        .expect("sth");
+    let forked = signed_tree_head_from_pb(&forked).expect("sth");
+    verify_sth_signature(&forked, &pubkey).expect("forked head is still signed");
+    assert_ne!(forked.root_hash, daemon.root());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn revocations_are_signed_and_streamed() {
+    let (daemon, mut client) = start(MockDaemonConfig::default()).await;
+    let pubkey = daemon.kernel_pubkey();
+    let first = run_sample_claim(&mut client, sample_create_request("alpha"), &pubkey, None)
+        .await
+        .expect("first claim");
+    let second = run_sample_claim(&mut client, sample_create_request("beta"), &pubkey, None)
+        .await
+        .expect("second claim");
+
+    client
+        .revoke_claim(pb::RevokeClaimRequest {
+            claim_id: first.claim_id.clone(),
+            reason: "withdrawn".to_string(),
+        })
+        .await
+        .expect("revoke");
+    let mut watcher = RevocationWatcher::new(client.clone(), &pubkey, RevocationSet::default())
+        .expect("watcher")
+        .with_reconnect(1, Duration::from_millis(10));
+    let replayed = watcher.next().await.expect("replayed revocation");
+    assert_eq!(replayed.claim_id_hex, hex::encode(&first.claim_id));
+
+    daemon
+        .revoke(&second.claim_id, "policy_violation")
+        .expect("revoke out of band");
+    let live = watcher.next().await.expect("live revocation");
+    assert_eq!(live.claim_id_hex, hex::encode(&second.claim_id));
+    assert_eq!(live.logical_epoch, 2);
+    assert_eq!(
+        daemon.claim_stage(&second.claim_id),
+        Some(ClaimStage::Revoked)
+    );
+
+    daemon.fail_next(Rpc::WatchRevocations, Fault::SwapKey);
+    let mut forged =
+        RevocationWatcher::new(client, &pubkey, RevocationSet::default()).expect("watcher");
+    let err = forged.next().await.expect_err("rogue-signed backlog");
+    assert!(
+        matches!(&err, ClientError::VerificationFailed(msg) if msg.contains("forged")),
+        "{err:?}"
+    );
+}
@@ -13,14 +13,29 @@
 // limitations under the License.
 
 use sha2::{Digest, Sha256};
-use std::{fs, path::Path};
+use std::path::Path;
+
+mod store;
+mod tree;
+
+use store::SegmentStore;
+pub use store::{EtlOptions, FsyncPolicy};
+use tree::MerkleTree;
+pub use tree::TreeMode;
 
 pub type InclusionProof = evidenceos_verifier::InclusionProof;
+pub type ConsistencyProof = evidenceos_verifier::ConsistencyProof;
 
+/// Append-only transparency log persisted as segment files in a directory.
+///
+/// Reopening a directory replays its segments, so the tree (and its root)
+/// is exactly the one that was last durably appended. Roots and proofs follow
+/// the log's [`TreeMode`], RFC 9162 unless another mode is selected when the
+/// log is created.
 #[derive(Debug)]
 pub struct Etl {
-    leaves: Vec<[u8; 32]>,
-    _dir: std::path::PathBuf,
+    tree: MerkleTree,
+    store: SegmentStore,
 }
 
 fn sha256(input: &[u8]) -> [u8; 32] {
@@ -36,112 +51,409 @@ fn merkle_leaf_hash(payload: &[u8]) -> [u8; 32] {
     sha256(&material)
 }
 
-fn merkle_node_hash(left: [u8; 32], right: [u8; 32]) -> [u8; 32] {
-    let mut material = Vec::with_capacity(65);
-    material.push(0x01);
-    material.extend_from_slice(&left);
-    material.extend_from_slice(&right);
-    sha256(&material)
-}
-
 impl Etl {
+    /// Opens the log in `dir` with [`EtlOptions::default`], creating it if
+    /// needed.
     pub fn new<P: AsRef<Path>>(dir: P) -> Result<Self, String> {
-        fs::create_dir_all(dir.as_ref()).map_err(|e| format!("create etl dir: {e}"))?;
-        Ok(Self {
-            leaves: Vec::new(),
-            _dir: dir.as_ref().to_path_buf(),
-        })
+        Self::open(dir, EtlOptions::default())
+    }
+
+    pub fn open<P: AsRef<Path>>(dir: P, options: EtlOptions) -> Result<Self, String> {
+        let mut tree = MerkleTree::new(options.mode);
+        let store = SegmentStore::open(dir.as_ref(), options, |payload| {
+            tree.push(merkle_leaf_hash(payload))
+        })?;
+        Ok(Self { tree, store })
+    }
+
+    pub fn mode(&self) -> TreeMode {
+        self.tree.mode()
+    }
+
+    pub fn len(&self) -> u64 {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.len() == 0
     }
 
     pub fn append(&mut self, payload: &[u8]) -> Result<(u64, InclusionProof), String> {
         let leaf_hash = merkle_leaf_hash(payload);
-        self.leaves.push(leaf_hash);
-        let leaf_index = (self.leaves.len() - 1) as u64;
+        self.store.append(payload)?;
+        self.tree.push(leaf_hash);
+        let leaf_index = self.tree.len() - 1;
         let proof = self.inclusion_proof(leaf_index)?;
         Ok((leaf_index, proof))
     }
 
+    /// Flushes appends not yet synced under the configured [`FsyncPolicy`].
+    pub fn sync(&mut self) -> Result<(), String> {
+        self.store.sync()
+    }
+
     pub fn root(&self) -> Option<[u8; 32]> {
-        if self.leaves.is_empty() {
-            None
-        } else {
-            Some(compute_root(&self.leaves))
-        }
+        self.tree.root_at(self.tree.len())
+    }
+
+    /// Root the log had when it held `tree_size` entries.
+    pub fn root_at(&self, tree_size: u64) -> Option<[u8; 32]> {
+        self.tree.root_at(tree_size)
     }
 
     pub fn inclusion_proof(&self, leaf_index: u64) -> Result<InclusionProof, String> {
-        if self.leaves.is_empty() {
+        let tree_size = self.tree.len();
+        if tree_size == 0 {
             return Err("empty tree".into());
         }
-        if leaf_index as usize >= self.leaves.len() {
-            return Err("leaf index out of range".into());
-        }
-        let audit_path = compute_audit_path(&self.leaves, leaf_index as usize);
+        let leaf_hash = self
+            .tree
+            .leaf(leaf_index)
+            .ok_or_else(|| "leaf index out of range".to_string())?;
         Ok(InclusionProof {
-            leaf_hash: self.leaves[leaf_index as usize],
+            leaf_hash,
             leaf_index,
-            tree_size: self.leaves.len() as u64,
-            audit_path,
+            tree_size,
+            audit_path: self.tree.audit_path(leaf_index, tree_size),
         })
     }
-}
 
-fn compute_root(leaves: &[[u8; 32]]) -> [u8; 32] {
-    let mut layer = leaves.to_vec();
-    while layer.len() > 1 {
-        let mut next = Vec::with_capacity(layer.len().div_ceil(2));
-        let mut i = 0;
-        while i < layer.len() {
-            let left = layer[i];
-            let right = if i + 1 < layer.len() {
-                layer[i + 1]
-            } else {
-                layer[i]
-            };
-            next.push(merkle_node_hash(left, right));
-            i += 2;
+    /// Proof that the log at `old_size` is a prefix of the log at
+    /// `new_size`, checked with [`TreeMode::verify_consistency`].
+    pub fn consistency_proof(
+        &self,
+        old_size: u64,
+        new_size: u64,
+    ) -> Result<ConsistencyProof, String> {
+        if old_size > new_size {
+            return Err("old tree size exceeds new tree size".into());
+        }
+        if new_size > self.tree.len() {
+            return Err("tree size out of range".into());
         }
-        layer = next;
+        Ok(ConsistencyProof {
+            old_tree_size: old_size,
+            new_tree_size: new_size,
+            path: self.tree.consistency_path(old_size, new_size),
+        })
     }
-    layer[0]
 }
 
-fn compute_audit_path(leaves: &[[u8; 32]], mut idx: usize) -> Vec<[u8; 32]> {
-    let mut layer = leaves.to_vec();
-    let mut path = Vec::new();
+/// Checks an inclusion proof from an RFC 9162 log.
+pub fn verify_inclusion_proof_ct(root: [u8; 32], proof: &InclusionProof) -> bool {
+    evidenceos_verifier::verify_inclusion_proof(root, proof)
+}
 
-    while layer.len() > 1 {
-        let sibling = if idx.is_multiple_of(2) {
-            if idx + 1 < layer.len() {
-                layer[idx + 1]
-            } else {
-                layer[idx]
-            }
-        } else {
-            layer[idx - 1]
+/// Checks a consistency proof from an RFC 9162 log.
+pub fn verify_consistency_proof_ct(
+    old_root: [u8; 32],
+    new_root: [u8; 32],
+    proof: &ConsistencyProof,
+) -> bool {
+    evidenceos_verifier::verify_consistency_proof(old_root, new_root, proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    fn segments(dir: &Path) -> Vec<PathBuf> {
+        let mut paths: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "seg"))
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    fn append_all(etl: &mut Etl, count: usize) {
+        for i in 0..count {
+            etl.append(format!("entry-{i}").as_bytes()).unwrap();
+        }
+    }
+
+    #[test]
+    fn reopen_restores_the_same_root() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = {
+            let mut etl = Etl::new(temp.path()).unwrap();
+            append_all(&mut etl, 7);
+            etl.root()
+        };
+
+        let mut etl = Etl::new(temp.path()).unwrap();
+        assert_eq!(etl.len(), 7);
+        assert_eq!(etl.root(), root);
+
+        let (index, proof) = etl.append(b"entry-7").unwrap();
+        assert_eq!(index, 7);
+        assert!(verify_inclusion_proof_ct(etl.root().unwrap(), &proof));
+    }
+
+    #[test]
+    fn torn_tail_is_truncated_on_open() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = {
+            let mut etl = Etl::new(temp.path()).unwrap();
+            append_all(&mut etl, 3);
+            etl.root()
+        };
+        let segment = segments(temp.path()).pop().unwrap();
+        let intact_len = fs::metadata(&segment).unwrap().len();
+        // Header of a 32-byte record followed by only part of its payload.
+        let record = store::encode_record(&[0xAB; 32]).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&segment).unwrap();
+        file.write_all(&record[..store::RECORD_HEADER_LEN + 5])
+            .unwrap();
+        drop(file);
+
+        let mut etl = Etl::new(temp.path()).unwrap();
+        assert_eq!(etl.len(), 3);
+        assert_eq!(etl.root(), root);
+        assert_eq!(fs::metadata(&segment).unwrap().len(), intact_len);
+
+        etl.append(b"entry-3").unwrap();
+        let root = etl.root();
+        drop(etl);
+        assert_eq!(Etl::new(temp.path()).unwrap().root(), root);
+    }
+
+    #[test]
+    fn record_failing_its_checksum_is_dropped() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = {
+            let mut etl = Etl::new(temp.path()).unwrap();
+            append_all(&mut etl, 2);
+            let root = etl.root();
+            etl.append(b"entry-2").unwrap();
+            root
         };
-        path.push(sibling);
-
-        let mut next = Vec::with_capacity(layer.len().div_ceil(2));
-        let mut i = 0;
-        while i < layer.len() {
-            let left = layer[i];
-            let right = if i + 1 < layer.len() {
-                layer[i + 1]
-            } else {
-                layer[i]
-            };
-            next.push(merkle_node_hash(left, right));
-            i += 2;
+        let segment = segments(temp.path()).pop().unwrap();
+        let mut bytes = fs::read(&segment).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0x01;
+        fs::write(&segment, bytes).unwrap();
+
+        let etl = Etl::new(temp.path()).unwrap();
+        assert_eq!(etl.len(), 2);
+        assert_eq!(etl.root(), root);
+    }
+
+    #[test]
+    fn damaged_record_with_data_after_it_is_corruption() {
+        let temp = tempfile::tempdir().unwrap();
+        {
+            let mut etl = Etl::new(temp.path()).unwrap();
+            append_all(&mut etl, 3);
         }
+        let segment = segments(temp.path()).pop().unwrap();
+        let intact = fs::read(&segment).unwrap();
+        // Last payload byte of the middle record ("entry-1").
+        let mut bytes = intact.clone();
+        let offset =
+            store::SEGMENT_MAGIC.len() + 2 * (store::RECORD_HEADER_LEN + b"entry-0".len()) - 1;
+        bytes[offset] ^= 0x01;
+        fs::write(&segment, &bytes).unwrap();
+
+        let err = Etl::new(temp.path()).unwrap_err();
+        assert!(err.contains("corrupt"), "{err}");
+        assert_eq!(fs::read(&segment).unwrap(), bytes);
 
-        idx /= 2;
-        layer = next;
+        // A damaged header is not a torn write either.
+        let mut bytes = intact;
+        bytes[0] ^= 0x01;
+        fs::write(&segment, &bytes).unwrap();
+        let err = Etl::new(temp.path()).unwrap_err();
+        assert!(err.contains("corrupt"), "{err}");
+        assert_eq!(fs::read(&segment).unwrap(), bytes);
     }
 
-    path
-}
+    #[test]
+    fn damaged_length_in_a_middle_record_is_corruption() {
+        let temp = tempfile::tempdir().unwrap();
+        {
+            let mut etl = Etl::new(temp.path()).unwrap();
+            append_all(&mut etl, 10);
+        }
+        let segment = segments(temp.path()).pop().unwrap();
+        // Point record 1's length far past the end of the file.
+        let mut bytes = fs::read(&segment).unwrap();
+        let offset = store::SEGMENT_MAGIC.len() + store::RECORD_HEADER_LEN + b"entry-0".len();
+        bytes[offset] ^= 0x01;
+        fs::write(&segment, &bytes).unwrap();
 
-pub fn verify_inclusion_proof_ct(root: [u8; 32], proof: &InclusionProof) -> bool {
-    evidenceos_verifier::verify_inclusion_proof(root, proof)
+        let err = Etl::new(temp.path()).unwrap_err();
+        assert!(err.contains("corrupt"), "{err}");
+        assert_eq!(fs::read(&segment).unwrap(), bytes);
+    }
+
+    #[test]
+    fn torn_header_at_the_end_is_truncated() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = {
+            let mut etl = Etl::new(temp.path()).unwrap();
+            append_all(&mut etl, 2);
+            etl.root()
+        };
+        let segment = segments(temp.path()).pop().unwrap();
+        let intact_len = fs::metadata(&segment).unwrap().len();
+        let mut record = store::encode_record(b"entry-2").unwrap();
+        record.truncate(store::RECORD_HEADER_LEN);
+        record[5] ^= 0x01;
+        let mut file = OpenOptions::new().append(true).open(&segment).unwrap();
+        file.write_all(&record).unwrap();
+        drop(file);
+
+        let etl = Etl::new(temp.path()).unwrap();
+        assert_eq!(etl.len(), 2);
+        assert_eq!(etl.root(), root);
+        assert_eq!(fs::metadata(&segment).unwrap().len(), intact_len);
+    }
+
+    #[test]
+    fn segments_roll_over_and_replay_in_order() {
+        let temp = tempfile::tempdir().unwrap();
+        let options = EtlOptions {
+            segment_max_bytes: 64,
+            fsync: FsyncPolicy::Every(4),
+            ..EtlOptions::default()
+        };
+        let root = {
+            let mut etl = Etl::open(temp.path(), options).unwrap();
+            append_all(&mut etl, 10);
+            etl.root()
+        };
+        let paths = segments(temp.path());
+        assert!(paths.len() > 1);
+
+        let etl = Etl::open(temp.path(), options).unwrap();
+        assert_eq!(etl.len(), 10);
+        assert_eq!(etl.root(), root);
+        drop(etl);
+
+        // Damage outside the newest segment cannot be a torn write.
+        let mut bytes = fs::read(&paths[0]).unwrap();
+        bytes.truncate(bytes.len() - 1);
+        fs::write(&paths[0], bytes).unwrap();
+        let err = Etl::open(temp.path(), options).unwrap_err();
+        assert!(err.contains("corrupt"), "{err}");
+    }
+
+    #[test]
+    fn inclusion_proofs_verify_for_every_leaf_and_size() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut etl = Etl::new(temp.path()).unwrap();
+        for size in 1..=13 {
+            etl.append(format!("entry-{size}").as_bytes()).unwrap();
+            let root = etl.root().unwrap();
+            for index in 0..size {
+                let proof = etl.inclusion_proof(index).unwrap();
+                assert!(verify_inclusion_proof_ct(root, &proof), "{index}/{size}");
+            }
+        }
+    }
+
+    #[test]
+    fn consistency_proofs_verify_between_all_sizes() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut etl = Etl::new(temp.path()).unwrap();
+        append_all(&mut etl, 13);
+        for new in 1..=13 {
+            let new_root = etl.root_at(new).unwrap();
+            for old in 1..=new {
+                let old_root = etl.root_at(old).unwrap();
+                let proof = etl.consistency_proof(old, new).unwrap();
+                assert!(
+                    verify_consistency_proof_ct(old_root, new_root, &proof),
+                    "{old}->{new}"
+                );
+            }
+        }
+        assert!(etl.consistency_proof(4, 3).is_err());
+        assert!(etl.consistency_proof(3, 14).is_err());
+    }
+
+    #[test]
+    fn reopened_log_extends_its_previous_root() {
+        let temp = tempfile::tempdir().unwrap();
+        let old_root = {
+            let mut etl = Etl::new(temp.path()).unwrap();
+            append_all(&mut etl, 5);
+            etl.root().unwrap()
+        };
+        let mut etl = Etl::new(temp.path()).unwrap();
+        etl.append(b"entry-5").unwrap();
+        etl.append(b"entry-6").unwrap();
+        let proof = etl.consistency_proof(5, 7).unwrap();
+        assert!(verify_consistency_proof_ct(
+            old_root,
+            etl.root().unwrap(),
+            &proof
+        ));
+    }
+
+    fn duplicate_last_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+        let mut layer = leaves.to_vec();
+        while layer.len() > 1 {
+            layer = layer
+                .chunks(2)
+                .map(|pair| tree::node_hash(pair[0], *pair.last().unwrap()))
+                .collect();
+        }
+        layer[0]
+    }
+
+    #[test]
+    fn duplicate_last_mode_keeps_padded_roots_and_its_own_proofs() {
+        let temp = tempfile::tempdir().unwrap();
+        let options = EtlOptions {
+            mode: TreeMode::DuplicateLast,
+            ..EtlOptions::default()
+        };
+        let mut etl = Etl::open(temp.path(), options).unwrap();
+        let mut leaves = Vec::new();
+        for size in 1..=11u64 {
+            let payload = format!("entry-{size}");
+            leaves.push(merkle_leaf_hash(payload.as_bytes()));
+            etl.append(payload.as_bytes()).unwrap();
+            let root = etl.root().unwrap();
+            assert_eq!(root, duplicate_last_root(&leaves), "{size}");
+            for index in 0..size {
+                let proof = etl.inclusion_proof(index).unwrap();
+                assert!(TreeMode::DuplicateLast.verify_inclusion(root, &proof));
+            }
+        }
+        for new in 1..=11 {
+            for old in 1..=new {
+                let proof = etl.consistency_proof(old, new).unwrap();
+                assert!(
+                    TreeMode::DuplicateLast.verify_consistency(
+                        etl.root_at(old).unwrap(),
+                        etl.root_at(new).unwrap(),
+                        &proof
+                    ),
+                    "{old}->{new}"
+                );
+            }
+        }
+        drop(etl);
+
+        let err = Etl::new(temp.path()).unwrap_err();
+        assert!(err.contains("duplicate-last"), "{err}");
+        let etl = Etl::open(temp.path(), options).unwrap();
+        assert_eq!(etl.mode(), TreeMode::DuplicateLast);
+        assert_eq!(etl.root(), Some(duplicate_last_root(&leaves)));
+    }
+
+    #[test]
+    fn directory_has_a_single_writer() {
+        let temp = tempfile::tempdir().unwrap();
+        let _etl = Etl::new(temp.path()).unwrap();
+        let err = Etl::new(temp.path()).unwrap_err();
+        assert!(err.contains("in use"), "{err}");
+    }
 }
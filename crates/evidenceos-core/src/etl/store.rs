@@ -0,0 +1,382 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Segment files backing [`super::Etl`].
+//!
+//! A log directory holds segments named after the index of their first
+//! record (`00000000000000000000.seg`, ...). Each segment starts with
+//! [`SEGMENT_MAGIC`] and is followed by records of the form
+//!
+//! ```text
+//! len: u32 BE | len check: 4 bytes | checksum: 8 bytes | payload: len bytes
+//! ```
+//!
+//! where the len check is the first four bytes of a domain-separated SHA-256
+//! over `len`, and the checksum the first eight bytes of one over
+//! `len || payload`. The len check means a damaged length cannot pass for a
+//! record that runs off the end of the file. Only the newest segment is ever
+//! written, so there a bad record that ends the file is a torn write and is
+//! truncated on open: a short header, a header failing its len check with
+//! nothing after it, a verified header with a short payload, or a payload
+//! failing its checksum with nothing after it. Any other damage, or damage in
+//! an older segment, is corruption and refuses to open.
+//!
+//! The [`TreeMode`] a log was created with is kept in a `TREE_MODE` file, since
+//! the same records give different roots under the other mode.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::{sha256, TreeMode};
+
+pub(super) const SEGMENT_MAGIC: &[u8; 8] = b"EOSETL1\n";
+const SEGMENT_EXTENSION: &str = "seg";
+const LOCK_FILE: &str = "LOCK";
+const TREE_MODE_FILE: &str = "TREE_MODE";
+pub(super) const RECORD_HEADER_LEN: usize = 4 + 4 + 8;
+const DOMAIN_RECORD_LEN_CHECK: &[u8] = b"evidenceos/etl-record-len/v1";
+const DOMAIN_RECORD_CHECKSUM: &[u8] = b"evidenceos/etl-record/v1";
+
+/// When appended records are flushed to stable storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsyncPolicy {
+    /// `fdatasync` after every append; an acknowledged append survives a
+    /// crash.
+    Always,
+    /// `fdatasync` after every `n` appends; up to `n - 1` acknowledged
+    /// appends can be lost.
+    Every(u32),
+    /// Only on [`super::Etl::sync`], segment rollover and drop.
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EtlOptions {
+    /// A segment is closed once the next record would take it past this
+    /// size. A single larger record still gets a segment of its own.
+    pub segment_max_bytes: u64,
+    pub fsync: FsyncPolicy,
+    /// Only applies to a new log; an existing one must be opened with the
+    /// mode it was created with.
+    pub mode: TreeMode,
+}
+
+impl Default for EtlOptions {
+    fn default() -> Self {
+        Self {
+            segment_max_bytes: 64 * 1024 * 1024,
+            fsync: FsyncPolicy::Always,
+            mode: TreeMode::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct SegmentStore {
+    dir: PathBuf,
+    options: EtlOptions,
+    // Held for its lock; one writer per directory.
+    _lock: File,
+    active: File,
+    active_len: u64,
+    next_index: u64,
+    unsynced: u32,
+}
+
+impl SegmentStore {
+    /// Opens or creates the log in `dir`, passing every stored payload to
+    /// `on_record` in order.
+    pub(super) fn open(
+        dir: &Path,
+        options: EtlOptions,
+        mut on_record: impl FnMut(&[u8]),
+    ) -> Result<Self, String> {
+        fs::create_dir_all(dir).map_err(|e| format!("create etl dir: {e}"))?;
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(dir.join(LOCK_FILE))
+            .map_err(|e| format!("open etl lock: {e}"))?;
+        lock.try_lock()
+            .map_err(|e| format!("etl dir {} is in use: {e}", dir.display()))?;
+
+        check_tree_mode(dir, options.mode)?;
+
+        let segments = list_segments(dir)?;
+        let mut next_index = 0u64;
+        let mut last = None;
+        for (i, (start, path)) in segments.iter().enumerate() {
+            if *start != next_index {
+                return Err(format!(
+                    "etl segment {} starts at record {start}, expected {next_index}",
+                    path.display()
+                ));
+            }
+            let bytes = fs::read(path).map_err(|e| format!("read etl segment: {e}"))?;
+            let scan = scan_segment(&bytes, &mut on_record);
+            next_index += scan.records;
+            let is_last = i + 1 == segments.len();
+            if scan.valid_len != bytes.len() as u64 && !(is_last && scan.torn_tail) {
+                return Err(format!(
+                    "etl segment {} is corrupt at byte {}",
+                    path.display(),
+                    scan.valid_len
+                ));
+            }
+            if is_last {
+                last = Some((path.clone(), scan.valid_len, bytes.len() as u64));
+            }
+        }
+
+        let (active, active_len) = match last {
+            Some((path, valid_len, file_len)) => {
+                let file = OpenOptions::new()
+                    .append(true)
+                    .open(&path)
+                    .map_err(|e| format!("open etl segment: {e}"))?;
+                let mut active_len = valid_len;
+                if valid_len != file_len {
+                    // Torn tail from a crash mid-append.
+                    file.set_len(valid_len)
+                        .map_err(|e| format!("truncate torn etl tail: {e}"))?;
+                    if valid_len == 0 {
+                        (&file)
+                            .write_all(SEGMENT_MAGIC)
+                            .map_err(|e| format!("write etl segment header: {e}"))?;
+                        active_len = SEGMENT_MAGIC.len() as u64;
+                    }
+                    file.sync_data()
+                        .map_err(|e| format!("sync etl segment: {e}"))?;
+                }
+                (file, active_len)
+            }
+            None => (create_segment(dir, 0)?, SEGMENT_MAGIC.len() as u64),
+        };
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            options,
+            _lock: lock,
+            active,
+            active_len,
+            next_index,
+            unsynced: 0,
+        })
+    }
+
+    /// Writes one record. On error the record is not left in the segment,
+    /// so the caller's tree and the files stay the same length.
+    pub(super) fn append(&mut self, payload: &[u8]) -> Result<(), String> {
+        let record = encode_record(payload)?;
+
+        let empty = self.active_len == SEGMENT_MAGIC.len() as u64;
+        if !empty && self.active_len + record.len() as u64 > self.options.segment_max_bytes {
+            self.roll()?;
+        }
+        let len_before = self.active_len;
+        if let Err(err) = self.active.write_all(&record) {
+            // Drop whatever part of the record reached the file.
+            let _ = self.active.set_len(len_before);
+            return Err(format!("append etl record: {err}"));
+        }
+        self.active_len += record.len() as u64;
+        self.next_index += 1;
+        self.unsynced += 1;
+        let synced = match self.options.fsync {
+            FsyncPolicy::Always => self.sync(),
+            FsyncPolicy::Every(n) if self.unsynced >= n.max(1) => self.sync(),
+            FsyncPolicy::Every(_) | FsyncPolicy::Never => Ok(()),
+        };
+        if synced.is_err() {
+            // The caller will not add this record to the tree, so it must
+            // not survive on disk either. Earlier unsynced records stay:
+            // they are already in the tree.
+            let _ = self.active.set_len(len_before);
+            self.active_len = len_before;
+            self.next_index -= 1;
+            self.unsynced -= 1;
+        }
+        synced
+    }
+
+    pub(super) fn sync(&mut self) -> Result<(), String> {
+        if self.unsynced > 0 {
+            self.active
+                .sync_data()
+                .map_err(|e| format!("sync etl segment: {e}"))?;
+            self.unsynced = 0;
+        }
+        Ok(())
+    }
+
+    fn roll(&mut self) -> Result<(), String> {
+        self.sync()?;
+        self.active = create_segment(&self.dir, self.next_index)?;
+        self.active_len = SEGMENT_MAGIC.len() as u64;
+        Ok(())
+    }
+}
+
+impl Drop for SegmentStore {
+    fn drop(&mut self) {
+        let _ = self.sync();
+    }
+}
+
+fn check_tree_mode(dir: &Path, mode: TreeMode) -> Result<(), String> {
+    let path = dir.join(TREE_MODE_FILE);
+    match fs::read_to_string(&path) {
+        Ok(stored) => {
+            let stored = stored.trim();
+            if stored != mode.as_str() {
+                return Err(format!(
+                    "etl dir {} holds a {stored} tree, not {}",
+                    dir.display(),
+                    mode.as_str()
+                ));
+            }
+            Ok(())
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            let mut file = File::create(&path).map_err(|e| format!("write etl tree mode: {e}"))?;
+            writeln!(file, "{}", mode.as_str())
+                .and_then(|()| file.sync_all())
+                .map_err(|e| format!("write etl tree mode: {e}"))
+        }
+        Err(err) => Err(format!("read etl tree mode: {err}")),
+    }
+}
+
+fn segment_path(dir: &Path, start: u64) -> PathBuf {
+    dir.join(format!("{start:020}.{SEGMENT_EXTENSION}"))
+}
+
+fn create_segment(dir: &Path, start: u64) -> Result<File, String> {
+    let mut file = OpenOptions::new()
+        .create_new(true)
+        .append(true)
+        .open(segment_path(dir, start))
+        .map_err(|e| format!("create etl segment: {e}"))?;
+    file.write_all(SEGMENT_MAGIC)
+        .and_then(|()| file.sync_data())
+        .map_err(|e| format!("write etl segment header: {e}"))?;
+    // Make the new directory entry durable too.
+    File::open(dir)
+        .and_then(|dir| dir.sync_all())
+        .map_err(|e| format!("sync etl dir: {e}"))?;
+    Ok(file)
+}
+
+fn list_segments(dir: &Path) -> Result<Vec<(u64, PathBuf)>, String> {
+    let mut segments = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|e| format!("list etl dir: {e}"))? {
+        let path = entry.map_err(|e| format!("list etl dir: {e}"))?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some(SEGMENT_EXTENSION) {
+            continue;
+        }
+        let start = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse::<u64>().ok())
+            .ok_or_else(|| format!("unexpected etl segment name {}", path.display()))?;
+        segments.push((start, path));
+    }
+    segments.sort();
+    Ok(segments)
+}
+
+struct SegmentScan {
+    records: u64,
+    /// Length of the longest prefix made of the header and whole, intact
+    /// records; 0 if the header itself is damaged.
+    valid_len: u64,
+    /// Whether everything past `valid_len` is one record (or header) cut
+    /// short by a crash, rather than damage with more data after it.
+    torn_tail: bool,
+}
+
+fn scan_segment(bytes: &[u8], on_record: &mut impl FnMut(&[u8])) -> SegmentScan {
+    if !bytes.starts_with(SEGMENT_MAGIC) {
+        return SegmentScan {
+            records: 0,
+            valid_len: 0,
+            torn_tail: SEGMENT_MAGIC.starts_with(bytes),
+        };
+    }
+    let mut pos = SEGMENT_MAGIC.len();
+    let mut records = 0;
+    let mut torn_tail = true;
+    while let Some(header) = bytes.get(pos..pos + RECORD_HEADER_LEN) {
+        let mut len = [0u8; 4];
+        len.copy_from_slice(&header[..4]);
+        let len = u32::from_be_bytes(len);
+        let start = pos + RECORD_HEADER_LEN;
+        if header[4..8] != record_len_check(len) {
+            torn_tail = start == bytes.len();
+            break;
+        }
+        // The length is verified, so a payload running past the end can only
+        // be an interrupted append.
+        let Some(payload) = bytes.get(start..start + len as usize) else {
+            break;
+        };
+        if header[8..] != record_checksum(len, payload) {
+            torn_tail = start + payload.len() == bytes.len();
+            break;
+        }
+        on_record(payload);
+        records += 1;
+        pos = start + payload.len();
+    }
+    SegmentScan {
+        records,
+        valid_len: pos as u64,
+        torn_tail,
+    }
+}
+
+/// A record as appended: header followed by `payload`.
+pub(super) fn encode_record(payload: &[u8]) -> Result<Vec<u8>, String> {
+    let len =
+        u32::try_from(payload.len()).map_err(|_| "etl record larger than 4 GiB".to_string())?;
+    let mut record = Vec::with_capacity(RECORD_HEADER_LEN + payload.len());
+    record.extend_from_slice(&len.to_be_bytes());
+    record.extend_from_slice(&record_len_check(len));
+    record.extend_from_slice(&record_checksum(len, payload));
+    record.extend_from_slice(payload);
+    Ok(record)
+}
+
+fn record_len_check(len: u32) -> [u8; 4] {
+    let mut material = Vec::with_capacity(DOMAIN_RECORD_LEN_CHECK.len() + 5);
+    material.extend_from_slice(DOMAIN_RECORD_LEN_CHECK);
+    material.push(0);
+    material.extend_from_slice(&len.to_be_bytes());
+    let mut check = [0u8; 4];
+    check.copy_from_slice(&sha256(&material)[..4]);
+    check
+}
+
+fn record_checksum(len: u32, payload: &[u8]) -> [u8; 8] {
+    let mut material = Vec::with_capacity(DOMAIN_RECORD_CHECKSUM.len() + 5 + payload.len());
+    material.extend_from_slice(DOMAIN_RECORD_CHECKSUM);
+    material.push(0);
+    material.extend_from_slice(&len.to_be_bytes());
+    material.extend_from_slice(payload);
+    let mut checksum = [0u8; 8];
+    checksum.copy_from_slice(&sha256(&material)[..8]);
+    checksum
+}
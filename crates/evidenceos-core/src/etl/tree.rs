@@ -0,0 +1,278 @@
+// Copyright 2026 Joseph Verdicchio
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Merkle tree behind [`super::Etl`].
+//!
+//! `levels[h][i]` caches the root of the complete subtree over leaves
+//! `[i << h, (i + 1) << h)`. Both tree modes agree on complete subtrees and
+//! differ only on the right edge, whose nodes are folded from at most one
+//! cached subtree per level. Append, roots and both kinds of proof are
+//! therefore O(log n).
+
+use super::sha256;
+
+/// Shape of a tree whose size is not a power of two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TreeMode {
+    /// RFC 9162: a tree of `n` leaves is split at the largest power of two
+    /// below `n`. This is what the kernel log, `evidenceos_verifier`'s
+    /// default checks and standard witnesses use.
+    #[default]
+    Rfc9162,
+    /// Every layer of odd length pairs its last node with itself, as the
+    /// local log did before it followed RFC 9162. Proofs are checked with the
+    /// `*_duplicate_last` functions of `evidenceos_verifier`.
+    DuplicateLast,
+}
+
+impl TreeMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Rfc9162 => "rfc9162",
+            Self::DuplicateLast => "duplicate-last",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "rfc9162" => Some(Self::Rfc9162),
+            "duplicate-last" => Some(Self::DuplicateLast),
+            _ => None,
+        }
+    }
+
+    pub fn verify_inclusion(self, root: [u8; 32], proof: &super::InclusionProof) -> bool {
+        match self {
+            Self::Rfc9162 => evidenceos_verifier::verify_inclusion_proof(root, proof),
+            Self::DuplicateLast => {
+                evidenceos_verifier::verify_inclusion_proof_duplicate_last(root, proof)
+            }
+        }
+    }
+
+    pub fn verify_consistency(
+        self,
+        old_root: [u8; 32],
+        new_root: [u8; 32],
+        proof: &super::ConsistencyProof,
+    ) -> bool {
+        match self {
+            Self::Rfc9162 => {
+                evidenceos_verifier::verify_consistency_proof(old_root, new_root, proof)
+            }
+            Self::DuplicateLast => evidenceos_verifier::verify_consistency_proof_duplicate_last(
+                old_root, new_root, proof,
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct MerkleTree {
+    mode: TreeMode,
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+pub(super) fn node_hash(left: [u8; 32], right: [u8; 32]) -> [u8; 32] {
+    let mut material = Vec::with_capacity(65);
+    material.push(0x01);
+    material.extend_from_slice(&left);
+    material.extend_from_slice(&right);
+    sha256(&material)
+}
+
+/// Largest power of two strictly below `n`; `n` must be at least 2.
+fn split(n: u64) -> u64 {
+    1 << (63 - (n - 1).leading_zeros())
+}
+
+/// Height of a duplicate-last tree over `n` leaves.
+fn padded_height(n: u64) -> usize {
+    (u64::BITS - n.saturating_sub(1).leading_zeros()) as usize
+}
+
+impl MerkleTree {
+    pub(super) fn new(mode: TreeMode) -> Self {
+        Self {
+            mode,
+            levels: Vec::new(),
+        }
+    }
+
+    pub(super) fn mode(&self) -> TreeMode {
+        self.mode
+    }
+
+    pub(super) fn len(&self) -> u64 {
+        self.levels.first().map_or(0, |leaves| leaves.len() as u64)
+    }
+
+    pub(super) fn leaf(&self, index: u64) -> Option<[u8; 32]> {
+        self.levels
+            .first()?
+            .get(usize::try_from(index).ok()?)
+            .copied()
+    }
+
+    pub(super) fn push(&mut self, leaf_hash: [u8; 32]) {
+        let mut node = leaf_hash;
+        let mut height = 0;
+        loop {
+            if self.levels.len() == height {
+                self.levels.push(Vec::new());
+            }
+            let level = &mut self.levels[height];
+            level.push(node);
+            if level.len() % 2 == 1 {
+                return;
+            }
+            node = node_hash(level[level.len() - 2], level[level.len() - 1]);
+            height += 1;
+        }
+    }
+
+    /// Root of the tree made of the first `tree_size` leaves.
+    pub(super) fn root_at(&self, tree_size: u64) -> Option<[u8; 32]> {
+        if tree_size == 0 || tree_size > self.len() {
+            return None;
+        }
+        Some(match self.mode {
+            TreeMode::Rfc9162 => self.range_hash(0, tree_size),
+            TreeMode::DuplicateLast => self.padded_node(padded_height(tree_size), 0, tree_size),
+        })
+    }
+
+    /// Audit path of a leaf within the first `tree_size` leaves.
+    pub(super) fn audit_path(&self, index: u64, tree_size: u64) -> Vec<[u8; 32]> {
+        let mut path = Vec::new();
+        match self.mode {
+            TreeMode::Rfc9162 => self.push_audit_path(index, 0, tree_size, &mut path),
+            TreeMode::DuplicateLast => self.push_padded_audit_path(index, tree_size, &mut path),
+        }
+        path
+    }
+
+    /// Consistency path from `old_size` to `new_size`, both within the
+    /// tree, in the form `TreeMode::verify_consistency` checks. For RFC 9162
+    /// trees the old root leads the path when `old_size` is a power of two.
+    pub(super) fn consistency_path(&self, old_size: u64, new_size: u64) -> Vec<[u8; 32]> {
+        let mut path = Vec::new();
+        if old_size == 0 || old_size == new_size {
+            return path;
+        }
+        match self.mode {
+            TreeMode::Rfc9162 => {
+                if old_size.is_power_of_two() {
+                    path.push(self.range_hash(0, old_size));
+                }
+                self.push_subproof(old_size, 0, new_size, true, &mut path);
+            }
+            TreeMode::DuplicateLast => {
+                let last = old_size - 1;
+                path.push(self.levels[0][last as usize]);
+                self.push_padded_audit_path(last, old_size, &mut path);
+                self.push_padded_audit_path(last, new_size, &mut path);
+            }
+        }
+        path
+    }
+
+    /// Hash of the tree node over `[start, end)`. Every node of an RFC 9162
+    /// tree starts at a multiple of the smallest power of two covering it,
+    /// so its left child is always a cached complete subtree.
+    fn range_hash(&self, start: u64, end: u64) -> [u8; 32] {
+        let len = end - start;
+        if len.is_power_of_two() {
+            let height = len.trailing_zeros() as usize;
+            return self.levels[height][(start >> height) as usize];
+        }
+        let k = split(len);
+        node_hash(
+            self.range_hash(start, start + k),
+            self.range_hash(start + k, end),
+        )
+    }
+
+    fn push_audit_path(&self, index: u64, start: u64, end: u64, out: &mut Vec<[u8; 32]>) {
+        if end - start <= 1 {
+            return;
+        }
+        let mid = start + split(end - start);
+        if index < mid {
+            self.push_audit_path(index, start, mid, out);
+            out.push(self.range_hash(mid, end));
+        } else {
+            self.push_audit_path(index, mid, end, out);
+            out.push(self.range_hash(start, mid));
+        }
+    }
+
+    fn push_subproof(
+        &self,
+        old_end: u64,
+        start: u64,
+        end: u64,
+        complete: bool,
+        out: &mut Vec<[u8; 32]>,
+    ) {
+        if old_end == end {
+            if !complete {
+                out.push(self.range_hash(start, end));
+            }
+            return;
+        }
+        let mid = start + split(end - start);
+        if old_end <= mid {
+            self.push_subproof(old_end, start, mid, complete, out);
+            out.push(self.range_hash(mid, end));
+        } else {
+            self.push_subproof(old_end, mid, end, false, out);
+            out.push(self.range_hash(start, mid));
+        }
+    }
+
+    /// Node `index` at `height` of the duplicate-last tree over `tree_size`
+    /// leaves; the node must cover at least one leaf.
+    fn padded_node(&self, height: usize, index: u64, tree_size: u64) -> [u8; 32] {
+        if (index + 1) << height <= tree_size {
+            return self.levels[height][index as usize];
+        }
+        let left = self.padded_node(height - 1, 2 * index, tree_size);
+        let right = if (2 * index + 1) << (height - 1) < tree_size {
+            self.padded_node(height - 1, 2 * index + 1, tree_size)
+        } else {
+            left
+        };
+        node_hash(left, right)
+    }
+
+    fn push_padded_audit_path(&self, index: u64, tree_size: u64, out: &mut Vec<[u8; 32]>) {
+        let mut node = self.levels[0][index as usize];
+        let mut index = index;
+        for height in 0..padded_height(tree_size) {
+            let sibling = if (index ^ 1) << height < tree_size {
+                self.padded_node(height, index ^ 1, tree_size)
+            } else {
+                node
+            };
+            out.push(sibling);
+            node = if index & 1 == 1 {
+                node_hash(sibling, node)
+            } else {
+                node_hash(node, sibling)
+            };
+            index >>= 1;
+        }
+    }
+}
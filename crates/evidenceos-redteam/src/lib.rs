@@ -1,7 +1,7 @@
 use std::time::Instant;
 
 use anyhow::Result;
-use discos_client::{pb, DiscosClient};
+use discos_client::{pb, DiscosClient, KernelError};
 use serde::Serialize;
 use sha2::{Digest, Sha256};
 use tonic::Code;
@@ -133,15 +133,14 @@ async fn create_claim(
             dp_delta_budget: None,
         })
         .await
-        .map_err(|e| {
-            let text = e.to_string();
-            if text.contains("UNSIGNED_NULLSPEC") {
+        .map_err(|e| match e.kernel().and_then(KernelError::reason) {
+            Some("UNSIGNED_NULLSPEC") => {
                 tonic::Status::new(Code::FailedPrecondition, "UNSIGNED_NULLSPEC")
-            } else if text.contains("INVALID_") || text.contains("UNKNOWN_CLAIM") {
+            }
+            Some(reason) if reason.starts_with("INVALID_") || reason == "UNKNOWN_CLAIM" => {
                 tonic::Status::new(Code::InvalidArgument, "INVALID_REQUEST")
-            } else {
-                tonic::Status::new(Code::Unknown, "UNKNOWN")
             }
+            _ => tonic::Status::new(Code::Unknown, "UNKNOWN"),
         })?;
     Ok(response.claim_id)
 }
@@ -80,6 +80,7 @@ fn merkle_node_hash(left: [u8; 32], right: [u8; 32]) -> [u8; 32] {
     sha256(&material)
 }
 
+/// Checks an inclusion proof in an RFC 9162 tree (section 2.1.3.2).
 pub fn verify_inclusion_proof(root: [u8; 32], proof: &InclusionProof) -> bool {
     if proof.tree_size == 0 || proof.leaf_index >= proof.tree_size {
         return false;
@@ -114,6 +115,8 @@ pub fn verify_inclusion_proof(root: [u8; 32], proof: &InclusionProof) -> bool {
     sn_idx == 0 && hash == root
 }
 
+/// Checks a consistency proof between RFC 9162 trees (section 2.1.4.2). When
+/// the old size is a power of two the old root must lead the path.
 pub fn verify_consistency_proof(
     old_root: [u8; 32],
     new_root: [u8; 32],
@@ -166,6 +169,96 @@ pub fn verify_consistency_proof(
     fr == old_root && sr == new_root
 }
 
+/// Height of a tree that pairs the last node of every odd layer with itself.
+fn duplicate_last_height(tree_size: u64) -> usize {
+    (u64::BITS - tree_size.saturating_sub(1).leading_zeros()) as usize
+}
+
+/// Folds `path` up from `leaf_hash` in a duplicate-last tree. A right sibling
+/// of the last node of a layer must be that node itself.
+fn duplicate_last_root(
+    leaf_hash: [u8; 32],
+    leaf_index: u64,
+    tree_size: u64,
+    path: &[[u8; 32]],
+) -> Option<[u8; 32]> {
+    if path.len() != duplicate_last_height(tree_size) {
+        return None;
+    }
+    let mut index = leaf_index;
+    let mut last = tree_size - 1;
+    let mut hash = leaf_hash;
+    for sibling in path {
+        if index & 1 == 1 {
+            hash = merkle_node_hash(*sibling, hash);
+        } else {
+            if index == last && *sibling != hash {
+                return None;
+            }
+            hash = merkle_node_hash(hash, *sibling);
+        }
+        index >>= 1;
+        last >>= 1;
+    }
+    Some(hash)
+}
+
+/// Checks an inclusion proof in a tree that pairs the last node of every odd
+/// layer with itself instead of following RFC 9162.
+pub fn verify_inclusion_proof_duplicate_last(root: [u8; 32], proof: &InclusionProof) -> bool {
+    if proof.tree_size == 0 || proof.leaf_index >= proof.tree_size {
+        return false;
+    }
+    duplicate_last_root(
+        proof.leaf_hash,
+        proof.leaf_index,
+        proof.tree_size,
+        &proof.audit_path,
+    ) == Some(root)
+}
+
+/// Checks a consistency proof between duplicate-last trees.
+///
+/// The path is the last leaf of the old tree followed by its audit paths in
+/// the old and in the new tree. Left siblings are complete subtrees, so they
+/// must agree between the two paths; every right sibling in the old tree is a
+/// duplicate of the node itself.
+pub fn verify_consistency_proof_duplicate_last(
+    old_root: [u8; 32],
+    new_root: [u8; 32],
+    proof: &ConsistencyProof,
+) -> bool {
+    if proof.old_tree_size == 0 {
+        return true;
+    }
+    if proof.old_tree_size > proof.new_tree_size {
+        return false;
+    }
+    if proof.old_tree_size == proof.new_tree_size {
+        return proof.path.is_empty() && old_root == new_root;
+    }
+    let old_height = duplicate_last_height(proof.old_tree_size);
+    let new_height = duplicate_last_height(proof.new_tree_size);
+    if proof.path.len() != 1 + old_height + new_height {
+        return false;
+    }
+
+    let leaf_index = proof.old_tree_size - 1;
+    let leaf_hash = proof.path[0];
+    let (old_path, new_path) = proof.path[1..].split_at(old_height);
+    if duplicate_last_root(leaf_hash, leaf_index, proof.old_tree_size, old_path) != Some(old_root)
+        || duplicate_last_root(leaf_hash, leaf_index, proof.new_tree_size, new_path)
+            != Some(new_root)
+    {
+        return false;
+    }
+    old_path
+        .iter()
+        .zip(new_path)
+        .enumerate()
+        .all(|(level, (old, new))| (leaf_index >> level) & 1 == 0 || old == new)
+}
+
 pub fn sth_signature_digest(tree_size: u64, root_hash: [u8; 32]) -> [u8; 32] {
     let mut payload = Vec::with_capacity(40);
     payload.extend_from_slice(&tree_size.to_be_bytes());
@@ -196,22 +289,26 @@ pub fn revocations_snapshot_digest(entries: &[RevocationEntry], sth: &SignedTree
     sha256_domain(DOMAIN_REVOCATIONS_SNAPSHOT_V1, &payload)
 }
 
-pub fn verify_sth_signature(
-    sth: &SignedTreeHead,
-    kernel_pubkey: &[u8],
-) -> Result<(), VerificationError> {
+fn kernel_verifying_key(kernel_pubkey: &[u8]) -> Result<VerifyingKey, VerificationError> {
     if kernel_pubkey.len() != 32 {
         return Err(VerificationError::InvalidInput(
             "ed25519 pubkey must be 32 bytes".into(),
         ));
     }
 
-    let pubkey =
-        VerifyingKey::from_bytes(kernel_pubkey.try_into().map_err(|_| {
+    VerifyingKey::from_bytes(
+        kernel_pubkey.try_into().map_err(|_| {
             VerificationError::InvalidInput("ed25519 pubkey must be 32 bytes".into())
-        })?)
-        .map_err(|e| VerificationError::InvalidInput(format!("invalid ed25519 pubkey: {e}")))?;
+        })?,
+    )
+    .map_err(|e| VerificationError::InvalidInput(format!("invalid ed25519 pubkey: {e}")))
+}
 
+pub fn verify_sth_signature(
+    sth: &SignedTreeHead,
+    kernel_pubkey: &[u8],
+) -> Result<(), VerificationError> {
+    let pubkey = kernel_verifying_key(kernel_pubkey)?;
     let signature = Signature::from_bytes(&sth.signature);
     let digest = sth_signature_digest(sth.tree_size, sth.root_hash);
 
@@ -220,6 +317,19 @@ pub fn verify_sth_signature(
         .map_err(|_| VerificationError::VerificationFailed("invalid STH signature".into()))
 }
 
+pub fn verify_revocation_signature(
+    entry: &RevocationEntry,
+    kernel_pubkey: &[u8],
+) -> Result<(), VerificationError> {
+    let pubkey = kernel_verifying_key(kernel_pubkey)?;
+    let signature = Signature::from_bytes(&entry.signature);
+    let digest = revocation_entry_digest(entry);
+
+    pubkey
+        .verify(&digest, &signature)
+        .map_err(|_| VerificationError::VerificationFailed("invalid revocation signature".into()))
+}
+
 pub fn verify_revocations_snapshot(
     entries: &[RevocationEntry],
     sth: &SignedTreeHead,
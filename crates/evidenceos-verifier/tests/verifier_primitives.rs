@@ -1,7 +1,9 @@
 use ed25519_dalek::{Signer, SigningKey};
 use evidenceos_verifier::{
-    etl_leaf_hash, sth_signature_digest, verify_consistency_proof, verify_inclusion_proof,
-    verify_sth_signature, ConsistencyProof, InclusionProof, SignedTreeHead,
+    etl_leaf_hash, revocation_entry_digest, sth_signature_digest, verify_consistency_proof,
+    verify_consistency_proof_duplicate_last, verify_inclusion_proof,
+    verify_inclusion_proof_duplicate_last, verify_revocation_signature, verify_sth_signature,
+    ConsistencyProof, InclusionProof, RevocationEntry, SignedTreeHead,
 };
 
 fn node(left: [u8; 32], right: [u8; 32]) -> [u8; 32] {
@@ -45,3 +47,65 @@ fn verifies_consistency_equal_tree_sizes() {
     };
     assert!(verify_consistency_proof(root, root, &proof));
 }
+
+#[test]
+fn verifies_revocation_signature_over_entry_digest() {
+    let sk = SigningKey::from_bytes(&[9u8; 32]);
+    let mut entry = RevocationEntry {
+        claim_id: vec![3u8; 32],
+        reason_code: "POLICY".into(),
+        logical_epoch: 4,
+        signature: [0u8; 64],
+    };
+    entry.signature = sk.sign(&revocation_entry_digest(&entry)).to_bytes();
+    assert!(verify_revocation_signature(&entry, sk.verifying_key().as_bytes()).is_ok());
+
+    entry.logical_epoch = 5;
+    assert!(verify_revocation_signature(&entry, sk.verifying_key().as_bytes()).is_err());
+    assert!(verify_revocation_signature(&entry, &[1u8; 31]).is_err());
+}
+
+#[test]
+fn verifies_duplicate_last_proofs_over_three_leaves() {
+    let leaves = [b"a", b"b", b"c"].map(|payload| etl_leaf_hash(payload));
+    let l01 = node(leaves[0], leaves[1]);
+    let l22 = node(leaves[2], leaves[2]);
+    let root3 = node(l01, l22);
+
+    let proof = InclusionProof {
+        leaf_hash: leaves[2],
+        leaf_index: 2,
+        tree_size: 3,
+        audit_path: vec![leaves[2], l01],
+    };
+    assert!(verify_inclusion_proof_duplicate_last(root3, &proof));
+    assert!(!verify_inclusion_proof(root3, &proof));
+    let forged = InclusionProof {
+        audit_path: vec![leaves[1], l01],
+        ..proof.clone()
+    };
+    assert!(!verify_inclusion_proof_duplicate_last(
+        node(l01, node(leaves[2], leaves[1])),
+        &forged
+    ));
+
+    let l3 = etl_leaf_hash(b"d");
+    let root4 = node(l01, node(leaves[2], l3));
+    let consistency = ConsistencyProof {
+        old_tree_size: 3,
+        new_tree_size: 4,
+        path: vec![leaves[2], leaves[2], l01, l3, l01],
+    };
+    assert!(verify_consistency_proof_duplicate_last(
+        root3,
+        root4,
+        &consistency
+    ));
+    let mut rewritten = consistency.clone();
+    rewritten.path[4] = node(leaves[0], leaves[0]);
+    assert!(!verify_consistency_proof_duplicate_last(
+        root3,
+        node(rewritten.path[4], node(leaves[2], l3)),
+        &rewritten
+    ));
+}
@@ -0,0 +1,14 @@
+#![no_main]
+
+use discos_core::structured_claims::{
+    canonicalize_cbrn_claim, decode_cbrn_claim_canonical, parse_cbrn_claim_json,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(claim) = parse_cbrn_claim_json(data) {
+        if let Ok(bytes) = canonicalize_cbrn_claim(&claim) {
+            assert_eq!(decode_cbrn_claim_canonical(&bytes).as_ref(), Ok(&claim));
+        }
+    }
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use discos_core::structured_claims::{
+    canonicalize_cbrn_claim, decode_cbrn_claim_canonical, validate_cbrn_claim,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(claim) = decode_cbrn_claim_canonical(data) {
+        assert!(validate_cbrn_claim(&claim).is_ok());
+        let bytes = canonicalize_cbrn_claim(&claim).expect("decoded claims re-encode");
+        assert_eq!(bytes, data);
+    }
+});
@@ -94,6 +94,8 @@ if [[ "${SKIP_FUZZ}" == "1" ]]; then
   printf '[WARN] fuzz step skipped\n' > "${ARTIFACT_DIR}/fuzz_structured_claims_json.txt"
   printf '[WARN] fuzz step skipped\n' > "${ARTIFACT_DIR}/fuzz_structured_claims_canonical.txt"
   printf '[WARN] fuzz step skipped\n' > "${ARTIFACT_DIR}/fuzz_structured_claim_parse_canonicalize.txt"
+  printf '[WARN] fuzz step skipped\n' > "${ARTIFACT_DIR}/fuzz_structured_claims_decode_canonical.txt"
+  printf '[WARN] fuzz step skipped\n' > "${ARTIFACT_DIR}/fuzz_structured_claim_json_canonical_roundtrip.txt"
 else
   run_logged "${ARTIFACT_DIR}/fuzz_structured_claims_json.txt" \
     bash -lc 'cd fuzz && cargo +nightly fuzz run fuzz_structured_claims_json -- -max_total_time=20'
@@ -103,6 +105,12 @@ else
 
   run_logged "${ARTIFACT_DIR}/fuzz_structured_claim_parse_canonicalize.txt" \
     bash -lc 'cd fuzz && cargo +nightly fuzz run fuzz_structured_claim_parse_canonicalize -- -max_total_time=10'
+
+  run_logged "${ARTIFACT_DIR}/fuzz_structured_claims_decode_canonical.txt" \
+    bash -lc 'cd fuzz && cargo +nightly fuzz run fuzz_structured_claims_decode_canonical -- -max_total_time=20'
+
+  run_logged "${ARTIFACT_DIR}/fuzz_structured_claim_json_canonical_roundtrip.txt" \
+    bash -lc 'cd fuzz && cargo +nightly fuzz run fuzz_structured_claim_json_canonical_roundtrip -- -max_total_time=10'
 fi
 
 required_files=(
@@ -115,6 +123,8 @@ required_files=(
   "${ARTIFACT_DIR}/fuzz_structured_claims_json.txt"
   "${ARTIFACT_DIR}/fuzz_structured_claims_canonical.txt"
   "${ARTIFACT_DIR}/fuzz_structured_claim_parse_canonicalize.txt"
+  "${ARTIFACT_DIR}/fuzz_structured_claims_decode_canonical.txt"
+  "${ARTIFACT_DIR}/fuzz_structured_claim_json_canonical_roundtrip.txt"
 )
 
 for f in "${required_files[@]}"; do